@@ -0,0 +1,106 @@
+//! Benchmarks for [servable::ServableRouter]
+
+#![expect(unused_crate_dependencies)]
+
+use axum::http::Method;
+use criterion::{Criterion, criterion_group, criterion_main};
+use servable::bench::{sample_router, send};
+use tokio::runtime::Runtime;
+
+#[expect(clippy::unwrap_used)]
+fn runtime() -> Runtime {
+	tokio::runtime::Builder::new_current_thread()
+		.build()
+		.unwrap()
+}
+
+fn routing(c: &mut Criterion) {
+	let rt = runtime();
+	let router = sample_router();
+
+	let mut group = c.benchmark_group("routing");
+
+	group.bench_function("static hit", |b| {
+		b.to_async(&rt)
+			.iter(|| send(&router, Method::GET, "/style.css"))
+	});
+
+	group.bench_function("html hit", |b| {
+		b.to_async(&rt).iter(|| send(&router, Method::GET, "/"))
+	});
+
+	group.bench_function("head", |b| {
+		b.to_async(&rt).iter(|| send(&router, Method::HEAD, "/"))
+	});
+
+	group.bench_function("404", |b| {
+		b.to_async(&rt)
+			.iter(|| send(&router, Method::GET, "/does/not/exist"))
+	});
+
+	group.bench_function("redirect", |b| {
+		b.to_async(&rt).iter(|| send(&router, Method::GET, "/old"))
+	});
+
+	group.bench_function("normalize", |b| {
+		b.to_async(&rt)
+			.iter(|| send(&router, Method::GET, "//style.css/"))
+	});
+
+	group.finish();
+}
+
+#[cfg(feature = "image")]
+fn transform(c: &mut Criterion) {
+	use servable::{ServableRouter, StaticAsset};
+	use std::io::Cursor;
+
+	let rt = runtime();
+
+	let mut png = Cursor::new(Vec::new());
+	#[expect(clippy::unwrap_used)]
+	image::DynamicImage::new_rgb8(512, 512)
+		.write_to(&mut png, image::ImageFormat::Png)
+		.unwrap();
+
+	let router = ServableRouter::new().add_page(
+		"/image.png",
+		StaticAsset {
+			bytes: png.into_inner().leak(),
+			br: None,
+			gz: None,
+			mime: mime::IMAGE_PNG,
+			ttl: StaticAsset::DEFAULT_TTL,
+		},
+	);
+
+	let mut group = c.benchmark_group("transform");
+
+	group.bench_function("original", |b| {
+		b.to_async(&rt)
+			.iter(|| send(&router, Method::GET, "/image.png"))
+	});
+
+	group.bench_function("maxdim", |b| {
+		b.to_async(&rt)
+			.iter(|| send(&router, Method::GET, "/image.png?t=maxdim(128,128)"))
+	});
+
+	group.bench_function("crop and format", |b| {
+		b.to_async(&rt).iter(|| {
+			send(
+				&router,
+				Method::GET,
+				"/image.png?t=crop(256,256,c);format(webp)",
+			)
+		})
+	});
+
+	group.finish();
+}
+
+#[cfg(not(feature = "image"))]
+fn transform(_c: &mut Criterion) {}
+
+criterion_group!(benches, routing, transform);
+criterion_main!(benches);
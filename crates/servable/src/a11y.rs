@@ -0,0 +1,174 @@
+use std::fmt::Display;
+use tracing::warn;
+
+use crate::{
+	HtmlRewriter, RenderContext, Rendered,
+	scan::{attribute, find_ignore_case, rewrite_tags},
+};
+
+/// An accessibility problem found by [AccessibilityLint]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum AccessibilityIssue {
+	/// An `<img>` without an `alt` attribute.
+	/// Decorative images should have an empty `alt=""`.
+	MissingAlt {
+		/// The `src` of the image
+		src: Option<String>,
+	},
+
+	/// A form control without a label: no `<label>` wraps it or points at its `id`,
+	/// and it has no `aria-label`, `aria-labelledby` or `title`.
+	MissingLabel {
+		/// The element, like `input` or `select`
+		element: String,
+
+		/// The control's `name`
+		name: Option<String>,
+	},
+
+	/// A heading more than one level below the heading before it, like an `<h4>` after an `<h2>`
+	SkippedHeading {
+		/// The level of the heading before this one
+		from: u8,
+
+		/// The level of this heading
+		to: u8,
+	},
+}
+
+impl Display for AccessibilityIssue {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::MissingAlt { src } => {
+				write!(
+					f,
+					"<img> without alt (src={})",
+					src.as_deref().unwrap_or("?")
+				)
+			}
+			Self::MissingLabel { element, name } => write!(
+				f,
+				"<{element}> without a label (name={})",
+				name.as_deref().unwrap_or("?")
+			),
+			Self::SkippedHeading { from, to } => write!(f, "<h{to}> after <h{from}>"),
+		}
+	}
+}
+
+/// An [crate::HtmlRewriter] that checks rendered html for common accessibility problems
+/// and logs a warning for each one, with the route of the page. Html is not changed.
+///
+/// This checks for images without `alt`, form controls without labels,
+/// and headings that skip levels (see [AccessibilityIssue]).
+/// Checks are cheap but not free, so this is meant for dev builds.
+///
+/// ```rust
+/// use servable::{AccessibilityLint, HtmlPage, ServableRouter};
+///
+/// let router = ServableRouter::new()
+/// 	.add_page("/", HtmlPage::default())
+/// 	.with_html_rewriter(AccessibilityLint);
+///
+/// let issues = AccessibilityLint::check(r#"<h1>Title</h1><h3>Oops</h3><img src="/a.png">"#);
+/// assert_eq!(issues.len(), 2);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct AccessibilityLint;
+
+impl AccessibilityLint {
+	/// Find every accessibility problem in `html`
+	pub fn check(html: &str) -> Vec<AccessibilityIssue> {
+		let mut issues = Vec::new();
+
+		// The byte ranges of `<label>` elements, and the ids their `for` points at
+		let mut labels = Vec::new();
+		let mut label_for = Vec::new();
+
+		// Form controls, and where they start
+		let mut controls = Vec::new();
+
+		let mut heading = None;
+
+		let names = [
+			"img", "input", "select", "textarea", "label", "h1", "h2", "h3", "h4", "h5", "h6",
+		];
+		rewrite_tags(html, &names, |name, open, rest| {
+			let start = html.len() - rest.len() - open.len();
+			let tag = &html[start..start + open.len()];
+
+			match name {
+				"img" => {
+					if attribute(open, "alt").is_none() {
+						issues.push(AccessibilityIssue::MissingAlt {
+							src: attribute(open, "src").map(str::to_owned),
+						});
+					}
+				}
+
+				"label" => {
+					let end = find_ignore_case(rest, "</label")
+						.map(|x| html.len() - rest.len() + x)
+						.unwrap_or(html.len());
+					labels.push(start..end);
+					label_for.extend(attribute(open, "for").map(str::to_owned));
+				}
+
+				"input" => {
+					let ty = attribute(open, "type")
+						.unwrap_or("text")
+						.to_ascii_lowercase();
+					if !matches!(
+						ty.as_str(),
+						"hidden" | "submit" | "button" | "reset" | "image"
+					) {
+						controls.push((name.to_owned(), tag, start));
+					}
+				}
+
+				"select" | "textarea" => controls.push((name.to_owned(), tag, start)),
+
+				_ => {
+					let level = name[1..].parse::<u8>().unwrap_or(1);
+					if let Some(from) = heading
+						&& level > from + 1
+					{
+						issues.push(AccessibilityIssue::SkippedHeading { from, to: level });
+					}
+					heading = Some(level);
+				}
+			}
+
+			None
+		});
+
+		// Controls can only be checked once we've seen every label
+		for (element, open, start) in controls {
+			let labelled = ["aria-label", "aria-labelledby", "title"]
+				.iter()
+				.any(|x| attribute(open, x).is_some_and(|x| !x.trim().is_empty()))
+				|| attribute(open, "id").is_some_and(|id| label_for.iter().any(|x| x == id))
+				|| labels.iter().any(|x| x.contains(&start));
+
+			if !labelled {
+				issues.push(AccessibilityIssue::MissingLabel {
+					element,
+					name: attribute(open, "name").map(str::to_owned),
+				});
+			}
+		}
+
+		issues
+	}
+}
+
+impl HtmlRewriter for AccessibilityLint {
+	fn rewrite(&self, ctx: &RenderContext, rend: &mut Rendered<()>, html: String) -> String {
+		if rend.code.is_success() {
+			for issue in Self::check(&html) {
+				warn!(message = "Accessibility issue", route = ctx.route, %issue);
+			}
+		}
+		html
+	}
+}
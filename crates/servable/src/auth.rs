@@ -0,0 +1,219 @@
+use axum::http::{HeaderMap, HeaderValue, StatusCode, header};
+use std::{pin::Pin, sync::Arc};
+
+use crate::{Error, RenderContext, Rendered, RenderedBody, servable::Servable};
+
+/// What an [AuthGuard] does with a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AuthDecision {
+	/// Serve the guarded page
+	Allow,
+
+	/// The client has not said who it is, or its credentials are invalid.
+	/// Reply with `401 Unauthorized`, or redirect to the login page
+	/// if there is one (see [AuthGuard::with_login_redirect]).
+	Unauthenticated,
+
+	/// The client is known, but may not see this page.
+	/// Reply with `403 Forbidden`.
+	Forbidden,
+}
+
+/// The async function that checks requests to an [AuthGuard]
+pub type AuthCheckFn = dyn Send
+	+ Sync
+	+ 'static
+	+ for<'a> Fn(&'a RenderContext) -> Pin<Box<dyn Future<Output = AuthDecision> + Send + Sync + 'a>>;
+
+/// A [Servable] that only serves `inner` to requests that pass an async check,
+/// like a session lookup or a token verification.
+///
+/// The check may read anything in the [RenderContext], usually
+/// [RenderContext::cookie], [RenderContext::bearer_token] or [RenderContext::extensions].
+///
+/// Every response is private, since it depends on who is asking.
+///
+/// ```rust
+/// use servable::{AuthDecision, AuthGuard, HtmlPage, ServableRouter};
+///
+/// let admin = AuthGuard::new(HtmlPage::default(), |ctx| {
+/// 	let session = ctx.cookie("session").map(str::to_owned);
+/// 	Box::pin(async move {
+/// 		match session.as_deref() {
+/// 			// Look this session up in a database
+/// 			Some("s3cr3t") => AuthDecision::Allow,
+/// 			Some(_) => AuthDecision::Forbidden,
+/// 			None => AuthDecision::Unauthenticated,
+/// 		}
+/// 	})
+/// })
+/// .with_login_redirect("/login");
+///
+/// let router = ServableRouter::new().add_page("/admin", admin);
+/// ```
+pub struct AuthGuard<S: Servable> {
+	inner: S,
+	check: Arc<AuthCheckFn>,
+
+	/// Where to send unauthenticated clients
+	login: Option<String>,
+
+	/// The `WWW-Authenticate` header of `401` responses
+	challenge: Option<HeaderValue>,
+}
+
+impl<S: Servable> AuthGuard<S> {
+	/// Serve `inner` to requests that `check` allows
+	pub fn new<
+		F: Send
+			+ Sync
+			+ 'static
+			+ for<'a> Fn(
+				&'a RenderContext,
+			) -> Pin<Box<dyn Future<Output = AuthDecision> + Send + Sync + 'a>>,
+	>(
+		inner: S,
+		check: F,
+	) -> Self {
+		Self {
+			inner,
+			check: Arc::new(check),
+			login: None,
+			challenge: None,
+		}
+	}
+
+	/// Redirect unauthenticated clients to `login` with `303 See Other`,
+	/// instead of replying with `401 Unauthorized`.
+	///
+	/// The requested url is passed to `login` in its `next` query parameter,
+	/// so the login page may send the client back after it logs in.
+	/// Absolute paths are prefixed with the router's base path, like [crate::Redirect].
+	///
+	/// - panics if `login` is not a valid header value
+	///
+	/// See [Self::try_with_login_redirect] for a version of this method that does not panic.
+	#[inline(always)]
+	pub fn with_login_redirect(self, login: impl Into<String>) -> Self {
+		match self.try_with_login_redirect(login) {
+			Ok(x) => x,
+			Err(err) => panic!("{err}"),
+		}
+	}
+
+	/// Redirect unauthenticated clients to `login`.
+	/// Behaves exactly like [Self::with_login_redirect], but returns an [Error] instead of panicking.
+	pub fn try_with_login_redirect(mut self, login: impl Into<String>) -> Result<Self, Error> {
+		let login = login.into();
+		if HeaderValue::from_str(&login).is_err() {
+			return Err(Error::InvalidHeader {
+				name: header::LOCATION.to_string(),
+			});
+		}
+
+		self.login = Some(login);
+		Ok(self)
+	}
+
+	/// Send `challenge` as the `WWW-Authenticate` header of `401` responses,
+	/// like `Bearer` or `Basic realm="admin"`.
+	#[inline(always)]
+	pub fn with_challenge(mut self, challenge: HeaderValue) -> Self {
+		self.challenge = Some(challenge);
+		self
+	}
+
+	/// The response to a request that was not allowed
+	fn denied(&self, ctx: &RenderContext, decision: AuthDecision) -> Rendered<()> {
+		let mut headers = HeaderMap::new();
+
+		let code = match (decision, &self.login) {
+			(AuthDecision::Forbidden, _) => StatusCode::FORBIDDEN,
+
+			(_, Some(login)) => {
+				let mut next = ctx.url(&ctx.route);
+				if !ctx.query.is_empty() {
+					next.push('?');
+					next.push_str(&serde_urlencoded::to_string(&ctx.query).unwrap_or_default());
+				}
+
+				let sep = if login.contains('?') { '&' } else { '?' };
+				let next = serde_urlencoded::to_string([("next", next)]).unwrap_or_default();
+				let location = format!("{}{sep}{next}", ctx.absolute_url(login));
+				match HeaderValue::from_str(&location) {
+					Ok(x) => {
+						headers.insert(header::LOCATION, x);
+						StatusCode::SEE_OTHER
+					}
+					Err(_err) => StatusCode::UNAUTHORIZED,
+				}
+			}
+
+			(_, None) => {
+				if let Some(challenge) = &self.challenge {
+					headers.insert(header::WWW_AUTHENTICATE, challenge.clone());
+				}
+				StatusCode::UNAUTHORIZED
+			}
+		};
+
+		Rendered {
+			code,
+			headers,
+			body: (),
+			ttl: None,
+			private: true,
+			mime: None,
+			content_length: None,
+		}
+	}
+}
+
+impl<S: Servable> Servable for AuthGuard<S> {
+	fn head<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<()>> + 'a + Send + Sync>> {
+		Box::pin(async move {
+			match (self.check)(ctx).await {
+				AuthDecision::Allow => {
+					let mut rend = self.inner.head(ctx).await;
+					rend.make_private();
+					rend
+				}
+				decision => self.denied(ctx, decision),
+			}
+		})
+	}
+
+	fn render<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<RenderedBody>> + 'a + Send + Sync>> {
+		Box::pin(async move {
+			match (self.check)(ctx).await {
+				AuthDecision::Allow => {
+					let mut rend = self.inner.render(ctx).await;
+					rend.make_private();
+					rend
+				}
+				decision => self.denied(ctx, decision).with_body(RenderedBody::Empty),
+			}
+		})
+	}
+
+	#[inline(always)]
+	fn header_template(&self) -> HeaderMap {
+		self.inner.header_template()
+	}
+
+	#[inline(always)]
+	fn linked_routes(&self) -> Vec<String> {
+		self.inner.linked_routes()
+	}
+
+	#[inline(always)]
+	fn preloads(&self) -> Vec<crate::Preload> {
+		self.inner.preloads()
+	}
+}
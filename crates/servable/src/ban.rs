@@ -0,0 +1,107 @@
+use std::{
+	collections::HashMap,
+	fmt::Debug,
+	net::IpAddr,
+	sync::{Arc, Mutex},
+	time::{Duration, Instant},
+};
+
+use crate::forwarded::client_key;
+
+/// A shared list of banned client ips, see [crate::ServableRouter::with_ban_list].
+///
+/// Cloning a [BanList] is cheap, and clones share the same bans.
+/// IPv6 clients are banned by their `/64` prefix, like [crate::RateLimited].
+///
+/// Bans are usually added by a [crate::Honeypot], but may be added by hand:
+/// ```rust
+/// use servable::{BanList, Clock, HtmlPage, ServableRouter, SystemClock};
+/// use std::time::Duration;
+///
+/// let bans = BanList::new();
+/// let router = ServableRouter::new()
+/// 	.add_page("/", HtmlPage::default())
+/// 	.with_ban_list(bans.clone());
+///
+/// let ip = "192.0.2.1".parse().unwrap();
+/// bans.ban(ip, SystemClock.instant(), Duration::from_secs(3600));
+/// ```
+#[derive(Clone, Default)]
+pub struct BanList {
+	/// Banned clients, and when their ban ends
+	bans: Arc<Mutex<HashMap<IpAddr, Instant>>>,
+}
+
+impl Debug for BanList {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let len = self.bans.lock().map(|x| x.len()).unwrap_or_default();
+		f.debug_struct("BanList").field("len", &len).finish()
+	}
+}
+
+impl BanList {
+	/// Create a new, empty [BanList]
+	#[inline(always)]
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Ban `ip` for `duration`, starting at `now`.
+	/// `now` should come from the router's [crate::Clock].
+	///
+	/// If `ip` is already banned, its ban ends at the later of the two times.
+	pub fn ban(&self, ip: IpAddr, now: Instant, duration: Duration) {
+		let Ok(mut bans) = self.bans.lock() else {
+			return;
+		};
+
+		// Forget bans that have ended
+		bans.retain(|_, x| *x > now);
+
+		let until = now + duration;
+		let entry = bans.entry(client_key(ip)).or_insert(until);
+		*entry = (*entry).max(until);
+	}
+
+	/// Forget bans that ended before `now`
+	pub(crate) fn prune(&self, now: Instant) {
+		if let Ok(mut bans) = self.bans.lock() {
+			bans.retain(|_, x| *x > now);
+		}
+	}
+
+	/// Remove `ip`'s ban, if it has one
+	pub fn unban(&self, ip: IpAddr) {
+		if let Ok(mut bans) = self.bans.lock() {
+			bans.remove(&client_key(ip));
+		}
+	}
+
+	/// Copy the bans in `other` into this list.
+	/// If a client is banned in both, its ban ends at the later of the two times.
+	pub(crate) fn absorb(&self, other: &BanList) {
+		if Arc::ptr_eq(&self.bans, &other.bans) {
+			return;
+		}
+
+		let Ok(other) = other.bans.lock() else {
+			return;
+		};
+		let Ok(mut bans) = self.bans.lock() else {
+			return;
+		};
+
+		for (ip, until) in other.iter() {
+			let entry = bans.entry(*ip).or_insert(*until);
+			*entry = (*entry).max(*until);
+		}
+	}
+
+	/// Returns true if `ip` is banned at `now`
+	pub fn is_banned(&self, ip: IpAddr, now: Instant) -> bool {
+		match self.bans.lock() {
+			Ok(bans) => bans.get(&client_key(ip)).is_some_and(|x| now < *x),
+			Err(_) => false,
+		}
+	}
+}
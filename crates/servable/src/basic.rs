@@ -0,0 +1,194 @@
+use axum::http::{HeaderMap, HeaderValue, StatusCode, header};
+use std::pin::Pin;
+
+use crate::{
+	RenderContext, Rendered, RenderedBody,
+	servable::Servable,
+	sign::{constant_time_eq, sha256},
+};
+
+/// A [Servable] that only serves `inner` to clients that log in with
+/// `Authorization: Basic`, like a staging site.
+///
+/// Credentials are compared in constant time, and only their hashes are kept in memory.
+/// Other clients get `401 Unauthorized` with a `WWW-Authenticate` challenge,
+/// which makes browsers ask for a username and password.
+/// Every response is private.
+///
+/// Basic auth sends passwords in plain text, so only use it over https.
+///
+/// ```rust
+/// use servable::{BasicAuth, HtmlPage, ServableRouter};
+///
+/// let staging = BasicAuth::new(HtmlPage::default(), "staging")
+/// 	.with_user("alice", "correct horse battery staple")
+/// 	.with_user("bob", "hunter2");
+///
+/// let router = ServableRouter::new().add_page("/", staging);
+/// ```
+pub struct BasicAuth<S: Servable> {
+	inner: S,
+
+	/// The `WWW-Authenticate` header of `401` responses
+	challenge: HeaderValue,
+
+	/// The sha256 hashes of `user:password` for every user we accept
+	credentials: Vec<[u8; 32]>,
+}
+
+impl<S: Servable> BasicAuth<S> {
+	/// Protect `inner`. Browsers may show `realm` when they ask for a password.
+	/// No one may log in until users are added with [Self::with_user].
+	pub fn new(inner: S, realm: &str) -> Self {
+		let realm: String = realm
+			.chars()
+			.filter(|x| !x.is_control())
+			.flat_map(|x| match x {
+				'"' | '\\' => vec!['\\', x],
+				_ => vec![x],
+			})
+			.collect();
+
+		Self {
+			inner,
+			challenge: HeaderValue::from_str(&format!(
+				"Basic realm=\"{realm}\", charset=\"UTF-8\""
+			))
+			.unwrap_or_else(|_err| HeaderValue::from_static("Basic")),
+			credentials: Vec::new(),
+		}
+	}
+
+	/// Accept `user` with `password`.
+	/// `user` may not contain a `:`.
+	#[inline(always)]
+	pub fn with_user(mut self, user: &str, password: &str) -> Self {
+		self.credentials
+			.push(sha256(format!("{user}:{password}").as_bytes()));
+		self
+	}
+
+	/// Returns true if `ctx` has valid credentials
+	fn allowed(&self, ctx: &RenderContext) -> bool {
+		let credentials = ctx
+			.headers
+			.get(header::AUTHORIZATION)
+			.and_then(|x| x.to_str().ok())
+			.and_then(|x| x.trim().split_once(' '))
+			.filter(|(scheme, _)| scheme.eq_ignore_ascii_case("basic"))
+			.and_then(|(_, x)| decode_base64(x.trim()));
+
+		let Some(credentials) = credentials else {
+			return false;
+		};
+
+		// Check every user, so that timing does not tell which one matched
+		let hash = sha256(&credentials);
+		self.credentials
+			.iter()
+			.fold(false, |acc, x| constant_time_eq(x, &hash) | acc)
+	}
+
+	/// The response to clients without valid credentials
+	fn unauthorized(&self) -> Rendered<()> {
+		let mut headers = HeaderMap::with_capacity(1);
+		headers.insert(header::WWW_AUTHENTICATE, self.challenge.clone());
+
+		Rendered {
+			code: StatusCode::UNAUTHORIZED,
+			headers,
+			body: (),
+			ttl: None,
+			private: true,
+			mime: None,
+			content_length: None,
+		}
+	}
+}
+
+/// Decode standard, padded base64
+fn decode_base64(s: &str) -> Option<Vec<u8>> {
+	if !s.len().is_multiple_of(4) {
+		return None;
+	}
+
+	let value = |c: u8| match c {
+		b'A'..=b'Z' => Some(c - b'A'),
+		b'a'..=b'z' => Some(c - b'a' + 26),
+		b'0'..=b'9' => Some(c - b'0' + 52),
+		b'+' => Some(62),
+		b'/' => Some(63),
+		_ => None,
+	};
+
+	let bytes = s.as_bytes();
+	let padding = bytes.iter().rev().take_while(|x| **x == b'=').count();
+	if padding > 2 {
+		return None;
+	}
+
+	let mut out = Vec::with_capacity(bytes.len() / 4 * 3);
+	let mut acc = 0u32;
+	for (i, c) in bytes[..bytes.len() - padding].iter().enumerate() {
+		acc = (acc << 6) | u32::from(value(*c)?);
+		if i % 4 == 3 {
+			out.extend_from_slice(&acc.to_be_bytes()[1..]);
+			acc = 0;
+		}
+	}
+
+	match padding {
+		1 => out.extend_from_slice(&(acc << 6).to_be_bytes()[1..3]),
+		2 => out.push((acc >> 4) as u8),
+		_ => {}
+	}
+
+	Some(out)
+}
+
+impl<S: Servable> Servable for BasicAuth<S> {
+	fn head<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<()>> + 'a + Send + Sync>> {
+		Box::pin(async move {
+			if !self.allowed(ctx) {
+				return self.unauthorized();
+			}
+
+			let mut rend = self.inner.head(ctx).await;
+			rend.make_private();
+			rend
+		})
+	}
+
+	fn render<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<RenderedBody>> + 'a + Send + Sync>> {
+		Box::pin(async move {
+			if !self.allowed(ctx) {
+				return self.unauthorized().with_body(RenderedBody::Empty);
+			}
+
+			let mut rend = self.inner.render(ctx).await;
+			rend.make_private();
+			rend
+		})
+	}
+
+	#[inline(always)]
+	fn header_template(&self) -> HeaderMap {
+		self.inner.header_template()
+	}
+
+	#[inline(always)]
+	fn linked_routes(&self) -> Vec<String> {
+		self.inner.linked_routes()
+	}
+
+	#[inline(always)]
+	fn preloads(&self) -> Vec<crate::Preload> {
+		self.inner.preloads()
+	}
+}
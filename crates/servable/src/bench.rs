@@ -0,0 +1,69 @@
+//! A small harness for benchmarking a [ServableRouter] without a network.
+//!
+//! This is used by this crate's benchmarks, and may be used to compare
+//! the performance of different router configurations.
+//! It is not part of this crate's stable api.
+
+use axum::{
+	body::{Body, Bytes},
+	http::{Method, Request, StatusCode},
+};
+use maud::html;
+use tower::Service;
+
+use crate::{HtmlPage, Redirect, ServableRouter, StaticAsset};
+
+/// Send one request to `router` and collect the full response body.
+///
+/// Panics if the request cannot be built or the body cannot be read.
+#[expect(clippy::expect_used)]
+pub async fn send(router: &ServableRouter, method: Method, uri: &str) -> (StatusCode, Bytes) {
+	let req = Request::builder()
+		.method(method)
+		.uri(uri)
+		.body(Body::empty())
+		.expect("invalid request");
+
+	let res = match router.clone().call(req).await {
+		Ok(x) => x,
+		Err(x) => match x {},
+	};
+
+	let (parts, body) = res.into_parts();
+	let body = axum::body::to_bytes(body, usize::MAX)
+		.await
+		.expect("could not read body");
+
+	(parts.status, body)
+}
+
+/// A small router with one of each kind of servable:
+/// - `/`, an [HtmlPage]
+/// - `/style.css`, a [StaticAsset]
+/// - `/old`, a [Redirect] to `/`
+#[expect(clippy::unwrap_used)]
+pub fn sample_router() -> ServableRouter {
+	ServableRouter::new()
+		.add_page(
+			"/",
+			HtmlPage::default().with_render(|_page, ctx| {
+				Box::pin(async move {
+					html! {
+						h1 { "Benchmark" }
+						p { "Route: " (ctx.route) }
+					}
+				})
+			}),
+		)
+		.add_page(
+			"/style.css",
+			StaticAsset {
+				bytes: b"body { color: red; }",
+				br: None,
+				gz: None,
+				mime: mime::TEXT_CSS,
+				ttl: StaticAsset::DEFAULT_TTL,
+			},
+		)
+		.add_page("/old", Redirect::new("/").unwrap())
+}
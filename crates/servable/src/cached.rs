@@ -0,0 +1,272 @@
+use axum::http::{HeaderMap, HeaderValue, header};
+use std::{
+	collections::HashMap,
+	fmt::Debug,
+	pin::Pin,
+	sync::{Arc, Mutex},
+	time::{Duration, Instant},
+};
+
+use crate::{
+	DeviceType, RenderContext, RenderMode, Rendered, RenderedBody, cachestatus::CacheStatus,
+	failsafe::head_of, normalize::has_route_prefix, servable::Servable,
+};
+
+/// What a [Cached] response is stored under
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Key {
+	route: String,
+	query: String,
+	device: DeviceType,
+}
+
+impl Key {
+	fn new(ctx: &RenderContext) -> Self {
+		Self {
+			route: ctx.route.clone(),
+			query: serde_urlencoded::to_string(&ctx.query).unwrap_or_default(),
+			device: ctx.client_info.device_type,
+		}
+	}
+
+	/// Add this key to `headers` as a diagnostic header
+	fn debug_header(&self, ctx: &RenderContext, headers: &mut HeaderMap) {
+		let key = format!("{}?{}; device={:?}", self.route, self.query, self.device);
+		ctx.debug_header(headers, "x-cache-key", &key);
+	}
+}
+
+/// A response kept by a [Cached] servable
+struct Entry {
+	rend: Rendered<RenderedBody>,
+	stored: Instant,
+}
+
+type Entries = Arc<Mutex<HashMap<Key, Entry>>>;
+
+/// A [Servable] that keeps the responses of another servable for `ttl`,
+/// so that slow pages (like [crate::HtmlPage]s that query a database)
+/// are rendered once instead of on every request.
+///
+/// Responses are stored by route, query and [DeviceType].
+/// Only successful responses are kept, and never responses
+/// that are private or set cookies.
+/// Responses that vary on other request headers (with `Vary` or `Content-Encoding`,
+/// in the response or in the servable's header template) are never kept either,
+/// since they would be replayed to clients that didn't ask for them.
+/// Cached responses have an `Age` header,
+/// and every response has a `Cache-Status` header.
+/// When the cache is full, the oldest response is forgotten.
+///
+/// Use a [CacheHandle] to forget responses before they expire,
+/// for example when the data they show changes.
+///
+/// ```rust
+/// use servable::{Cached, HtmlPage, ServableRouter};
+/// use std::time::Duration;
+///
+/// let posts = Cached::new(HtmlPage::default(), Duration::from_secs(60));
+/// let cache = posts.handle();
+///
+/// let router = ServableRouter::new().add_page("/posts/{id}", posts);
+///
+/// // After post 3 is edited:
+/// cache.invalidate("/posts/3");
+/// ```
+pub struct Cached<S: Servable> {
+	inner: S,
+	entries: Entries,
+	ttl: Duration,
+
+	/// The maximum number of cached responses
+	capacity: usize,
+
+	/// If true, the header template of `inner` has a `Vary`
+	template_varies: bool,
+}
+
+/// Forgets responses kept by a [Cached] servable,
+/// see [Cached::handle].
+///
+/// Cloning a [CacheHandle] is cheap, and clones control the same cache.
+#[derive(Clone)]
+pub struct CacheHandle {
+	entries: Entries,
+}
+
+impl Debug for CacheHandle {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let len = self.entries.lock().map(|x| x.len()).unwrap_or_default();
+		f.debug_struct("CacheHandle").field("len", &len).finish()
+	}
+}
+
+impl CacheHandle {
+	/// Forget every response to `route`, with any query.
+	/// `route` does not include the router's base path, like [RenderContext::route].
+	pub fn invalidate(&self, route: &str) {
+		if let Ok(mut entries) = self.entries.lock() {
+			entries.retain(|key, _| key.route != route);
+		}
+	}
+
+	/// Forget every response to a route under `prefix`.
+	/// `prefix` matches whole path segments, and does not include the base path.
+	pub fn invalidate_prefix(&self, prefix: &str) {
+		if let Ok(mut entries) = self.entries.lock() {
+			entries.retain(|key, _| !has_route_prefix(&key.route, prefix));
+		}
+	}
+
+	/// Forget every response
+	pub fn clear(&self) {
+		if let Ok(mut entries) = self.entries.lock() {
+			entries.clear();
+		}
+	}
+}
+
+impl<S: Servable> Cached<S> {
+	/// Keep the responses of `inner` for `ttl`.
+	/// By default, we keep at most 1000 responses.
+	pub fn new(inner: S, ttl: Duration) -> Self {
+		let template_varies = inner.header_template().contains_key(header::VARY);
+		Self {
+			inner,
+			entries: Arc::new(Mutex::new(HashMap::new())),
+			ttl,
+			capacity: 1000,
+			template_varies,
+		}
+	}
+
+	/// Set the maximum number of cached responses
+	#[inline(always)]
+	pub fn with_capacity(mut self, capacity: usize) -> Self {
+		self.capacity = capacity;
+		self
+	}
+
+	/// A handle that forgets responses in this cache
+	pub fn handle(&self) -> CacheHandle {
+		CacheHandle {
+			entries: self.entries.clone(),
+		}
+	}
+
+	/// The cached response to `ctx`, if it hasn't expired
+	fn get(&self, ctx: &RenderContext) -> Option<Rendered<RenderedBody>> {
+		let entries = self.entries.lock().ok()?;
+		let key = Key::new(ctx);
+		let entry = entries.get(&key)?;
+		let age = ctx.clock.instant().duration_since(entry.stored);
+		if age >= self.ttl {
+			return None;
+		}
+
+		let mut rend = entry.rend.clone();
+		rend.headers
+			.insert(header::AGE, HeaderValue::from(age.as_secs()));
+		CacheStatus::Hit {
+			ttl: Some(self.ttl - age),
+		}
+		.append_to(&mut rend.headers);
+		key.debug_header(ctx, &mut rend.headers);
+		Some(rend)
+	}
+
+	/// Keep `rend`, if it may be cached.
+	/// Returns true if it was kept.
+	fn store(&self, ctx: &RenderContext, rend: &Rendered<RenderedBody>) -> bool {
+		if ctx.mode != RenderMode::Full
+			|| !rend.code.is_success()
+			|| rend.private
+			|| rend.headers.contains_key(header::SET_COOKIE)
+			|| rend.headers.contains_key(header::VARY)
+			|| rend.headers.contains_key(header::CONTENT_ENCODING)
+			|| self.template_varies
+			|| !ctx.vary_headers().is_empty()
+			|| self.capacity == 0
+		{
+			return false;
+		}
+
+		let Ok(mut entries) = self.entries.lock() else {
+			return false;
+		};
+
+		let now = ctx.clock.instant();
+		let key = Key::new(ctx);
+		if entries.len() >= self.capacity && !entries.contains_key(&key) {
+			entries.retain(|_, x| now.duration_since(x.stored) < self.ttl);
+			if entries.len() >= self.capacity
+				&& let Some(oldest) = entries
+					.iter()
+					.min_by_key(|(_, x)| x.stored)
+					.map(|(k, _)| k.clone())
+			{
+				entries.remove(&oldest);
+			}
+		}
+
+		entries.insert(
+			key,
+			Entry {
+				rend: rend.clone(),
+				stored: now,
+			},
+		);
+		true
+	}
+}
+
+impl<S: Servable> Servable for Cached<S> {
+	fn head<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<()>> + 'a + Send + Sync>> {
+		Box::pin(async move {
+			match self.get(ctx) {
+				Some(rend) => head_of(rend),
+				None => {
+					let mut rend = self.inner.head(ctx).await;
+					CacheStatus::Miss { stored: false }.append_to(&mut rend.headers);
+					Key::new(ctx).debug_header(ctx, &mut rend.headers);
+					rend
+				}
+			}
+		})
+	}
+
+	fn render<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<RenderedBody>> + 'a + Send + Sync>> {
+		Box::pin(async move {
+			if let Some(rend) = self.get(ctx) {
+				return rend;
+			}
+
+			let mut rend = self.inner.render(ctx).await;
+			let stored = self.store(ctx, &rend);
+			CacheStatus::Miss { stored }.append_to(&mut rend.headers);
+			Key::new(ctx).debug_header(ctx, &mut rend.headers);
+			rend
+		})
+	}
+
+	#[inline(always)]
+	fn header_template(&self) -> HeaderMap {
+		self.inner.header_template()
+	}
+
+	#[inline(always)]
+	fn linked_routes(&self) -> Vec<String> {
+		self.inner.linked_routes()
+	}
+
+	#[inline(always)]
+	fn preloads(&self) -> Vec<crate::Preload> {
+		self.inner.preloads()
+	}
+}
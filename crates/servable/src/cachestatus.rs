@@ -0,0 +1,53 @@
+use axum::http::{HeaderMap, HeaderValue};
+use std::time::Duration;
+
+/// The name of our caches in `Cache-Status` headers
+const CACHE_NAME: &str = "servable";
+
+/// How one of our caches handled a request,
+/// sent in a [`Cache-Status`](https://www.rfc-editor.org/rfc/rfc9211) header.
+pub(crate) enum CacheStatus {
+	/// The response came from the cache.
+	/// `ttl` is how much longer it is fresh for, if it expires.
+	Hit { ttl: Option<Duration> },
+
+	/// The response was not in the cache.
+	/// `stored` is true if it was cached afterwards.
+	Miss { stored: bool },
+
+	/// The cached response was stale, and was revalidated upstream.
+	/// `status` is upstream's answer.
+	#[cfg(feature = "proxy")]
+	Stale {
+		status: axum::http::StatusCode,
+		stored: bool,
+	},
+}
+
+impl CacheStatus {
+	/// Add this status to `headers`.
+	/// Caches closer to the origin come first, so this is appended after existing statuses.
+	pub(crate) fn append_to(&self, headers: &mut HeaderMap) {
+		let value = match self {
+			Self::Hit { ttl: None } => format!("{CACHE_NAME}; hit"),
+			Self::Hit { ttl: Some(ttl) } => format!("{CACHE_NAME}; hit; ttl={}", ttl.as_secs()),
+			Self::Miss { stored } => format!("{CACHE_NAME}; fwd=miss{}", stored_param(*stored)),
+			#[cfg(feature = "proxy")]
+			Self::Stale { status, stored } => format!(
+				"{CACHE_NAME}; fwd=stale; fwd-status={}{}",
+				status.as_u16(),
+				stored_param(*stored)
+			),
+		};
+
+		#[expect(clippy::unwrap_used)]
+		headers.append("cache-status", HeaderValue::from_str(&value).unwrap());
+	}
+}
+
+fn stored_param(stored: bool) -> &'static str {
+	match stored {
+		true => "; stored",
+		false => "",
+	}
+}
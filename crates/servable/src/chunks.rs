@@ -0,0 +1,65 @@
+use axum::body::Bytes;
+use http_body::{Body, Frame, SizeHint};
+use std::{
+	collections::VecDeque,
+	convert::Infallible,
+	ops::Range,
+	pin::Pin,
+	task::{Context, Poll},
+};
+
+/// A response body made of buffers that are sent one after another,
+/// see [crate::RenderedBody::Chunks]
+pub(crate) struct ChunkedBody {
+	chunks: VecDeque<Bytes>,
+}
+
+impl ChunkedBody {
+	pub(crate) fn new(chunks: Vec<Bytes>) -> Self {
+		Self {
+			chunks: chunks.into_iter().filter(|x| !x.is_empty()).collect(),
+		}
+	}
+}
+
+impl Body for ChunkedBody {
+	type Data = Bytes;
+	type Error = Infallible;
+
+	fn poll_frame(
+		mut self: Pin<&mut Self>,
+		_cx: &mut Context<'_>,
+	) -> Poll<Option<Result<Frame<Bytes>, Infallible>>> {
+		Poll::Ready(self.chunks.pop_front().map(|x| Ok(Frame::data(x))))
+	}
+
+	fn is_end_stream(&self) -> bool {
+		self.chunks.is_empty()
+	}
+
+	fn size_hint(&self) -> SizeHint {
+		SizeHint::with_exact(self.chunks.iter().map(|x| x.len() as u64).sum())
+	}
+}
+
+/// Keep only the bytes in `range` of the body made of `chunks`.
+/// Chunks are sliced, never copied.
+pub(crate) fn slice_chunks(chunks: Vec<Bytes>, range: Range<usize>) -> Vec<Bytes> {
+	let mut out = Vec::new();
+	let mut offset = 0;
+
+	for chunk in chunks {
+		let start = range.start.max(offset);
+		let end = range.end.min(offset + chunk.len());
+		if start < end {
+			out.push(chunk.slice(start - offset..end - offset));
+		}
+
+		offset += chunk.len();
+		if offset >= range.end {
+			break;
+		}
+	}
+
+	out
+}
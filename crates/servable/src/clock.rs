@@ -0,0 +1,106 @@
+use chrono::{DateTime, Utc};
+use std::{
+	fmt::Debug,
+	sync::{Arc, Mutex},
+	time::{Duration, Instant},
+};
+
+/// A source of time. See [crate::ServableRouter::with_clock].
+///
+/// Servables should read the time from [crate::RenderContext::clock]
+/// instead of the system, so that tests can control it with a [TestClock].
+pub trait Clock: Debug + Send + Sync {
+	/// The current date and time
+	fn now(&self) -> DateTime<Utc>;
+
+	/// A monotonic timestamp, used to measure durations
+	fn instant(&self) -> Instant;
+}
+
+impl<C: Clock + ?Sized> Clock for Arc<C> {
+	#[inline(always)]
+	fn now(&self) -> DateTime<Utc> {
+		(**self).now()
+	}
+
+	#[inline(always)]
+	fn instant(&self) -> Instant {
+		(**self).instant()
+	}
+}
+
+/// The system clock. This is the default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+	#[inline(always)]
+	fn now(&self) -> DateTime<Utc> {
+		Utc::now()
+	}
+
+	#[inline(always)]
+	fn instant(&self) -> Instant {
+		Instant::now()
+	}
+}
+
+/// A clock that only moves when it is told to.
+///
+/// ```rust
+/// use servable::{Clock, ServableRouter, TestClock};
+/// use chrono::DateTime;
+/// use std::{sync::Arc, time::Duration};
+///
+/// let clock = Arc::new(TestClock::new(DateTime::UNIX_EPOCH));
+/// let router = ServableRouter::new().with_clock(clock.clone());
+///
+/// clock.advance(Duration::from_secs(60));
+/// assert_eq!(clock.now().timestamp(), 60);
+/// ```
+#[derive(Debug)]
+pub struct TestClock {
+	/// The value of [Clock::now] when this clock was created
+	start_time: DateTime<Utc>,
+
+	/// The value of [Clock::instant] when this clock was created
+	start_instant: Instant,
+
+	/// How far this clock has been advanced
+	offset: Mutex<Duration>,
+}
+
+impl TestClock {
+	/// Create a new [TestClock] that starts at `now`
+	pub fn new(now: DateTime<Utc>) -> Self {
+		Self {
+			start_time: now,
+			start_instant: Instant::now(),
+			offset: Mutex::new(Duration::ZERO),
+		}
+	}
+
+	/// Move this clock forward
+	pub fn advance(&self, by: Duration) {
+		if let Ok(mut offset) = self.offset.lock() {
+			*offset += by;
+		}
+	}
+
+	fn offset(&self) -> Duration {
+		self.offset.lock().map(|x| *x).unwrap_or_default()
+	}
+}
+
+impl Clock for TestClock {
+	fn now(&self) -> DateTime<Utc> {
+		let offset = chrono::TimeDelta::from_std(self.offset()).unwrap_or(chrono::TimeDelta::MAX);
+		self.start_time
+			.checked_add_signed(offset)
+			.unwrap_or(DateTime::<Utc>::MAX_UTC)
+	}
+
+	fn instant(&self) -> Instant {
+		self.start_instant + self.offset()
+	}
+}
@@ -0,0 +1,282 @@
+use axum::{
+	body::Bytes,
+	http::{HeaderMap, HeaderValue, header},
+};
+use std::{
+	collections::{HashMap, VecDeque},
+	io::Write,
+	sync::{LazyLock, Mutex},
+};
+
+use crate::encoding::ContentEncoding;
+
+/// Bodies larger than this are compressed on a blocking thread
+const BLOCKING_SIZE: usize = 64 * 1024;
+
+/// The maximum total size of all bodies in [CACHE], in bytes
+const CACHE_SIZE: usize = 16 * 1024 * 1024;
+
+/// Compressed static bodies, by `(address, length, encoding)` of the static body
+static CACHE: LazyLock<Mutex<CompressionCache>> = LazyLock::new(Mutex::default);
+
+#[derive(Default)]
+struct CompressionCache {
+	bodies: HashMap<(usize, usize, ContentEncoding), Bytes>,
+
+	/// Keys in `bodies`, oldest first
+	order: VecDeque<(usize, usize, ContentEncoding)>,
+
+	/// The total size of all bodies in `bodies`
+	size: usize,
+}
+
+/// Compress response bodies in the router,
+/// see [crate::ServableRouter::with_compression].
+///
+/// Bodies are compressed with brotli, zstd or gzip,
+/// whichever the client prefers according to its `Accept-Encoding` header.
+/// Responses that already have a `Content-Encoding` (like precompressed
+/// [crate::StaticAsset]s) are never compressed again.
+///
+/// Compressed responses don't support range requests.
+/// Static bodies (like [crate::StaticAsset]s) are compressed once and cached,
+/// large bodies are compressed on a blocking thread.
+/// Responses to `HEAD` requests get the same `Content-Encoding` as `GET` responses,
+/// assuming that compression makes their body smaller.
+///
+/// ```rust
+/// use servable::{Compression, HtmlPage, ServableRouter};
+///
+/// let router = ServableRouter::new()
+/// 	.add_page("/", HtmlPage::default())
+/// 	.with_compression(Compression::default().with_min_size(1024));
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Compression {
+	/// Bodies smaller than this many bytes are sent uncompressed
+	pub min_size: usize,
+
+	/// Returns true if bodies with the given `Content-Type` should be compressed.
+	/// The default is [Compression::is_compressible].
+	pub predicate: fn(&str) -> bool,
+}
+
+impl Default for Compression {
+	fn default() -> Self {
+		Self {
+			min_size: 256,
+			predicate: Self::is_compressible,
+		}
+	}
+}
+
+impl Compression {
+	/// The encodings we compress with, in order of preference
+	const ENCODINGS: &[ContentEncoding] = &[
+		ContentEncoding::Brotli,
+		ContentEncoding::Zstd,
+		ContentEncoding::Gzip,
+	];
+
+	/// Set `self.min_size`
+	#[inline(always)]
+	pub fn with_min_size(mut self, min_size: usize) -> Self {
+		self.min_size = min_size;
+		self
+	}
+
+	/// Set `self.predicate`
+	#[inline(always)]
+	pub fn with_predicate(mut self, predicate: fn(&str) -> bool) -> Self {
+		self.predicate = predicate;
+		self
+	}
+
+	/// The default [Compression::predicate].
+	///
+	/// Returns true for text, json, javascript, xml, svg and wasm.
+	/// Returns false for everything else, including images, audio, video, fonts
+	/// and archives, which are usually compressed already.
+	pub fn is_compressible(content_type: &str) -> bool {
+		let mime = content_type
+			.split(';')
+			.next()
+			.unwrap_or("")
+			.trim()
+			.to_ascii_lowercase();
+
+		mime.starts_with("text/")
+			|| mime.ends_with("+json")
+			|| mime.ends_with("+xml")
+			|| matches!(
+				mime.as_str(),
+				"application/json"
+					| "application/javascript"
+					| "application/xml"
+					| "application/wasm"
+					| "image/svg+xml"
+			)
+	}
+
+	/// The encoding a response of `len` bytes should be compressed with, if any.
+	/// `request` is the request's headers, `response` is the response's headers.
+	///
+	/// This adds `Vary: accept-encoding` to `response` if it may be compressed.
+	fn negotiate(
+		&self,
+		request: &HeaderMap,
+		response: &mut HeaderMap,
+		len: usize,
+	) -> Option<ContentEncoding> {
+		if len < self.min_size || response.contains_key(header::CONTENT_ENCODING) {
+			return None;
+		}
+
+		let content_type = response
+			.get(header::CONTENT_TYPE)
+			.and_then(|x| x.to_str().ok())
+			.unwrap_or("");
+		if !(self.predicate)(content_type) {
+			return None;
+		}
+
+		// Responses depend on `Accept-Encoding` even if this client doesn't get a compressed body
+		let varies = response
+			.get_all(header::VARY)
+			.iter()
+			.filter_map(|x| x.to_str().ok())
+			.flat_map(|x| x.split(','))
+			.any(|x| x.trim().eq_ignore_ascii_case("accept-encoding"));
+		if !varies {
+			response.append(header::VARY, HeaderValue::from_static("accept-encoding"));
+		}
+
+		ContentEncoding::negotiate(request.get(header::ACCEPT_ENCODING), Self::ENCODINGS)
+	}
+
+	/// Update `response` to describe a body compressed with `encoding`
+	fn mark(response: &mut HeaderMap, encoding: ContentEncoding) {
+		response.insert(header::CONTENT_ENCODING, encoding.header_value());
+		response.remove(header::CONTENT_LENGTH);
+		response.remove(header::ACCEPT_RANGES);
+
+		// A compressed body is a different representation, and needs a different etag
+		if let Some(etag) = response.get(header::ETAG).and_then(|x| x.to_str().ok())
+			&& let Some(tag) = etag.strip_suffix('"')
+			&& let Ok(x) = HeaderValue::from_str(&format!("{tag}-{}\"", encoding.name()))
+		{
+			response.insert(header::ETAG, x);
+		}
+	}
+
+	/// Compress `bytes` if the client accepts a compressed response.
+	/// `request` is the request's headers, `response` is the response's headers.
+	/// `bytes` is only cached if it is `'static`, see [Bytes::from_static].
+	///
+	/// If the body is compressed, `response` is updated to match.
+	pub(crate) async fn apply(
+		&self,
+		request: &HeaderMap,
+		response: &mut HeaderMap,
+		bytes: Bytes,
+		is_static: bool,
+	) -> Bytes {
+		let Some(encoding) = self.negotiate(request, response, bytes.len()) else {
+			return bytes;
+		};
+
+		let key = (bytes.as_ptr() as usize, bytes.len(), encoding);
+		let cached = match is_static {
+			true => CACHE
+				.lock()
+				.ok()
+				.and_then(|x| x.bodies.get(&key).cloned()),
+			false => None,
+		};
+
+		let compressed = match cached {
+			Some(x) => x,
+			None => {
+				let compressed = match bytes.len() > BLOCKING_SIZE {
+					false => compress(encoding, &bytes),
+					true => {
+						let bytes = bytes.clone();
+						tokio::task::spawn_blocking(move || compress(encoding, &bytes))
+							.await
+							.unwrap_or_else(|err| Err(std::io::Error::other(err)))
+					}
+				};
+
+				let Ok(compressed) = compressed.map(Bytes::from) else {
+					return bytes;
+				};
+
+				if is_static {
+					cache(key, compressed.clone());
+				}
+				compressed
+			}
+		};
+
+		if compressed.len() >= bytes.len() {
+			return bytes;
+		}
+
+		Self::mark(response, encoding);
+		compressed
+	}
+
+	/// Update the headers of a response to a `HEAD` request to match the `GET` response.
+	/// `len` is the length of the uncompressed body, if it is known.
+	pub(crate) fn apply_head(&self, request: &HeaderMap, response: &mut HeaderMap, len: Option<u64>) {
+		let len = len.map(|x| x as usize).unwrap_or(usize::MAX);
+		if let Some(encoding) = self.negotiate(request, response, len) {
+			Self::mark(response, encoding);
+		}
+	}
+}
+
+/// Remember the compressed static body at `key`.
+/// Bodies larger than the cache are ignored.
+fn cache(key: (usize, usize, ContentEncoding), bytes: Bytes) {
+	let len = bytes.len();
+	let Ok(mut cache) = CACHE.lock() else {
+		return;
+	};
+	if len > CACHE_SIZE || cache.bodies.contains_key(&key) {
+		return;
+	}
+
+	while cache.size + len > CACHE_SIZE {
+		let Some(old) = cache.order.pop_front() else {
+			break;
+		};
+		if let Some(old) = cache.bodies.remove(&old) {
+			cache.size -= old.len();
+		}
+	}
+
+	cache.size += len;
+	cache.order.push_back(key);
+	cache.bodies.insert(key, bytes);
+}
+
+/// Compress `bytes` with `encoding`
+fn compress(encoding: ContentEncoding, bytes: &[u8]) -> std::io::Result<Vec<u8>> {
+	match encoding {
+		ContentEncoding::Brotli => {
+			let mut writer = brotli::CompressorWriter::new(Vec::new(), 4096, 5, 22);
+			writer.write_all(bytes)?;
+			Ok(writer.into_inner())
+		}
+
+		ContentEncoding::Gzip => {
+			let mut writer =
+				flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+			writer.write_all(bytes)?;
+			writer.finish()
+		}
+
+		ContentEncoding::Zstd => zstd::bulk::compress(bytes, 3),
+	}
+}
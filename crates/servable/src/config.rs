@@ -0,0 +1,360 @@
+//! Build a [ServableRouter] from a config file,
+//! so routing can be changed without recompiling.
+//!
+//! A config can set router options, security headers,
+//! directories served from disk, and redirects:
+//!
+//! ```toml
+//! base_url = "https://example.com"
+//! timeout = 30                 # seconds
+//! trusted_proxies = ["10.0.0.0/8"]
+//!
+//! [security_headers]
+//! preset = "default"           # or "none"
+//! frame_options = "deny"       # "sameorigin", "deny", or "none"
+//! hsts = 31536000              # seconds, 0 to disable
+//!
+//! [[dirs]]
+//! route = "/static"
+//! path = "./static"
+//! ttl = 3600                   # seconds
+//! ttls = { woff2 = 31536000, html = 0 }
+//!
+//! [[redirects]]
+//! from = "/old"
+//! to = "/new"
+//! permanent = false            # 307 instead of 308
+//! ```
+//!
+//! Pages defined in Rust are added as usual, before or after the config:
+//!
+//! ```rust
+//! use servable::{HtmlPage, ServableRouter, config::RouterConfig};
+//!
+//! let config = RouterConfig::from_toml(r#"
+//! [[redirects]]
+//! from = "/home"
+//! to = "/"
+//! "#).unwrap();
+//!
+//! let router = ServableRouter::new()
+//! 	.add_page("/", HtmlPage::default())
+//! 	.with_config(&config);
+//! ```
+//!
+//! To reload a config while the server is running,
+//! build a new router and install it with [crate::SwappableRouter::swap].
+
+use chrono::TimeDelta;
+use serde::Deserialize;
+use std::{collections::HashMap, path::Path, time::Duration};
+
+use crate::{DirServable, Error, FrameOptions, Redirect, SecurityHeaders, ServableRouter};
+
+/// A declarative [ServableRouter] config,
+/// see the [module docs](self) for its format.
+///
+/// Durations and ttls are in seconds.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RouterConfig {
+	/// See [ServableRouter::with_base_path]
+	#[serde(default)]
+	pub base_path: Option<String>,
+
+	/// See [ServableRouter::with_base_url]
+	#[serde(default)]
+	pub base_url: Option<String>,
+
+	/// See [ServableRouter::with_timeout]
+	#[serde(default)]
+	pub timeout: Option<u64>,
+
+	/// See [ServableRouter::with_trusted_proxy]
+	#[serde(default)]
+	pub trusted_proxies: Vec<String>,
+
+	/// See [ServableRouter::with_security_headers]
+	#[serde(default)]
+	pub security_headers: Option<SecurityConfig>,
+
+	/// Directories served from disk
+	#[serde(default)]
+	pub dirs: Vec<DirConfig>,
+
+	/// Redirects
+	#[serde(default)]
+	pub redirects: Vec<RedirectConfig>,
+}
+
+/// The set of [SecurityHeaders] a [SecurityConfig] starts from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SecurityPreset {
+	/// [SecurityHeaders::default]
+	#[default]
+	Default,
+
+	/// [SecurityHeaders::none]
+	None,
+}
+
+/// Possible values of [SecurityConfig::frame_options]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FrameOptionsConfig {
+	/// [FrameOptions::Deny]
+	Deny,
+
+	/// [FrameOptions::SameOrigin]
+	SameOrigin,
+
+	/// Do not send `X-Frame-Options`
+	None,
+}
+
+/// The [SecurityHeaders] of a [RouterConfig].
+/// Options that are not set keep the value of `preset`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SecurityConfig {
+	/// The headers to start from
+	#[serde(default)]
+	pub preset: SecurityPreset,
+
+	/// See [SecurityHeaders::nosniff]
+	#[serde(default)]
+	pub nosniff: Option<bool>,
+
+	/// See [SecurityHeaders::referrer_policy].
+	/// An empty string disables this header.
+	#[serde(default)]
+	pub referrer_policy: Option<String>,
+
+	/// See [SecurityHeaders::frame_options]
+	#[serde(default)]
+	pub frame_options: Option<FrameOptionsConfig>,
+
+	/// See [SecurityHeaders::hsts].
+	/// `0` disables this header.
+	#[serde(default)]
+	pub hsts: Option<u64>,
+
+	/// See [SecurityHeaders::hsts_include_subdomains]
+	#[serde(default)]
+	pub hsts_include_subdomains: Option<bool>,
+}
+
+/// A directory served from disk with a [DirServable]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DirConfig {
+	/// The route to serve this directory at, like `/static`
+	pub route: String,
+
+	/// The directory to serve
+	pub path: String,
+
+	/// See [DirServable::with_index].
+	/// An empty string disables index files.
+	#[serde(default)]
+	pub index: Option<String>,
+
+	/// See [DirServable::with_hidden]
+	#[serde(default)]
+	pub hidden: bool,
+
+	/// See [DirServable::with_ttl]
+	#[serde(default)]
+	pub ttl: Option<u64>,
+
+	/// See [DirServable::with_ttl_for]
+	#[serde(default)]
+	pub ttls: HashMap<String, u64>,
+}
+
+/// A [Redirect] from one route to another
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RedirectConfig {
+	/// The route to redirect
+	pub from: String,
+
+	/// Where to redirect to
+	pub to: String,
+
+	/// If true (the default), this is a permanent redirect (308).
+	/// Otherwise, it is a temporary redirect (307).
+	#[serde(default = "yes")]
+	pub permanent: bool,
+}
+
+fn yes() -> bool {
+	true
+}
+
+impl RouterConfig {
+	/// Parse a config in TOML
+	pub fn from_toml(config: &str) -> Result<Self, Error> {
+		toml::from_str(config).map_err(|err| Error::InvalidConfig(err.to_string()))
+	}
+
+	/// Parse a config in YAML
+	#[cfg(feature = "config-yaml")]
+	pub fn from_yaml(config: &str) -> Result<Self, Error> {
+		serde_yaml::from_str(config).map_err(|err| Error::InvalidConfig(err.to_string()))
+	}
+
+	/// Read a config file.
+	/// Its format is inferred from its extension (`.toml`, or `.yaml` with `config-yaml`).
+	pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+		let path = path.as_ref();
+		let config = std::fs::read_to_string(path)?;
+
+		match path.extension().and_then(|x| x.to_str()) {
+			Some("toml") => Self::from_toml(&config),
+
+			#[cfg(feature = "config-yaml")]
+			Some("yaml" | "yml") => Self::from_yaml(&config),
+
+			_ => Err(Error::InvalidConfig(format!(
+				"unknown config format `{}`",
+				path.display()
+			))),
+		}
+	}
+}
+
+impl SecurityConfig {
+	/// The headers this config describes
+	pub fn headers(&self) -> Result<SecurityHeaders, Error> {
+		let mut headers = match self.preset {
+			SecurityPreset::Default => SecurityHeaders::default(),
+			SecurityPreset::None => SecurityHeaders::none(),
+		};
+
+		if let Some(nosniff) = self.nosniff {
+			headers.nosniff = nosniff;
+		}
+
+		match self.referrer_policy.as_deref() {
+			None => {}
+			Some("") => headers.referrer_policy = None,
+			Some(x) => {
+				let invalid = |_err| Error::InvalidConfig(format!("invalid referrer policy `{x}`"));
+				headers.referrer_policy = Some(x.parse().map_err(invalid)?);
+			}
+		}
+
+		headers.frame_options = match self.frame_options {
+			None => headers.frame_options,
+			Some(FrameOptionsConfig::Deny) => Some(FrameOptions::Deny),
+			Some(FrameOptionsConfig::SameOrigin) => Some(FrameOptions::SameOrigin),
+			Some(FrameOptionsConfig::None) => None,
+		};
+
+		headers.hsts = match self.hsts {
+			None => headers.hsts,
+			Some(0) => None,
+			Some(x) => Some(seconds(x)?),
+		};
+
+		if let Some(x) = self.hsts_include_subdomains {
+			headers.hsts_include_subdomains = x;
+		}
+
+		Ok(headers)
+	}
+}
+
+impl DirConfig {
+	/// The servable this config describes
+	pub fn servable(&self) -> Result<DirServable, Error> {
+		let mut dir = DirServable::new(&self.path).with_hidden(self.hidden);
+
+		if let Some(index) = &self.index {
+			dir = dir.with_index(Some(index.clone()).filter(|x| !x.is_empty()));
+		}
+
+		if let Some(ttl) = self.ttl {
+			dir = dir.with_ttl(Some(seconds(ttl)?));
+		}
+
+		for (extension, ttl) in &self.ttls {
+			dir = dir.with_ttl_for(extension, Some(seconds(*ttl)?));
+		}
+
+		Ok(dir)
+	}
+}
+
+impl RedirectConfig {
+	/// The servable this config describes
+	pub fn servable(&self) -> Result<Redirect, Error> {
+		let redirect = match self.permanent {
+			true => Redirect::new(&self.to),
+			false => Redirect::new_307(&self.to),
+		};
+
+		redirect.map_err(|_err| Error::InvalidConfig(format!("invalid redirect to `{}`", self.to)))
+	}
+}
+
+/// A ttl of `x` seconds
+fn seconds(x: u64) -> Result<TimeDelta, Error> {
+	i64::try_from(x)
+		.ok()
+		.and_then(TimeDelta::try_seconds)
+		.ok_or_else(|| Error::InvalidConfig(format!("duration `{x}` is too long")))
+}
+
+impl ServableRouter {
+	/// Apply `config` to this router, adding its pages.
+	/// - panics if `config` is invalid
+	/// - panics if one of its routes is invalid or already taken, like [Self::add_page].
+	///
+	/// See [Self::try_with_config] for a version of this method that does not panic.
+	pub fn with_config(self, config: &RouterConfig) -> Self {
+		match self.try_with_config(config) {
+			Ok(x) => x,
+			Err(err) => panic!("{err}"),
+		}
+	}
+
+	/// Apply `config` to this router, adding its pages.
+	/// Behaves exactly like [Self::with_config], but returns an [Error] instead of panicking.
+	pub fn try_with_config(mut self, config: &RouterConfig) -> Result<Self, Error> {
+		if let Some(base_path) = &config.base_path {
+			self = self.try_with_base_path(base_path)?;
+		}
+
+		if let Some(base_url) = &config.base_url {
+			self = self.try_with_base_url(base_url)?;
+		}
+
+		if let Some(timeout) = config.timeout {
+			self = self.with_timeout(Some(Duration::from_secs(timeout)));
+		}
+
+		for cidr in &config.trusted_proxies {
+			self = self.try_with_trusted_proxy(cidr)?;
+		}
+
+		if let Some(security) = &config.security_headers {
+			self = self.with_security_headers(security.headers()?);
+		}
+
+		for dir in &config.dirs {
+			let servable = dir.servable()?;
+			let route = dir.route.trim_end_matches('/');
+			self = self.try_add_page(format!("{route}/{{*path}}"), servable.clone())?;
+			self = self.try_add_page(if route.is_empty() { "/" } else { route }, servable)?;
+		}
+
+		for redirect in &config.redirects {
+			self = self.try_add_page(&redirect.from, redirect.servable()?)?;
+		}
+
+		Ok(self)
+	}
+}
@@ -0,0 +1,101 @@
+use axum::http::{HeaderMap, HeaderValue, header};
+use std::pin::Pin;
+
+use crate::{
+	DeviceType, RenderContext, Rendered, RenderedBody, RenderedBodyType, router::merge_template,
+	servable::Servable,
+};
+
+/// A [Servable] that serves different content to mobile and desktop clients,
+/// as detected by [crate::ClientInfo::device_type].
+///
+/// Every response has `Vary: Sec-CH-UA-Mobile, User-Agent`,
+/// so caches keep one copy of this page for each kind of device.
+///
+/// ```rust
+/// use servable::{DeviceSwitch, HtmlPage, ServableRouter};
+/// use maud::html;
+///
+/// let mobile = HtmlPage::default().with_render(|_page, _ctx| Box::pin(async { html! { "Small" } }));
+/// let desktop = HtmlPage::default().with_render(|_page, _ctx| Box::pin(async { html! { "Large" } }));
+///
+/// let router = ServableRouter::new().add_page("/", DeviceSwitch::new(mobile, desktop));
+/// ```
+pub struct DeviceSwitch<M: Servable, D: Servable> {
+	mobile: M,
+	desktop: D,
+
+	/// [Servable::header_template]s of `mobile` and `desktop`
+	templates: (HeaderMap, HeaderMap),
+}
+
+impl<M: Servable, D: Servable> DeviceSwitch<M, D> {
+	/// Serve `mobile` to mobile clients, and `desktop` to everyone else
+	pub fn new(mobile: M, desktop: D) -> Self {
+		let templates = (mobile.header_template(), desktop.header_template());
+		Self {
+			mobile,
+			desktop,
+			templates,
+		}
+	}
+
+	/// Add `Vary` and the chosen servable's header template to `rend`
+	fn finish<T: RenderedBodyType>(
+		&self,
+		device: DeviceType,
+		mut rend: Rendered<T>,
+	) -> Rendered<T> {
+		rend.headers.append(
+			header::VARY,
+			HeaderValue::from_static("Sec-CH-UA-Mobile, User-Agent"),
+		);
+
+		let template = match device {
+			DeviceType::Mobile => &self.templates.0,
+			DeviceType::Desktop => &self.templates.1,
+		};
+
+		if rend.code.is_success() {
+			merge_template(&mut rend.headers, template);
+		}
+
+		rend
+	}
+}
+
+impl<M: Servable, D: Servable> Servable for DeviceSwitch<M, D> {
+	fn head<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<()>> + 'a + Send + Sync>> {
+		Box::pin(async move {
+			let device = ctx.client_info.device_type;
+			let rend = match device {
+				DeviceType::Mobile => self.mobile.head(ctx).await,
+				DeviceType::Desktop => self.desktop.head(ctx).await,
+			};
+			self.finish(device, rend)
+		})
+	}
+
+	fn render<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<RenderedBody>> + 'a + Send + Sync>> {
+		Box::pin(async move {
+			let device = ctx.client_info.device_type;
+			let rend = match device {
+				DeviceType::Mobile => self.mobile.render(ctx).await,
+				DeviceType::Desktop => self.desktop.render(ctx).await,
+			};
+			self.finish(device, rend)
+		})
+	}
+
+	fn linked_routes(&self) -> Vec<String> {
+		let mut routes = self.mobile.linked_routes();
+		routes.extend(self.desktop.linked_routes());
+		routes
+	}
+}
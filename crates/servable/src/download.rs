@@ -0,0 +1,133 @@
+use axum::http::{HeaderMap, HeaderValue, header};
+use mime::Mime;
+use std::pin::Pin;
+
+use crate::{
+	RenderContext, Rendered, RenderedBody, RenderedBodyType, StaticAsset, servable::Servable,
+};
+
+/// A [Servable] that browsers save as a file instead of showing inline,
+/// with a `Content-Disposition: attachment` header.
+///
+/// The file is named after the last segment of the requested route,
+/// or [Download::with_filename]. If that name has no extension,
+/// one is added from the type of the response (see [StaticAsset::extension_from_mime]).
+///
+/// Responses that already have a `Content-Disposition` header,
+/// and responses that aren't successful (2xx), are not changed.
+///
+/// ```rust
+/// use servable::{Download, ServableRouter, StaticAsset};
+///
+/// let report = StaticAsset {
+/// 	bytes: b"fake pdf",
+/// 	br: None,
+/// 	gz: None,
+/// 	mime: mime::APPLICATION_PDF,
+/// 	ttl: StaticAsset::DEFAULT_TTL,
+/// };
+///
+/// // Saved as `report-2024.pdf`
+/// let router = ServableRouter::new()
+/// 	.add_page("/files/report", Download::new(report).with_filename("report-2024"));
+/// ```
+pub struct Download<S: Servable> {
+	inner: S,
+
+	/// The name browsers save this file as.
+	/// If `None`, the last segment of the requested route is used.
+	pub filename: Option<String>,
+}
+
+impl<S: Servable> Download<S> {
+	/// Make browsers download `inner`
+	pub fn new(inner: S) -> Self {
+		Self {
+			inner,
+			filename: None,
+		}
+	}
+
+	/// Set `self.filename`
+	#[inline(always)]
+	pub fn with_filename(mut self, filename: impl Into<String>) -> Self {
+		self.filename = Some(filename.into());
+		self
+	}
+
+	/// The name a response of type `mime` to `ctx` is saved as
+	fn filename(&self, ctx: &RenderContext, mime: Option<&Mime>) -> String {
+		let name = match &self.filename {
+			Some(x) => x.as_str(),
+			None => ctx
+				.route
+				.trim_end_matches('/')
+				.rsplit('/')
+				.next()
+				.filter(|x| !x.is_empty())
+				.unwrap_or("download"),
+		};
+
+		match mime.and_then(StaticAsset::extension_from_mime) {
+			Some(extension) if !name.contains('.') => format!("{name}.{extension}"),
+			_ => name.to_owned(),
+		}
+	}
+
+	/// Add `Content-Disposition` to a response
+	fn attach<T: RenderedBodyType>(
+		&self,
+		ctx: &RenderContext,
+		mut rend: Rendered<T>,
+	) -> Rendered<T> {
+		if rend.code.is_success() && !rend.headers.contains_key(header::CONTENT_DISPOSITION) {
+			let filename = self.filename(ctx, rend.mime.as_ref());
+			rend.headers
+				.insert(header::CONTENT_DISPOSITION, attachment(&filename));
+		}
+		rend
+	}
+}
+
+impl<S: Servable> Servable for Download<S> {
+	fn head<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<()>> + 'a + Send + Sync>> {
+		Box::pin(async { self.attach(ctx, self.inner.head(ctx).await) })
+	}
+
+	fn render<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<RenderedBody>> + 'a + Send + Sync>> {
+		Box::pin(async { self.attach(ctx, self.inner.render(ctx).await) })
+	}
+
+	#[inline(always)]
+	fn header_template(&self) -> HeaderMap {
+		self.inner.header_template()
+	}
+
+	#[inline(always)]
+	fn linked_routes(&self) -> Vec<String> {
+		self.inner.linked_routes()
+	}
+
+	#[inline(always)]
+	fn preloads(&self) -> Vec<crate::Preload> {
+		self.inner.preloads()
+	}
+}
+
+/// The value of a `Content-Disposition` header that saves a response as `filename`.
+/// Characters that can't appear in a quoted ascii filename are removed.
+pub(crate) fn attachment(filename: &str) -> HeaderValue {
+	let filename: String = filename
+		.chars()
+		.filter(|x| x.is_ascii() && !x.is_ascii_control() && *x != '"' && *x != '\\')
+		.collect();
+
+	HeaderValue::from_str(&format!("attachment; filename=\"{filename}\""))
+		.unwrap_or(HeaderValue::from_static("attachment"))
+}
@@ -0,0 +1,314 @@
+use axum::http::{HeaderValue, Uri, header};
+use maud::{Markup, html};
+
+use crate::{
+	HtmlRewriter, RenderContext, Rendered,
+	scan::{attribute, rewrite_tags},
+};
+
+/// A third-party page shown in a sandboxed `<iframe>`, like a video or a map.
+///
+/// The constructors for well-known providers use their privacy-enhanced urls
+/// and the permissions their players need. Every embed is lazy-loaded.
+/// Use [EmbedCsp] to allow embeds in the page's `Content-Security-Policy`.
+///
+/// ```rust
+/// use servable::Embed;
+///
+/// let video = Embed::youtube("dQw4w9WgXcQ").with_title("Our launch video");
+/// assert_eq!(video.url(), "https://www.youtube-nocookie.com/embed/dQw4w9WgXcQ");
+/// assert_eq!(video.origin().as_deref(), Some("https://www.youtube-nocookie.com"));
+///
+/// let html = video.iframe().into_string();
+/// assert!(html.contains("loading=\"lazy\""));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Embed {
+	url: String,
+
+	/// Describes this embed to screen readers
+	pub title: String,
+
+	/// The value of `allow`, see [Self::with_allow]
+	pub allow: Option<String>,
+
+	/// The value of `sandbox`, see [Self::with_sandbox]
+	pub sandbox: Option<String>,
+
+	/// The value of `referrerpolicy`
+	pub referrer_policy: &'static str,
+
+	/// If true, this embed may go fullscreen
+	pub fullscreen: bool,
+
+	/// The `aspect-ratio` of this embed, like `16/9`.
+	/// Embeds are as wide as their container.
+	pub aspect_ratio: String,
+}
+
+impl Embed {
+	/// Embed the page at `url`.
+	/// By default, the page may only run scripts.
+	pub fn new(url: impl Into<String>) -> Self {
+		Self {
+			url: url.into(),
+			title: "Embedded content".into(),
+			allow: None,
+			sandbox: Some("allow-scripts".into()),
+			referrer_policy: "strict-origin-when-cross-origin",
+			fullscreen: false,
+			aspect_ratio: "16/9".into(),
+		}
+	}
+
+	/// Embed the YouTube video with the given id,
+	/// without tracking cookies (`youtube-nocookie.com`)
+	pub fn youtube(id: &str) -> Self {
+		Self {
+			title: "YouTube video".into(),
+			allow: Some(
+				"accelerometer; autoplay; clipboard-write; encrypted-media; gyroscope; picture-in-picture; web-share"
+					.into(),
+			),
+			sandbox: Some(
+				"allow-scripts allow-same-origin allow-presentation allow-popups allow-popups-to-escape-sandbox"
+					.into(),
+			),
+			fullscreen: true,
+			..Self::new(format!("https://www.youtube-nocookie.com/embed/{}", encode(id)))
+		}
+	}
+
+	/// Embed the Vimeo video with the given id, with tracking disabled (`dnt=1`)
+	pub fn vimeo(id: &str) -> Self {
+		Self {
+			title: "Vimeo video".into(),
+			allow: Some("autoplay; fullscreen; picture-in-picture".into()),
+			sandbox: Some(
+				"allow-scripts allow-same-origin allow-presentation allow-popups allow-popups-to-escape-sandbox"
+					.into(),
+			),
+			fullscreen: true,
+			..Self::new(format!("https://player.vimeo.com/video/{}?dnt=1", encode(id)))
+		}
+	}
+
+	/// Embed an OpenStreetMap map of the area between the given coordinates
+	pub fn openstreetmap(min_lon: f64, min_lat: f64, max_lon: f64, max_lat: f64) -> Self {
+		Self {
+			title: "Map".into(),
+			sandbox: Some("allow-scripts allow-same-origin allow-popups".into()),
+			aspect_ratio: "4/3".into(),
+			..Self::new(format!(
+				"https://www.openstreetmap.org/export/embed.html?bbox={min_lon}%2C{min_lat}%2C{max_lon}%2C{max_lat}&layer=mapnik"
+			))
+		}
+	}
+
+	/// Embed a Google Maps map of the result of searching for `query`, like an address
+	pub fn google_maps(query: &str) -> Self {
+		Self {
+			title: "Map".into(),
+			sandbox: Some(
+				"allow-scripts allow-same-origin allow-popups allow-popups-to-escape-sandbox"
+					.into(),
+			),
+			referrer_policy: "no-referrer-when-downgrade",
+			fullscreen: true,
+			aspect_ratio: "4/3".into(),
+			..Self::new(format!(
+				"https://www.google.com/maps?q={}&output=embed",
+				encode(query)
+			))
+		}
+	}
+
+	/// Set `self.title`
+	#[inline(always)]
+	pub fn with_title(mut self, title: impl Into<String>) -> Self {
+		self.title = title.into();
+		self
+	}
+
+	/// Set the features this embed may use, like `autoplay; encrypted-media`.
+	/// If `None`, no `allow` attribute is sent.
+	#[inline(always)]
+	pub fn with_allow(mut self, allow: Option<String>) -> Self {
+		self.allow = allow;
+		self
+	}
+
+	/// Set the restrictions lifted from this embed, like `allow-scripts allow-popups`.
+	/// If `None`, this embed is not sandboxed.
+	#[inline(always)]
+	pub fn with_sandbox(mut self, sandbox: Option<String>) -> Self {
+		self.sandbox = sandbox;
+		self
+	}
+
+	/// Set `self.aspect_ratio`
+	#[inline(always)]
+	pub fn with_aspect_ratio(mut self, aspect_ratio: impl Into<String>) -> Self {
+		self.aspect_ratio = aspect_ratio.into();
+		self
+	}
+
+	/// The url of the embedded page
+	pub fn url(&self) -> &str {
+		&self.url
+	}
+
+	/// The origin of the embedded page, like `https://player.vimeo.com`.
+	/// This is the source that must be allowed by `frame-src`.
+	pub fn origin(&self) -> Option<String> {
+		origin(&self.url)
+	}
+
+	/// An `<iframe>` that shows this embed
+	pub fn iframe(&self) -> Markup {
+		html! {
+			iframe
+				src=(self.url)
+				title=(self.title)
+				loading="lazy"
+				allow=[self.allow.as_deref()]
+				sandbox=[self.sandbox.as_deref()]
+				referrerpolicy=(self.referrer_policy)
+				allowfullscreen[self.fullscreen]
+				style={ "display:block;border:0;width:100%;aspect-ratio:" (self.aspect_ratio) }
+			{}
+		}
+	}
+}
+
+/// Percent-encode everything but unreserved characters
+fn encode(s: &str) -> String {
+	let mut out = String::with_capacity(s.len());
+	for b in s.bytes() {
+		match b {
+			b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+				out.push(b as char)
+			}
+			_ => out.push_str(&format!("%{b:02X}")),
+		}
+	}
+	out
+}
+
+/// The origin of an absolute `url`
+fn origin(url: &str) -> Option<String> {
+	let uri: Uri = url.parse().ok()?;
+	Some(format!("{}://{}", uri.scheme_str()?, uri.authority()?))
+}
+
+/// An [HtmlRewriter] that allows every `<iframe>` in a page
+/// in the `frame-src` directive of its `Content-Security-Policy`,
+/// so that policies and embeds (see [Embed]) never go out of sync.
+///
+/// Only policies that restrict frames are changed:
+/// origins are appended to `frame-src`, or to a new `frame-src`
+/// copied from `child-src` or `default-src` if there is none.
+/// Policies that allow every frame are left alone.
+///
+/// This sees policies sent by servables, their [crate::servable::Servable::header_template]s,
+/// and earlier rewriters, but not policies added by layers outside the router.
+///
+/// ```rust
+/// use servable::{EmbedCsp, HtmlPage, ServableRouter};
+///
+/// let router = ServableRouter::new()
+/// 	.add_page("/", HtmlPage::default())
+/// 	.with_html_rewriter(EmbedCsp);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct EmbedCsp;
+
+impl EmbedCsp {
+	/// Allow `origins` in the `frame-src` of `policy`
+	fn extend(policy: &str, origins: &[String]) -> String {
+		let directives: Vec<&str> = policy
+			.split(';')
+			.map(str::trim)
+			.filter(|x| !x.is_empty())
+			.collect();
+
+		let find = |name: &str| {
+			directives.iter().position(|x| {
+				x.split_whitespace()
+					.next()
+					.is_some_and(|x| x.eq_ignore_ascii_case(name))
+			})
+		};
+
+		// Without `frame-src`, frames fall back to `child-src`, then `default-src`
+		let (index, fallback) = match find("frame-src") {
+			Some(i) => (Some(i), i),
+			None => match find("child-src").or_else(|| find("default-src")) {
+				Some(i) => (None, i),
+				None => return policy.to_owned(),
+			},
+		};
+
+		let mut sources: Vec<&str> = directives[fallback].split_whitespace().skip(1).collect();
+		if sources.contains(&"*") {
+			return policy.to_owned();
+		}
+
+		// `'none'` can't be combined with other sources
+		sources.retain(|x| !x.eq_ignore_ascii_case("'none'"));
+		for origin in origins {
+			if !sources.contains(&origin.as_str()) {
+				sources.push(origin);
+			}
+		}
+
+		let frame_src = format!("frame-src {}", sources.join(" "));
+		let mut directives: Vec<String> = directives.iter().map(|x| (*x).to_owned()).collect();
+		match index {
+			Some(i) => directives[i] = frame_src,
+			None => directives.push(frame_src),
+		}
+		directives.join("; ")
+	}
+}
+
+impl HtmlRewriter for EmbedCsp {
+	fn rewrite(&self, _ctx: &RenderContext, rend: &mut Rendered<()>, html: String) -> String {
+		if !rend.headers.contains_key(header::CONTENT_SECURITY_POLICY) {
+			return html;
+		}
+
+		let mut origins: Vec<String> = Vec::new();
+		rewrite_tags(&html, &["iframe"], |_, open, _| {
+			if let Some(origin) =
+				attribute(open, "src").and_then(|x| origin(&x.replace("&amp;", "&")))
+				&& !origins.contains(&origin)
+			{
+				origins.push(origin);
+			}
+			None
+		});
+
+		if origins.is_empty() {
+			return html;
+		}
+
+		let policies: Vec<HeaderValue> = rend
+			.headers
+			.get_all(header::CONTENT_SECURITY_POLICY)
+			.iter()
+			.map(|x| match x.to_str() {
+				Ok(policy) => HeaderValue::from_str(&Self::extend(policy, &origins))
+					.unwrap_or_else(|_err| x.clone()),
+				Err(_) => x.clone(),
+			})
+			.collect();
+
+		rend.headers.remove(header::CONTENT_SECURITY_POLICY);
+		for policy in policies {
+			rend.headers.append(header::CONTENT_SECURITY_POLICY, policy);
+		}
+
+		html
+	}
+}
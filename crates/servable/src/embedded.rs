@@ -0,0 +1,210 @@
+use chrono::TimeDelta;
+use include_dir::{Dir, File};
+use std::collections::HashMap;
+
+use crate::{Error, ServableRouter, StaticAsset, servable::mime_from_extension};
+
+/// A directory embedded in the binary at build time with [include_dir],
+/// served as [StaticAsset]s under a prefix (see [ServableRouter::add_embedded_dir]).
+///
+/// Every file in the directory is served at `{prefix}/{path}`,
+/// with a type inferred from its extension.
+/// Directories with an index file (`index.html` by default) are also served at `{prefix}/{dir}`.
+/// Hidden files (like `.gitignore`) are skipped, unless [EmbeddedDir::with_hidden] is set.
+///
+/// This needs the same version of `include_dir` as this crate (`0.7`).
+///
+/// ```rust
+/// use servable::{EmbeddedDir, ServableRouter};
+/// use include_dir::{Dir, include_dir};
+/// use chrono::TimeDelta;
+///
+/// static ASSETS: Dir<'static> = include_dir!("$CARGO_MANIFEST_DIR/src");
+///
+/// let assets = EmbeddedDir::new("/assets", &ASSETS)
+/// 	.with_ttl_for("woff2", Some(TimeDelta::days(365)))
+/// 	.with_ttl_for_file("lib.rs", None);
+///
+/// assert!(assets.routes().contains(&"/assets/lib.rs".to_owned()));
+///
+/// let router = ServableRouter::new().add_embedded_dir(&assets);
+/// ```
+#[derive(Debug, Clone)]
+pub struct EmbeddedDir {
+	/// The route files are served under
+	prefix: String,
+	dir: &'static Dir<'static>,
+
+	/// The file served for a directory
+	index: Option<String>,
+	hidden: bool,
+
+	ttl: Option<TimeDelta>,
+
+	/// Ttls of files with these extensions, lowercase
+	ttls: HashMap<String, Option<TimeDelta>>,
+
+	/// Ttls of files at these paths, relative to `dir`
+	file_ttls: HashMap<String, Option<TimeDelta>>,
+}
+
+impl EmbeddedDir {
+	/// Serve the files in `dir` under `prefix`, like `/assets`
+	pub fn new(prefix: impl Into<String>, dir: &'static Dir<'static>) -> Self {
+		let prefix: String = prefix.into();
+		Self {
+			prefix: prefix.trim_end_matches('/').to_owned(),
+			dir,
+			index: Some("index.html".into()),
+			hidden: false,
+			ttl: StaticAsset::DEFAULT_TTL,
+			ttls: HashMap::new(),
+			file_ttls: HashMap::new(),
+		}
+	}
+
+	/// Set the file served for directories.
+	/// If `None`, directories are not served. The default is `index.html`.
+	#[inline(always)]
+	pub fn with_index(mut self, index: Option<String>) -> Self {
+		self.index = index;
+		self
+	}
+
+	/// If true, serve files and directories whose names start with a `.`
+	#[inline(always)]
+	pub fn with_hidden(mut self, hidden: bool) -> Self {
+		self.hidden = hidden;
+		self
+	}
+
+	/// Set how long to cache files.
+	/// The default is [StaticAsset::DEFAULT_TTL].
+	#[inline(always)]
+	pub fn with_ttl(mut self, ttl: Option<TimeDelta>) -> Self {
+		self.ttl = ttl;
+		self
+	}
+
+	/// Set how long to cache files with the extension `extension`, like `woff2`.
+	/// This overrides [Self::with_ttl].
+	#[inline(always)]
+	pub fn with_ttl_for(mut self, extension: &str, ttl: Option<TimeDelta>) -> Self {
+		self.ttls.insert(extension.to_ascii_lowercase(), ttl);
+		self
+	}
+
+	/// Set how long to cache the file at `path`, relative to the embedded directory,
+	/// like `js/app.js`. This overrides [Self::with_ttl] and [Self::with_ttl_for].
+	#[inline(always)]
+	pub fn with_ttl_for_file(mut self, path: &str, ttl: Option<TimeDelta>) -> Self {
+		self.file_ttls
+			.insert(path.trim_start_matches('/').to_owned(), ttl);
+		self
+	}
+
+	/// The route of every file in this directory,
+	/// and of every directory with an index file
+	pub fn routes(&self) -> Vec<String> {
+		self.assets().into_iter().map(|(route, _)| route).collect()
+	}
+
+	/// Every route in this directory, with the asset served there
+	fn assets(&self) -> Vec<(String, StaticAsset)> {
+		let mut out = Vec::new();
+		self.collect(self.dir, &mut out);
+		out
+	}
+
+	/// Add the assets in `dir` to `out`
+	fn collect(&self, dir: &'static Dir<'static>, out: &mut Vec<(String, StaticAsset)>) {
+		for file in dir.files() {
+			let Some(path) = self.path(file.path()) else {
+				continue;
+			};
+
+			let asset = self.asset(&path, file);
+			let name = path.rsplit('/').next().unwrap_or_default();
+			if self.index.as_deref() == Some(name) {
+				let dir = path[..path.len() - name.len()].trim_end_matches('/');
+				let route = match (self.prefix.as_str(), dir) {
+					("", "") => "/".to_owned(),
+					(prefix, "") => prefix.to_owned(),
+					(prefix, dir) => format!("{prefix}/{}", escape(dir)),
+				};
+				out.push((route, self.asset(&path, file)));
+			}
+
+			out.push((format!("{}/{}", self.prefix, escape(&path)), asset));
+		}
+
+		for dir in dir.dirs() {
+			if self.path(dir.path()).is_some() {
+				self.collect(dir, out);
+			}
+		}
+	}
+
+	/// The path of an entry in `self.dir`, with `/` separators.
+	/// Returns `None` if it is hidden or not UTF-8.
+	fn path(&self, path: &std::path::Path) -> Option<String> {
+		let mut segments = Vec::new();
+		for segment in path.iter() {
+			let segment = segment.to_str()?;
+			if !self.hidden && segment.starts_with('.') {
+				return None;
+			}
+			segments.push(segment);
+		}
+		Some(segments.join("/"))
+	}
+
+	/// The asset that serves `file`, which is at `path`
+	fn asset(&self, path: &str, file: &'static File<'static>) -> StaticAsset {
+		let extension = path
+			.rsplit_once('.')
+			.map(|(_, x)| x.to_ascii_lowercase())
+			.filter(|x| !x.contains('/'))
+			.unwrap_or_default();
+
+		let ttl = match self.file_ttls.get(path) {
+			Some(ttl) => *ttl,
+			None => self.ttls.get(&extension).copied().unwrap_or(self.ttl),
+		};
+
+		StaticAsset {
+			bytes: file.contents(),
+			br: None,
+			gz: None,
+			mime: mime_from_extension(&extension),
+			ttl,
+		}
+	}
+}
+
+/// Escape the braces in `path`, which are route parameters
+fn escape(path: &str) -> String {
+	path.replace('{', "{{").replace('}', "}}")
+}
+
+impl ServableRouter {
+	/// Serve every file in `dir` at its route (see [EmbeddedDir::routes]).
+	/// - panics if a route is invalid or already taken, like [Self::add_page].
+	///
+	/// See [Self::try_add_embedded_dir] for a version of this method that does not panic.
+	pub fn add_embedded_dir(self, dir: &EmbeddedDir) -> Self {
+		match self.try_add_embedded_dir(dir) {
+			Ok(x) => x,
+			Err(err) => panic!("{err}"),
+		}
+	}
+
+	/// Serve every file in `dir` at its route (see [EmbeddedDir::routes]).
+	/// Behaves exactly like [Self::add_embedded_dir], but returns an [Error] instead of panicking.
+	pub fn try_add_embedded_dir(mut self, dir: &EmbeddedDir) -> Result<Self, Error> {
+		for (route, asset) in dir.assets() {
+			self = self.try_add_page(route, asset)?;
+		}
+		Ok(self)
+	}
+}
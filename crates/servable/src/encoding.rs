@@ -0,0 +1,78 @@
+use axum::http::HeaderValue;
+
+/// A content encoding we may serve precompressed bodies in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum ContentEncoding {
+	Brotli,
+	Gzip,
+
+	#[cfg(feature = "compression")]
+	Zstd,
+}
+
+impl ContentEncoding {
+	/// The name of this encoding in `Accept-Encoding` and `Content-Encoding`
+	pub(crate) const fn name(&self) -> &'static str {
+		match self {
+			Self::Brotli => "br",
+			Self::Gzip => "gzip",
+
+			#[cfg(feature = "compression")]
+			Self::Zstd => "zstd",
+		}
+	}
+
+	/// The value of a `Content-Encoding` header for this encoding
+	pub(crate) const fn header_value(&self) -> HeaderValue {
+		HeaderValue::from_static(self.name())
+	}
+
+	/// Pick the encoding in `available` that the client prefers,
+	/// according to the value of an `Accept-Encoding` header.
+	///
+	/// `available` should be ordered by our preference,
+	/// which breaks ties between equal quality values.
+	/// Returns `None` if the client accepts none of `available`,
+	/// or if `accept` is missing or malformed.
+	pub(crate) fn negotiate(accept: Option<&HeaderValue>, available: &[Self]) -> Option<Self> {
+		let accept = accept?.to_str().ok()?;
+
+		let mut best: Option<(Self, f32)> = None;
+		for encoding in available {
+			let Some(q) = Self::quality(accept, encoding.name()) else {
+				continue;
+			};
+
+			if q > 0.0 && best.is_none_or(|(_, x)| q > x) {
+				best = Some((*encoding, q));
+			}
+		}
+
+		best.map(|(x, _)| x)
+	}
+
+	/// Find the quality value of `name` in an `Accept-Encoding` header.
+	/// An explicit entry for `name` takes precedence over `*`.
+	fn quality(accept: &str, name: &str) -> Option<f32> {
+		let mut wildcard = None;
+
+		for entry in accept.split(',') {
+			let mut parts = entry.split(';');
+			let coding = parts.next().unwrap_or("").trim();
+
+			let q = parts
+				.filter_map(|x| x.trim().strip_prefix("q="))
+				.next()
+				.map(|x| x.trim().parse::<f32>().unwrap_or(0.0))
+				.unwrap_or(1.0);
+
+			if coding.eq_ignore_ascii_case(name) {
+				return Some(q);
+			} else if coding == "*" {
+				wildcard = Some(q);
+			}
+		}
+
+		wildcard
+	}
+}
@@ -0,0 +1,213 @@
+use axum::{
+	http::StatusCode,
+	response::{IntoResponse, Response},
+};
+use std::time::Duration;
+use thiserror::Error;
+
+/// An error produced by this crate.
+///
+/// Errors may be turned into responses with [IntoResponse].
+/// Client errors (4xx) include their message in the body,
+/// server errors (5xx) have an empty body.
+#[derive(Debug, Error)]
+pub enum Error {
+	/// A route or route prefix is invalid,
+	/// see [crate::ServableRouter::add_page].
+	#[error("invalid route `{route}`: {reason}")]
+	InvalidRoute {
+		/// The route we were given
+		route: String,
+
+		/// Why this route is invalid
+		reason: &'static str,
+	},
+
+	/// A header name or value is invalid
+	#[error("invalid header `{name}`")]
+	InvalidHeader {
+		/// The name of the header we were given
+		name: String,
+	},
+
+	/// A page links to a route that no page is served at,
+	/// see [crate::ServableRouter::validate].
+	#[error("page `{route}` links to `{link}`, which does not exist")]
+	BrokenLink {
+		/// The route of the page with the broken link
+		route: String,
+
+		/// The link
+		link: String,
+	},
+
+	/// A trusted proxy range is invalid,
+	/// see [crate::ServableRouter::with_trusted_proxy].
+	#[error("invalid trusted proxy `{cidr}`: {reason}")]
+	InvalidTrustedProxy {
+		/// The range we were given
+		cidr: String,
+
+		/// Why this range is invalid
+		reason: &'static str,
+	},
+
+	/// A base url is invalid,
+	/// see [crate::ServableRouter::with_base_url].
+	#[error("invalid base url `{url}`: {reason}")]
+	InvalidBaseUrl {
+		/// The url we were given
+		url: String,
+
+		/// Why this url is invalid
+		reason: &'static str,
+	},
+
+	/// A periodic task is invalid,
+	/// see [crate::ServableRouter::with_task].
+	#[error("invalid task `{name}`: {reason}")]
+	InvalidTask {
+		/// The name of the task we were given
+		name: String,
+
+		/// Why this task is invalid
+		reason: &'static str,
+	},
+
+	/// A bundle could not be built from its sources,
+	/// see [crate::BundleAsset::new].
+	#[error("invalid bundle: {reason}")]
+	InvalidBundle {
+		/// Why this bundle is invalid
+		reason: &'static str,
+	},
+
+	/// A page was added to a router after it was started
+	#[error("pages may not be added after a router is started")]
+	RouterStarted,
+
+	/// A page was added to or removed from a running router without dynamic routes,
+	/// see [crate::ServableRouter::with_dynamic_routes].
+	#[error("this router's routes are not dynamic")]
+	StaticRoutes,
+
+	/// A request contained an invalid image transformation
+	#[error("invalid transformation: {0}")]
+	InvalidTransform(String),
+
+	/// We could not transform an image
+	#[cfg(feature = "image")]
+	#[error(transparent)]
+	Transform(#[from] crate::transform::TransformBytesError),
+
+	/// A submitted form could not be parsed
+	#[error("invalid form: {0}")]
+	InvalidForm(String),
+
+	/// A submitted form was larger than the router's limit,
+	/// see [crate::ServableRouter::with_form_limit].
+	#[error("form is larger than {0} bytes")]
+	FormTooLarge(usize),
+
+	/// A submitted form had a content type we can't parse
+	#[error("unsupported form content type `{0}`")]
+	UnsupportedFormType(String),
+
+	/// We could not serialize a response body
+	#[error("error while serializing json")]
+	Json(#[from] serde_json::Error),
+
+	/// A servable took too long to respond,
+	/// see [crate::ServableRouter::with_timeout].
+	#[error("servable timed out after {0:?}")]
+	Timeout(Duration),
+
+	/// A proxy upstream url is invalid,
+	/// see [crate::proxy::Proxy::new].
+	#[cfg(feature = "proxy")]
+	#[error("invalid upstream `{url}`: {reason}")]
+	InvalidUpstream {
+		/// The url we were given
+		url: String,
+
+		/// Why this url is invalid
+		reason: &'static str,
+	},
+
+	/// A proxy could not get a response from its upstream
+	#[cfg(feature = "proxy")]
+	#[error("upstream request failed: {0}")]
+	Upstream(String),
+
+	/// A Sentry dsn is invalid,
+	/// see [crate::SentryReporter::new].
+	#[cfg(feature = "sentry")]
+	#[error("invalid dsn `{dsn}`: {reason}")]
+	InvalidDsn {
+		/// The dsn we were given
+		dsn: String,
+
+		/// Why this dsn is invalid
+		reason: &'static str,
+	},
+
+	/// A router config could not be parsed or applied,
+	/// see [crate::config::RouterConfig].
+	#[cfg(feature = "config")]
+	#[error("invalid config: {0}")]
+	InvalidConfig(String),
+
+	/// An io error
+	#[error(transparent)]
+	Io(#[from] std::io::Error),
+}
+
+impl Error {
+	/// The status code of the response this error produces
+	pub fn status_code(&self) -> StatusCode {
+		match self {
+			Self::InvalidTransform(_) | Self::InvalidForm(_) => StatusCode::BAD_REQUEST,
+			Self::FormTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+			Self::UnsupportedFormType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+			Self::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+			Self::Io(x) if x.kind() == std::io::ErrorKind::NotFound => StatusCode::NOT_FOUND,
+
+			Self::InvalidRoute { .. }
+			| Self::InvalidHeader { .. }
+			| Self::BrokenLink { .. }
+			| Self::InvalidTrustedProxy { .. }
+			| Self::InvalidBaseUrl { .. }
+			| Self::InvalidTask { .. }
+			| Self::InvalidBundle { .. }
+			| Self::RouterStarted
+			| Self::StaticRoutes
+			| Self::Json(_)
+			| Self::Io(_) => StatusCode::INTERNAL_SERVER_ERROR,
+
+			#[cfg(feature = "image")]
+			Self::Transform(_) => StatusCode::INTERNAL_SERVER_ERROR,
+
+			#[cfg(feature = "proxy")]
+			Self::InvalidUpstream { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+
+			#[cfg(feature = "proxy")]
+			Self::Upstream(_) => StatusCode::BAD_GATEWAY,
+
+			#[cfg(feature = "sentry")]
+			Self::InvalidDsn { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+
+			#[cfg(feature = "config")]
+			Self::InvalidConfig(_) => StatusCode::INTERNAL_SERVER_ERROR,
+		}
+	}
+}
+
+impl IntoResponse for Error {
+	fn into_response(self) -> Response {
+		let code = self.status_code();
+		match code.is_client_error() {
+			true => (code, self.to_string()).into_response(),
+			false => code.into_response(),
+		}
+	}
+}
@@ -0,0 +1,492 @@
+use axum::http::{HeaderMap, HeaderValue, StatusCode, header};
+use std::{
+	collections::HashMap,
+	pin::Pin,
+	sync::{Arc, Mutex},
+	time::{Duration, Instant},
+};
+
+use crate::{
+	Error, RenderContext, RenderMode, Rendered, RenderedBody, RenderedBodyType,
+	ratelimit::retry_after, router::cache_control, servable::Servable,
+};
+
+/// The number of stale responses a [FailSafe] keeps
+const STALE_CAPACITY: usize = 1000;
+
+/// The `Warning` of stale responses sent while the circuit is open
+const WARNING_STALE: HeaderValue = HeaderValue::from_static("110 - \"Response is Stale\"");
+
+/// The `Warning` of stale responses sent because a render failed
+const WARNING_FAILED: HeaderValue = HeaderValue::from_static("111 - \"Revalidation Failed\"");
+
+/// The state of a [FailSafe]
+#[derive(Debug, Clone, Copy)]
+enum State {
+	/// Requests are served by the wrapped servable
+	Closed { failures: u32 },
+
+	/// Requests are not sent to the wrapped servable until `until`
+	Open { until: Instant },
+
+	/// One request is testing the wrapped servable,
+	/// the others are handled as if the circuit were open
+	Trial,
+}
+
+/// A response kept for when the wrapped servable fails
+struct Stale {
+	rend: Rendered<RenderedBody>,
+	stored: Instant,
+}
+
+/// A [Servable] that stops calling a failing servable for a while (a circuit breaker).
+///
+/// A response fails if it is a server error (5xx), or if it is cancelled,
+/// like when the request times out (see [crate::ServableRouter::with_timeout]).
+/// After `threshold` failures in a row the circuit opens, and for `cooldown`
+/// requests are answered without calling the wrapped servable.
+/// After that, one request is let through: if it succeeds the circuit closes,
+/// and if it fails the circuit opens for another `cooldown`.
+///
+/// While the circuit is open, requests are answered with:
+/// - the last successful response to the same route and query, if it is recent enough
+///   (see [FailSafe::with_stale])
+/// - the fallback page, if there is one (see [FailSafe::with_fallback])
+/// - `503 Service Unavailable` otherwise, which is replaced by the router's
+///   error page (see [crate::ServableRouter::with_error_page])
+///
+/// Stale responses are also sent instead of a single failed or slow render
+/// (see [FailSafe::with_timeout]), so a flaky dependency doesn't turn into errors.
+/// They have `Age` and `Warning` headers, and are never cached.
+/// Responses that vary on request headers (with `Vary` or `Content-Encoding`)
+/// are never kept, since the stale response is picked by route and query alone.
+/// The fallback and `503` have a `Retry-After` header, and are never cached either.
+/// Successful responses advertise the same window to shared caches with `stale-if-error`.
+///
+/// Each [FailSafe] has one circuit, shared by every route it is served at.
+///
+/// ```rust
+/// use servable::{FailSafe, HtmlPage, ServableRouter};
+/// use maud::html;
+/// use std::time::Duration;
+///
+/// let dashboard = HtmlPage::default().with_render(|_page, _ctx| {
+/// 	Box::pin(async { html! { "Stats from a flaky service" } })
+/// });
+///
+/// let fallback = HtmlPage::default().with_render(|_page, _ctx| {
+/// 	Box::pin(async { html! { "Stats are unavailable, try again in a minute." } })
+/// });
+///
+/// // Stop calling `dashboard` for 30 seconds after 5 failures in a row
+/// let dashboard = FailSafe::new(dashboard, 5, Duration::from_secs(30))
+/// 	.with_stale(Some(Duration::from_secs(600)))
+/// 	// Don't wait more than two seconds if we have a stale response
+/// 	.with_timeout(Some(Duration::from_secs(2)))
+/// 	.with_fallback(fallback);
+///
+/// let router = ServableRouter::new().add_page("/dashboard", dashboard);
+/// ```
+pub struct FailSafe<S: Servable> {
+	inner: S,
+	threshold: u32,
+	cooldown: Duration,
+
+	/// How long successful responses are kept
+	stale: Option<Duration>,
+
+	/// How long to wait for a render if we have a stale response
+	timeout: Option<Duration>,
+	fallback: Option<Arc<dyn Servable>>,
+
+	state: Mutex<State>,
+
+	/// The last successful response to each route and query
+	responses: Mutex<HashMap<String, Stale>>,
+}
+
+/// A request sent to the wrapped servable.
+/// If it is dropped before it finishes, it counts as a failure.
+struct Attempt<'a, S: Servable> {
+	breaker: &'a FailSafe<S>,
+	ctx: &'a RenderContext,
+	done: bool,
+}
+
+impl<S: Servable> Attempt<'_, S> {
+	fn finish(mut self, ok: bool) {
+		self.done = true;
+		self.breaker.record(ok, self.ctx.clock.instant());
+	}
+}
+
+impl<S: Servable> Drop for Attempt<'_, S> {
+	fn drop(&mut self) {
+		if !self.done {
+			self.breaker.record(false, self.ctx.clock.instant());
+		}
+	}
+}
+
+impl<S: Servable> FailSafe<S> {
+	/// Default value of [Self::with_stale]
+	pub const DEFAULT_STALE: Option<Duration> = Some(Duration::from_secs(60 * 60));
+
+	/// Open the circuit for `cooldown` after `threshold` failures in a row.
+	/// See [FailSafe].
+	pub fn new(inner: S, threshold: u32, cooldown: Duration) -> Self {
+		Self {
+			inner,
+			threshold: threshold.max(1),
+			cooldown,
+			stale: Self::DEFAULT_STALE,
+			timeout: None,
+			fallback: None,
+			state: Mutex::new(State::Closed { failures: 0 }),
+			responses: Mutex::new(HashMap::new()),
+		}
+	}
+
+	/// Set how long successful responses may be served after the wrapped servable fails.
+	/// If `None`, successful responses are not kept.
+	/// The default is [Self::DEFAULT_STALE].
+	///
+	/// Only public (see [Rendered::private]) responses to `GET`s
+	/// that don't set cookies are kept.
+	#[inline(always)]
+	pub fn with_stale(mut self, stale: Option<Duration>) -> Self {
+		self.stale = stale;
+		self
+	}
+
+	/// If there is a stale response to send, stop waiting for renders after `timeout`
+	/// and send it instead. Slow renders count as failures.
+	///
+	/// Renders are also stopped just before the request times out
+	/// (see [crate::ServableRouter::with_timeout]), even if this is `None`.
+	/// Without a stale response, renders are never stopped early.
+	#[inline(always)]
+	pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+		self.timeout = timeout;
+		self
+	}
+
+	/// Serve `fallback` while the circuit is open
+	/// and there is no stale response to send
+	#[inline(always)]
+	pub fn with_fallback<F: Servable + 'static>(mut self, fallback: F) -> Self {
+		self.fallback = Some(Arc::new(fallback));
+		self
+	}
+
+	/// If true, the circuit is open and the wrapped servable is not being called
+	pub fn is_open(&self) -> bool {
+		self.state
+			.lock()
+			.is_ok_and(|x| !matches!(*x, State::Closed { .. }))
+	}
+
+	/// Check if a request may be sent to the wrapped servable.
+	/// If not, returns how long until it may be.
+	fn admit(&self, now: Instant) -> Result<(), Duration> {
+		let Ok(mut state) = self.state.lock() else {
+			return Ok(());
+		};
+
+		match *state {
+			State::Closed { .. } => Ok(()),
+			State::Open { until } if now >= until => {
+				*state = State::Trial;
+				Ok(())
+			}
+			State::Open { until } => Err(until - now),
+			State::Trial => Err(Duration::ZERO),
+		}
+	}
+
+	/// Record the result of a request sent to the wrapped servable
+	fn record(&self, ok: bool, now: Instant) {
+		let Ok(mut state) = self.state.lock() else {
+			return;
+		};
+
+		*state = match (ok, *state) {
+			(true, _) => State::Closed { failures: 0 },
+			(false, State::Closed { failures }) if failures + 1 < self.threshold => State::Closed {
+				failures: failures + 1,
+			},
+			(false, State::Open { until }) => State::Open { until },
+			(false, _) => State::Open {
+				until: now + self.cooldown,
+			},
+		};
+	}
+
+	/// The key of the stale response to `ctx`
+	fn key(ctx: &RenderContext) -> String {
+		let query = serde_urlencoded::to_string(&ctx.query).unwrap_or_default();
+		format!("{}?{query}", ctx.route)
+	}
+
+	/// Keep `rend` for when the wrapped servable fails
+	fn store(&self, ctx: &RenderContext, rend: &Rendered<RenderedBody>) {
+		let Some(stale) = self.stale else { return };
+		if ctx.mode != RenderMode::Full
+			|| !rend.code.is_success()
+			|| rend.private
+			|| rend.headers.contains_key(header::SET_COOKIE)
+			|| rend.headers.contains_key(header::VARY)
+			|| rend.headers.contains_key(header::CONTENT_ENCODING)
+		{
+			return;
+		}
+
+		let Ok(mut responses) = self.responses.lock() else {
+			return;
+		};
+
+		let now = ctx.clock.instant();
+		let key = Self::key(ctx);
+		if responses.len() >= STALE_CAPACITY && !responses.contains_key(&key) {
+			responses.retain(|_, x| now.duration_since(x.stored) < stale);
+			if responses.len() >= STALE_CAPACITY {
+				return;
+			}
+		}
+
+		responses.insert(
+			key,
+			Stale {
+				rend: rend.clone(),
+				stored: now,
+			},
+		);
+	}
+
+	/// If true, we have a stale response to `ctx`
+	fn has_stale(&self, ctx: &RenderContext) -> bool {
+		let Some(stale) = self.stale else {
+			return false;
+		};
+
+		let now = ctx.clock.instant();
+		self.responses.lock().is_ok_and(|x| {
+			x.get(&Self::key(ctx))
+				.is_some_and(|x| now.duration_since(x.stored) <= stale)
+		})
+	}
+
+	/// The last successful response to `ctx`, if it is recent enough,
+	/// with the given `Warning`
+	fn stale(&self, ctx: &RenderContext, warning: HeaderValue) -> Option<Rendered<RenderedBody>> {
+		let stale = self.stale?;
+		let responses = self.responses.lock().ok()?;
+		let x = responses.get(&Self::key(ctx))?;
+		let age = ctx.clock.instant().duration_since(x.stored);
+		if age > stale {
+			return None;
+		}
+
+		let mut rend = x.rend.clone();
+		rend.ttl = None;
+		rend.headers
+			.insert(header::CACHE_CONTROL, cache_control(None, false));
+		rend.headers
+			.insert(header::AGE, HeaderValue::from(age.as_secs()));
+		rend.headers.insert(header::WARNING, warning);
+		Some(rend)
+	}
+
+	/// Send a request to the wrapped servable, and record its result.
+	/// Returns `Err` if it took longer than we may wait, see [Self::with_timeout].
+	async fn attempt<T: RenderedBodyType>(
+		&self,
+		ctx: &RenderContext,
+		render: impl Future<Output = Rendered<T>>,
+	) -> Result<Rendered<T>, Error> {
+		let attempt = Attempt {
+			breaker: self,
+			ctx,
+			done: false,
+		};
+
+		let timeout = match self.has_stale(ctx) {
+			false => None,
+			true => match (self.timeout, ctx.remaining()) {
+				(Some(a), Some(b)) => Some(a.min(b)),
+				(a, b) => a.or(b),
+			},
+		};
+
+		let rend = match timeout {
+			None => render.await,
+			Some(timeout) => tokio::time::timeout(timeout, render)
+				.await
+				.map_err(|_err| Error::Timeout(timeout))?,
+		};
+
+		attempt.finish(!rend.code.is_server_error());
+		Ok(rend)
+	}
+
+	/// Allow shared caches to serve `rend` if we fail, like we would
+	fn stale_if_error<T: RenderedBodyType>(&self, mut rend: Rendered<T>) -> Rendered<T> {
+		let Some(stale) = self.stale else { return rend };
+		if !rend.code.is_success()
+			|| rend.private
+			|| rend.headers.contains_key(header::CACHE_CONTROL)
+		{
+			return rend;
+		}
+
+		let value = cache_control(rend.ttl, false);
+		let value = format!(
+			"{}, stale-if-error={}",
+			value.to_str().unwrap_or_default(),
+			stale.as_secs()
+		);
+		if let Ok(value) = HeaderValue::from_str(&value) {
+			rend.headers.insert(header::CACHE_CONTROL, value);
+		}
+		rend
+	}
+
+	/// Mark `rend` as a response sent instead of the wrapped servable's
+	fn unavailable<T: RenderedBodyType>(mut rend: Rendered<T>, wait: Duration) -> Rendered<T> {
+		if rend.code.is_success() {
+			rend.code = StatusCode::SERVICE_UNAVAILABLE;
+		}
+		rend.ttl = None;
+		rend.headers
+			.insert(header::CACHE_CONTROL, cache_control(None, false));
+		rend.headers.insert(header::RETRY_AFTER, retry_after(wait));
+		rend
+	}
+
+	/// An empty `503 Service Unavailable`
+	fn service_unavailable(wait: Duration) -> Rendered<()> {
+		Self::unavailable(empty(StatusCode::SERVICE_UNAVAILABLE), wait)
+	}
+
+	/// The response to a render we stopped waiting for, if its stale response is gone
+	fn timed_out(ctx: &RenderContext, err: Error) -> Rendered<()> {
+		let code = err.status_code();
+		ctx.report_error(err);
+		empty(code)
+	}
+}
+
+impl<S: Servable> Servable for FailSafe<S> {
+	fn head<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<()>> + 'a + Send + Sync>> {
+		Box::pin(async move {
+			if let Err(wait) = self.admit(ctx.clock.instant()) {
+				if let Some(rend) = self.stale(ctx, WARNING_STALE) {
+					return head_of(rend);
+				}
+
+				return match &self.fallback {
+					Some(fallback) => Self::unavailable(fallback.head(ctx).await, wait),
+					None => Self::service_unavailable(wait),
+				};
+			}
+
+			let rend = match self.attempt(ctx, self.inner.head(ctx)).await {
+				Ok(rend) => rend,
+				Err(err) => {
+					return match self.stale(ctx, WARNING_FAILED) {
+						Some(stale) => head_of(stale),
+						None => Self::timed_out(ctx, err),
+					};
+				}
+			};
+
+			if rend.code.is_server_error()
+				&& let Some(stale) = self.stale(ctx, WARNING_FAILED)
+			{
+				return head_of(stale);
+			}
+
+			self.stale_if_error(rend)
+		})
+	}
+
+	fn render<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<RenderedBody>> + 'a + Send + Sync>> {
+		Box::pin(async move {
+			if let Err(wait) = self.admit(ctx.clock.instant()) {
+				if let Some(rend) = self.stale(ctx, WARNING_STALE) {
+					return rend;
+				}
+
+				return match &self.fallback {
+					Some(fallback) => Self::unavailable(fallback.render(ctx).await, wait),
+					None => Self::service_unavailable(wait).with_body(RenderedBody::Empty),
+				};
+			}
+
+			let rend = match self.attempt(ctx, self.inner.render(ctx)).await {
+				Ok(rend) => rend,
+				Err(err) => {
+					return match self.stale(ctx, WARNING_FAILED) {
+						Some(stale) => stale,
+						None => Self::timed_out(ctx, err).with_body(RenderedBody::Empty),
+					};
+				}
+			};
+
+			if rend.code.is_server_error() {
+				return self.stale(ctx, WARNING_FAILED).unwrap_or(rend);
+			}
+
+			self.store(ctx, &rend);
+			self.stale_if_error(rend)
+		})
+	}
+
+	#[inline(always)]
+	fn header_template(&self) -> HeaderMap {
+		self.inner.header_template()
+	}
+
+	#[inline(always)]
+	fn linked_routes(&self) -> Vec<String> {
+		self.inner.linked_routes()
+	}
+
+	#[inline(always)]
+	fn preloads(&self) -> Vec<crate::Preload> {
+		self.inner.preloads()
+	}
+}
+
+/// An empty response with status `code`
+fn empty(code: StatusCode) -> Rendered<()> {
+	Rendered {
+		code,
+		body: (),
+		ttl: None,
+		private: false,
+		headers: HeaderMap::new(),
+		mime: None,
+		content_length: None,
+	}
+}
+
+/// `rend`, without its body
+pub(crate) fn head_of(rend: Rendered<RenderedBody>) -> Rendered<()> {
+	Rendered {
+		code: rend.code,
+		headers: rend.headers,
+		body: (),
+		mime: rend.mime,
+		ttl: rend.ttl,
+		private: rend.private,
+		content_length: rend.content_length,
+	}
+}
@@ -0,0 +1,192 @@
+use axum::http::{HeaderMap, StatusCode};
+use std::{pin::Pin, sync::Arc};
+use tracing::{error, trace};
+
+use crate::{Preload, RenderContext, Rendered, RenderedBody, RenderedBodyType, servable::Servable};
+
+/// A [Servable] that may fail.
+/// Wrap it in a [Fallible] to add it to a [crate::ServableRouter].
+///
+/// Implementations may use `?` instead of building an error response for every failure,
+/// [Fallible] turns errors into responses.
+///
+/// ```rust
+/// use servable::{Fallible, RenderContext, Rendered, RenderedBody, ServableRouter, TryServable};
+/// use axum::http::{HeaderMap, StatusCode};
+/// use std::{num::ParseIntError, pin::Pin};
+///
+/// struct Square;
+///
+/// impl TryServable for Square {
+/// 	type Error = ParseIntError;
+///
+/// 	fn try_head<'a>(
+/// 		&'a self,
+/// 		_ctx: &'a RenderContext,
+/// 	) -> Pin<Box<dyn Future<Output = Result<Rendered<()>, Self::Error>> + 'a + Send + Sync>> {
+/// 		Box::pin(async {
+/// 			Ok(Rendered {
+/// 				code: StatusCode::OK,
+/// 				body: (),
+/// 				ttl: None,
+/// 				private: false,
+/// 				headers: HeaderMap::new(),
+/// 				mime: Some(mime::TEXT_PLAIN),
+/// 				content_length: None,
+/// 			})
+/// 		})
+/// 	}
+///
+/// 	fn try_render<'a>(
+/// 		&'a self,
+/// 		ctx: &'a RenderContext,
+/// 	) -> Pin<Box<dyn Future<Output = Result<Rendered<RenderedBody>, Self::Error>> + 'a + Send + Sync>>
+/// 	{
+/// 		Box::pin(async {
+/// 			let n: u64 = ctx.params["n"].parse()?;
+/// 			let rend = self.try_head(ctx).await?;
+/// 			Ok(rend.with_body(RenderedBody::String((n * n).to_string())))
+/// 		})
+/// 	}
+/// }
+///
+/// // `/square/x` is answered with a 400
+/// let router = ServableRouter::new().add_page(
+/// 	"/square/{n}",
+/// 	Fallible::new(Square).with_status(|_err| StatusCode::BAD_REQUEST),
+/// );
+/// ```
+pub trait TryServable: Send + Sync {
+	/// The error this servable may fail with
+	type Error: std::error::Error + Send + Sync + 'static;
+
+	/// Return the same response as [TryServable::try_render], but with an empty body.
+	/// See [Servable::head].
+	fn try_head<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Result<Rendered<()>, Self::Error>> + 'a + Send + Sync>>;
+
+	/// Render this page, see [Servable::render].
+	fn try_render<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Result<Rendered<RenderedBody>, Self::Error>> + 'a + Send + Sync>>;
+
+	/// See [Servable::header_template]
+	fn header_template(&self) -> HeaderMap {
+		HeaderMap::new()
+	}
+
+	/// See [Servable::linked_routes]
+	fn linked_routes(&self) -> Vec<String> {
+		Vec::new()
+	}
+
+	/// See [Servable::preloads]
+	fn preloads(&self) -> Vec<Preload> {
+		Vec::new()
+	}
+}
+
+/// A [Servable] that serves a [TryServable], and turns its errors into responses.
+///
+/// Errors are answered with an empty response, with the status code picked by
+/// [Fallible::with_status] (`500 Internal Server Error` by default).
+/// The router replaces these with its error pages, see [crate::ServableRouter::with_error_page].
+///
+/// Server errors (5xx) are logged and reported with [RenderContext::report_error],
+/// other errors are only traced.
+pub struct Fallible<S: TryServable> {
+	inner: S,
+	status: Arc<dyn Fn(&S::Error) -> StatusCode + Send + Sync>,
+}
+
+impl<S: TryServable> Fallible<S> {
+	/// Serve `inner`, answering its errors with a `500`
+	pub fn new(inner: S) -> Self {
+		Self {
+			inner,
+			status: Arc::new(|_| StatusCode::INTERNAL_SERVER_ERROR),
+		}
+	}
+
+	/// Set the status code of the response to each error.
+	/// For errors of this crate, this may be [crate::Error::status_code].
+	#[inline(always)]
+	pub fn with_status(
+		mut self,
+		status: impl Fn(&S::Error) -> StatusCode + Send + Sync + 'static,
+	) -> Self {
+		self.status = Arc::new(status);
+		self
+	}
+
+	/// The response to `err`
+	fn error_response<T: RenderedBodyType>(
+		&self,
+		ctx: &RenderContext,
+		err: S::Error,
+		body: T,
+	) -> Rendered<T> {
+		let code = (self.status)(&err);
+		match code.is_server_error() {
+			true => {
+				error!(message = "Error while rendering page", route = ctx.route, error = %err);
+				ctx.report_error(err);
+			}
+			false => trace!(message = "Page returned an error", route = ctx.route, error = %err),
+		}
+
+		Rendered {
+			code,
+			body,
+			ttl: None,
+			private: false,
+			headers: HeaderMap::new(),
+			mime: None,
+			content_length: None,
+		}
+	}
+}
+
+impl<S: TryServable> Servable for Fallible<S> {
+	fn head<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<()>> + 'a + Send + Sync>> {
+		Box::pin(async {
+			match self.inner.try_head(ctx).await {
+				Ok(x) => x,
+				Err(err) => self.error_response(ctx, err, ()),
+			}
+		})
+	}
+
+	fn render<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<RenderedBody>> + 'a + Send + Sync>> {
+		Box::pin(async {
+			match self.inner.try_render(ctx).await {
+				Ok(x) => x,
+				Err(err) => self.error_response(ctx, err, RenderedBody::Empty),
+			}
+		})
+	}
+
+	#[inline(always)]
+	fn header_template(&self) -> HeaderMap {
+		self.inner.header_template()
+	}
+
+	#[inline(always)]
+	fn linked_routes(&self) -> Vec<String> {
+		self.inner.linked_routes()
+	}
+
+	#[inline(always)]
+	fn preloads(&self) -> Vec<Preload> {
+		self.inner.preloads()
+	}
+}
@@ -0,0 +1,290 @@
+use axum::http::{HeaderMap, StatusCode, header};
+use chrono::TimeDelta;
+use image::{
+	DynamicImage, ExtendedColorType, ImageFormat, RgbaImage,
+	codecs::ico::{IcoEncoder, IcoFrame},
+	imageops,
+};
+use mime::Mime;
+use std::{io::Cursor, pin::Pin, sync::Arc};
+use tokio::sync::OnceCell;
+
+use crate::{
+	Error, ManifestIcon, RenderContext, Rendered, RenderedBody, ServableRouter, StaticAsset,
+	failsafe::head_of, router::cache_control, servable::Servable, transform::TransformerChain,
+};
+
+/// The png icons of a [Favicons], and their sizes
+const FAVICON_PNGS: &[(&str, u32)] = &[
+	("/favicon-16x16.png", 16),
+	("/favicon-32x32.png", 32),
+	("/apple-touch-icon.png", 180),
+	("/android-chrome-192x192.png", 192),
+	("/android-chrome-512x512.png", 512),
+];
+
+/// The sizes stored in `/favicon.ico`
+const FAVICON_ICO_SIZES: &[u32] = &[16, 32, 48];
+
+/// A generated [Favicons]
+struct Generated {
+	/// Each png in [FAVICON_PNGS], in order
+	pngs: Vec<Vec<u8>>,
+	ico: Vec<u8>,
+}
+
+/// The standard set of favicons, generated from one high-resolution image.
+///
+/// The router serves (see [ServableRouter::add_favicons]):
+/// - `/favicon.ico`, with 16, 32 and 48 pixel icons,
+/// - `/favicon-16x16.png` and `/favicon-32x32.png`,
+/// - `/apple-touch-icon.png`, 180 pixels,
+/// - `/android-chrome-192x192.png` and `/android-chrome-512x512.png`,
+///   which may be added to a [crate::WebManifest] with [Favicons::manifest_icons].
+///
+/// Every icon is resized from `source` with [crate::transform].
+/// Sources that aren't square are centered on a transparent square.
+/// Icons are generated once, when one of them is first requested.
+///
+/// ```rust
+/// use servable::{Favicons, ServableRouter, StaticAsset, WebManifest};
+///
+/// static LOGO: StaticAsset = StaticAsset {
+/// 	bytes: b"fake image",
+/// 	br: None,
+/// 	gz: None,
+/// 	mime: mime::IMAGE_PNG,
+/// 	ttl: StaticAsset::DEFAULT_TTL,
+/// };
+///
+/// let favicons = Favicons::new(&LOGO);
+/// let manifest = favicons
+/// 	.manifest_icons()
+/// 	.into_iter()
+/// 	.fold(WebManifest::new("My App"), |m, icon| m.with_icon(icon));
+///
+/// let router = ServableRouter::new()
+/// 	.add_favicons(&favicons)
+/// 	.add_page("/manifest.webmanifest", manifest);
+/// ```
+#[derive(Clone)]
+pub struct Favicons {
+	source: &'static StaticAsset,
+
+	/// How long clients may cache these icons.
+	/// The default is [Favicons::DEFAULT_TTL].
+	pub ttl: Option<TimeDelta>,
+
+	generated: Arc<OnceCell<Result<Generated, String>>>,
+}
+
+impl Favicons {
+	/// The default ttl of favicons.
+	/// Favicon routes can't contain a content hash, so this isn't forever.
+	pub const DEFAULT_TTL: Option<TimeDelta> = Some(TimeDelta::days(30));
+
+	/// Create a favicon set from the image `source`
+	pub fn new(source: &'static StaticAsset) -> Self {
+		Self {
+			source,
+			ttl: Self::DEFAULT_TTL,
+			generated: Arc::new(OnceCell::new()),
+		}
+	}
+
+	/// Set `self.ttl`
+	#[inline(always)]
+	pub fn with_ttl(mut self, ttl: Option<TimeDelta>) -> Self {
+		self.ttl = ttl;
+		self
+	}
+
+	/// The routes of every icon in this set
+	pub fn routes(&self) -> Vec<&'static str> {
+		std::iter::once("/favicon.ico")
+			.chain(FAVICON_PNGS.iter().map(|(route, _)| *route))
+			.collect()
+	}
+
+	/// The icons of this set that belong in a [crate::WebManifest]
+	pub fn manifest_icons(&self) -> Vec<ManifestIcon> {
+		FAVICON_PNGS
+			.iter()
+			.filter(|(route, _)| route.starts_with("/android-chrome"))
+			.map(|(route, size)| {
+				ManifestIcon::new(*route, format!("{size}x{size}")).with_mime(mime::IMAGE_PNG)
+			})
+			.collect()
+	}
+
+	/// Generate these icons, or get them if they were already generated
+	async fn generated(&self, ctx: &RenderContext) -> Result<&Generated, Rendered<()>> {
+		let generated = self
+			.generated
+			.get_or_init(|| async {
+				let favicons = self.clone();
+				match tokio::task::spawn_blocking(move || favicons.generate()).await {
+					Ok(Ok(x)) => Ok(x),
+					Ok(Err(err)) => Err(err.to_string()),
+					Err(err) => Err(format!("error while generating favicons: {err}")),
+				}
+			})
+			.await;
+
+		generated.as_ref().map_err(|err| {
+			ctx.report_error(err.clone());
+			Rendered {
+				code: StatusCode::INTERNAL_SERVER_ERROR,
+				headers: HeaderMap::new(),
+				body: (),
+				ttl: None,
+				private: false,
+				mime: None,
+				content_length: None,
+			}
+		})
+	}
+
+	/// Generate these icons
+	fn generate(&self) -> Result<Generated, Error> {
+		let format = ImageFormat::from_mime_type(&self.source.mime).ok_or_else(|| {
+			crate::transform::TransformBytesError::NotAnImage(self.source.mime.to_string())
+		})?;
+		let source = image::load_from_memory_with_format(self.source.bytes, format)
+			.map_err(crate::transform::TransformBytesError::from)?;
+
+		let mut pngs = Vec::with_capacity(FAVICON_PNGS.len());
+		for (_, size) in FAVICON_PNGS {
+			let icon = square(&source, *size);
+			let mut png = Cursor::new(Vec::new());
+			DynamicImage::ImageRgba8(icon)
+				.write_to(&mut png, ImageFormat::Png)
+				.map_err(crate::transform::TransformBytesError::from)?;
+			pngs.push(png.into_inner());
+		}
+
+		let mut frames = Vec::with_capacity(FAVICON_ICO_SIZES.len());
+		for size in FAVICON_ICO_SIZES {
+			let icon = square(&source, *size);
+			frames.push(
+				IcoFrame::as_png(icon.as_raw(), *size, *size, ExtendedColorType::Rgba8)
+					.map_err(crate::transform::TransformBytesError::from)?,
+			);
+		}
+
+		let mut ico = Vec::new();
+		IcoEncoder::new(&mut ico)
+			.encode_images(&frames)
+			.map_err(crate::transform::TransformBytesError::from)?;
+
+		Ok(Generated { pngs, ico })
+	}
+}
+
+/// Resize `source` to fit in a `size` pixel square, and center it on a transparent square
+#[expect(clippy::unwrap_used)]
+fn square(source: &DynamicImage, size: u32) -> RgbaImage {
+	let chain: TransformerChain = format!("maxdim({size},{size})").parse().unwrap();
+	let image = chain.transform_image(source.clone()).into_rgba8();
+
+	let mut out = RgbaImage::new(size, size);
+	let x = size.saturating_sub(image.width()) / 2;
+	let y = size.saturating_sub(image.height()) / 2;
+	imageops::overlay(&mut out, &image, i64::from(x), i64::from(y));
+	out
+}
+
+/// A [Servable] that serves one icon of a [Favicons]
+struct FaviconServable {
+	favicons: Favicons,
+
+	/// An index into [FAVICON_PNGS], or `None` for `/favicon.ico`
+	png: Option<usize>,
+}
+
+impl FaviconServable {
+	async fn get(&self, ctx: &RenderContext) -> Rendered<RenderedBody> {
+		let generated = match self.favicons.generated(ctx).await {
+			Ok(x) => x,
+			Err(rend) => return rend.with_body(RenderedBody::Empty),
+		};
+
+		#[expect(clippy::unwrap_used)]
+		let (mime, body): (Mime, _) = match self.png {
+			Some(i) => (mime::IMAGE_PNG, generated.pngs[i].clone()),
+			None => ("image/x-icon".parse().unwrap(), generated.ico.clone()),
+		};
+
+		Rendered {
+			code: StatusCode::OK,
+			headers: HeaderMap::new(),
+			content_length: Some(body.len() as u64),
+			body: RenderedBody::Bytes(body),
+			ttl: self.favicons.ttl,
+			private: false,
+			mime: Some(mime),
+		}
+	}
+}
+
+impl Servable for FaviconServable {
+	fn head<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<()>> + 'a + Send + Sync>> {
+		Box::pin(async { head_of(self.get(ctx).await) })
+	}
+
+	fn render<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<RenderedBody>> + 'a + Send + Sync>> {
+		Box::pin(self.get(ctx))
+	}
+
+	fn header_template(&self) -> HeaderMap {
+		let mut headers = HeaderMap::with_capacity(1);
+		headers.insert(
+			header::CACHE_CONTROL,
+			cache_control(self.favicons.ttl, false),
+		);
+		headers
+	}
+}
+
+impl ServableRouter {
+	/// Serve every icon in `favicons` at [Favicons::routes].
+	/// - panics if one of these routes is already taken, like [Self::add_page].
+	///
+	/// See [Self::try_add_favicons] for a version of this method that does not panic.
+	pub fn add_favicons(self, favicons: &Favicons) -> Self {
+		match self.try_add_favicons(favicons) {
+			Ok(x) => x,
+			Err(err) => panic!("{err}"),
+		}
+	}
+
+	/// Serve every icon in `favicons`.
+	/// Behaves exactly like [Self::add_favicons], but returns an [Error] instead of panicking.
+	pub fn try_add_favicons(mut self, favicons: &Favicons) -> Result<Self, Error> {
+		self = self.try_add_page(
+			"/favicon.ico",
+			FaviconServable {
+				favicons: favicons.clone(),
+				png: None,
+			},
+		)?;
+
+		for (i, (route, _)) in FAVICON_PNGS.iter().enumerate() {
+			self = self.try_add_page(
+				*route,
+				FaviconServable {
+					favicons: favicons.clone(),
+					png: Some(i),
+				},
+			)?;
+		}
+
+		Ok(self)
+	}
+}
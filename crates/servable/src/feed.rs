@@ -0,0 +1,600 @@
+use axum::http::{HeaderMap, HeaderValue, StatusCode, header};
+use chrono::{DateTime, TimeDelta, Utc};
+use mime::Mime;
+use serde::Serialize;
+use std::{fmt::Write, pin::Pin, sync::Arc};
+
+use crate::{
+	Error, PageMetadata, RenderContext, RenderMode, Rendered, RenderedBody, ServableRouter,
+	negotiated::quality, router::cache_control, servable::Servable,
+};
+
+/// One entry of a [Feed], like a blog post
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeedItem {
+	/// A permanent, unique id for this item.
+	/// This is `url` by default.
+	pub id: String,
+
+	/// The url of this item, like `/posts/hello`
+	pub url: String,
+
+	/// The title of this item
+	pub title: String,
+
+	/// When this item was last changed
+	pub updated: DateTime<Utc>,
+
+	/// A short summary of this item, in plain text
+	pub summary: Option<String>,
+
+	/// The full content of this item, in html
+	pub content_html: Option<String>,
+
+	/// The name of this item's author
+	pub author: Option<String>,
+}
+
+impl FeedItem {
+	/// Create a [FeedItem] for the page at `url`
+	pub fn new(url: impl Into<String>, title: impl Into<String>, updated: DateTime<Utc>) -> Self {
+		let url = url.into();
+		Self {
+			id: url.clone(),
+			url,
+			title: title.into(),
+			updated,
+			summary: None,
+			content_html: None,
+			author: None,
+		}
+	}
+
+	/// Create a [FeedItem] for an [crate::HtmlPage] at `url`,
+	/// with the title, description and author of its `meta`.
+	pub fn from_meta(url: impl Into<String>, meta: &PageMetadata, updated: DateTime<Utc>) -> Self {
+		Self {
+			summary: meta.description.clone(),
+			author: meta.author.clone(),
+			..Self::new(url, meta.title.clone(), updated)
+		}
+	}
+
+	/// Set `self.id`
+	#[inline(always)]
+	pub fn with_id(mut self, id: impl Into<String>) -> Self {
+		self.id = id.into();
+		self
+	}
+
+	/// Set `self.summary`
+	#[inline(always)]
+	pub fn with_summary(mut self, summary: impl Into<String>) -> Self {
+		self.summary = Some(summary.into());
+		self
+	}
+
+	/// Set `self.content_html`
+	#[inline(always)]
+	pub fn with_content_html(mut self, content_html: impl Into<String>) -> Self {
+		self.content_html = Some(content_html.into());
+		self
+	}
+
+	/// Set `self.author`
+	#[inline(always)]
+	pub fn with_author(mut self, author: impl Into<String>) -> Self {
+		self.author = Some(author.into());
+		self
+	}
+}
+
+/// A format a [Feed] may be served in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FeedFormat {
+	/// [Atom](https://www.rfc-editor.org/rfc/rfc4287) (`.atom`)
+	Atom,
+
+	/// [RSS 2.0](https://www.rssboard.org/rss-specification) (`.rss`)
+	Rss,
+
+	/// [JSON Feed 1.1](https://www.jsonfeed.org/version/1.1/) (`.json`)
+	Json,
+}
+
+impl FeedFormat {
+	/// Every format, in order of preference
+	pub const ALL: [Self; 3] = [Self::Atom, Self::Rss, Self::Json];
+
+	/// The extension of routes that serve this format, without a dot
+	pub fn extension(&self) -> &'static str {
+		match self {
+			Self::Atom => "atom",
+			Self::Rss => "rss",
+			Self::Json => "json",
+		}
+	}
+
+	/// The mime type of this format
+	pub fn mime(&self) -> Mime {
+		match self {
+			#[expect(clippy::unwrap_used)]
+			Self::Atom => "application/atom+xml".parse().unwrap(),
+			#[expect(clippy::unwrap_used)]
+			Self::Rss => "application/rss+xml".parse().unwrap(),
+			#[expect(clippy::unwrap_used)]
+			Self::Json => "application/feed+json".parse().unwrap(),
+		}
+	}
+
+	/// The format a client with the given `Accept` header prefers.
+	/// Returns `None` if it prefers none of them.
+	fn negotiate(accept: &str) -> Option<Self> {
+		let mut best: Option<(Self, f32)> = None;
+		for format in Self::ALL {
+			let q = match format {
+				Self::Json => quality(accept, "application/feed+json")
+					.max(quality(accept, "application/json")),
+				_ => quality(accept, format.mime().essence_str()),
+			};
+
+			if q > 0.0 && best.is_none_or(|(_, x)| q > x) {
+				best = Some((format, q));
+			}
+		}
+
+		best.map(|(x, _)| x)
+	}
+}
+
+/// The closure that lists the items of a [Feed]
+pub type FeedItemsFn = dyn Send
+	+ Sync
+	+ 'static
+	+ for<'a> Fn(&'a RenderContext) -> Pin<Box<dyn Future<Output = Vec<FeedItem>> + Send + Sync + 'a>>;
+
+/// A feed of items, like blog posts, served as Atom, RSS and JSON Feed
+/// from one definition. See [ServableRouter::add_feed].
+///
+/// Relative urls in items are made absolute with [RenderContext::absolute_url],
+/// so routers that serve feeds should have a base url
+/// (see [ServableRouter::with_base_url]).
+///
+/// ```rust
+/// use chrono::{TimeZone, Utc};
+/// use servable::{Feed, FeedItem, HtmlPage, PageMetadata, ServableRouter};
+///
+/// let hello = HtmlPage::default().with_meta(PageMetadata {
+/// 	title: "Hello".into(),
+/// 	description: Some("My first post".into()),
+/// 	..Default::default()
+/// });
+/// let published = Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap();
+/// let item = FeedItem::from_meta("/posts/hello", &hello.meta, published);
+///
+/// let feed = Feed::new("My blog", "/").with_items(move |_ctx| {
+/// 	let items = vec![item.clone()];
+/// 	Box::pin(async move { items })
+/// });
+///
+/// // Serves `/feed`, `/feed.atom`, `/feed.rss` and `/feed.json`
+/// let router = ServableRouter::new()
+/// 	.with_base_url("https://example.com")
+/// 	.add_page("/posts/hello", hello)
+/// 	.add_feed("/feed", feed);
+/// ```
+#[derive(Clone)]
+pub struct Feed {
+	/// The title of this feed
+	pub title: String,
+
+	/// The page this feed belongs to, like the index of a blog
+	pub home: String,
+
+	/// A description of this feed, in plain text
+	pub description: Option<String>,
+
+	/// The name of this feed's author,
+	/// used for items without an author
+	pub author: Option<String>,
+
+	/// How long this feed may be cached.
+	/// If `None`, this feed is never cached.
+	pub ttl: Option<TimeDelta>,
+
+	/// Lists the items of this feed, newest first
+	pub items: Arc<FeedItemsFn>,
+}
+
+impl Feed {
+	/// Create an empty [Feed] for the page at `home`
+	pub fn new(title: impl Into<String>, home: impl Into<String>) -> Self {
+		Self {
+			title: title.into(),
+			home: home.into(),
+			description: None,
+			author: None,
+			ttl: Some(TimeDelta::hours(1)),
+			items: Arc::new(|_| Box::pin(async { Vec::new() })),
+		}
+	}
+
+	/// Set `self.description`
+	#[inline(always)]
+	pub fn with_description(mut self, description: impl Into<String>) -> Self {
+		self.description = Some(description.into());
+		self
+	}
+
+	/// Set `self.author`
+	#[inline(always)]
+	pub fn with_author(mut self, author: impl Into<String>) -> Self {
+		self.author = Some(author.into());
+		self
+	}
+
+	/// Set `self.ttl`
+	#[inline(always)]
+	pub fn with_ttl(mut self, ttl: Option<TimeDelta>) -> Self {
+		self.ttl = ttl;
+		self
+	}
+
+	/// Set the function that lists this feed's items
+	#[inline(always)]
+	pub fn with_items<F>(mut self, items: F) -> Self
+	where
+		F: Send
+			+ Sync
+			+ 'static
+			+ for<'a> Fn(
+				&'a RenderContext,
+			) -> Pin<Box<dyn Future<Output = Vec<FeedItem>> + Send + Sync + 'a>>,
+	{
+		self.items = Arc::new(items);
+		self
+	}
+
+	/// Render `items` as `format`.
+	/// `url` is the absolute url of the feed itself.
+	pub fn render_as(
+		&self,
+		format: FeedFormat,
+		items: &[FeedItem],
+		url: &str,
+		ctx: &RenderContext,
+	) -> String {
+		match format {
+			FeedFormat::Atom => self.atom(items, url, ctx),
+			FeedFormat::Rss => self.rss(items, url, ctx),
+			FeedFormat::Json => self.json(items, url, ctx),
+		}
+	}
+
+	/// When this feed last changed: when its newest item was updated
+	fn updated(items: &[FeedItem], ctx: &RenderContext) -> DateTime<Utc> {
+		items
+			.iter()
+			.map(|x| x.updated)
+			.max()
+			.unwrap_or_else(|| ctx.clock.now())
+	}
+
+	fn atom(&self, items: &[FeedItem], url: &str, ctx: &RenderContext) -> String {
+		let mut out = String::new();
+		out.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+		out.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+		let _ = writeln!(out, "<title>{}</title>", escape(&self.title));
+		let _ = writeln!(out, "<id>{}</id>", escape(url));
+		let _ = writeln!(out, "<link rel=\"self\" href=\"{}\"/>", escape(url));
+		let _ = writeln!(
+			out,
+			"<link rel=\"alternate\" type=\"text/html\" href=\"{}\"/>",
+			escape(&ctx.absolute_url(&self.home))
+		);
+		let _ = writeln!(
+			out,
+			"<updated>{}</updated>",
+			Self::updated(items, ctx).to_rfc3339()
+		);
+		if let Some(description) = &self.description {
+			let _ = writeln!(out, "<subtitle>{}</subtitle>", escape(description));
+		}
+		if let Some(author) = &self.author {
+			let _ = writeln!(out, "<author><name>{}</name></author>", escape(author));
+		}
+
+		for item in items {
+			out.push_str("<entry>\n");
+			let _ = writeln!(out, "<title>{}</title>", escape(&item.title));
+			let _ = writeln!(out, "<id>{}</id>", escape(&ctx.absolute_url(&item.id)));
+			let _ = writeln!(
+				out,
+				"<link rel=\"alternate\" type=\"text/html\" href=\"{}\"/>",
+				escape(&ctx.absolute_url(&item.url))
+			);
+			let _ = writeln!(out, "<updated>{}</updated>", item.updated.to_rfc3339());
+			if let Some(summary) = &item.summary {
+				let _ = writeln!(out, "<summary>{}</summary>", escape(summary));
+			}
+			if let Some(content) = &item.content_html {
+				let _ = writeln!(out, "<content type=\"html\">{}</content>", escape(content));
+			}
+			if let Some(author) = &item.author {
+				let _ = writeln!(out, "<author><name>{}</name></author>", escape(author));
+			}
+			out.push_str("</entry>\n");
+		}
+
+		out.push_str("</feed>\n");
+		out
+	}
+
+	fn rss(&self, items: &[FeedItem], url: &str, ctx: &RenderContext) -> String {
+		let mut out = String::new();
+		out.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+		out.push_str(concat!(
+			"<rss version=\"2.0\" xmlns:atom=\"http://www.w3.org/2005/Atom\"",
+			" xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n"
+		));
+		out.push_str("<channel>\n");
+		let _ = writeln!(out, "<title>{}</title>", escape(&self.title));
+		let _ = writeln!(
+			out,
+			"<link>{}</link>",
+			escape(&ctx.absolute_url(&self.home))
+		);
+		let _ = writeln!(
+			out,
+			"<description>{}</description>",
+			escape(self.description.as_ref().unwrap_or(&self.title))
+		);
+		let _ = writeln!(
+			out,
+			"<atom:link href=\"{}\" rel=\"self\" type=\"application/rss+xml\"/>",
+			escape(url)
+		);
+		let _ = writeln!(
+			out,
+			"<lastBuildDate>{}</lastBuildDate>",
+			Self::updated(items, ctx).to_rfc2822()
+		);
+
+		for item in items {
+			out.push_str("<item>\n");
+			let _ = writeln!(out, "<title>{}</title>", escape(&item.title));
+			let _ = writeln!(out, "<link>{}</link>", escape(&ctx.absolute_url(&item.url)));
+			let _ = writeln!(
+				out,
+				"<guid isPermaLink=\"{}\">{}</guid>",
+				item.id == item.url,
+				escape(&ctx.absolute_url(&item.id))
+			);
+			let _ = writeln!(out, "<pubDate>{}</pubDate>", item.updated.to_rfc2822());
+			if let Some(description) = item.content_html.as_ref().or(item.summary.as_ref()) {
+				let _ = writeln!(out, "<description>{}</description>", escape(description));
+			}
+			if let Some(author) = item.author.as_ref().or(self.author.as_ref()) {
+				let _ = writeln!(out, "<dc:creator>{}</dc:creator>", escape(author));
+			}
+			out.push_str("</item>\n");
+		}
+
+		out.push_str("</channel>\n</rss>\n");
+		out
+	}
+
+	fn json(&self, items: &[FeedItem], url: &str, ctx: &RenderContext) -> String {
+		#[derive(Serialize)]
+		struct Author<'a> {
+			name: &'a str,
+		}
+
+		#[derive(Serialize)]
+		struct Item<'a> {
+			id: String,
+			url: String,
+			title: &'a str,
+			#[serde(skip_serializing_if = "Option::is_none")]
+			summary: Option<&'a str>,
+			#[serde(skip_serializing_if = "Option::is_none")]
+			content_html: Option<&'a str>,
+			#[serde(skip_serializing_if = "Option::is_none")]
+			content_text: Option<&'a str>,
+			date_modified: String,
+			#[serde(skip_serializing_if = "Vec::is_empty")]
+			authors: Vec<Author<'a>>,
+		}
+
+		#[derive(Serialize)]
+		struct JsonFeed<'a> {
+			version: &'static str,
+			title: &'a str,
+			home_page_url: String,
+			feed_url: &'a str,
+			#[serde(skip_serializing_if = "Option::is_none")]
+			description: Option<&'a str>,
+			#[serde(skip_serializing_if = "Vec::is_empty")]
+			authors: Vec<Author<'a>>,
+			items: Vec<Item<'a>>,
+		}
+
+		let feed = JsonFeed {
+			version: "https://jsonfeed.org/version/1.1",
+			title: &self.title,
+			home_page_url: ctx.absolute_url(&self.home),
+			feed_url: url,
+			description: self.description.as_deref(),
+			authors: self.author.iter().map(|x| Author { name: x }).collect(),
+			items: items
+				.iter()
+				.map(|x| Item {
+					id: ctx.absolute_url(&x.id),
+					url: ctx.absolute_url(&x.url),
+					title: &x.title,
+					summary: x.summary.as_deref(),
+					content_html: x.content_html.as_deref(),
+					// Every item needs content
+					content_text: match x.content_html {
+						Some(_) => None,
+						None => Some(x.summary.as_deref().unwrap_or_default()),
+					},
+					date_modified: x.updated.to_rfc3339(),
+					authors: x.author.iter().map(|x| Author { name: x }).collect(),
+				})
+				.collect(),
+		};
+
+		serde_json::to_string(&feed).unwrap_or_default()
+	}
+}
+
+/// Escape `s` for use in xml text and attributes
+fn escape(s: &str) -> String {
+	let mut out = String::with_capacity(s.len());
+	for c in s.chars() {
+		match c {
+			'&' => out.push_str("&amp;"),
+			'<' => out.push_str("&lt;"),
+			'>' => out.push_str("&gt;"),
+			'"' => out.push_str("&quot;"),
+			'\'' => out.push_str("&apos;"),
+			// Not allowed in xml 1.0
+			c if c.is_control() && !matches!(c, '\t' | '\n' | '\r') => {}
+			c => out.push(c),
+		}
+	}
+	out
+}
+
+/// A [Servable] that serves a [Feed] at one route.
+/// [ServableRouter::add_feed] serves a feed at several routes, one for each format.
+///
+/// By default, clients get the format they prefer by their `Accept` header (Atom by default).
+///
+/// ```rust
+/// use servable::{Feed, FeedFormat, FeedServable, ServableRouter};
+///
+/// let feed = Feed::new("My blog", "/");
+/// let router = ServableRouter::new()
+/// 	.add_page("/rss.xml", FeedServable::new(feed).with_format(Some(FeedFormat::Rss)));
+/// ```
+pub struct FeedServable {
+	feed: Feed,
+
+	/// If `None`, negotiate
+	format: Option<FeedFormat>,
+}
+
+impl FeedServable {
+	/// Serve `feed`
+	#[inline(always)]
+	pub fn new(feed: Feed) -> Self {
+		Self { feed, format: None }
+	}
+
+	/// Always serve this feed as `format`.
+	/// If `None`, serve the format the client prefers.
+	#[inline(always)]
+	pub fn with_format(mut self, format: Option<FeedFormat>) -> Self {
+		self.format = format;
+		self
+	}
+
+	/// The format `ctx` should get
+	fn format(&self, ctx: &RenderContext) -> FeedFormat {
+		self.format
+			.or_else(|| {
+				ctx.headers
+					.get(header::ACCEPT)
+					.and_then(|x| x.to_str().ok())
+					.and_then(FeedFormat::negotiate)
+			})
+			.unwrap_or(FeedFormat::Atom)
+	}
+
+	fn head_as(&self, format: FeedFormat) -> Rendered<()> {
+		let mut headers = HeaderMap::new();
+		if self.format.is_none() {
+			headers.insert(header::VARY, HeaderValue::from_static("Accept"));
+		}
+
+		Rendered {
+			code: StatusCode::OK,
+			headers,
+			body: (),
+			mime: Some(format.mime()),
+			ttl: self.feed.ttl,
+			private: false,
+			content_length: None,
+		}
+	}
+}
+
+impl Servable for FeedServable {
+	fn head<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<()>> + 'a + Send + Sync>> {
+		Box::pin(async { self.head_as(self.format(ctx)) })
+	}
+
+	fn render<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<RenderedBody>> + 'a + Send + Sync>> {
+		Box::pin(async {
+			let format = self.format(ctx);
+			let rend = self.head_as(format);
+			if ctx.mode == RenderMode::Head {
+				return rend.with_body(RenderedBody::Empty);
+			}
+
+			let items = (self.feed.items)(ctx).await;
+			let url = ctx.absolute_url(&ctx.route);
+			let body = self.feed.render_as(format, &items, &url, ctx);
+			rend.with_body(RenderedBody::String(body))
+		})
+	}
+
+	fn header_template(&self) -> HeaderMap {
+		let mut headers = HeaderMap::with_capacity(1);
+		headers.insert(header::CACHE_CONTROL, cache_control(self.feed.ttl, false));
+		headers
+	}
+
+	#[inline(always)]
+	fn linked_routes(&self) -> Vec<String> {
+		vec![self.feed.home.clone()]
+	}
+}
+
+impl ServableRouter {
+	/// Serve `feed` at `route` in every [FeedFormat].
+	/// `route` serves the format the client prefers by its `Accept` header (Atom by default),
+	/// and `route` with a format's extension, like `/feed.rss`, always serves that format.
+	///
+	/// - panics if one of these routes is invalid or already taken, like [Self::add_page].
+	///
+	/// See [Self::try_add_feed] for a version of this method that does not panic.
+	pub fn add_feed(self, route: impl Into<String>, feed: Feed) -> Self {
+		match self.try_add_feed(route, feed) {
+			Ok(x) => x,
+			Err(err) => panic!("{err}"),
+		}
+	}
+
+	/// Serve `feed` in every [FeedFormat].
+	/// Behaves exactly like [Self::add_feed], but returns an [Error] instead of panicking.
+	pub fn try_add_feed(mut self, route: impl Into<String>, feed: Feed) -> Result<Self, Error> {
+		let route = route.into();
+		for format in FeedFormat::ALL {
+			self = self.try_add_page(
+				format!("{route}.{}", format.extension()),
+				FeedServable::new(feed.clone()).with_format(Some(format)),
+			)?;
+		}
+
+		self.try_add_page(route, FeedServable::new(feed))
+	}
+}
@@ -0,0 +1,124 @@
+use axum::http::{HeaderMap, HeaderName, StatusCode, header};
+
+use crate::normalize::has_route_prefix;
+
+/// Rules that reject requests before they reach a page,
+/// see [crate::ServableRouter::with_request_filter].
+///
+/// This is basic scraper hygiene, not a replacement for a real firewall:
+/// every rule here is trivially bypassed by a client that sends the right headers.
+///
+/// ```rust
+/// use servable::{HtmlPage, RequestFilter, ServableRouter};
+/// use axum::http::HeaderName;
+///
+/// let filter = RequestFilter::default()
+/// 	.with_block_empty_user_agent(true)
+/// 	.with_blocked_user_agent("GPTBot")
+/// 	.with_required_header("/api", HeaderName::from_static("x-api-key"));
+///
+/// let router = ServableRouter::new()
+/// 	.add_page("/", HtmlPage::default())
+/// 	.with_request_filter(filter);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestFilter {
+	/// If true, reject requests with a missing or empty `User-Agent`
+	pub block_empty_user_agent: bool,
+
+	/// Reject requests whose `User-Agent` contains any of these strings.
+	/// Matching is case-insensitive.
+	pub blocked_user_agents: Vec<String>,
+
+	/// `(route prefix, header)` pairs.
+	/// Requests to routes that start with `route prefix` must have `header`.
+	///
+	/// A prefix matches whole path segments:
+	/// `/api` matches `/api` and `/api/x`, but not `/apis`.
+	pub required_headers: Vec<(String, HeaderName)>,
+
+	/// The status code of rejected requests.
+	/// This is usually `403 Forbidden` or `429 Too Many Requests`.
+	pub status: StatusCode,
+}
+
+impl Default for RequestFilter {
+	/// A [RequestFilter] that doesn't reject any requests
+	fn default() -> Self {
+		Self {
+			block_empty_user_agent: false,
+			blocked_user_agents: Vec::new(),
+			required_headers: Vec::new(),
+			status: StatusCode::FORBIDDEN,
+		}
+	}
+}
+
+impl RequestFilter {
+	/// Set `self.block_empty_user_agent`
+	#[inline(always)]
+	pub fn with_block_empty_user_agent(mut self, block_empty_user_agent: bool) -> Self {
+		self.block_empty_user_agent = block_empty_user_agent;
+		self
+	}
+
+	/// Reject requests whose `User-Agent` contains `signature`
+	#[inline(always)]
+	pub fn with_blocked_user_agent(mut self, signature: impl Into<String>) -> Self {
+		self.blocked_user_agents.push(signature.into());
+		self
+	}
+
+	/// Reject requests to routes under `route_prefix` that don't have `header`
+	#[inline(always)]
+	pub fn with_required_header(
+		mut self,
+		route_prefix: impl Into<String>,
+		header: HeaderName,
+	) -> Self {
+		self.required_headers.push((route_prefix.into(), header));
+		self
+	}
+
+	/// Set `self.status`
+	#[inline(always)]
+	pub fn with_status(mut self, status: StatusCode) -> Self {
+		self.status = status;
+		self
+	}
+
+	/// Check a request for `route`.
+	/// Returns the reason the request was rejected, if it was.
+	pub(crate) fn check(&self, route: &str, headers: &HeaderMap) -> Option<&'static str> {
+		let ua = headers
+			.get(header::USER_AGENT)
+			.and_then(|x| x.to_str().ok())
+			.map(str::trim)
+			.unwrap_or("");
+
+		if self.block_empty_user_agent && ua.is_empty() {
+			return Some("empty user agent");
+		}
+
+		if !self.blocked_user_agents.is_empty() {
+			let ua = ua.to_lowercase();
+			if self
+				.blocked_user_agents
+				.iter()
+				.any(|x| !x.is_empty() && ua.contains(&x.to_lowercase()))
+			{
+				return Some("blocked user agent");
+			}
+		}
+
+		let missing = self
+			.required_headers
+			.iter()
+			.any(|(prefix, name)| has_route_prefix(route, prefix) && !headers.contains_key(name));
+		if missing {
+			return Some("missing required header");
+		}
+
+		None
+	}
+}
@@ -0,0 +1,333 @@
+//! Helpers for serving web fonts.
+//!
+//! Fonts rarely change, so [FontAsset]s are cached for a year and marked `immutable`.
+//! Serve them at a cache-busted route (see [crate::CACHE_BUST_STR] and
+//! [crate::ServableWithRoute]), and use [FontFace] to generate matching css.
+//!
+//! ```rust
+//! use servable::{CACHE_BUST_STR, HtmlPage, ServableRouter, ServableWithRoute};
+//! use servable::fonts::{FontAsset, FontFace, FontFormat};
+//!
+//! static INTER: ServableWithRoute<FontAsset> = ServableWithRoute::new(
+//! 	|| format!("/{}/inter.woff2", *CACHE_BUST_STR),
+//! 	FontAsset::new(b"fake font data", FontFormat::Woff2),
+//! );
+//!
+//! let face = FontFace::new("Inter", INTER.route(), FontFormat::Woff2).with_weight("100 900");
+//! let page = HtmlPage::default().with_style_inline(face.css());
+//!
+//! let router = ServableRouter::new()
+//! 	.add_page_with_route(&INTER)
+//! 	.add_page("/", page);
+//! ```
+
+use axum::http::{HeaderMap, HeaderValue, StatusCode, header};
+use chrono::TimeDelta;
+use mime::Mime;
+use std::{fmt::Write, pin::Pin};
+
+use crate::{RenderContext, Rendered, RenderedBody, servable::Servable};
+
+/// A font file format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FontFormat {
+	/// Web Open Font Format 2 (`.woff2`).
+	/// Prefer this format, it is the smallest and is supported everywhere.
+	Woff2,
+
+	/// Web Open Font Format (`.woff`)
+	Woff,
+
+	/// TrueType (`.ttf`)
+	TrueType,
+
+	/// OpenType (`.otf`)
+	OpenType,
+}
+
+impl FontFormat {
+	/// The mime type of this format
+	pub fn mime(&self) -> Mime {
+		match self {
+			Self::Woff2 => mime::FONT_WOFF2,
+			Self::Woff => mime::FONT_WOFF,
+			#[expect(clippy::unwrap_used)]
+			Self::TrueType => "font/ttf".parse().unwrap(),
+			#[expect(clippy::unwrap_used)]
+			Self::OpenType => "font/otf".parse().unwrap(),
+		}
+	}
+
+	/// The name of this format in a css `format()` hint
+	pub fn css_name(&self) -> &'static str {
+		match self {
+			Self::Woff2 => "woff2",
+			Self::Woff => "woff",
+			Self::TrueType => "truetype",
+			Self::OpenType => "opentype",
+		}
+	}
+}
+
+//
+// MARK: FontAsset
+//
+
+/// A static font file.
+///
+/// Responses are cached for [FontAsset::TTL] and marked `immutable`,
+/// so fonts should be served at a cache-busted route.
+pub struct FontAsset {
+	/// The font file
+	pub bytes: &'static [u8],
+
+	/// The format of `bytes`
+	pub format: FontFormat,
+}
+
+impl FontAsset {
+	/// How long fonts may be cached
+	pub const TTL: TimeDelta = TimeDelta::days(365);
+
+	/// Create a new [FontAsset]
+	pub const fn new(bytes: &'static [u8], format: FontFormat) -> Self {
+		Self { bytes, format }
+	}
+}
+
+impl Servable for FontAsset {
+	fn head<'a>(
+		&'a self,
+		_ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<()>> + 'a + Send + Sync>> {
+		Box::pin(async {
+			return Rendered {
+				code: StatusCode::OK,
+				body: (),
+				ttl: Some(Self::TTL),
+				private: false,
+
+				headers: HeaderMap::new(),
+				mime: Some(self.format.mime()),
+				content_length: Some(self.bytes.len() as u64),
+			};
+		})
+	}
+
+	fn render<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<RenderedBody>> + 'a + Send + Sync>> {
+		Box::pin(async {
+			self.head(ctx)
+				.await
+				.with_body(RenderedBody::Static(self.bytes))
+		})
+	}
+
+	fn header_template(&self) -> HeaderMap {
+		let mut headers = HeaderMap::with_capacity(2);
+
+		#[expect(clippy::unwrap_used)]
+		headers.insert(
+			header::CACHE_CONTROL,
+			HeaderValue::from_str(&format!(
+				"public, max-age={}, immutable",
+				Self::TTL.num_seconds()
+			))
+			.unwrap(),
+		);
+
+		if let Ok(mime) = HeaderValue::from_str(self.format.mime().as_ref()) {
+			headers.insert(header::CONTENT_TYPE, mime);
+		}
+
+		headers
+	}
+}
+
+//
+// MARK: FontFace
+//
+
+/// Possible values of the css `font-display` descriptor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum FontDisplay {
+	/// Let the browser decide
+	Auto,
+
+	/// Hide text for a short time while the font loads
+	Block,
+
+	/// Show text in a fallback font until this font loads
+	#[default]
+	Swap,
+
+	/// Like swap, but give up on this font if it takes too long to load
+	Fallback,
+
+	/// Only use this font if it is available almost immediately
+	Optional,
+}
+
+impl FontDisplay {
+	/// The css value of this descriptor
+	pub fn css_name(&self) -> &'static str {
+		match self {
+			Self::Auto => "auto",
+			Self::Block => "block",
+			Self::Swap => "swap",
+			Self::Fallback => "fallback",
+			Self::Optional => "optional",
+		}
+	}
+}
+
+/// A css `@font-face` rule.
+///
+/// ```rust
+/// use servable::fonts::{FontFace, FontFormat};
+///
+/// let face = FontFace::new("Inter", "/inter-latin.woff2", FontFormat::Woff2)
+/// 	.with_unicode_range("U+0000-00FF");
+///
+/// assert_eq!(
+/// 	face.css(),
+/// 	"@font-face{font-family:\"Inter\";\
+/// 	src:url(\"/inter-latin.woff2\") format(\"woff2\");\
+/// 	font-display:swap;unicode-range:U+0000-00FF;}"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FontFace {
+	/// The name of this font family
+	pub family: String,
+
+	/// Urls this font may be loaded from, in order of preference
+	pub src: Vec<(String, FontFormat)>,
+
+	/// The `font-weight` descriptor, like `400` or `100 900`
+	pub weight: Option<String>,
+
+	/// The `font-style` descriptor, like `italic`
+	pub style: Option<String>,
+
+	/// The `font-display` descriptor
+	pub display: FontDisplay,
+
+	/// The `unicode-range` descriptor, like `U+0000-00FF, U+0131`.
+	///
+	/// Split large fonts into files that cover a few ranges each,
+	/// and add one [FontFace] per file. Browsers only download
+	/// the files with characters that are used on a page.
+	pub unicode_range: Option<String>,
+}
+
+impl FontFace {
+	/// Create a new [FontFace] that loads `family` from `url`.
+	/// `font-display` is [FontDisplay::Swap].
+	pub fn new(family: impl Into<String>, url: impl Into<String>, format: FontFormat) -> Self {
+		Self {
+			family: family.into(),
+			src: vec![(url.into(), format)],
+			weight: None,
+			style: None,
+			display: FontDisplay::default(),
+			unicode_range: None,
+		}
+	}
+
+	/// Add a url to `self.src` (after existing urls)
+	#[inline(always)]
+	pub fn with_src(mut self, url: impl Into<String>, format: FontFormat) -> Self {
+		self.src.push((url.into(), format));
+		self
+	}
+
+	/// Set `self.weight`
+	#[inline(always)]
+	pub fn with_weight(mut self, weight: impl Into<String>) -> Self {
+		self.weight = Some(weight.into());
+		self
+	}
+
+	/// Set `self.style`
+	#[inline(always)]
+	pub fn with_style(mut self, style: impl Into<String>) -> Self {
+		self.style = Some(style.into());
+		self
+	}
+
+	/// Set `self.display`
+	#[inline(always)]
+	pub fn with_display(mut self, display: FontDisplay) -> Self {
+		self.display = display;
+		self
+	}
+
+	/// Set `self.unicode_range`
+	#[inline(always)]
+	pub fn with_unicode_range(mut self, unicode_range: impl Into<String>) -> Self {
+		self.unicode_range = Some(unicode_range.into());
+		self
+	}
+
+	/// Generate this `@font-face` rule
+	pub fn css(&self) -> String {
+		let mut out = String::with_capacity(128);
+		out.push_str("@font-face{font-family:");
+		push_css_string(&mut out, &self.family);
+		out.push_str(";src:");
+
+		for (i, (url, format)) in self.src.iter().enumerate() {
+			if i != 0 {
+				out.push(',');
+			}
+			out.push_str("url(");
+			push_css_string(&mut out, url);
+			let _ = write!(out, ") format(\"{}\")", format.css_name());
+		}
+
+		if let Some(weight) = &self.weight {
+			let _ = write!(out, ";font-weight:{}", strip_css(weight));
+		}
+
+		if let Some(style) = &self.style {
+			let _ = write!(out, ";font-style:{}", strip_css(style));
+		}
+
+		let _ = write!(out, ";font-display:{}", self.display.css_name());
+
+		if let Some(range) = &self.unicode_range {
+			let _ = write!(out, ";unicode-range:{}", strip_css(range));
+		}
+
+		out.push_str(";}");
+		out
+	}
+}
+
+/// Append `s` to `out` as a quoted css string
+fn push_css_string(out: &mut String, s: &str) {
+	out.push('"');
+	for c in s.chars() {
+		match c {
+			'"' | '\\' => {
+				out.push('\\');
+				out.push(c);
+			}
+			// Never let user strings close a <style> tag
+			'<' => out.push_str("\\3C "),
+			'\n' | '\r' => {}
+			c => out.push(c),
+		}
+	}
+	out.push('"');
+}
+
+/// Remove characters that could end a css declaration from `s`
+fn strip_css(s: &str) -> String {
+	s.chars()
+		.filter(|c| !matches!(c, ';' | '{' | '}' | '<' | '\n' | '\r'))
+		.collect()
+}
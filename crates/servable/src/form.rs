@@ -0,0 +1,95 @@
+use std::{collections::BTreeMap, pin::Pin};
+
+use crate::{RenderContext, Rendered, RenderedBody, servable::Servable};
+
+/// The fields of a submitted form
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct FormData {
+	/// The fields in this form.
+	/// If a field is submitted more than once, this is its last value.
+	pub fields: BTreeMap<String, String>,
+}
+
+impl FormData {
+	/// Parse an `application/x-www-form-urlencoded` body
+	pub fn from_urlencoded(body: &[u8]) -> Result<Self, serde_urlencoded::de::Error> {
+		Ok(Self {
+			fields: serde_urlencoded::from_bytes(body)?,
+		})
+	}
+
+	/// Get the value of a field
+	#[inline(always)]
+	pub fn get(&self, name: &str) -> Option<&str> {
+		self.fields.get(name).map(|x| x.as_str())
+	}
+}
+
+/// A [Servable] that also accepts `POST` requests.
+/// Add these to a router with [crate::ServableRouter::add_form].
+///
+/// Only `application/x-www-form-urlencoded` bodies are accepted,
+/// which is what html forms and htmx send by default.
+///
+/// ```rust
+/// use servable::{
+/// 	FormData, FormServable, HtmlPage, RenderContext, Rendered, RenderedBody,
+/// 	Servable, ServableRouter,
+/// };
+/// use std::pin::Pin;
+///
+/// struct Contact {
+/// 	page: HtmlPage,
+/// }
+///
+/// impl Servable for Contact {
+/// 	fn head<'a>(
+/// 		&'a self,
+/// 		ctx: &'a RenderContext,
+/// 	) -> Pin<Box<dyn Future<Output = Rendered<()>> + 'a + Send + Sync>> {
+/// 		self.page.head(ctx)
+/// 	}
+///
+/// 	fn render<'a>(
+/// 		&'a self,
+/// 		ctx: &'a RenderContext,
+/// 	) -> Pin<Box<dyn Future<Output = Rendered<RenderedBody>> + 'a + Send + Sync>> {
+/// 		self.page.render(ctx)
+/// 	}
+/// }
+///
+/// impl FormServable for Contact {
+/// 	fn submit<'a>(
+/// 		&'a self,
+/// 		ctx: &'a RenderContext,
+/// 		form: &'a FormData,
+/// 	) -> Pin<Box<dyn Future<Output = Rendered<RenderedBody>> + 'a + Send + Sync>> {
+/// 		Box::pin(async move {
+/// 			let name = form.get("name").unwrap_or("stranger");
+/// 			let mut rend = self.page.head(ctx).await;
+/// 			rend.ttl = None;
+/// 			rend.private = true;
+/// 			rend.with_body(RenderedBody::String(format!("<p>Thanks, {name}!</p>")))
+/// 		})
+/// 	}
+/// }
+///
+/// let router = ServableRouter::new().add_form(
+/// 	"/contact",
+/// 	Contact {
+/// 		page: HtmlPage::default(),
+/// 	},
+/// );
+/// ```
+pub trait FormServable: Servable {
+	/// Respond to a submitted form.
+	///
+	/// This method is used to respond to `POST` requests.
+	/// Unlike [Servable::render], the [Servable::header_template]
+	/// of this page is not merged into these responses.
+	fn submit<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+		form: &'a FormData,
+	) -> Pin<Box<dyn Future<Output = Rendered<RenderedBody>> + 'a + Send + Sync>>;
+}
@@ -0,0 +1,155 @@
+use axum::http::{HeaderMap, header};
+use std::net::IpAddr;
+
+use crate::Error;
+
+/// A range of ip addresses, like `10.0.0.0/8`.
+/// See [crate::ServableRouter::with_trusted_proxy].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct Cidr {
+	addr: IpAddr,
+	prefix: u8,
+}
+
+impl Cidr {
+	/// Parse a range like `10.0.0.0/8` or `fd00::/8`.
+	/// A single address (like `127.0.0.1`) is a range that contains only that address.
+	pub fn parse(cidr: &str) -> Result<Self, Error> {
+		let invalid = |reason| Error::InvalidTrustedProxy {
+			cidr: cidr.to_owned(),
+			reason,
+		};
+
+		let (addr, prefix) = match cidr.split_once('/') {
+			Some((addr, prefix)) => (addr, Some(prefix)),
+			None => (cidr, None),
+		};
+
+		let addr: IpAddr = addr.parse().map_err(|_err| invalid("invalid ip address"))?;
+		let addr = addr.to_canonical();
+		let max = match addr {
+			IpAddr::V4(_) => 32,
+			IpAddr::V6(_) => 128,
+		};
+
+		let prefix = match prefix {
+			None => max,
+			Some(x) => x.parse().map_err(|_err| invalid("invalid prefix length"))?,
+		};
+
+		if prefix > max {
+			return Err(invalid("prefix length is too long"));
+		}
+
+		Ok(Self { addr, prefix })
+	}
+
+	/// Returns true if `ip` is in this range
+	pub fn contains(&self, ip: IpAddr) -> bool {
+		match (self.addr, ip.to_canonical()) {
+			(IpAddr::V4(a), IpAddr::V4(b)) => {
+				let mask = u32::MAX
+					.checked_shl(32 - u32::from(self.prefix))
+					.unwrap_or(0);
+				u32::from(a) & mask == u32::from(b) & mask
+			}
+			(IpAddr::V6(a), IpAddr::V6(b)) => {
+				let mask = u128::MAX
+					.checked_shl(128 - u32::from(self.prefix))
+					.unwrap_or(0);
+				u128::from(a) & mask == u128::from(b) & mask
+			}
+			_ => false,
+		}
+	}
+}
+
+/// Parse one node of a `Forwarded` or `X-Forwarded-For` header,
+/// like `192.0.2.1`, `192.0.2.1:8080`, `2001:db8::1` or `"[2001:db8::1]:8080"`.
+/// Returns `None` for obfuscated or unknown nodes.
+fn parse_node(node: &str) -> Option<IpAddr> {
+	let node = node.trim().trim_matches('"');
+
+	if let Some(rest) = node.strip_prefix('[') {
+		let (addr, _) = rest.split_once(']')?;
+		return addr.parse().ok();
+	}
+
+	if let Ok(x) = node.parse() {
+		return Some(x);
+	}
+
+	// An ipv4 address with a port
+	let (addr, _) = node.split_once(':')?;
+	addr.parse().ok()
+}
+
+/// The addresses in the `Forwarded` header, or in the `X-Forwarded-For` header
+/// if there is no `Forwarded` header, from first (the client) to last.
+fn forwarded_for(headers: &HeaderMap) -> Vec<Option<IpAddr>> {
+	let forwarded: Vec<&str> = headers
+		.get_all(header::FORWARDED)
+		.iter()
+		.filter_map(|x| x.to_str().ok())
+		.collect();
+
+	if !forwarded.is_empty() {
+		return forwarded
+			.iter()
+			.flat_map(|x| x.split(','))
+			.map(|element| {
+				element.split(';').find_map(|pair| {
+					let (key, value) = pair.split_once('=')?;
+					key.trim().eq_ignore_ascii_case("for").then_some(value)
+				})
+			})
+			.map(|x| x.and_then(parse_node))
+			.collect();
+	}
+
+	headers
+		.get_all("x-forwarded-for")
+		.iter()
+		.filter_map(|x| x.to_str().ok())
+		.flat_map(|x| x.split(','))
+		.map(parse_node)
+		.collect()
+}
+
+/// The address that identifies `ip`'s client, for rate limits and bans.
+/// IPv6 clients are grouped by their `/64` prefix, since one client often owns a whole `/64`.
+pub(crate) fn client_key(ip: IpAddr) -> IpAddr {
+	match ip {
+		IpAddr::V4(_) => ip,
+		IpAddr::V6(x) => match x.to_ipv4_mapped() {
+			Some(x) => IpAddr::V4(x),
+			None => IpAddr::V6((u128::from(x) & !(u128::MAX >> 64)).into()),
+		},
+	}
+}
+
+/// Find the ip of the client that sent a request.
+///
+/// If `peer` is a trusted proxy, we walk the forwarding headers from the last
+/// hop to the first, and return the first address that is not a trusted proxy.
+/// Forwarding headers from untrusted peers are ignored, since anyone can set them.
+pub(crate) fn client_ip(peer: IpAddr, headers: &HeaderMap, trusted: &[Cidr]) -> IpAddr {
+	let mut ip = peer.to_canonical();
+	if !trusted.iter().any(|x| x.contains(ip)) {
+		return ip;
+	}
+
+	for hop in forwarded_for(headers).into_iter().rev() {
+		// We can't look past a hop we can't read
+		let Some(hop) = hop else {
+			return ip;
+		};
+
+		ip = hop.to_canonical();
+		if !trusted.iter().any(|x| x.contains(ip)) {
+			return ip;
+		}
+	}
+
+	ip
+}
@@ -0,0 +1,164 @@
+use maud::{Markup, html};
+
+use crate::{Error, RenderContext, ServableRouter, StaticAsset, transform::TransformerChain};
+
+/// One image in a [Gallery]
+#[derive(Clone)]
+struct GalleryImage {
+	name: String,
+	alt: String,
+	asset: &'static StaticAsset,
+}
+
+/// A responsive grid of images, with thumbnails and larger detail views.
+///
+/// Every image is served at `{prefix}/{name}` (see [ServableRouter::add_gallery]).
+/// Thumbnails and detail views are the same route, resized on the fly with
+/// [Gallery::thumbnail] and [Gallery::detail] (see [crate::transform]).
+/// Thumbnails are lazy-loaded, and link to their detail view.
+///
+/// ```rust
+/// use servable::{Gallery, HtmlPage, ServableRouter, StaticAsset};
+/// use maud::html;
+///
+/// static SUNSET: StaticAsset = StaticAsset {
+/// 	bytes: b"fake image",
+/// 	br: None,
+/// 	gz: None,
+/// 	mime: mime::IMAGE_JPEG,
+/// 	ttl: StaticAsset::DEFAULT_TTL,
+/// };
+///
+/// let gallery = Gallery::new("/photos").with_image("sunset.jpg", "A sunset over the sea", &SUNSET);
+///
+/// let page = {
+/// 	let gallery = gallery.clone();
+/// 	HtmlPage::default().with_render(move |_page, ctx| {
+/// 		let grid = gallery.grid(ctx);
+/// 		Box::pin(async move { html! { h1 { "Photos" } (grid) } })
+/// 	})
+/// };
+///
+/// let router = ServableRouter::new()
+/// 	.add_page("/photos", page)
+/// 	.add_gallery(&gallery);
+/// ```
+#[derive(Clone)]
+pub struct Gallery {
+	/// The route images are served under
+	prefix: String,
+	images: Vec<GalleryImage>,
+
+	/// The transformation applied to thumbnails.
+	/// The default is `maxdim(400,400)`.
+	pub thumbnail: TransformerChain,
+
+	/// The transformation applied to detail views.
+	/// The default is `maxdim(1600,1600)`.
+	pub detail: TransformerChain,
+}
+
+impl Gallery {
+	/// Create an empty gallery that serves images under `prefix`, like `/photos`
+	#[expect(clippy::unwrap_used)]
+	pub fn new(prefix: impl Into<String>) -> Self {
+		let prefix: String = prefix.into();
+		Self {
+			prefix: prefix.trim_end_matches('/').to_owned(),
+			images: Vec::new(),
+			thumbnail: "maxdim(400,400)".parse().unwrap(),
+			detail: "maxdim(1600,1600)".parse().unwrap(),
+		}
+	}
+
+	/// Add an image to the end of this gallery.
+	/// `name` is the last segment of the image's route, like `sunset.jpg`,
+	/// and `alt` describes the image to visitors who can't see it.
+	#[inline(always)]
+	pub fn with_image(
+		mut self,
+		name: impl Into<String>,
+		alt: impl Into<String>,
+		asset: &'static StaticAsset,
+	) -> Self {
+		self.images.push(GalleryImage {
+			name: name.into(),
+			alt: alt.into(),
+			asset,
+		});
+		self
+	}
+
+	/// Set `self.thumbnail`
+	#[inline(always)]
+	pub fn with_thumbnail(mut self, thumbnail: TransformerChain) -> Self {
+		self.thumbnail = thumbnail;
+		self
+	}
+
+	/// Set `self.detail`
+	#[inline(always)]
+	pub fn with_detail(mut self, detail: TransformerChain) -> Self {
+		self.detail = detail;
+		self
+	}
+
+	/// The route of the image named `name`
+	pub fn route(&self, name: &str) -> String {
+		format!("{}/{name}", self.prefix)
+	}
+
+	/// The url of the thumbnail of the image named `name`
+	pub fn thumbnail_url(&self, ctx: &RenderContext, name: &str) -> String {
+		format!("{}?t={}", ctx.url(&self.route(name)), self.thumbnail)
+	}
+
+	/// The url of the detail view of the image named `name`
+	pub fn detail_url(&self, ctx: &RenderContext, name: &str) -> String {
+		format!("{}?t={}", ctx.url(&self.route(name)), self.detail)
+	}
+
+	/// A responsive grid of this gallery's thumbnails, each linking to its detail view.
+	/// The grid has the class `gallery`, which may be used to style it further.
+	pub fn grid(&self, ctx: &RenderContext) -> Markup {
+		html! {
+			div
+				class="gallery"
+				style="display:grid;grid-template-columns:repeat(auto-fill,minmax(12rem,1fr));gap:0.5rem"
+			{
+				@for image in &self.images {
+					a href=(self.detail_url(ctx, &image.name)) {
+						img
+							src=(self.thumbnail_url(ctx, &image.name))
+							alt=(image.alt)
+							loading="lazy"
+							decoding="async"
+							style="display:block;width:100%;height:auto";
+					}
+				}
+			}
+		}
+	}
+}
+
+impl ServableRouter {
+	/// Serve every image in `gallery` at its [Gallery::route].
+	/// - panics if an image's route is invalid, like [Self::add_page].
+	///
+	/// See [Self::try_add_gallery] for a version of this method that does not panic.
+	pub fn add_gallery(self, gallery: &Gallery) -> Self {
+		match self.try_add_gallery(gallery) {
+			Ok(x) => x,
+			Err(err) => panic!("{err}"),
+		}
+	}
+
+	/// Serve every image in `gallery` at its [Gallery::route].
+	/// Behaves exactly like [Self::add_gallery], but returns an [Error] instead of panicking.
+	pub fn try_add_gallery(mut self, gallery: &Gallery) -> Result<Self, Error> {
+		for image in &gallery.images {
+			self = self.try_add_page(gallery.route(&image.name), image.asset)?;
+		}
+		Ok(self)
+	}
+}
@@ -0,0 +1,293 @@
+use axum::http::{HeaderMap, HeaderName, HeaderValue, StatusCode, header};
+use chrono::TimeDelta;
+use std::{pin::Pin, sync::Arc, time::Duration};
+
+use crate::{
+	Error, HtmlPage, RenderContext, Rendered, RenderedBody, router::merge_template,
+	servable::Servable,
+};
+
+/// A function that decides if a request may be served.
+/// See [RouteGroup::with_guard].
+pub type RouteGuard = dyn Fn(&RenderContext) -> bool + Send + Sync + 'static;
+
+/// Settings shared by all pages in a [RouteGroup]
+struct GroupSettings {
+	ttl: Option<TimeDelta>,
+	headers: HeaderMap,
+	guard: Option<Arc<RouteGuard>>,
+}
+
+/// A set of pages under a common prefix that share settings.
+///
+/// Use as follows:
+/// ```rust
+/// use servable::{RouteGroup, ServableRouter};
+/// use chrono::TimeDelta;
+/// use maud::html;
+///
+/// let blog = RouteGroup::new("/blog")
+/// 	.with_ttl(Some(TimeDelta::hours(1)))
+/// 	.with_header("X-Robots-Tag", "noindex");
+///
+/// let post = blog.page().with_render(|_page, _ctx| {
+/// 	Box::pin(async { html! { h1 { "First post" } } })
+/// });
+///
+/// let index = blog.page();
+/// let blog = blog.add_page("/", index).add_page("/first", post);
+///
+/// // Serves `/blog` and `/blog/first`
+/// let router = ServableRouter::new().add_group(blog);
+/// ```
+pub struct RouteGroup {
+	prefix: String,
+	settings: GroupSettings,
+	layout: HtmlPage,
+	timeout: Option<Option<Duration>>,
+	pages: Vec<(String, Box<dyn Servable>)>,
+}
+
+impl RouteGroup {
+	/// Create a new, empty [RouteGroup].
+	/// All routes in this group are prefixed with `prefix`.
+	///
+	/// - panics if `prefix` is not empty and does not start with a `/`,
+	///   or if it ends with a `/` or contains `//`.
+	///
+	/// See [Self::try_new] for a version of this method that does not panic.
+	pub fn new(prefix: impl Into<String>) -> Self {
+		match Self::try_new(prefix) {
+			Ok(x) => x,
+			Err(err) => panic!("{err}"),
+		}
+	}
+
+	/// Create a new, empty [RouteGroup].
+	/// Behaves exactly like [Self::new], but returns an [Error] instead of panicking.
+	pub fn try_new(prefix: impl Into<String>) -> Result<Self, Error> {
+		let prefix: String = prefix.into();
+		let prefix = match prefix.as_str() {
+			"/" => String::new(),
+			_ => prefix,
+		};
+
+		if !prefix.is_empty() && !prefix.starts_with("/") {
+			return Err(Error::InvalidRoute {
+				route: prefix,
+				reason: "prefix must start with /",
+			});
+		};
+
+		if prefix.ends_with("/") || prefix.contains("//") {
+			return Err(Error::InvalidRoute {
+				route: prefix,
+				reason: "prefix must not end with / or contain //",
+			});
+		};
+
+		Ok(Self {
+			prefix,
+			settings: GroupSettings {
+				ttl: None,
+				headers: HeaderMap::new(),
+				guard: None,
+			},
+			layout: HtmlPage::default(),
+			timeout: None,
+			pages: Vec::new(),
+		})
+	}
+
+	/// Set the default ttl of pages in this group.
+	/// This is used for every response without a ttl.
+	#[inline(always)]
+	pub fn with_ttl(mut self, ttl: Option<TimeDelta>) -> Self {
+		self.settings.ttl = ttl;
+		self
+	}
+
+	/// Add a header to every response in this group,
+	/// unless the response already contains it.
+	///
+	/// - panics if `name` or `value` is not a valid header
+	///
+	/// See [Self::try_with_header] for a version of this method that does not panic.
+	#[inline(always)]
+	pub fn with_header(self, name: &str, value: &str) -> Self {
+		match self.try_with_header(name, value) {
+			Ok(x) => x,
+			Err(err) => panic!("{err}"),
+		}
+	}
+
+	/// Add a header to every response in this group.
+	/// Behaves exactly like [Self::with_header], but returns an [Error] instead of panicking.
+	#[inline(always)]
+	pub fn try_with_header(mut self, name: &str, value: &str) -> Result<Self, Error> {
+		let invalid = || Error::InvalidHeader {
+			name: name.to_owned(),
+		};
+
+		self.settings.headers.append(
+			HeaderName::from_bytes(name.as_bytes()).map_err(|_err| invalid())?,
+			HeaderValue::from_str(value).map_err(|_err| invalid())?,
+		);
+		Ok(self)
+	}
+
+	/// Only serve pages in this group if `guard` returns `true`.
+	/// Otherwise, reply with `403 Forbidden`.
+	#[inline(always)]
+	pub fn with_guard<G: Fn(&RenderContext) -> bool + Send + Sync + 'static>(
+		mut self,
+		guard: G,
+	) -> Self {
+		self.settings.guard = Some(Arc::new(guard));
+		self
+	}
+
+	/// Override the router's timeout for pages in this group.
+	/// See [crate::ServableRouter::add_page_with_timeout].
+	#[inline(always)]
+	pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+		self.timeout = Some(timeout);
+		self
+	}
+
+	/// Set the layout of this group, see [Self::page].
+	#[inline(always)]
+	pub fn with_layout(mut self, layout: HtmlPage) -> Self {
+		self.layout = layout;
+		self
+	}
+
+	/// Make a new [HtmlPage] with this group's layout.
+	///
+	/// The returned page has the same metadata, scripts,
+	/// styles and render function as the page given to [Self::with_layout].
+	#[inline(always)]
+	pub fn page(&self) -> HtmlPage {
+		self.layout.clone()
+	}
+
+	/// Add a [Servable] to this group.
+	/// `route` is relative to this group's prefix,
+	/// and must satisfy the same conditions as in [crate::ServableRouter::add_page].
+	///
+	/// `/` is this group's prefix.
+	#[inline(always)]
+	pub fn add_page<S: Servable + 'static>(mut self, route: impl Into<String>, page: S) -> Self {
+		let route: String = route.into();
+		let route = match (self.prefix.as_str(), route.as_str()) {
+			("", _) => route,
+			(prefix, "/") => prefix.to_owned(),
+			(prefix, route) => format!("{prefix}{route}"),
+		};
+
+		self.pages.push((route, Box::new(page)));
+		self
+	}
+
+	/// Consume this group, returning the pages in it.
+	/// The third element of each tuple is a timeout override.
+	pub(crate) fn into_pages(
+		self,
+	) -> impl Iterator<Item = (String, GroupPage, Option<Option<Duration>>)> {
+		let settings = Arc::new(self.settings);
+		let timeout = self.timeout;
+		self.pages.into_iter().map(move |(route, page)| {
+			(
+				route,
+				GroupPage {
+					inner: page,
+					settings: settings.clone(),
+				},
+				timeout,
+			)
+		})
+	}
+}
+
+/// A [Servable] in a [RouteGroup]
+pub(crate) struct GroupPage {
+	inner: Box<dyn Servable>,
+	settings: Arc<GroupSettings>,
+}
+
+impl GroupPage {
+	fn allowed(&self, ctx: &RenderContext) -> bool {
+		self.settings.guard.as_ref().map(|g| g(ctx)).unwrap_or(true)
+	}
+
+	fn forbidden() -> Rendered<()> {
+		Rendered {
+			code: StatusCode::FORBIDDEN,
+			body: (),
+			ttl: None,
+			private: true,
+			headers: HeaderMap::new(),
+			mime: None,
+			content_length: None,
+		}
+	}
+
+	fn apply<T: crate::RenderedBodyType>(&self, rend: &mut Rendered<T>) {
+		if rend.ttl.is_none() {
+			rend.ttl = self.settings.ttl;
+		}
+
+		merge_template(&mut rend.headers, &self.settings.headers);
+	}
+}
+
+impl Servable for GroupPage {
+	fn head<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<()>> + 'a + Send + Sync>> {
+		Box::pin(async {
+			if !self.allowed(ctx) {
+				return Self::forbidden();
+			}
+
+			let mut rend = self.inner.head(ctx).await;
+			self.apply(&mut rend);
+			rend
+		})
+	}
+
+	fn render<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<RenderedBody>> + 'a + Send + Sync>> {
+		Box::pin(async {
+			if !self.allowed(ctx) {
+				return Self::forbidden().with_body(RenderedBody::Empty);
+			}
+
+			let mut rend = self.inner.render(ctx).await;
+			self.apply(&mut rend);
+			rend
+		})
+	}
+
+	fn header_template(&self) -> HeaderMap {
+		let mut headers = self.inner.header_template();
+
+		// The inner template was computed without our ttl
+		if self.settings.ttl.is_some() {
+			headers.remove(header::CACHE_CONTROL);
+		}
+
+		headers
+	}
+
+	fn linked_routes(&self) -> Vec<String> {
+		self.inner.linked_routes()
+	}
+
+	fn preloads(&self) -> Vec<crate::Preload> {
+		self.inner.preloads()
+	}
+}
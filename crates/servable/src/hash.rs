@@ -0,0 +1,48 @@
+//! The content hashes used to fingerprint assets,
+//! see [crate::StaticAsset::content_hash].
+//!
+//! These hashes are stable: the algorithm and format below will not change
+//! without a major version bump, so build pipelines may compute them ahead of time
+//! and expect the same values as the server.
+//!
+//! - the algorithm is 64-bit [FNV-1a](http://www.isthe.com/chongo/tech/comp/fnv/),
+//!   over the exact bytes of the content.
+//! - [content_hash] formats this as 16 lowercase hex digits, padded with zeros.
+//!
+//! FNV-1a is fast but not cryptographic,
+//! don't use these hashes to check that content wasn't tampered with.
+//!
+//! ```rust
+//! use servable::hash::{content_hash, content_hash_u64};
+//!
+//! assert_eq!(content_hash(b""), "cbf29ce484222325");
+//! assert_eq!(content_hash(b"hello"), "a430d84680aabd0b");
+//! assert_eq!(content_hash(b"hello"), format!("{:016x}", content_hash_u64(b"hello")));
+//! ```
+
+/// The FNV-1a offset basis
+const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+
+/// The FNV-1a prime
+const PRIME: u64 = 0x100000001b3;
+
+/// The 64-bit FNV-1a hash of `bytes`.
+///
+/// This is a `const fn`, so small assets may be hashed at compile time.
+/// Hashing more than a few hundred kilobytes at compile time
+/// trips the `long_running_const_eval` lint, hash them at runtime instead.
+pub const fn content_hash_u64(bytes: &[u8]) -> u64 {
+	let mut hash = OFFSET_BASIS;
+	let mut i = 0;
+	while i < bytes.len() {
+		hash = (hash ^ bytes[i] as u64).wrapping_mul(PRIME);
+		i += 1;
+	}
+	hash
+}
+
+/// The hash of `bytes`, as 16 lowercase hex digits.
+/// This is [content_hash_u64] formatted with `{:016x}`.
+pub fn content_hash(bytes: &[u8]) -> String {
+	format!("{:016x}", content_hash_u64(bytes))
+}
@@ -0,0 +1,204 @@
+use axum::http::{HeaderMap, HeaderValue, StatusCode, header};
+use serde::Serialize;
+use std::{collections::BTreeMap, pin::Pin, sync::Arc, time::Duration};
+use tokio::time::Instant;
+
+use crate::{RenderContext, RenderMode, Rendered, RenderedBody, servable::Servable};
+
+/// The closure run by a [HealthCheck] probe.
+/// Returns `Err` with a reason if the thing it checks is unhealthy.
+pub type ProbeFn = dyn Send
+	+ Sync
+	+ 'static
+	+ Fn() -> Pin<Box<dyn Future<Output = Result<(), String>> + Send + 'static>>;
+
+/// One probe of a [HealthCheck]
+struct Probe {
+	name: String,
+	run: Arc<ProbeFn>,
+}
+
+/// The result of one probe, as sent in a [HealthCheck]'s response
+#[derive(Debug, Clone, Serialize)]
+pub struct ProbeResult {
+	/// True if this probe passed
+	pub ok: bool,
+
+	/// Why this probe failed
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub error: Option<String>,
+
+	/// How long this probe took, in milliseconds
+	pub millis: u64,
+}
+
+/// The body of a [HealthCheck]'s response
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthReport {
+	/// True if every probe passed
+	pub ok: bool,
+
+	/// The result of each probe, by name
+	pub probes: BTreeMap<String, ProbeResult>,
+}
+
+/// A [Servable] for load balancers and uptime monitors that runs
+/// a set of async probes, like a database ping or an upstream check.
+///
+/// Responds with `200 OK` if every probe passes and `503 Service Unavailable` otherwise,
+/// with a json [HealthReport] of every probe's result.
+/// Probes run concurrently, and a probe that takes longer than its timeout fails.
+/// Responses are never cached (`Cache-Control: no-store`).
+///
+/// ```rust
+/// use servable::{HealthCheck, ServableRouter};
+/// use std::time::Duration;
+///
+/// let health = HealthCheck::new()
+/// 	.with_timeout(Duration::from_secs(2))
+/// 	.with_probe("database", || Box::pin(async { Ok(()) }))
+/// 	.with_probe("upstream", || Box::pin(async { Err("connection refused".to_owned()) }));
+///
+/// let router = ServableRouter::new().add_page("/healthz", health);
+/// ```
+pub struct HealthCheck {
+	probes: Vec<Probe>,
+
+	/// How long each probe may take
+	timeout: Duration,
+}
+
+impl Default for HealthCheck {
+	fn default() -> Self {
+		Self {
+			probes: Vec::new(),
+			timeout: Duration::from_secs(5),
+		}
+	}
+}
+
+impl HealthCheck {
+	/// Create a [HealthCheck] without probes, which is always healthy
+	#[inline(always)]
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Add a probe called `name`.
+	/// If a probe called `name` already exists, it is replaced.
+	pub fn with_probe<F>(mut self, name: impl Into<String>, probe: F) -> Self
+	where
+		F: Send
+			+ Sync
+			+ 'static
+			+ Fn() -> Pin<Box<dyn Future<Output = Result<(), String>> + Send + 'static>>,
+	{
+		let probe = Probe {
+			name: name.into(),
+			run: Arc::new(probe),
+		};
+
+		match self.probes.iter_mut().find(|x| x.name == probe.name) {
+			Some(x) => *x = probe,
+			None => self.probes.push(probe),
+		}
+
+		self
+	}
+
+	/// Set how long each probe may take. The default is five seconds.
+	#[inline(always)]
+	pub fn with_timeout(mut self, timeout: Duration) -> Self {
+		self.timeout = timeout;
+		self
+	}
+
+	/// Run every probe
+	pub async fn check(&self) -> HealthReport {
+		// Spawn every probe first, so that they run concurrently
+		let running: Vec<_> = self
+			.probes
+			.iter()
+			.map(|probe| {
+				let run = tokio::time::timeout(self.timeout, (probe.run)());
+				let task = tokio::spawn(async move {
+					let start = Instant::now();
+					(run.await, start.elapsed())
+				});
+				(probe.name.clone(), task)
+			})
+			.collect();
+
+		let mut probes = BTreeMap::new();
+		for (name, task) in running {
+			let (error, elapsed) = match task.await {
+				Ok((Ok(Ok(())), elapsed)) => (None, elapsed),
+				Ok((Ok(Err(error)), elapsed)) => (Some(error), elapsed),
+				Ok((Err(_), elapsed)) => {
+					(Some(format!("timed out after {:?}", self.timeout)), elapsed)
+				}
+				Err(_) => (Some("probe panicked".to_owned()), Duration::ZERO),
+			};
+
+			probes.insert(
+				name,
+				ProbeResult {
+					ok: error.is_none(),
+					error,
+					millis: u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX),
+				},
+			);
+		}
+
+		HealthReport {
+			ok: probes.values().all(|x| x.ok),
+			probes,
+		}
+	}
+
+	/// The head of a response to `report`
+	fn respond(report: &HealthReport) -> Rendered<()> {
+		let mut headers = HeaderMap::with_capacity(1);
+		headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+
+		Rendered {
+			code: match report.ok {
+				true => StatusCode::OK,
+				false => StatusCode::SERVICE_UNAVAILABLE,
+			},
+			headers,
+			body: (),
+			mime: Some(mime::APPLICATION_JSON),
+			ttl: None,
+			private: true,
+			content_length: None,
+		}
+	}
+}
+
+impl Servable for HealthCheck {
+	fn head<'a>(
+		&'a self,
+		_ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<()>> + 'a + Send + Sync>> {
+		Box::pin(async {
+			let report = self.check().await;
+			Self::respond(&report)
+		})
+	}
+
+	fn render<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<RenderedBody>> + 'a + Send + Sync>> {
+		Box::pin(async {
+			let report = self.check().await;
+			let rend = Self::respond(&report);
+
+			match ctx.mode {
+				RenderMode::Head => rend.with_body(RenderedBody::Empty),
+				_ => rend.with_body(RenderedBody::json(report)),
+			}
+		})
+	}
+}
@@ -0,0 +1,111 @@
+use axum::http::{HeaderMap, StatusCode};
+use chrono::TimeDelta;
+use std::{pin::Pin, sync::Arc, time::Duration};
+
+use crate::{
+	BanList, FormData, FormServable, RenderContext, Rendered, RenderedBody, servable::Servable,
+};
+
+/// A trap for scrapers and vulnerability scanners, see [crate::ServableRouter::add_honeypot].
+///
+/// Honeypots are served at routes no real visitor requests, like `/wp-login.php` or `/.env`.
+/// They reply with an empty `404 Not Found`, call a hook with every request they receive,
+/// and may ban the client that sent it.
+///
+/// ```rust
+/// use servable::{BanList, Honeypot, HtmlPage, ServableRouter};
+/// use std::time::Duration;
+///
+/// let bans = BanList::new();
+/// let trap = Honeypot::new()
+/// 	.with_on_hit(|ctx| println!("honeypot hit: {} from {:?}", ctx.route, ctx.client_ip))
+/// 	.with_ban(bans.clone(), Duration::from_secs(24 * 3600));
+///
+/// let router = ServableRouter::new()
+/// 	.add_page("/", HtmlPage::default())
+/// 	.add_honeypot("/wp-login.php", trap.clone())
+/// 	.add_honeypot("/.env", trap)
+/// 	.with_ban_list(bans);
+/// ```
+#[derive(Clone, Default)]
+pub struct Honeypot {
+	/// Called with every request this honeypot receives
+	on_hit: Option<Arc<dyn Fn(&RenderContext) + Send + Sync>>,
+
+	/// If `Some`, clients that hit this honeypot are banned for this long
+	ban: Option<(BanList, Duration)>,
+}
+
+impl Honeypot {
+	/// Create a new [Honeypot] that doesn't record or ban anything
+	#[inline(always)]
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Call `on_hit` with every request this honeypot receives
+	#[inline(always)]
+	pub fn with_on_hit(mut self, on_hit: impl Fn(&RenderContext) + Send + Sync + 'static) -> Self {
+		self.on_hit = Some(Arc::new(on_hit));
+		self
+	}
+
+	/// Add clients that hit this honeypot to `bans` for `duration`.
+	/// Requests without a [RenderContext::client_ip] are never banned.
+	#[inline(always)]
+	pub fn with_ban(mut self, bans: BanList, duration: Duration) -> Self {
+		self.ban = Some((bans, duration));
+		self
+	}
+
+	/// Record a request to this honeypot
+	fn hit(&self, ctx: &RenderContext) {
+		if let Some(on_hit) = &self.on_hit {
+			on_hit(ctx);
+		}
+
+		if let Some((bans, duration)) = &self.ban
+			&& let Some(ip) = ctx.client_ip
+		{
+			bans.ban(ip, ctx.clock.instant(), *duration);
+		}
+	}
+}
+
+impl Servable for Honeypot {
+	fn head<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<()>> + 'a + Send + Sync>> {
+		Box::pin(async {
+			self.hit(ctx);
+
+			return Rendered {
+				code: StatusCode::NOT_FOUND,
+				body: (),
+				ttl: Some(TimeDelta::days(1)),
+				private: true,
+				headers: HeaderMap::new(),
+				mime: None,
+				content_length: None,
+			};
+		})
+	}
+
+	fn render<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<RenderedBody>> + 'a + Send + Sync>> {
+		Box::pin(async { self.head(ctx).await.with_body(RenderedBody::Empty) })
+	}
+}
+
+impl FormServable for Honeypot {
+	fn submit<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+		_form: &'a FormData,
+	) -> Pin<Box<dyn Future<Output = Rendered<RenderedBody>> + 'a + Send + Sync>> {
+		self.render(ctx)
+	}
+}
@@ -0,0 +1,254 @@
+use axum::http::{HeaderMap, HeaderValue, StatusCode, Uri, header};
+use std::{pin::Pin, sync::Arc};
+
+use crate::{
+	RenderContext, Rendered, RenderedBody, RenderedBodyType, router::merge_template,
+	servable::Servable,
+};
+
+/// What a [HotlinkProtected] servable sends to other sites
+#[derive(Clone)]
+pub enum HotlinkAction {
+	/// Reply with `403 Forbidden`
+	Forbid,
+
+	/// Serve this servable instead, for example a placeholder image
+	Replace(Arc<dyn Servable>),
+
+	/// Apply this transformation to the image, replacing any the request asked for.
+	/// For example, `maxdim(64,64)` serves a small thumbnail.
+	///
+	/// This only works if the wrapped servable is a [crate::StaticAsset] image.
+	#[cfg(feature = "image")]
+	Transform(crate::transform::TransformerChain),
+}
+
+/// A [Servable] that treats requests embedded in other sites differently,
+/// to keep them from hotlinking images.
+///
+/// A request is offsite if its `Referer` is another host,
+/// or if `Sec-Fetch-Site` is `cross-site` and it has no `Referer`.
+/// Requests without either header (like direct visits) are never offsite.
+/// The request's own `Host`, its [RenderContext::base_url],
+/// and any allowed hosts are never offsite.
+///
+/// Responses vary on `Referer` and `Sec-Fetch-Site`,
+/// which makes them hard for shared caches to store.
+///
+/// ```rust
+/// use servable::{HotlinkAction, HotlinkProtected, ServableRouter, StaticAsset};
+///
+/// let image = StaticAsset {
+/// 	bytes: b"fake image",
+/// 	br: None,
+/// 	gz: None,
+/// 	mime: mime::IMAGE_PNG,
+/// 	ttl: StaticAsset::DEFAULT_TTL,
+/// };
+///
+/// let image = HotlinkProtected::new(image)
+/// 	.with_allowed_host("example.com")
+/// 	.with_allowed_host("*.example.com")
+/// 	.with_action(HotlinkAction::Forbid);
+///
+/// let router = ServableRouter::new().add_page("/image.png", image);
+/// ```
+pub struct HotlinkProtected<S: Servable> {
+	inner: S,
+
+	/// Hosts that may embed `inner`.
+	/// `*.example.com` allows every subdomain of `example.com`.
+	allowed_hosts: Vec<String>,
+	action: HotlinkAction,
+
+	/// The header templates of onsite and offsite responses
+	templates: (HeaderMap, HeaderMap),
+}
+
+impl<S: Servable> HotlinkProtected<S> {
+	/// Protect `inner` from hotlinking.
+	/// By default, offsite requests are answered with [HotlinkAction::Forbid].
+	pub fn new(inner: S) -> Self {
+		let templates = (inner.header_template(), HeaderMap::new());
+		Self {
+			inner,
+			allowed_hosts: Vec::new(),
+			action: HotlinkAction::Forbid,
+			templates,
+		}
+	}
+
+	/// Allow `host` to embed this servable.
+	/// `*.example.com` allows every subdomain of `example.com`, but not `example.com` itself.
+	#[inline(always)]
+	pub fn with_allowed_host(mut self, host: impl Into<String>) -> Self {
+		self.allowed_hosts.push(host.into().to_ascii_lowercase());
+		self
+	}
+
+	/// Set what offsite requests receive
+	#[inline(always)]
+	pub fn with_action(mut self, action: HotlinkAction) -> Self {
+		self.templates.1 = match &action {
+			HotlinkAction::Forbid => HeaderMap::new(),
+			HotlinkAction::Replace(x) => x.header_template(),
+
+			#[cfg(feature = "image")]
+			HotlinkAction::Transform(_) => self.templates.0.clone(),
+		};
+		self.action = action;
+		self
+	}
+
+	/// Returns true if `host` may embed this servable
+	fn allowed(&self, ctx: &RenderContext, host: &str) -> bool {
+		let own_host = ctx
+			.headers
+			.get(header::HOST)
+			.and_then(|x| x.to_str().ok())
+			.and_then(|x| x.parse::<Uri>().ok())
+			.and_then(|x| x.host().map(str::to_ascii_lowercase));
+
+		let base_host = ctx
+			.base_url
+			.as_deref()
+			.and_then(|x| x.parse::<Uri>().ok())
+			.and_then(|x| x.host().map(str::to_ascii_lowercase));
+
+		own_host.as_deref() == Some(host)
+			|| base_host.as_deref() == Some(host)
+			|| self
+				.allowed_hosts
+				.iter()
+				.any(|x| match x.strip_prefix("*.") {
+					Some(domain) => host.strip_suffix(domain).is_some_and(|x| x.ends_with('.')),
+					None => x == host,
+				})
+	}
+
+	/// Returns true if this request came from another site
+	fn is_offsite(&self, ctx: &RenderContext) -> bool {
+		let referer = ctx
+			.headers
+			.get(header::REFERER)
+			.and_then(|x| x.to_str().ok())
+			.and_then(|x| x.parse::<Uri>().ok());
+
+		if let Some(referer) = referer {
+			return match referer.host() {
+				Some(host) => !self.allowed(ctx, &host.to_ascii_lowercase()),
+				None => false,
+			};
+		}
+
+		ctx.headers
+			.get("sec-fetch-site")
+			.is_some_and(|x| x.as_bytes().eq_ignore_ascii_case(b"cross-site"))
+	}
+
+	/// The response to an offsite request, without a body
+	async fn offsite_head(&self, ctx: &RenderContext) -> Rendered<()> {
+		match &self.action {
+			HotlinkAction::Forbid => Rendered {
+				code: StatusCode::FORBIDDEN,
+				body: (),
+				ttl: None,
+				private: true,
+				headers: HeaderMap::new(),
+				mime: None,
+				content_length: None,
+			},
+
+			HotlinkAction::Replace(x) => x.head(ctx).await,
+
+			#[cfg(feature = "image")]
+			HotlinkAction::Transform(chain) => self.inner.head(&Self::transformed(ctx, chain)).await,
+		}
+	}
+
+	/// The response to an offsite request
+	async fn offsite_render(&self, ctx: &RenderContext) -> Rendered<RenderedBody> {
+		match &self.action {
+			HotlinkAction::Forbid => self.offsite_head(ctx).await.with_body(RenderedBody::Empty),
+			HotlinkAction::Replace(x) => x.render(ctx).await,
+
+			#[cfg(feature = "image")]
+			HotlinkAction::Transform(chain) => self.inner.render(&Self::transformed(ctx, chain)).await,
+		}
+	}
+
+	/// A copy of `ctx` that requests `chain`
+	#[cfg(feature = "image")]
+	fn transformed(
+		ctx: &RenderContext,
+		chain: &crate::transform::TransformerChain,
+	) -> RenderContext {
+		let mut ctx = ctx.clone();
+		ctx.query.insert("t".to_owned(), chain.to_string());
+		// This transformation is ours, not the client's
+		ctx.transforms = true;
+		ctx
+	}
+
+	/// Add `Vary` and the header template of the servable that answered to `rend`
+	fn finish<T: RenderedBodyType>(&self, offsite: bool, mut rend: Rendered<T>) -> Rendered<T> {
+		let template = match offsite {
+			false => &self.templates.0,
+			true => &self.templates.1,
+		};
+
+		if rend.code.is_success() {
+			merge_template(&mut rend.headers, template);
+		}
+
+		let mut vary = HeaderMap::with_capacity(1);
+		vary.insert(
+			header::VARY,
+			HeaderValue::from_static("referer, sec-fetch-site"),
+		);
+		merge_template(&mut rend.headers, &vary);
+		rend
+	}
+}
+
+impl<S: Servable> Servable for HotlinkProtected<S> {
+	fn head<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<()>> + 'a + Send + Sync>> {
+		Box::pin(async {
+			let offsite = self.is_offsite(ctx);
+			let rend = match offsite {
+				false => self.inner.head(ctx).await,
+				true => self.offsite_head(ctx).await,
+			};
+
+			self.finish(offsite, rend)
+		})
+	}
+
+	fn render<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<RenderedBody>> + 'a + Send + Sync>> {
+		Box::pin(async {
+			let offsite = self.is_offsite(ctx);
+			let rend = match offsite {
+				false => self.inner.render(ctx).await,
+				true => self.offsite_render(ctx).await,
+			};
+
+			self.finish(offsite, rend)
+		})
+	}
+
+	#[inline(always)]
+	fn linked_routes(&self) -> Vec<String> {
+		self.inner.linked_routes()
+	}
+
+	#[inline(always)]
+	fn preloads(&self) -> Vec<crate::Preload> {
+		self.inner.preloads()
+	}
+}
@@ -0,0 +1,297 @@
+//! Number and date formatting helpers for render functions.
+//!
+//! This is not a general i18n library: formats are hard-coded for a closed
+//! list of locales, the variants of [Locale]. Requests for other locales
+//! fall back to the router's default locale.
+//!
+//! To add a locale, add a variant to [Locale] and handle it in
+//! [Locale::from_tag], the separator and month tables, and [Locale::fmt_date].
+//!
+//! The locale of a request is picked from its `Accept-Language` header,
+//! see [crate::RenderContext::locale]. Attach a [Locale] to a router
+//! with [crate::ServableRouter::with_extension] to change the fallback locale.
+//! Responses that use [crate::RenderContext::locale] automatically
+//! get `Vary: Accept-Language`.
+
+use axum::http::HeaderValue;
+use chrono::Datelike;
+
+use crate::RenderContext;
+
+/// A locale we know how to format numbers and dates in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Locale {
+	/// English (United States)
+	#[default]
+	EnUs,
+
+	/// English (United Kingdom)
+	EnGb,
+
+	/// German
+	De,
+
+	/// French
+	Fr,
+
+	/// Spanish
+	Es,
+
+	/// Italian
+	It,
+
+	/// Dutch
+	Nl,
+}
+
+/// How a date should be formatted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum DateStyle {
+	/// Numeric, like `10/17/2026` or `17.10.2026`
+	#[default]
+	Short,
+
+	/// With the name of the month, like `October 17, 2026` or `17. Oktober 2026`
+	Long,
+}
+
+impl Locale {
+	/// Find the locale that matches a language tag, like `en-GB` or `de`.
+	/// Regions we don't know are matched by language.
+	pub fn from_tag(tag: &str) -> Option<Self> {
+		let tag = tag.trim().to_ascii_lowercase().replace('_', "-");
+		let (lang, region) = tag.split_once('-').unwrap_or((&tag, ""));
+
+		return Some(match (lang, region) {
+			("en", "gb" | "ie" | "au" | "nz") => Self::EnGb,
+			("en", _) => Self::EnUs,
+			("de", _) => Self::De,
+			("fr", _) => Self::Fr,
+			("es", _) => Self::Es,
+			("it", _) => Self::It,
+			("nl", _) => Self::Nl,
+			_ => return None,
+		});
+	}
+
+	/// Pick the locale the client prefers,
+	/// according to the value of an `Accept-Language` header.
+	/// Returns `None` if the client accepts none of our locales.
+	pub fn negotiate(accept: Option<&HeaderValue>) -> Option<Self> {
+		let accept = accept?.to_str().ok()?;
+		crate::language::parse_accept_language(accept)
+			.into_iter()
+			.find_map(|(tag, _)| Self::from_tag(tag))
+	}
+
+	/// The character that separates groups of digits
+	fn group_separator(&self) -> &'static str {
+		match self {
+			Self::EnUs | Self::EnGb => ",",
+			Self::De | Self::Es | Self::It | Self::Nl => ".",
+			Self::Fr => "\u{202F}",
+		}
+	}
+
+	/// The character that separates the integer and fractional part of a number
+	fn decimal_separator(&self) -> &'static str {
+		match self {
+			Self::EnUs | Self::EnGb => ".",
+			Self::De | Self::Fr | Self::Es | Self::It | Self::Nl => ",",
+		}
+	}
+
+	/// The smallest number of integer digits that is grouped
+	fn min_grouping_digits(&self) -> usize {
+		match self {
+			Self::Es => 5,
+			_ => 4,
+		}
+	}
+
+	/// The names of the months of the year
+	fn months(&self) -> &'static [&'static str; 12] {
+		match self {
+			Self::EnUs | Self::EnGb => &[
+				"January",
+				"February",
+				"March",
+				"April",
+				"May",
+				"June",
+				"July",
+				"August",
+				"September",
+				"October",
+				"November",
+				"December",
+			],
+			Self::De => &[
+				"Januar",
+				"Februar",
+				"März",
+				"April",
+				"Mai",
+				"Juni",
+				"Juli",
+				"August",
+				"September",
+				"Oktober",
+				"November",
+				"Dezember",
+			],
+			Self::Fr => &[
+				"janvier",
+				"février",
+				"mars",
+				"avril",
+				"mai",
+				"juin",
+				"juillet",
+				"août",
+				"septembre",
+				"octobre",
+				"novembre",
+				"décembre",
+			],
+			Self::Es => &[
+				"enero",
+				"febrero",
+				"marzo",
+				"abril",
+				"mayo",
+				"junio",
+				"julio",
+				"agosto",
+				"septiembre",
+				"octubre",
+				"noviembre",
+				"diciembre",
+			],
+			Self::It => &[
+				"gennaio",
+				"febbraio",
+				"marzo",
+				"aprile",
+				"maggio",
+				"giugno",
+				"luglio",
+				"agosto",
+				"settembre",
+				"ottobre",
+				"novembre",
+				"dicembre",
+			],
+			Self::Nl => &[
+				"januari",
+				"februari",
+				"maart",
+				"april",
+				"mei",
+				"juni",
+				"juli",
+				"augustus",
+				"september",
+				"oktober",
+				"november",
+				"december",
+			],
+		}
+	}
+
+	/// Format `n` with exactly `decimals` fractional digits.
+	///
+	/// ```rust
+	/// use servable::i18n::Locale;
+	///
+	/// assert_eq!(Locale::EnUs.fmt_number(1234567.891, 2), "1,234,567.89");
+	/// assert_eq!(Locale::De.fmt_number(-1234.5, 1), "-1.234,5");
+	/// assert_eq!(Locale::Es.fmt_number(1234.0, 0), "1234");
+	/// ```
+	pub fn fmt_number(&self, n: f64, decimals: usize) -> String {
+		let digits = format!("{:.*}", decimals, n.abs());
+		let (int, frac) = digits.split_once('.').unwrap_or((&digits, ""));
+
+		let mut out = String::with_capacity(digits.len() + int.len() / 3 + 1);
+		if n.is_sign_negative() && digits.bytes().any(|x| x.is_ascii_digit() && x != b'0') {
+			out.push('-');
+		}
+
+		let group = int.len() >= self.min_grouping_digits();
+		for (i, c) in int.chars().enumerate() {
+			if group && i != 0 && (int.len() - i) % 3 == 0 {
+				out.push_str(self.group_separator());
+			}
+			out.push(c);
+		}
+
+		if !frac.is_empty() {
+			out.push_str(self.decimal_separator());
+			out.push_str(frac);
+		}
+
+		out
+	}
+
+	/// Format the date part of `date`.
+	///
+	/// ```rust
+	/// use servable::i18n::{DateStyle, Locale};
+	/// use chrono::NaiveDate;
+	///
+	/// let date = NaiveDate::from_ymd_opt(2026, 3, 7).unwrap();
+	/// assert_eq!(Locale::EnUs.fmt_date(&date, DateStyle::Short), "3/7/2026");
+	/// assert_eq!(Locale::De.fmt_date(&date, DateStyle::Short), "07.03.2026");
+	/// assert_eq!(Locale::Fr.fmt_date(&date, DateStyle::Long), "7 mars 2026");
+	/// ```
+	pub fn fmt_date(&self, date: &impl Datelike, style: DateStyle) -> String {
+		let (y, m, d) = (date.year(), date.month(), date.day());
+		let month = self.months()[date.month0() as usize];
+
+		match (style, self) {
+			(DateStyle::Short, Self::EnUs) => format!("{m}/{d}/{y}"),
+			(DateStyle::Short, Self::EnGb | Self::Fr | Self::It) => format!("{d:02}/{m:02}/{y}"),
+			(DateStyle::Short, Self::De) => format!("{d:02}.{m:02}.{y}"),
+			(DateStyle::Short, Self::Es) => format!("{d}/{m}/{y}"),
+			(DateStyle::Short, Self::Nl) => format!("{d}-{m}-{y}"),
+
+			(DateStyle::Long, Self::EnUs) => format!("{month} {d}, {y}"),
+			(DateStyle::Long, Self::De) => format!("{d}. {month} {y}"),
+			(DateStyle::Long, Self::Es) => format!("{d} de {month} de {y}"),
+			(DateStyle::Long, Self::EnGb | Self::Fr | Self::It | Self::Nl) => {
+				format!("{d} {month} {y}")
+			}
+		}
+	}
+}
+
+impl RenderContext {
+	/// The locale this request should be rendered in.
+	///
+	/// This is picked from the `Accept-Language` header.
+	/// If the client accepts none of our locales, we use the [Locale]
+	/// attached to the router (see [crate::ServableRouter::with_extension]),
+	/// or [Locale::EnUs] if there is none.
+	///
+	/// This adds `Accept-Language` to the response's `Vary` header, see [Self::vary].
+	pub fn locale(&self) -> Locale {
+		self.vary(axum::http::header::ACCEPT_LANGUAGE);
+		Locale::negotiate(self.headers.get(axum::http::header::ACCEPT_LANGUAGE))
+			.or_else(|| self.extension::<Locale>().copied())
+			.unwrap_or_default()
+	}
+
+	/// Format a number in this request's locale.
+	/// See [Self::locale] and [Locale::fmt_number].
+	#[inline(always)]
+	pub fn fmt_number(&self, n: f64, decimals: usize) -> String {
+		self.locale().fmt_number(n, decimals)
+	}
+
+	/// Format a date in this request's locale.
+	/// See [Self::locale] and [Locale::fmt_date].
+	#[inline(always)]
+	pub fn fmt_date(&self, date: &impl Datelike, style: DateStyle) -> String {
+		self.locale().fmt_date(date, style)
+	}
+}
@@ -0,0 +1,235 @@
+use axum::http::{HeaderMap, HeaderValue, header};
+use std::pin::Pin;
+
+use crate::{
+	RenderContext, Rendered, RenderedBody, RenderedBodyType, router::merge_template,
+	servable::Servable,
+};
+
+/// The language tags in an `Accept-Language` header, most preferred first.
+/// Tags with `q=0` are left out.
+pub(crate) fn parse_accept_language(accept: &str) -> Vec<(&str, f32)> {
+	let mut tags: Vec<(&str, f32)> = accept
+		.split(',')
+		.filter_map(|entry| {
+			let mut parts = entry.split(';');
+			let tag = parts.next().unwrap_or("").trim();
+
+			let q = parts
+				.filter_map(|x| x.trim().strip_prefix("q="))
+				.next()
+				.map(|x| x.trim().parse::<f32>().unwrap_or(0.0))
+				.unwrap_or(1.0);
+
+			(!tag.is_empty() && q > 0.0).then_some((tag, q))
+		})
+		.collect();
+
+	// Stable, so tags with the same q keep their order
+	tags.sort_by(|a, b| b.1.total_cmp(&a.1));
+	tags
+}
+
+/// One language of a [LanguageSwitch]
+struct Language {
+	tag: String,
+	servable: Box<dyn Servable>,
+
+	/// `servable`'s [Servable::header_template]
+	template: HeaderMap,
+}
+
+/// A [Servable] that serves a different version of a page for each language.
+///
+/// The language of a request is the first of these that we have:
+/// - the `?lang=` query parameter, so links may pick a language,
+/// - the `lang` cookie, so clients may remember a choice,
+/// - the client's preferred languages, from `Accept-Language`,
+/// - the first language of this switch.
+///
+/// Tags are matched case-insensitively, and by language if no region matches:
+/// a client that asks for `en-GB` gets `en` or `en-US` if there is no `en-GB`.
+///
+/// Responses have a `Content-Language` header and `Vary: Accept-Language, Cookie`.
+///
+/// ```rust
+/// use servable::{HtmlPage, LanguageSwitch, ServableRouter};
+/// use maud::html;
+///
+/// let en = HtmlPage::default().with_render(|_page, _ctx| Box::pin(async { html! { "Hello" } }));
+/// let de = HtmlPage::default().with_render(|_page, _ctx| Box::pin(async { html! { "Hallo" } }));
+///
+/// let home = LanguageSwitch::new("en", en).with_language("de", de);
+///
+/// // `/?lang=de` is always in German
+/// let router = ServableRouter::new().add_page("/", home);
+/// ```
+pub struct LanguageSwitch {
+	/// The first language is the default
+	languages: Vec<Language>,
+
+	/// The query parameter that picks a language
+	query: Option<String>,
+
+	/// The cookie that picks a language
+	cookie: Option<String>,
+}
+
+impl LanguageSwitch {
+	/// Serve `servable` in the language `tag`, like `en` or `pt-BR`.
+	/// This is the default language.
+	pub fn new(tag: impl Into<String>, servable: impl Servable + 'static) -> Self {
+		Self {
+			languages: Vec::new(),
+			query: Some("lang".to_owned()),
+			cookie: Some("lang".to_owned()),
+		}
+		.with_language(tag, servable)
+	}
+
+	/// Serve `servable` to clients who prefer the language `tag`.
+	/// If `tag` was already added, the new servable replaces the old one.
+	pub fn with_language(
+		mut self,
+		tag: impl Into<String>,
+		servable: impl Servable + 'static,
+	) -> Self {
+		let language = Language {
+			tag: tag.into(),
+			template: servable.header_template(),
+			servable: Box::new(servable),
+		};
+
+		match self
+			.languages
+			.iter_mut()
+			.find(|x| x.tag.eq_ignore_ascii_case(&language.tag))
+		{
+			Some(x) => *x = language,
+			None => self.languages.push(language),
+		}
+
+		self
+	}
+
+	/// Set the query parameter that picks a language.
+	/// If `None`, the query never picks a language. The default is `lang`.
+	#[inline(always)]
+	pub fn with_query_param(mut self, name: Option<String>) -> Self {
+		self.query = name;
+		self
+	}
+
+	/// Set the cookie that picks a language.
+	/// If `None`, cookies never pick a language. The default is `lang`.
+	#[inline(always)]
+	pub fn with_cookie(mut self, name: Option<String>) -> Self {
+		self.cookie = name;
+		self
+	}
+
+	/// The index of the language that best matches `tag`
+	fn find(&self, tag: &str) -> Option<usize> {
+		let tag = tag.trim().replace('_', "-");
+		if tag == "*" {
+			return Some(0);
+		}
+
+		let primary = |x: &str| x.split('-').next().unwrap_or(x).to_ascii_lowercase();
+		self.languages
+			.iter()
+			.position(|x| x.tag.eq_ignore_ascii_case(&tag))
+			.or_else(|| {
+				let lang = primary(&tag);
+				self.languages.iter().position(|x| primary(&x.tag) == lang)
+			})
+	}
+
+	/// The index of the language `ctx` should be served in
+	fn pick(&self, ctx: &RenderContext) -> usize {
+		let query = self.query.as_ref().and_then(|x| ctx.query.get(x));
+		if let Some(i) = query.and_then(|x| self.find(x)) {
+			return i;
+		}
+
+		let cookie = self.cookie.as_ref().and_then(|x| ctx.cookie(x));
+		if let Some(i) = cookie.and_then(|x| self.find(x)) {
+			return i;
+		}
+
+		ctx.headers
+			.get(header::ACCEPT_LANGUAGE)
+			.and_then(|x| x.to_str().ok())
+			.and_then(|x| {
+				parse_accept_language(x)
+					.into_iter()
+					.find_map(|(tag, _)| self.find(tag))
+			})
+			.unwrap_or(0)
+	}
+
+	/// The language tag `ctx` should be served in
+	pub fn language(&self, ctx: &RenderContext) -> &str {
+		self.languages
+			.get(self.pick(ctx))
+			.map(|x| x.tag.as_str())
+			.unwrap_or_default()
+	}
+
+	/// Add `Content-Language`, `Vary` and the chosen servable's header template to `rend`
+	fn finish<T: RenderedBodyType>(
+		&self,
+		language: &Language,
+		mut rend: Rendered<T>,
+	) -> Rendered<T> {
+		let vary = match self.cookie {
+			Some(_) => "Accept-Language, Cookie",
+			None => "Accept-Language",
+		};
+		rend.headers
+			.append(header::VARY, HeaderValue::from_static(vary));
+
+		if !rend.headers.contains_key(header::CONTENT_LANGUAGE)
+			&& let Ok(tag) = HeaderValue::from_str(&language.tag)
+		{
+			rend.headers.insert(header::CONTENT_LANGUAGE, tag);
+		}
+
+		if rend.code.is_success() {
+			merge_template(&mut rend.headers, &language.template);
+		}
+
+		rend
+	}
+}
+
+impl Servable for LanguageSwitch {
+	fn head<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<()>> + 'a + Send + Sync>> {
+		Box::pin(async move {
+			let language = &self.languages[self.pick(ctx)];
+			let rend = language.servable.head(ctx).await;
+			self.finish(language, rend)
+		})
+	}
+
+	fn render<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<RenderedBody>> + 'a + Send + Sync>> {
+		Box::pin(async move {
+			let language = &self.languages[self.pick(ctx)];
+			let rend = language.servable.render(ctx).await;
+			self.finish(language, rend)
+		})
+	}
+
+	fn linked_routes(&self) -> Vec<String> {
+		self.languages
+			.iter()
+			.flat_map(|x| x.servable.linked_routes())
+			.collect()
+	}
+}
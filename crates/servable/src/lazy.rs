@@ -0,0 +1,104 @@
+use crate::{
+	FetchPriority,
+	scan::{attribute, rewrite_tags},
+};
+
+/// Adds `loading="lazy"` and `decoding="async"` to the images in an html document,
+/// so that browsers only fetch images when they are about to be shown.
+///
+/// Attributes that are already present are never changed,
+/// so images may be marked eager with `loading="eager"`.
+/// Images at the top of a page should usually be eager,
+/// see [LazyImages::eager].
+///
+/// ```rust
+/// use servable::LazyImages;
+///
+/// let html = LazyImages::default()
+/// 	.with_eager(1)
+/// 	.apply("<img src=\"a.png\"><img src=\"b.png\"><img src=\"c.png\" loading=\"eager\">");
+///
+/// assert_eq!(
+/// 	html,
+/// 	"<img src=\"a.png\" decoding=\"async\">\
+/// 	<img src=\"b.png\" loading=\"lazy\" decoding=\"async\">\
+/// 	<img src=\"c.png\" loading=\"eager\" decoding=\"async\">"
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct LazyImages {
+	/// The number of images at the start of the document that are not made lazy.
+	/// These are usually visible as soon as the page loads.
+	pub eager: usize,
+
+	/// If `Some`, give the eager images at the start of the document this `fetchpriority`,
+	/// like [FetchPriority::High] for a large image at the top of a page.
+	pub eager_priority: Option<FetchPriority>,
+}
+
+impl LazyImages {
+	/// Set `self.eager`
+	#[inline(always)]
+	pub fn with_eager(mut self, eager: usize) -> Self {
+		self.eager = eager;
+		self
+	}
+
+	/// Set `self.eager_priority`
+	///
+	/// ```rust
+	/// use servable::{FetchPriority, LazyImages};
+	///
+	/// let html = LazyImages::default()
+	/// 	.with_eager(1)
+	/// 	.with_eager_priority(Some(FetchPriority::High))
+	/// 	.apply("<img src=\"hero.png\" decoding=\"sync\">");
+	///
+	/// assert_eq!(html, "<img src=\"hero.png\" decoding=\"sync\" fetchpriority=\"high\">");
+	/// ```
+	#[inline(always)]
+	pub fn with_eager_priority(mut self, eager_priority: Option<FetchPriority>) -> Self {
+		self.eager_priority = eager_priority;
+		self
+	}
+
+	/// Add `loading`, `decoding` and `fetchpriority` attributes to every `<img>` in `html`.
+	/// Images inside `<script>` and `<style>` tags are ignored.
+	pub fn apply(&self, html: &str) -> String {
+		let mut n = 0;
+
+		rewrite_tags(html, &["img"], |_, open, _| {
+			n += 1;
+			let lazy = n > self.eager && attribute(open, "loading").is_none();
+			let decoding = attribute(open, "decoding").is_none();
+			let priority = match n <= self.eager && attribute(open, "fetchpriority").is_none() {
+				true => self.eager_priority,
+				false => None,
+			};
+			if !lazy && !decoding && priority.is_none() {
+				return None;
+			}
+
+			let (start, end) = match open.strip_suffix("/>") {
+				Some(x) => (x.trim_end(), " />"),
+				None => (&open[..open.len() - 1], ">"),
+			};
+
+			let mut out = String::with_capacity(open.len() + 32);
+			out.push_str(start);
+			if lazy {
+				out.push_str(" loading=\"lazy\"");
+			}
+			if decoding {
+				out.push_str(" decoding=\"async\"");
+			}
+			if let Some(priority) = priority {
+				out.push_str(" fetchpriority=\"");
+				out.push_str(priority.as_str());
+				out.push('"');
+			}
+			out.push_str(end);
+			Some(out)
+		})
+	}
+}
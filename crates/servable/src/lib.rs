@@ -4,20 +4,151 @@
 // and needs a different relative path than cargo build.
 // https://github.com/rust-lang/cargo/issues/13309
 
+mod cachestatus;
+mod chunks;
+mod encoding;
+mod forwarded;
+mod range;
+mod scan;
 mod types;
 
+mod error;
+pub use error::*;
+
+mod report;
+pub use report::*;
+
 use rand::{Rng, distr::Alphanumeric};
 pub use types::*;
 
 mod router;
 pub use router::*;
 
+mod swap;
+pub use swap::*;
+
+mod links;
+pub use links::*;
+
+mod schedule;
+pub use schedule::*;
+
 mod servable;
 pub use servable::*;
 
+mod security;
+pub use security::*;
+
+mod scrub;
+pub use scrub::*;
+
+mod filter;
+pub use filter::*;
+
+mod ban;
+pub use ban::*;
+
+mod honeypot;
+pub use honeypot::*;
+
+mod normalize;
+pub use normalize::*;
+
+mod group;
+pub use group::*;
+
+mod form;
+pub use form::*;
+
+mod mirror;
+pub use mirror::*;
+
+mod rollout;
+pub use rollout::*;
+
+mod device;
+pub use device::*;
+
+mod language;
+pub use language::*;
+
+mod negotiated;
+pub use negotiated::*;
+
+mod feed;
+pub use feed::*;
+
+mod wellknown;
+pub use wellknown::*;
+
+mod manifest;
+pub use manifest::*;
+
+mod ratelimit;
+pub use ratelimit::*;
+
+mod failsafe;
+pub use failsafe::*;
+
+mod fallible;
+pub use fallible::*;
+
+mod health;
+pub use health::*;
+
+mod cached;
+pub use cached::*;
+
+mod auth;
+pub use auth::*;
+
+mod basic;
+pub use basic::*;
+
+mod hotlink;
+pub use hotlink::*;
+
+mod sign;
+pub use sign::*;
+
+mod throttle;
+pub use throttle::*;
+
+mod clock;
+pub use clock::*;
+
+mod toc;
+pub use toc::*;
+
+mod preview;
+pub use preview::*;
+
+mod lazy;
+pub use lazy::*;
+
+mod priority;
+pub use priority::*;
+
+mod rewrite;
+pub use rewrite::*;
+
+mod embed;
+pub use embed::*;
+
+mod download;
+pub use download::*;
+
 #[cfg(test)] // Used in doctests
 use tower_http as _;
 
+#[cfg(test)] // Used in benchmarks
+use criterion as _;
+
+#[doc(hidden)]
+pub mod bench;
+
+pub mod hash;
+
 //
 //
 //
@@ -25,11 +156,81 @@ use tower_http as _;
 #[cfg(feature = "image")]
 pub mod transform;
 
+#[cfg(feature = "image")]
+mod gallery;
+#[cfg(feature = "image")]
+pub use gallery::*;
+
+#[cfg(feature = "image")]
+mod sprite;
+#[cfg(feature = "image")]
+pub use sprite::*;
+
+#[cfg(feature = "image")]
+mod favicon;
+#[cfg(feature = "image")]
+pub use favicon::*;
+
+#[cfg(feature = "image")]
+mod placeholder;
+#[cfg(feature = "image")]
+pub use placeholder::*;
+
+#[cfg(feature = "i18n")]
+pub mod i18n;
+
+#[cfg(feature = "fonts")]
+pub mod fonts;
+
+#[cfg(feature = "dev")]
+mod a11y;
+#[cfg(feature = "dev")]
+pub use a11y::*;
+#[cfg(feature = "dev")]
+mod validator;
+#[cfg(feature = "dev")]
+pub use validator::*;
+
+#[cfg(feature = "proxy")]
+pub mod proxy;
+
+#[cfg(feature = "config")]
+pub mod config;
+
+#[cfg(feature = "compression")]
+mod compression;
+#[cfg(feature = "compression")]
+pub use compression::*;
+
+#[cfg(feature = "server")]
+mod server;
+
+#[cfg(feature = "sentry")]
+mod sentry;
+#[cfg(feature = "sentry")]
+pub use sentry::*;
+
+#[cfg(feature = "include_dir")]
+mod embedded;
+#[cfg(feature = "include_dir")]
+pub use embedded::*;
+
+#[cfg(any(feature = "tera", feature = "askama"))]
+mod template;
+#[cfg(any(feature = "tera", feature = "askama"))]
+pub use template::*;
+
 /// A unique string that can be used for cache-busting.
 ///
 /// Note that this string changes every time this code is started,
 /// even if the data inside the program did not change.
+///
+/// If the `deterministic` feature is enabled, this is always [DETERMINISTIC_CACHE_BUST_STR].
 pub static CACHE_BUST_STR: std::sync::LazyLock<String> = std::sync::LazyLock::new(|| {
+	if cfg!(feature = "deterministic") {
+		return DETERMINISTIC_CACHE_BUST_STR.to_owned();
+	}
+
 	rand::rng()
 		.sample_iter(&Alphanumeric)
 		.take(10)
@@ -37,24 +238,19 @@ pub static CACHE_BUST_STR: std::sync::LazyLock<String> = std::sync::LazyLock::ne
 		.collect()
 });
 
+/// The value of [CACHE_BUST_STR] when the `deterministic` feature is enabled
+pub const DETERMINISTIC_CACHE_BUST_STR: &str = "0000000000";
+
 //
 //
 //
 
 /// HTMX 2.0.8, minified
 #[cfg(feature = "htmx-2.0.8")]
-pub const HTMX_2_0_8: servable::StaticAsset = servable::StaticAsset {
-	bytes: include_str!("../htmx/htmx-2.0.8.min.js").as_bytes(),
-	mime: mime::TEXT_JAVASCRIPT,
-	ttl: StaticAsset::DEFAULT_TTL,
-};
+pub const HTMX_2_0_8: servable::StaticAsset = asset!("../htmx/htmx-2.0.8.min.js");
 
 /// HTMX json extension, 1.19.2.
 /// Compatible with:
 /// - [HTMX_2_0_8]
 #[cfg(feature = "htmx-2.0.8")]
-pub const EXT_JSON_1_19_12: servable::StaticAsset = servable::StaticAsset {
-	bytes: include_str!("../htmx/json-enc-1.9.12.js").as_bytes(),
-	mime: mime::TEXT_JAVASCRIPT,
-	ttl: StaticAsset::DEFAULT_TTL,
-};
+pub const EXT_JSON_1_19_12: servable::StaticAsset = asset!("../htmx/json-enc-1.9.12.js");
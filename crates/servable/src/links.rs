@@ -0,0 +1,247 @@
+use axum::{
+	body::Body,
+	http::{Method, Request, StatusCode, header},
+};
+use std::collections::{BTreeMap, BTreeSet};
+use tower::ServiceExt;
+
+use crate::{
+	ServableRouter,
+	scan::{attribute, rewrite_tags},
+};
+
+/// The elements and attributes that [ServableRouter::check_links] follows
+const LINK_ATTRIBUTES: &[(&str, &[&str])] = &[
+	("a", &["href"]),
+	("link", &["href"]),
+	("script", &["src"]),
+	("img", &["src", "srcset"]),
+	("source", &["src", "srcset"]),
+	("iframe", &["src"]),
+	("video", &["src", "poster"]),
+	("audio", &["src"]),
+	("track", &["src"]),
+	("embed", &["src"]),
+];
+
+/// A link to a route that doesn't resolve, see [ServableRouter::check_links]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeadLink {
+	/// The route of the page with this link
+	pub page: String,
+
+	/// The link, as it appears in the page
+	pub link: String,
+
+	/// The status our router answered this link with
+	pub status: StatusCode,
+}
+
+impl ServableRouter {
+	/// Render every page of this router and check that each of their internal links resolves.
+	/// This is a more thorough, slower version of [Self::validate] for tests and dev builds.
+	///
+	/// Every route without parameters is requested, and the `href`s and `src`s
+	/// of html pages are extracted along with their [crate::Servable::linked_routes].
+	/// Each internal link (including links to assets with content hashes and
+	/// absolute links that start with [Self::with_base_url]) is then requested with `HEAD`.
+	/// A link is dead if it gets `404`, `410` or a server error.
+	///
+	/// Pages that don't respond with `2xx` themselves, like pages behind auth, are not checked.
+	///
+	/// ```rust
+	/// use servable::{HtmlPage, ServableRouter};
+	/// use maud::html;
+	///
+	/// # tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap().block_on(async {
+	/// let router = ServableRouter::new()
+	/// 	.add_page("/", HtmlPage::default().with_render(|_page, _ctx| {
+	/// 		Box::pin(async { html! { a href="/about" { "About" } a href="/missing" { "?" } } })
+	/// 	}))
+	/// 	.add_page("/about", HtmlPage::default());
+	///
+	/// let dead = router.check_links().await;
+	/// assert_eq!(dead.len(), 1);
+	/// assert_eq!(dead[0].link, "/missing");
+	/// # });
+	/// ```
+	pub async fn check_links(&self) -> Vec<DeadLink> {
+		let mut dead = Vec::new();
+
+		// The status of every link we've checked
+		let mut checked: BTreeMap<String, StatusCode> = BTreeMap::new();
+
+		for (route, servable) in self.page_routes() {
+			if route.contains('{') {
+				continue;
+			}
+
+			let url = self.route_url(&route);
+			let Some((status, html)) = self.fetch(Method::GET, &url).await else {
+				continue;
+			};
+
+			if !status.is_success() {
+				continue;
+			}
+
+			let mut links: BTreeSet<String> = servable
+				.linked_routes()
+				.into_iter()
+				.map(|x| match x.starts_with('/') && !x.starts_with("//") {
+					true => self.route_url(&x),
+					false => x,
+				})
+				.collect();
+			if let Some(html) = html {
+				links.extend(extract_links(&html));
+			}
+
+			for link in links {
+				let Some(target) = self.resolve(&url, &link) else {
+					continue;
+				};
+
+				let status = match checked.get(&target) {
+					Some(x) => *x,
+					None => {
+						let status = self
+							.fetch(Method::HEAD, &target)
+							.await
+							.map(|(x, _)| x)
+							.unwrap_or(StatusCode::BAD_REQUEST);
+						checked.insert(target, status);
+						status
+					}
+				};
+
+				if matches!(status, StatusCode::NOT_FOUND | StatusCode::GONE)
+					|| status.is_server_error()
+				{
+					dead.push(DeadLink {
+						page: route.clone(),
+						link,
+						status,
+					});
+				}
+			}
+		}
+
+		dead
+	}
+
+	/// Request `url` from this router.
+	/// Returns the response's status, and its body if it is html.
+	async fn fetch(&self, method: Method, url: &str) -> Option<(StatusCode, Option<String>)> {
+		let req = Request::builder()
+			.method(method)
+			.uri(url)
+			.body(Body::empty())
+			.ok()?;
+
+		let Ok(res) = self.clone().oneshot(req).await;
+		let status = res.status();
+
+		let is_html = res
+			.headers()
+			.get(header::CONTENT_TYPE)
+			.and_then(|x| x.to_str().ok())
+			.is_some_and(|x| x.starts_with("text/html"));
+		if !is_html {
+			return Some((status, None));
+		}
+
+		let body = axum::body::to_bytes(res.into_body(), usize::MAX).await.ok();
+		let html = body.map(|x| String::from_utf8_lossy(&x).into_owned());
+		Some((status, html))
+	}
+
+	/// Turn `link` on the page at `page` into a path on this router.
+	/// Returns `None` if `link` is not internal.
+	fn resolve(&self, page: &str, link: &str) -> Option<String> {
+		let link = link.split('#').next().unwrap_or("").trim();
+		if link.is_empty() || link.starts_with("//") {
+			return None;
+		}
+
+		// Absolute urls are internal if they start with our base url
+		let scheme_end = link.find(':');
+		let path_start = link.find(['/', '?']);
+		if scheme_end.is_some_and(|x| path_start.is_none_or(|y| x < y)) {
+			let rest = link.strip_prefix(self.base_url()?)?;
+			return match rest.chars().next() {
+				None => Some("/".to_owned()),
+				Some('/' | '?') => Some(format!("/{}", rest.trim_start_matches('/'))),
+				Some(_) => None,
+			};
+		}
+
+		if link.starts_with('/') {
+			return Some(link.to_owned());
+		}
+
+		// Relative links
+		let dir = &page[..page.rfind('/').map(|x| x + 1).unwrap_or(0)];
+		let (path, query) = match link.split_once('?') {
+			Some((path, query)) => (path, Some(query)),
+			None => (link, None),
+		};
+
+		let mut segments: Vec<&str> = dir.split('/').filter(|x| !x.is_empty()).collect();
+		for segment in path.split('/') {
+			match segment {
+				"" | "." => {}
+				".." => {
+					segments.pop();
+				}
+				x => segments.push(x),
+			}
+		}
+
+		let mut out = format!("/{}", segments.join("/"));
+		if path.ends_with('/') && out != "/" {
+			out.push('/');
+		}
+		if let Some(query) = query {
+			out.push('?');
+			out.push_str(query);
+		}
+		Some(out)
+	}
+}
+
+/// Every link in `html`, see [LINK_ATTRIBUTES]
+fn extract_links(html: &str) -> Vec<String> {
+	let names: Vec<&str> = LINK_ATTRIBUTES.iter().map(|(x, _)| *x).collect();
+	let mut links = Vec::new();
+
+	rewrite_tags(html, &names, |name, tag, _| {
+		let attributes = LINK_ATTRIBUTES
+			.iter()
+			.find(|(x, _)| *x == name)
+			.map(|(_, x)| *x)
+			.unwrap_or_default();
+
+		for attr in attributes {
+			let Some(value) = attribute(tag, attr) else {
+				continue;
+			};
+			let value = value.replace("&amp;", "&");
+
+			match *attr {
+				// `url 1x, url 2x`
+				"srcset" => links.extend(
+					value
+						.split(',')
+						.filter_map(|x| x.split_whitespace().next())
+						.map(str::to_owned),
+				),
+				_ => links.push(value),
+			}
+		}
+
+		None
+	});
+
+	links
+}
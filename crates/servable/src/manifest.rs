@@ -0,0 +1,339 @@
+use axum::http::{HeaderMap, StatusCode, header};
+use chrono::TimeDelta;
+use mime::Mime;
+use serde::Serialize;
+use std::pin::Pin;
+
+use crate::{
+	RenderContext, RenderMode, Rendered, RenderedBody, router::cache_control, servable::Servable,
+};
+
+/// How an installed web app is shown, see [WebManifest::display]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum DisplayMode {
+	/// Use the whole screen, without any browser ui
+	Fullscreen,
+
+	/// Look like a native app, in its own window
+	#[default]
+	Standalone,
+
+	/// Like [Self::Standalone], with minimal navigation controls
+	MinimalUi,
+
+	/// Open in a normal browser tab
+	Browser,
+}
+
+impl DisplayMode {
+	/// The value of this mode in a manifest
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			Self::Fullscreen => "fullscreen",
+			Self::Standalone => "standalone",
+			Self::MinimalUi => "minimal-ui",
+			Self::Browser => "browser",
+		}
+	}
+}
+
+/// One icon of a [WebManifest]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestIcon {
+	/// The url of this icon, like `/icon.png`
+	pub src: String,
+
+	/// The sizes of this icon, like `192x192` or `any`
+	pub sizes: String,
+
+	/// The mime type of this icon
+	pub mime: Option<Mime>,
+
+	/// What this icon may be used for, like `maskable` or `any maskable`
+	pub purpose: Option<String>,
+}
+
+impl ManifestIcon {
+	/// Create a [ManifestIcon] at `src` with the given sizes
+	pub fn new(src: impl Into<String>, sizes: impl Into<String>) -> Self {
+		Self {
+			src: src.into(),
+			sizes: sizes.into(),
+			mime: None,
+			purpose: None,
+		}
+	}
+
+	/// Set `self.mime`
+	#[inline(always)]
+	pub fn with_mime(mut self, mime: Mime) -> Self {
+		self.mime = Some(mime);
+		self
+	}
+
+	/// Set `self.purpose`
+	#[inline(always)]
+	pub fn with_purpose(mut self, purpose: impl Into<String>) -> Self {
+		self.purpose = Some(purpose.into());
+		self
+	}
+}
+
+/// A [web app manifest](https://developer.mozilla.org/en-US/docs/Web/Manifest),
+/// which lets browsers install a site as an app.
+/// Link it from pages with [crate::HtmlPage::with_manifest].
+///
+/// Urls in this manifest that start with `/` are relative to the router's base path.
+///
+/// ```rust
+/// use servable::{DisplayMode, HtmlPage, ServableRouter, WebManifest};
+///
+/// let manifest = WebManifest::new("My App")
+/// 	.with_short_name("App")
+/// 	.with_theme_color("#1a1a1a")
+/// 	.with_display(DisplayMode::Standalone);
+///
+/// let router = ServableRouter::new()
+/// 	.add_page("/manifest.webmanifest", manifest)
+/// 	.add_page("/", HtmlPage::default().with_manifest("/manifest.webmanifest"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebManifest {
+	/// The name of this app
+	pub name: String,
+
+	/// A shorter name, for places without room for [Self::name]
+	pub short_name: Option<String>,
+
+	/// What this app does
+	pub description: Option<String>,
+
+	/// The page this app opens at. This is `/` by default.
+	pub start_url: String,
+
+	/// The urls that are part of this app
+	pub scope: Option<String>,
+
+	/// How this app is shown
+	pub display: DisplayMode,
+
+	/// The color of the browser ui around this app, like `#1a1a1a`
+	pub theme_color: Option<String>,
+
+	/// The color shown while this app loads
+	pub background_color: Option<String>,
+
+	/// This app's icons
+	pub icons: Vec<ManifestIcon>,
+
+	/// How long this manifest may be cached.
+	/// This is one day by default.
+	pub ttl: Option<TimeDelta>,
+}
+
+impl WebManifest {
+	/// Create a [WebManifest] for the app named `name`
+	pub fn new(name: impl Into<String>) -> Self {
+		Self {
+			name: name.into(),
+			short_name: None,
+			description: None,
+			start_url: "/".to_owned(),
+			scope: None,
+			display: DisplayMode::default(),
+			theme_color: None,
+			background_color: None,
+			icons: Vec::new(),
+			ttl: Some(TimeDelta::days(1)),
+		}
+	}
+
+	/// Set `self.short_name`
+	#[inline(always)]
+	pub fn with_short_name(mut self, short_name: impl Into<String>) -> Self {
+		self.short_name = Some(short_name.into());
+		self
+	}
+
+	/// Set `self.description`
+	#[inline(always)]
+	pub fn with_description(mut self, description: impl Into<String>) -> Self {
+		self.description = Some(description.into());
+		self
+	}
+
+	/// Set `self.start_url`
+	#[inline(always)]
+	pub fn with_start_url(mut self, start_url: impl Into<String>) -> Self {
+		self.start_url = start_url.into();
+		self
+	}
+
+	/// Set `self.scope`
+	#[inline(always)]
+	pub fn with_scope(mut self, scope: impl Into<String>) -> Self {
+		self.scope = Some(scope.into());
+		self
+	}
+
+	/// Set `self.display`
+	#[inline(always)]
+	pub fn with_display(mut self, display: DisplayMode) -> Self {
+		self.display = display;
+		self
+	}
+
+	/// Set `self.theme_color`
+	#[inline(always)]
+	pub fn with_theme_color(mut self, color: impl Into<String>) -> Self {
+		self.theme_color = Some(color.into());
+		self
+	}
+
+	/// Set `self.background_color`
+	#[inline(always)]
+	pub fn with_background_color(mut self, color: impl Into<String>) -> Self {
+		self.background_color = Some(color.into());
+		self
+	}
+
+	/// Add an icon to this manifest
+	#[inline(always)]
+	pub fn with_icon(mut self, icon: ManifestIcon) -> Self {
+		self.icons.push(icon);
+		self
+	}
+
+	/// Add one png icon for each size in `sizes`,
+	/// all generated from the square image served at `route` (see [crate::transform]).
+	/// The router must have transforms enabled.
+	///
+	/// ```rust
+	/// use servable::WebManifest;
+	///
+	/// let manifest = WebManifest::new("My App").with_icon_sizes("/icon.png", &[192, 512]);
+	/// assert_eq!(manifest.icons[0].src, "/icon.png?t=maxdim(192,192);format(png)");
+	/// assert_eq!(manifest.icons[1].sizes, "512x512");
+	/// ```
+	#[cfg(feature = "image")]
+	pub fn with_icon_sizes(mut self, route: &str, sizes: &[u32]) -> Self {
+		for size in sizes {
+			self.icons.push(
+				ManifestIcon::new(
+					format!("{route}?t=maxdim({size},{size});format(png)"),
+					format!("{size}x{size}"),
+				)
+				.with_mime(mime::IMAGE_PNG),
+			);
+		}
+		self
+	}
+
+	/// Set `self.ttl`
+	#[inline(always)]
+	pub fn with_ttl(mut self, ttl: Option<TimeDelta>) -> Self {
+		self.ttl = ttl;
+		self
+	}
+
+	/// The json of this manifest
+	pub fn render_json(&self, ctx: &RenderContext) -> String {
+		#[derive(Serialize)]
+		struct Icon {
+			src: String,
+			sizes: String,
+			#[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+			mime: Option<String>,
+			#[serde(skip_serializing_if = "Option::is_none")]
+			purpose: Option<String>,
+		}
+
+		#[derive(Serialize)]
+		struct Manifest<'a> {
+			name: &'a str,
+			#[serde(skip_serializing_if = "Option::is_none")]
+			short_name: Option<&'a str>,
+			#[serde(skip_serializing_if = "Option::is_none")]
+			description: Option<&'a str>,
+			start_url: String,
+			#[serde(skip_serializing_if = "Option::is_none")]
+			scope: Option<String>,
+			display: &'static str,
+			#[serde(skip_serializing_if = "Option::is_none")]
+			theme_color: Option<&'a str>,
+			#[serde(skip_serializing_if = "Option::is_none")]
+			background_color: Option<&'a str>,
+			#[serde(skip_serializing_if = "Vec::is_empty")]
+			icons: Vec<Icon>,
+		}
+
+		let manifest = Manifest {
+			name: &self.name,
+			short_name: self.short_name.as_deref(),
+			description: self.description.as_deref(),
+			start_url: ctx.url(&self.start_url),
+			scope: self.scope.as_deref().map(|x| ctx.url(x)),
+			display: self.display.as_str(),
+			theme_color: self.theme_color.as_deref(),
+			background_color: self.background_color.as_deref(),
+			icons: self
+				.icons
+				.iter()
+				.map(|x| Icon {
+					src: ctx.url(&x.src),
+					sizes: x.sizes.clone(),
+					mime: x.mime.as_ref().map(|x| x.to_string()),
+					purpose: x.purpose.clone(),
+				})
+				.collect(),
+		};
+
+		serde_json::to_string(&manifest).unwrap_or_default()
+	}
+}
+
+impl Servable for WebManifest {
+	#[expect(clippy::unwrap_used)]
+	fn head<'a>(
+		&'a self,
+		_ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<()>> + 'a + Send + Sync>> {
+		Box::pin(async {
+			Rendered {
+				code: StatusCode::OK,
+				headers: HeaderMap::new(),
+				body: (),
+				mime: Some("application/manifest+json".parse().unwrap()),
+				ttl: self.ttl,
+				private: false,
+				content_length: None,
+			}
+		})
+	}
+
+	fn render<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<RenderedBody>> + 'a + Send + Sync>> {
+		Box::pin(async {
+			let rend = self.head(ctx).await;
+			match ctx.mode {
+				RenderMode::Head => rend.with_body(RenderedBody::Empty),
+				_ => rend.with_body(RenderedBody::String(self.render_json(ctx))),
+			}
+		})
+	}
+
+	fn header_template(&self) -> HeaderMap {
+		let mut headers = HeaderMap::with_capacity(1);
+		headers.insert(header::CACHE_CONTROL, cache_control(self.ttl, false));
+		headers
+	}
+
+	fn linked_routes(&self) -> Vec<String> {
+		let icons = self.icons.iter().map(|x| x.src.clone());
+		std::iter::once(self.start_url.clone())
+			.chain(icons)
+			.collect()
+	}
+}
@@ -0,0 +1,148 @@
+use axum::{
+	body::Body,
+	http::{Request, header},
+	response::{IntoResponse, Response},
+};
+use rand::Rng;
+use std::convert::Infallible;
+use tower::{Service, ServiceExt, util::BoxCloneSyncService};
+use tracing::trace;
+
+#[cfg(feature = "proxy")]
+use crate::Error;
+
+/// Where a [Mirror] sends requests
+#[derive(Clone)]
+enum MirrorTarget {
+	Service(BoxCloneSyncService<Request<Body>, Response, Infallible>),
+
+	#[cfg(feature = "proxy")]
+	Url {
+		/// The mirror's url, without a trailing `/`
+		url: String,
+		client: hyper_util::client::legacy::Client<
+			hyper_util::client::legacy::connect::HttpConnector,
+			http_body_util::Empty<axum::body::Bytes>,
+		>,
+	},
+}
+
+/// Sends a copy of some requests to a secondary service,
+/// for example to test a new version of a site with real traffic.
+/// See [crate::ServableRouter::with_mirror].
+///
+/// Mirrored requests have the same method, path, query and headers as the original,
+/// but no body. They are sent in the background, and their responses are ignored.
+/// They never change or delay the response to the original request.
+///
+/// ```rust
+/// use servable::{HtmlPage, Mirror, ServableRouter};
+///
+/// let next = ServableRouter::new().add_page("/", HtmlPage::default());
+///
+/// // Send 10% of all requests to `next`
+/// let router = ServableRouter::new()
+/// 	.add_page("/", HtmlPage::default())
+/// 	.with_mirror(Mirror::new(next).with_fraction(0.1));
+/// ```
+#[derive(Clone)]
+pub struct Mirror {
+	target: MirrorTarget,
+
+	/// The fraction of requests that are mirrored, between 0 and 1
+	fraction: f64,
+}
+
+impl Mirror {
+	/// Mirror every request to `service`
+	pub fn new<S>(service: S) -> Self
+	where
+		S: Service<Request<Body>, Error = Infallible> + Clone + Send + Sync + 'static,
+		S::Response: IntoResponse,
+		S::Future: Send + 'static,
+	{
+		Self {
+			target: MirrorTarget::Service(BoxCloneSyncService::new(
+				service.map_response(IntoResponse::into_response),
+			)),
+			fraction: 1.0,
+		}
+	}
+
+	/// Mirror every request to another http server.
+	/// A request for `/a?b` is sent to `{url}/a?b`.
+	///
+	/// Returns an error if `url` is not an `http://` url,
+	/// or if it has a query.
+	#[cfg(feature = "proxy")]
+	pub fn url(url: &str) -> Result<Self, Error> {
+		use hyper_util::{client::legacy::Client, rt::TokioExecutor};
+
+		Ok(Self {
+			target: MirrorTarget::Url {
+				url: crate::proxy::Upstream::new(url)?.url,
+				client: Client::builder(TokioExecutor::new()).build_http(),
+			},
+			fraction: 1.0,
+		})
+	}
+
+	/// Only mirror a fraction of requests, between `0.0` (none) and `1.0` (all).
+	/// Requests are picked at random.
+	#[inline(always)]
+	pub fn with_fraction(mut self, fraction: f64) -> Self {
+		self.fraction = fraction.clamp(0.0, 1.0);
+		self
+	}
+
+	/// Maybe send a copy of `req` to this mirror, in the background.
+	pub(crate) fn mirror(&self, req: &Request<Body>) {
+		if self.fraction < 1.0 && rand::rng().random::<f64>() >= self.fraction {
+			return;
+		}
+
+		let mut copy = Request::new(Body::empty());
+		*copy.method_mut() = req.method().clone();
+		*copy.uri_mut() = req.uri().clone();
+		*copy.headers_mut() = req.headers().clone();
+
+		// Mirrored requests have no body
+		copy.headers_mut().remove(header::CONTENT_LENGTH);
+		copy.headers_mut().remove(header::TRANSFER_ENCODING);
+
+		match &self.target {
+			MirrorTarget::Service(service) => {
+				let service = service.clone();
+				tokio::spawn(async move {
+					let Ok(res) = service.oneshot(copy).await;
+					trace!(message = "Mirrored request", status = ?res.status());
+				});
+			}
+
+			#[cfg(feature = "proxy")]
+			MirrorTarget::Url { url, client } => {
+				let path = req
+					.uri()
+					.path_and_query()
+					.map(|x| x.as_str())
+					.unwrap_or("/");
+				let Ok(uri) = format!("{url}{path}").parse() else {
+					return;
+				};
+
+				let (parts, _) = copy.into_parts();
+				let mut copy = Request::from_parts(parts, http_body_util::Empty::new());
+				copy.headers_mut().remove(header::HOST);
+				*copy.uri_mut() = uri;
+
+				let client = client.clone();
+				tokio::spawn(async move {
+					match client.request(copy).await {
+						Ok(res) => trace!(message = "Mirrored request", status = ?res.status()),
+						Err(error) => trace!(message = "Mirrored request failed", ?error),
+					}
+				});
+			}
+		}
+	}
+}
@@ -0,0 +1,177 @@
+use axum::http::{HeaderMap, HeaderValue, header};
+use std::{pin::Pin, sync::Arc};
+
+use crate::{
+	DynHtmlRender, HtmlPage, HtmlRender, RenderContext, RenderMode, Rendered, RenderedBody,
+	RenderedBodyType, router::merge_template, servable::Servable,
+};
+
+/// The closure that generates the json of a [Negotiated]
+pub type JsonRenderFn = dyn Send
+	+ Sync
+	+ 'static
+	+ for<'a> Fn(
+		&'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = serde_json::Value> + Send + Sync + 'a>>;
+
+/// Find the quality value of `mime` in an `Accept` header.
+/// The most specific matching range wins: `text/html` over `text/*` over `*/*`.
+pub(crate) fn quality(accept: &str, mime: &str) -> f32 {
+	let (ty, _) = mime.split_once('/').unwrap_or((mime, ""));
+
+	// (specificity, q) of the best match so far
+	let mut best: Option<(u8, f32)> = None;
+	for entry in accept.split(',') {
+		let mut parts = entry.split(';');
+		let range = parts.next().unwrap_or("").trim();
+
+		let q = parts
+			.filter_map(|x| x.trim().strip_prefix("q="))
+			.next()
+			.map(|x| x.trim().parse::<f32>().unwrap_or(0.0))
+			.unwrap_or(1.0);
+
+		let specificity = match range.split_once('/') {
+			_ if range.eq_ignore_ascii_case(mime) => 2,
+			Some((t, "*")) if t.eq_ignore_ascii_case(ty) => 1,
+			Some(("*", "*")) => 0,
+			_ => continue,
+		};
+
+		if best.is_none_or(|(s, _)| specificity > s) {
+			best = Some((specificity, q));
+		}
+	}
+
+	best.map(|(_, q)| q).unwrap_or(0.0)
+}
+
+/// A [Servable] that serves one resource as html to browsers
+/// and as json to api clients, picked by the request's `Accept` header.
+///
+/// Json is served only if the client prefers `application/json` to `text/html`,
+/// so browsers and clients that send `*/*` (or no `Accept` header) get html.
+/// The json response has the same code, ttl and privacy as the page.
+///
+/// Every response has `Vary: Accept`.
+///
+/// ```rust
+/// use servable::{HtmlPage, Negotiated, ServableRouter};
+/// use maud::html;
+/// use serde_json::json;
+///
+/// let page = HtmlPage::default().with_render(|_page, _ctx| Box::pin(async { html! { "3 users" } }));
+/// let users = Negotiated::new(page, |_ctx| Box::pin(async { json!({ "users": 3 }) }));
+///
+/// let router = ServableRouter::new().add_page("/users", users);
+/// ```
+pub struct Negotiated<R: HtmlRender = DynHtmlRender> {
+	/// The page served to browsers
+	pub page: HtmlPage<R>,
+
+	/// The function that generates the json served to api clients
+	pub json: Arc<JsonRenderFn>,
+
+	/// `page`'s [Servable::header_template]
+	template: HeaderMap,
+}
+
+impl<R: HtmlRender> Negotiated<R> {
+	/// Serve `page` to browsers and the value returned by `json` to api clients
+	pub fn new<F>(page: HtmlPage<R>, json: F) -> Self
+	where
+		F: Send
+			+ Sync
+			+ 'static
+			+ for<'a> Fn(
+				&'a RenderContext,
+			) -> Pin<Box<dyn Future<Output = serde_json::Value> + Send + Sync + 'a>>,
+	{
+		Self {
+			template: page.header_template(),
+			page,
+			json: Arc::new(json),
+		}
+	}
+
+	/// Returns true if `ctx` should get json
+	pub fn wants_json(ctx: &RenderContext) -> bool {
+		let Some(accept) = ctx
+			.headers
+			.get(header::ACCEPT)
+			.and_then(|x| x.to_str().ok())
+		else {
+			return false;
+		};
+
+		let json = quality(accept, "application/json");
+		let html = quality(accept, "text/html").max(quality(accept, "application/xhtml+xml"));
+		json > 0.0 && json > html
+	}
+
+	/// The head of the json response
+	fn json_head(&self) -> Rendered<()> {
+		Rendered {
+			code: self.page.response_code,
+			headers: HeaderMap::new(),
+			body: (),
+			mime: Some(mime::APPLICATION_JSON),
+			ttl: self.page.ttl,
+			private: self.page.private,
+			content_length: None,
+		}
+	}
+
+	/// Add `Vary` and, for html, the page's header template to `rend`
+	fn finish<T: RenderedBodyType>(&self, json: bool, mut rend: Rendered<T>) -> Rendered<T> {
+		rend.headers
+			.append(header::VARY, HeaderValue::from_static("Accept"));
+
+		if !json && rend.code.is_success() {
+			merge_template(&mut rend.headers, &self.template);
+		}
+
+		rend
+	}
+}
+
+impl<R: HtmlRender> Servable for Negotiated<R> {
+	fn head<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<()>> + 'a + Send + Sync>> {
+		Box::pin(async move {
+			let json = Self::wants_json(ctx);
+			let rend = match json {
+				true => self.json_head(),
+				false => self.page.head(ctx).await,
+			};
+			self.finish(json, rend)
+		})
+	}
+
+	fn render<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<RenderedBody>> + 'a + Send + Sync>> {
+		Box::pin(async move {
+			let json = Self::wants_json(ctx);
+			let rend = match json {
+				false => self.page.render(ctx).await,
+				true if ctx.mode == RenderMode::Head => {
+					self.json_head().with_body(RenderedBody::Empty)
+				}
+				true => {
+					let value = (self.json)(ctx).await;
+					self.json_head().with_body(RenderedBody::json(value))
+				}
+			};
+			self.finish(json, rend)
+		})
+	}
+
+	#[inline(always)]
+	fn linked_routes(&self) -> Vec<String> {
+		self.page.linked_routes()
+	}
+}
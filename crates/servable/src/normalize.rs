@@ -0,0 +1,247 @@
+use std::borrow::Cow;
+
+/// What a [crate::ServableRouter] does when it receives
+/// a request for a url that is not in canonical form.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NonCanonicalAction {
+	/// Reply with a permanent redirect to the canonical url
+	#[default]
+	Redirect,
+
+	/// Reply with this router's 404 page
+	NotFound,
+}
+
+/// Controls how a [crate::ServableRouter] normalizes request paths.
+///
+/// Paths are always normalized by removing trailing slashes
+/// and collapsing repeated slashes (`//`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct UrlNormalization {
+	/// If true, the canonical form of a path is lowercase.
+	/// Only ascii characters are changed.
+	///
+	/// If this is enabled, all routes should be lowercase.
+	pub lowercase: bool,
+
+	/// If true, normalize percent-encoding in paths:
+	/// - unreserved characters are decoded (`%7E` becomes `~`)
+	/// - all other escapes are uppercased (`%2f` becomes `%2F`)
+	pub percent_encoding: bool,
+
+	/// Query parameters to remove from urls, like tracking parameters.
+	/// Entries that end with `*` match every parameter with that prefix,
+	/// so `utm_*` matches `utm_source` and `utm_campaign`.
+	pub strip_query: &'static [&'static str],
+
+	/// If true, the canonical form of a query string is sorted by parameter name.
+	/// Parameters with the same name keep their order.
+	pub sort_query: bool,
+
+	/// If true, image transformations (the `t` parameter) are rewritten in canonical form,
+	/// so that equivalent urls share cache entries. See [crate::transform::TransformerChain].
+	#[cfg(feature = "image")]
+	pub transforms: bool,
+
+	/// What to do with requests for non-canonical urls.
+	///
+	/// Requests with a non-canonical query string are never
+	/// sent to the 404 page. If this is [NonCanonicalAction::NotFound],
+	/// they are served as if they had a canonical query string.
+	pub action: NonCanonicalAction,
+}
+
+impl UrlNormalization {
+	/// Set `self.lowercase`
+	#[inline(always)]
+	pub fn with_lowercase(mut self, lowercase: bool) -> Self {
+		self.lowercase = lowercase;
+		self
+	}
+
+	/// Set `self.percent_encoding`
+	#[inline(always)]
+	pub fn with_percent_encoding(mut self, percent_encoding: bool) -> Self {
+		self.percent_encoding = percent_encoding;
+		self
+	}
+
+	/// Set `self.strip_query`
+	#[inline(always)]
+	pub fn with_strip_query(mut self, strip_query: &'static [&'static str]) -> Self {
+		self.strip_query = strip_query;
+		self
+	}
+
+	/// Set `self.sort_query`
+	#[inline(always)]
+	pub fn with_sort_query(mut self, sort_query: bool) -> Self {
+		self.sort_query = sort_query;
+		self
+	}
+
+	/// Set `self.transforms`
+	#[cfg(feature = "image")]
+	#[inline(always)]
+	pub fn with_transforms(mut self, transforms: bool) -> Self {
+		self.transforms = transforms;
+		self
+	}
+
+	/// Set `self.action`
+	#[inline(always)]
+	pub fn with_action(mut self, action: NonCanonicalAction) -> Self {
+		self.action = action;
+		self
+	}
+
+	/// Return the canonical form of `path`,
+	/// which must start with a `/`.
+	///
+	/// ```rust
+	/// use servable::UrlNormalization;
+	///
+	/// let norm = UrlNormalization::default()
+	/// 	.with_lowercase(true)
+	/// 	.with_percent_encoding(true);
+	///
+	/// assert_eq!(norm.canonical_path("//About/%7euser/"), "/about/~user");
+	/// assert_eq!(norm.canonical_path("/a%2fb"), "/a%2Fb");
+	/// assert_eq!(norm.canonical_path("/"), "/");
+	/// ```
+	pub fn canonical_path(&self, path: &str) -> String {
+		let mut out: Vec<u8> = Vec::with_capacity(path.len());
+		let case = |c: u8| match self.lowercase {
+			true => c.to_ascii_lowercase(),
+			false => c,
+		};
+
+		let mut bytes = path.bytes().peekable();
+		while let Some(b) = bytes.next() {
+			match b {
+				// Collapse repeated slashes
+				b'/' if out.last() == Some(&b'/') => {}
+
+				b'%' if self.percent_encoding => {
+					let hi = bytes.next_if(u8::is_ascii_hexdigit);
+					let lo = hi.and_then(|_| bytes.next_if(u8::is_ascii_hexdigit));
+
+					match (hi, lo) {
+						(Some(hi), Some(lo)) => {
+							let c = (hex_value(hi) << 4) | hex_value(lo);
+							if c.is_ascii_alphanumeric() || matches!(c, b'-' | b'.' | b'_' | b'~') {
+								out.push(case(c));
+							} else {
+								out.extend_from_slice(format!("%{c:02X}").as_bytes());
+							}
+						}
+
+						// Malformed escapes are left as-is
+						(Some(hi), None) => out.extend_from_slice(&[b'%', hi]),
+						(None, _) => out.push(b'%'),
+					}
+				}
+
+				_ => out.push(case(b)),
+			}
+		}
+
+		if out.len() > 1 && out.last() == Some(&b'/') {
+			out.pop();
+		}
+
+		// We only ever replace ascii characters with other ascii characters,
+		// so `out` is always valid utf-8.
+		return String::from_utf8(out).unwrap_or_else(|_| path.to_owned());
+	}
+}
+
+impl UrlNormalization {
+	/// Return the canonical form of `query`,
+	/// which should not include a leading `?`.
+	///
+	/// Parameters are compared by their raw (still percent-encoded) names.
+	/// Empty parameters (as in `a=1&&b=2`) are removed.
+	///
+	/// ```rust
+	/// use servable::UrlNormalization;
+	///
+	/// let norm = UrlNormalization::default()
+	/// 	.with_strip_query(&["utm_*", "fbclid"])
+	/// 	.with_sort_query(true);
+	///
+	/// assert_eq!(norm.canonical_query("utm_source=x&t=1&fbclid=y&a=2"), "a=2&t=1");
+	/// assert_eq!(norm.canonical_query("b&a=1&b=2"), "a=1&b&b=2");
+	/// assert_eq!(norm.canonical_query("utm_medium=z"), "");
+	/// ```
+	pub fn canonical_query(&self, query: &str) -> String {
+		#[cfg(feature = "image")]
+		let transforms = self.transforms;
+		#[cfg(not(feature = "image"))]
+		let transforms = false;
+
+		if self.strip_query.is_empty() && !self.sort_query && !transforms {
+			return query.to_owned();
+		}
+
+		let name = |param: &str| {
+			param
+				.split_once('=')
+				.map(|x| x.0)
+				.unwrap_or(param)
+				.to_owned()
+		};
+		let stripped = |name: &str| {
+			self.strip_query.iter().any(|x| match x.strip_suffix('*') {
+				Some(prefix) => name.starts_with(prefix),
+				None => name == *x,
+			})
+		};
+
+		let mut params: Vec<(String, Cow<'_, str>)> = query
+			.split('&')
+			.filter(|x| !x.is_empty())
+			.map(|x| (name(x), Cow::Borrowed(x)))
+			.filter(|(name, _)| !stripped(name))
+			.collect();
+
+		#[cfg(feature = "image")]
+		if transforms {
+			for (name, param) in &mut params {
+				if name == "t"
+					&& let Some(x) = crate::transform::canonical_param(param)
+				{
+					*param = Cow::Owned(x);
+				}
+			}
+		}
+
+		if self.sort_query {
+			params.sort_by(|a, b| a.0.cmp(&b.0));
+		}
+
+		params
+			.into_iter()
+			.map(|x| x.1)
+			.collect::<Vec<_>>()
+			.join("&")
+	}
+}
+
+/// Convert an ascii hex digit to its value
+fn hex_value(digit: u8) -> u8 {
+	match digit {
+		b'0'..=b'9' => digit - b'0',
+		b'a'..=b'f' => digit - b'a' + 10,
+		b'A'..=b'F' => digit - b'A' + 10,
+		_ => 0,
+	}
+}
+
+/// Returns true if `route` is `prefix` or is under `prefix`.
+/// Only whole segments match: `/a` is a prefix of `/a/b`, but not of `/ab`.
+pub(crate) fn has_route_prefix(route: &str, prefix: &str) -> bool {
+	route
+		.strip_prefix(prefix.trim_end_matches('/'))
+		.is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
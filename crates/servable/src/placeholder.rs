@@ -0,0 +1,553 @@
+use axum::http::{HeaderMap, StatusCode, header};
+use chrono::TimeDelta;
+use image::{DynamicImage, ImageFormat, Rgb, RgbImage};
+use std::{io::Cursor, pin::Pin};
+
+use crate::{
+	RenderContext, RenderMode, Rendered, RenderedBody, StaticAsset, router::cache_control,
+	servable::Servable, transform::transformers::parse_color,
+};
+
+/// A 5x7 pixel font for placeholder labels.
+/// Each row is five bits, with the leftmost pixel in the highest bit.
+const GLYPHS: &[(char, [u8; 7])] = &[
+	(' ', [0, 0, 0, 0, 0, 0, 0]),
+	(
+		'A',
+		[
+			0b01110, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001,
+		],
+	),
+	(
+		'B',
+		[
+			0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110,
+		],
+	),
+	(
+		'C',
+		[
+			0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110,
+		],
+	),
+	(
+		'D',
+		[
+			0b11100, 0b10010, 0b10001, 0b10001, 0b10001, 0b10010, 0b11100,
+		],
+	),
+	(
+		'E',
+		[
+			0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b11111,
+		],
+	),
+	(
+		'F',
+		[
+			0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000,
+		],
+	),
+	(
+		'G',
+		[
+			0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111,
+		],
+	),
+	(
+		'H',
+		[
+			0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001,
+		],
+	),
+	(
+		'I',
+		[
+			0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110,
+		],
+	),
+	(
+		'J',
+		[
+			0b00111, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100,
+		],
+	),
+	(
+		'K',
+		[
+			0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001,
+		],
+	),
+	(
+		'L',
+		[
+			0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111,
+		],
+	),
+	(
+		'M',
+		[
+			0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001,
+		],
+	),
+	(
+		'N',
+		[
+			0b10001, 0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b10001,
+		],
+	),
+	(
+		'O',
+		[
+			0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110,
+		],
+	),
+	(
+		'P',
+		[
+			0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000,
+		],
+	),
+	(
+		'Q',
+		[
+			0b01110, 0b10001, 0b10001, 0b10001, 0b10101, 0b10010, 0b01101,
+		],
+	),
+	(
+		'R',
+		[
+			0b11110, 0b10001, 0b10001, 0b11110, 0b10100, 0b10010, 0b10001,
+		],
+	),
+	(
+		'S',
+		[
+			0b01111, 0b10000, 0b10000, 0b01110, 0b00001, 0b00001, 0b11110,
+		],
+	),
+	(
+		'T',
+		[
+			0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100,
+		],
+	),
+	(
+		'U',
+		[
+			0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110,
+		],
+	),
+	(
+		'V',
+		[
+			0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100,
+		],
+	),
+	(
+		'W',
+		[
+			0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010,
+		],
+	),
+	(
+		'X',
+		[
+			0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001,
+		],
+	),
+	(
+		'Y',
+		[
+			0b10001, 0b10001, 0b10001, 0b01010, 0b00100, 0b00100, 0b00100,
+		],
+	),
+	(
+		'Z',
+		[
+			0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111,
+		],
+	),
+	(
+		'0',
+		[
+			0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110,
+		],
+	),
+	(
+		'1',
+		[
+			0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110,
+		],
+	),
+	(
+		'2',
+		[
+			0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111,
+		],
+	),
+	(
+		'3',
+		[
+			0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110,
+		],
+	),
+	(
+		'4',
+		[
+			0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010,
+		],
+	),
+	(
+		'5',
+		[
+			0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110,
+		],
+	),
+	(
+		'6',
+		[
+			0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110,
+		],
+	),
+	(
+		'7',
+		[
+			0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000,
+		],
+	),
+	(
+		'8',
+		[
+			0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110,
+		],
+	),
+	(
+		'9',
+		[
+			0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100,
+		],
+	),
+	(
+		'×',
+		[
+			0b00000, 0b00000, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001,
+		],
+	),
+	(
+		'-',
+		[
+			0b00000, 0b00000, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000,
+		],
+	),
+	(
+		'.',
+		[
+			0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b01100,
+		],
+	),
+	(
+		',',
+		[
+			0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b00100, 0b01000,
+		],
+	),
+	(
+		':',
+		[
+			0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b01100, 0b00000,
+		],
+	),
+	(
+		'/',
+		[
+			0b00000, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b00000,
+		],
+	),
+	(
+		'!',
+		[
+			0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00000, 0b00100,
+		],
+	),
+	(
+		'?',
+		[
+			0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b00000, 0b00100,
+		],
+	),
+];
+
+/// The glyph of `c`. Lowercase letters are drawn as uppercase,
+/// and characters without a glyph are drawn as `?`.
+fn glyph(c: char) -> [u8; 7] {
+	let c = c.to_ascii_uppercase();
+	GLYPHS
+		.iter()
+		.find(|(x, _)| *x == c)
+		.or_else(|| GLYPHS.iter().find(|(x, _)| *x == '?'))
+		.map(|(_, x)| *x)
+		.unwrap_or_default()
+}
+
+/// A [Servable] that generates placeholder images for layout development,
+/// sized and labeled by query parameters:
+///
+/// - `w` and `h`: the size of the image, in pixels.
+///   If only one is given, the image is square. The default is 300 by 150.
+/// - `text`: the label of the image. The default is its size, like `300×150`.
+///   If empty, the image is a solid color.
+/// - `bg` and `fg`: hex colors for the background and the label, like `ddd` or `1a2b3c`.
+///
+/// Images are png, and invalid parameters get `400 Bad Request`.
+///
+/// ```rust
+/// use servable::{Placeholder, ServableRouter};
+///
+/// // `/placeholder?w=300&h=200&text=Hero`
+/// let router = ServableRouter::new().add_page("/placeholder", Placeholder::default());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Placeholder {
+	/// The default background color
+	pub background: [u8; 3],
+
+	/// The default label color
+	pub foreground: [u8; 3],
+
+	/// The largest width and height we generate.
+	/// Larger images get `400 Bad Request`.
+	pub max_size: u32,
+
+	/// How long clients may cache placeholders
+	pub ttl: Option<TimeDelta>,
+}
+
+impl Default for Placeholder {
+	fn default() -> Self {
+		Self {
+			background: [0xcc, 0xcc, 0xcc],
+			foreground: [0x55, 0x55, 0x55],
+			max_size: 4096,
+			ttl: StaticAsset::DEFAULT_TTL,
+		}
+	}
+}
+
+/// The parameters of one placeholder image
+struct PlaceholderParams {
+	width: u32,
+	height: u32,
+	text: String,
+	background: [u8; 3],
+	foreground: [u8; 3],
+}
+
+impl Placeholder {
+	/// Set `self.background`
+	#[inline(always)]
+	pub fn with_background(mut self, background: [u8; 3]) -> Self {
+		self.background = background;
+		self
+	}
+
+	/// Set `self.foreground`
+	#[inline(always)]
+	pub fn with_foreground(mut self, foreground: [u8; 3]) -> Self {
+		self.foreground = foreground;
+		self
+	}
+
+	/// Set `self.max_size`
+	#[inline(always)]
+	pub fn with_max_size(mut self, max_size: u32) -> Self {
+		self.max_size = max_size;
+		self
+	}
+
+	/// Set `self.ttl`
+	#[inline(always)]
+	pub fn with_ttl(mut self, ttl: Option<TimeDelta>) -> Self {
+		self.ttl = ttl;
+		self
+	}
+
+	/// Read the parameters of the image requested in `ctx`
+	fn params(&self, ctx: &RenderContext) -> Result<PlaceholderParams, String> {
+		let size = |name: &str| -> Result<Option<u32>, String> {
+			let Some(value) = ctx.query.get(name) else {
+				return Ok(None);
+			};
+
+			match value.parse::<u32>() {
+				Ok(x) if x > 0 && x <= self.max_size => Ok(Some(x)),
+				_ => Err(format!(
+					"`{name}` must be between 1 and {}, got {value}",
+					self.max_size
+				)),
+			}
+		};
+
+		let (width, height) = match (size("w")?, size("h")?) {
+			(Some(w), Some(h)) => (w, h),
+			(Some(x), None) | (None, Some(x)) => (x, x),
+			(None, None) => (300, 150),
+		};
+
+		let color = |name: &str, default: [u8; 3]| match ctx.query.get(name) {
+			Some(x) => parse_color(x),
+			None => Ok(default),
+		};
+
+		Ok(PlaceholderParams {
+			width,
+			height,
+			text: ctx
+				.query
+				.get("text")
+				.cloned()
+				.unwrap_or_else(|| format!("{width}×{height}")),
+			background: color("bg", self.background)?,
+			foreground: color("fg", self.foreground)?,
+		})
+	}
+
+	fn bad_request(&self, message: String) -> Rendered<RenderedBody> {
+		Rendered {
+			code: StatusCode::BAD_REQUEST,
+			headers: HeaderMap::new(),
+			body: RenderedBody::String(message),
+			mime: Some(mime::TEXT_PLAIN_UTF_8),
+			ttl: self.ttl,
+			private: false,
+			content_length: None,
+		}
+	}
+}
+
+impl PlaceholderParams {
+	/// Draw this image
+	fn draw(&self) -> RgbImage {
+		let mut image = RgbImage::from_pixel(self.width, self.height, Rgb(self.background));
+
+		// Labels are one line, and at most 80% of the image's width and half of its height
+		let chars: Vec<char> = self.text.chars().take(64).collect();
+		let n = u32::try_from(chars.len()).unwrap_or(64);
+		if n == 0 {
+			return image;
+		}
+
+		let text_width = 6 * n - 1;
+		let scale = (self.width * 4 / 5 / text_width).min(self.height / 2 / 7);
+		if scale == 0 {
+			return image;
+		}
+
+		let left = (self.width - text_width * scale) / 2;
+		let top = (self.height - 7 * scale) / 2;
+		for (i, c) in (0..n).zip(chars) {
+			for (row, bits) in (0..7).zip(glyph(c)) {
+				for col in 0..5 {
+					if bits & (0b10000 >> col) == 0 {
+						continue;
+					}
+
+					let x = left + (6 * i + col) * scale;
+					let y = top + row * scale;
+					for dy in 0..scale {
+						for dx in 0..scale {
+							image.put_pixel(x + dx, y + dy, Rgb(self.foreground));
+						}
+					}
+				}
+			}
+		}
+
+		image
+	}
+}
+
+impl Servable for Placeholder {
+	fn head<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<()>> + 'a + Send + Sync>> {
+		Box::pin(async {
+			let code = match self.params(ctx) {
+				Ok(_) => StatusCode::OK,
+				Err(_) => StatusCode::BAD_REQUEST,
+			};
+
+			Rendered {
+				code,
+				headers: HeaderMap::new(),
+				body: (),
+				mime: Some(mime::IMAGE_PNG),
+				ttl: self.ttl,
+				private: false,
+				content_length: None,
+			}
+		})
+	}
+
+	fn render<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<RenderedBody>> + 'a + Send + Sync>> {
+		Box::pin(async {
+			if ctx.mode == RenderMode::Head {
+				return self.head(ctx).await.with_body(RenderedBody::Empty);
+			}
+
+			let params = match self.params(ctx) {
+				Ok(x) => x,
+				Err(err) => return self.bad_request(err),
+			};
+
+			let task = tokio::task::spawn_blocking(move || {
+				let mut png = Cursor::new(Vec::new());
+				DynamicImage::ImageRgb8(params.draw())
+					.write_to(&mut png, ImageFormat::Png)
+					.map(|_| png.into_inner())
+			});
+
+			let bytes = match task.await {
+				Ok(Ok(x)) => Ok(x),
+				Ok(Err(err)) => Err(err.to_string()),
+				Err(err) => Err(err.to_string()),
+			};
+
+			match bytes {
+				Ok(bytes) => Rendered {
+					code: StatusCode::OK,
+					headers: HeaderMap::new(),
+					content_length: Some(bytes.len() as u64),
+					body: RenderedBody::Bytes(bytes),
+					mime: Some(mime::IMAGE_PNG),
+					ttl: self.ttl,
+					private: false,
+				},
+
+				Err(err) => {
+					let body = format!("Error while drawing placeholder: {err}");
+					ctx.report_error(err);
+					Rendered {
+						code: StatusCode::INTERNAL_SERVER_ERROR,
+						headers: HeaderMap::new(),
+						body: RenderedBody::String(body),
+						mime: None,
+						ttl: None,
+						private: false,
+						content_length: None,
+					}
+				}
+			}
+		})
+	}
+
+	fn header_template(&self) -> HeaderMap {
+		let mut headers = HeaderMap::with_capacity(1);
+		headers.insert(header::CACHE_CONTROL, cache_control(self.ttl, false));
+		headers
+	}
+}
@@ -0,0 +1,317 @@
+use maud::{Markup, html};
+use std::{
+	collections::HashMap,
+	fmt::Debug,
+	pin::Pin,
+	sync::{Arc, Mutex},
+	time::{Duration, Instant},
+};
+use tracing::debug;
+
+use crate::{
+	RenderContext,
+	scan::{attribute, find_ignore_case, rewrite_tags},
+};
+
+/// A boxed future that fetches the html at a url, see [LinkPreviews::new]
+pub type FetchFuture = Pin<Box<dyn Future<Output = Option<String>> + Send>>;
+
+/// The metadata of an external page, used to render a link preview card.
+/// See [LinkPreviews].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LinkPreview {
+	/// The url this preview links to
+	pub url: String,
+
+	/// `og:title`, or the page's `<title>`
+	pub title: Option<String>,
+
+	/// `og:description`, or the page's `description` meta tag
+	pub description: Option<String>,
+
+	/// `og:image`
+	pub image: Option<String>,
+
+	/// `og:site_name`
+	pub site_name: Option<String>,
+}
+
+impl LinkPreview {
+	/// Read the Open Graph metadata of the page at `url`, whose html is `html`
+	pub fn parse(url: &str, html: &str) -> Self {
+		let mut meta: HashMap<String, String> = HashMap::new();
+		let mut title = None;
+
+		rewrite_tags(html, &["meta", "title"], |name, open, rest| {
+			match name {
+				"title" if title.is_none() => {
+					let end = find_ignore_case(rest, "</title").unwrap_or(rest.len());
+					title = Some(decode_entities(rest[..end].trim()));
+				}
+
+				"meta" => {
+					let key = attribute(open, "property").or_else(|| attribute(open, "name"));
+					if let (Some(key), Some(content)) = (key, attribute(open, "content")) {
+						meta.entry(key.to_ascii_lowercase())
+							.or_insert_with(|| decode_entities(content.trim()));
+					}
+				}
+
+				_ => {}
+			}
+			None
+		});
+
+		let mut get = |key: &str| meta.remove(key).filter(|x| !x.is_empty());
+		Self {
+			url: url.to_owned(),
+			title: get("og:title").or(title.filter(|x| !x.is_empty())),
+			description: get("og:description").or_else(|| get("description")),
+			image: get("og:image"),
+			site_name: get("og:site_name"),
+		}
+	}
+
+	/// A link preview card.
+	/// The card has the class `link-preview`, which may be used to style it.
+	pub fn card(&self) -> Markup {
+		html! {
+			a class="link-preview" href=(self.url) rel="noopener" {
+				@if let Some(image) = &self.image {
+					img src=(image) alt="" loading="lazy" decoding="async";
+				}
+				div {
+					strong { (self.title.as_deref().unwrap_or(&self.url)) }
+					@if let Some(description) = &self.description {
+						p { (description) }
+					}
+					@if let Some(site_name) = &self.site_name {
+						small { (site_name) }
+					}
+				}
+			}
+		}
+	}
+}
+
+/// One cached [LinkPreview]
+struct CachedPreview {
+	/// `None` if fetching this preview failed
+	preview: Option<LinkPreview>,
+	fetched: Instant,
+}
+
+/// A bounded cache of [LinkPreview]s of external pages,
+/// so pages can show rich link previews without fetching them in the browser.
+///
+/// Previews are fetched when they are first requested, and kept for `ttl`.
+/// Failed fetches are also cached, so broken links aren't fetched on every request.
+/// When the cache is full, the oldest preview is forgotten.
+///
+/// Fetches time out after `timeout`, or when the request they are made for
+/// runs out of time (see [RenderContext::remaining]).
+///
+/// Cloning [LinkPreviews] is cheap, and clones share the same cache.
+///
+/// ```rust
+/// use servable::{HtmlPage, LinkPreviews};
+///
+/// // Fetch pages with any http client
+/// let previews = LinkPreviews::new(|url| {
+/// 	Box::pin(async move { Some(format!("<title>{url}</title>")) })
+/// });
+///
+/// let page = HtmlPage::default().with_render(move |_page, ctx| {
+/// 	let previews = previews.clone();
+/// 	Box::pin(async move {
+/// 		let preview = previews.get(ctx, "https://example.com").await;
+/// 		maud::html! {
+/// 			@if let Some(preview) = preview { (preview.card()) }
+/// 		}
+/// 	})
+/// });
+/// ```
+#[derive(Clone)]
+pub struct LinkPreviews {
+	fetch: Arc<dyn Fn(String) -> FetchFuture + Send + Sync>,
+	cache: Arc<Mutex<HashMap<String, CachedPreview>>>,
+
+	/// The maximum number of cached previews
+	capacity: usize,
+	ttl: Duration,
+	timeout: Duration,
+}
+
+impl Debug for LinkPreviews {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let len = self.cache.lock().map(|x| x.len()).unwrap_or_default();
+		f.debug_struct("LinkPreviews")
+			.field("len", &len)
+			.field("capacity", &self.capacity)
+			.field("ttl", &self.ttl)
+			.field("timeout", &self.timeout)
+			.finish()
+	}
+}
+
+impl LinkPreviews {
+	/// Create an empty cache that fetches pages with `fetch`.
+	/// `fetch` returns the html at a url, or `None` if it could not be fetched.
+	///
+	/// By default, we keep 1000 previews for a day, and fetches time out after 3 seconds.
+	pub fn new(fetch: impl Fn(String) -> FetchFuture + Send + Sync + 'static) -> Self {
+		Self {
+			fetch: Arc::new(fetch),
+			cache: Arc::new(Mutex::new(HashMap::new())),
+			capacity: 1000,
+			ttl: Duration::from_secs(24 * 3600),
+			timeout: Duration::from_secs(3),
+		}
+	}
+
+	/// Create an empty cache that fetches pages with a plain http client.
+	/// Only `http://` urls are supported, other urls never have a preview.
+	/// Use [Self::new] with another client to fetch `https://` urls.
+	///
+	/// Pages larger than 512 KiB are ignored.
+	#[cfg(feature = "proxy")]
+	pub fn http() -> Self {
+		use axum::body::Bytes;
+		use http_body_util::{BodyExt, Empty, Limited};
+		use hyper_util::{client::legacy::Client, rt::TokioExecutor};
+
+		let client: Client<_, Empty<Bytes>> = Client::builder(TokioExecutor::new()).build_http();
+		Self::new(move |url| {
+			let client = client.clone();
+			Box::pin(async move {
+				let res = client.get(url.parse().ok()?).await.ok()?;
+				if !res.status().is_success() {
+					return None;
+				}
+
+				let body = Limited::new(res.into_body(), 512 * 1024);
+				let bytes = body.collect().await.ok()?.to_bytes();
+				Some(String::from_utf8_lossy(&bytes).into_owned())
+			})
+		})
+	}
+
+	/// Set the maximum number of cached previews
+	#[inline(always)]
+	pub fn with_capacity(mut self, capacity: usize) -> Self {
+		self.capacity = capacity;
+		self
+	}
+
+	/// Set how long previews are cached
+	#[inline(always)]
+	pub fn with_ttl(mut self, ttl: Duration) -> Self {
+		self.ttl = ttl;
+		self
+	}
+
+	/// Set how long we wait for a page
+	#[inline(always)]
+	pub fn with_timeout(mut self, timeout: Duration) -> Self {
+		self.timeout = timeout;
+		self
+	}
+
+	/// Get the preview of `url`, fetching it if it isn't cached.
+	/// Returns `None` if `url` could not be fetched.
+	pub async fn get(&self, ctx: &RenderContext, url: &str) -> Option<LinkPreview> {
+		let now = ctx.clock.instant();
+		if let Ok(cache) = self.cache.lock()
+			&& let Some(cached) = cache.get(url)
+			&& now < cached.fetched + self.ttl
+		{
+			return cached.preview.clone();
+		}
+
+		let timeout = match ctx.remaining() {
+			Some(x) => x.min(self.timeout),
+			None => self.timeout,
+		};
+
+		// Fetch on another task, since `fetch` need not be `Sync`
+		let mut task = tokio::spawn((self.fetch)(url.to_owned()));
+		let preview = match tokio::time::timeout(timeout, &mut task).await {
+			Ok(Ok(Some(html))) => Some(LinkPreview::parse(url, &html)),
+			Ok(Ok(None)) | Ok(Err(_)) => None,
+			Err(_) => {
+				task.abort();
+				debug!(message = "Link preview timed out", url);
+				None
+			}
+		};
+
+		if let Ok(mut cache) = self.cache.lock() {
+			cache.retain(|_, x| now < x.fetched + self.ttl);
+
+			if cache.len() >= self.capacity
+				&& let Some(oldest) = cache
+					.iter()
+					.min_by_key(|(_, x)| x.fetched)
+					.map(|(k, _)| k.clone())
+			{
+				cache.remove(&oldest);
+			}
+
+			if self.capacity > 0 {
+				cache.insert(
+					url.to_owned(),
+					CachedPreview {
+						preview: preview.clone(),
+						fetched: now,
+					},
+				);
+			}
+		}
+
+		preview
+	}
+}
+
+/// Decode the html character references in an attribute or text
+fn decode_entities(s: &str) -> String {
+	let mut out = String::with_capacity(s.len());
+	let mut rest = s;
+
+	while let Some(i) = rest.find('&') {
+		out.push_str(&rest[..i]);
+		rest = &rest[i..];
+
+		let decoded = rest.find(';').filter(|x| *x <= 10).and_then(|end| {
+			let c = match &rest[1..end] {
+				"amp" => Some('&'),
+				"lt" => Some('<'),
+				"gt" => Some('>'),
+				"quot" => Some('"'),
+				"apos" => Some('\''),
+				"nbsp" => Some('\u{a0}'),
+				x => match x.strip_prefix("#x").or_else(|| x.strip_prefix("#X")) {
+					Some(hex) => u32::from_str_radix(hex, 16).ok().and_then(char::from_u32),
+					None => x
+						.strip_prefix('#')
+						.and_then(|x| x.parse().ok())
+						.and_then(char::from_u32),
+				},
+			};
+			c.map(|c| (c, end))
+		});
+
+		match decoded {
+			Some((c, end)) => {
+				out.push(c);
+				rest = &rest[end + 1..];
+			}
+			None => {
+				out.push('&');
+				rest = &rest[1..];
+			}
+		}
+	}
+
+	out.push_str(rest);
+	out
+}
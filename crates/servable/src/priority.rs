@@ -0,0 +1,169 @@
+use axum::http::{HeaderMap, HeaderValue, header};
+use mime::Mime;
+
+use crate::PreloadKind;
+
+/// The priority of a response, sent in the
+/// [`Priority`](https://www.rfc-editor.org/rfc/rfc9218) header.
+/// HTTP/2 and HTTP/3 servers and proxies use this to order responses on one connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Priority {
+	/// How urgent this response is, from 0 (most urgent) to 7.
+	/// The default urgency of a response is 3.
+	pub urgency: u8,
+
+	/// If true, this response is useful before it is complete,
+	/// like a progressive image. Incremental responses may share bandwidth.
+	pub incremental: bool,
+}
+
+impl Priority {
+	/// Create a non-incremental [Priority] with the given urgency.
+	/// Urgencies above 7 are clamped.
+	pub fn new(urgency: u8) -> Self {
+		Self {
+			urgency: urgency.min(7),
+			incremental: false,
+		}
+	}
+
+	/// Set `self.incremental`
+	#[inline(always)]
+	pub fn with_incremental(mut self, incremental: bool) -> Self {
+		self.incremental = incremental;
+		self
+	}
+
+	/// The value of the `Priority` header
+	pub fn header_value(&self) -> HeaderValue {
+		let value = match self.incremental {
+			true => format!("u={}, i", self.urgency.min(7)),
+			false => format!("u={}", self.urgency.min(7)),
+		};
+
+		#[expect(clippy::unwrap_used)]
+		HeaderValue::from_str(&value).unwrap()
+	}
+}
+
+/// The `Priority` header sent with each kind of asset,
+/// see [crate::ServableRouter::with_priority_hints].
+///
+/// The kind of a response is picked by its mime type:
+/// stylesheets, scripts, fonts (`font/*`) and images (`image/*`).
+/// Other responses, like html pages, get no `Priority` header.
+/// Responses that already have one are not changed.
+///
+/// ```rust
+/// use servable::{PreloadKind, Priority, PriorityHints, ServableRouter};
+///
+/// let hints = PriorityHints::default().with(PreloadKind::Font, Some(Priority::new(1)));
+/// assert_eq!(hints.get(PreloadKind::Style), Some(Priority::new(1)));
+///
+/// let router = ServableRouter::new().with_priority_hints(Some(hints));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PriorityHints {
+	/// The priority of stylesheets. The default is `u=1`.
+	pub style: Option<Priority>,
+
+	/// The priority of scripts. The default is `u=2`.
+	pub script: Option<Priority>,
+
+	/// The priority of fonts. The default is `u=2`.
+	pub font: Option<Priority>,
+
+	/// The priority of images. The default is `u=5, i`.
+	pub image: Option<Priority>,
+}
+
+impl Default for PriorityHints {
+	fn default() -> Self {
+		Self {
+			style: Some(Priority::new(1)),
+			script: Some(Priority::new(2)),
+			font: Some(Priority::new(2)),
+			image: Some(Priority::new(5).with_incremental(true)),
+		}
+	}
+}
+
+impl PriorityHints {
+	/// The priority of assets of kind `kind`
+	pub fn get(&self, kind: PreloadKind) -> Option<Priority> {
+		match kind {
+			PreloadKind::Style => self.style,
+			PreloadKind::Script => self.script,
+			PreloadKind::Font => self.font,
+			PreloadKind::Image => self.image,
+		}
+	}
+
+	/// Set the priority of assets of kind `kind`.
+	/// If `None`, these assets get no `Priority` header.
+	#[inline(always)]
+	pub fn with(mut self, kind: PreloadKind, priority: Option<Priority>) -> Self {
+		match kind {
+			PreloadKind::Style => self.style = priority,
+			PreloadKind::Script => self.script = priority,
+			PreloadKind::Font => self.font = priority,
+			PreloadKind::Image => self.image = priority,
+		}
+		self
+	}
+
+	/// The kind of asset with type `mime`
+	fn kind(mime: &Mime) -> Option<PreloadKind> {
+		match (mime.type_().as_str(), mime.subtype().as_str()) {
+			("text", "css") => Some(PreloadKind::Style),
+			("text" | "application", "javascript" | "ecmascript") => Some(PreloadKind::Script),
+			("font", _) => Some(PreloadKind::Font),
+			("image", _) => Some(PreloadKind::Image),
+			_ => None,
+		}
+	}
+
+	/// Add a `Priority` header to a response by its `Content-Type`, if it doesn't have one
+	pub(crate) fn apply(&self, headers: &mut HeaderMap) {
+		if headers.contains_key("priority") {
+			return;
+		}
+
+		let priority = headers
+			.get(header::CONTENT_TYPE)
+			.and_then(|x| x.to_str().ok())
+			.and_then(|x| x.parse::<Mime>().ok())
+			.and_then(|x| Self::kind(&x))
+			.and_then(|x| self.get(x));
+
+		if let Some(priority) = priority {
+			headers.insert("priority", priority.header_value());
+		}
+	}
+}
+
+/// The value of an html `fetchpriority` attribute,
+/// which tells browsers how soon to fetch an image or script
+/// relative to others of the same kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FetchPriority {
+	/// Fetch before other resources of the same kind
+	High,
+
+	/// Fetch after other resources of the same kind
+	Low,
+
+	/// Let the browser decide
+	Auto,
+}
+
+impl FetchPriority {
+	/// The value of this priority's `fetchpriority` attribute
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			Self::High => "high",
+			Self::Low => "low",
+			Self::Auto => "auto",
+		}
+	}
+}
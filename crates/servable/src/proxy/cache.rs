@@ -0,0 +1,116 @@
+use axum::{body::Bytes, http::HeaderMap};
+use std::{
+	collections::{HashMap, VecDeque},
+	sync::{Arc, Mutex},
+	time::{Duration, Instant},
+};
+
+/// Identifies one upstream response:
+/// `(upstream path and query, Accept-Encoding of the request)`
+pub(crate) type CacheKey = (String, String);
+
+/// An upstream response with status `200 OK`
+pub(crate) struct CachedResponse {
+	/// The headers we propagate from upstream
+	pub headers: HeaderMap,
+
+	/// The body of this response
+	pub body: Bytes,
+
+	/// When this response was received or last revalidated
+	pub stored: Instant,
+
+	/// How long this response is fresh for, counted from `stored`
+	pub fresh_for: Duration,
+}
+
+impl CachedResponse {
+	/// The time since this response was received or revalidated
+	pub fn age(&self, now: Instant) -> Duration {
+		now.saturating_duration_since(self.stored)
+	}
+
+	/// If true, this response may be served without revalidation
+	pub fn is_fresh(&self, now: Instant) -> bool {
+		self.age(now) < self.fresh_for
+	}
+}
+
+/// A small cache of upstream responses, see [super::Proxy::with_cache]
+pub(crate) struct ProxyCache {
+	/// The maximum total size of all bodies in this cache, in bytes
+	max_size: usize,
+	inner: Mutex<ProxyCacheInner>,
+}
+
+struct ProxyCacheInner {
+	responses: HashMap<CacheKey, Arc<CachedResponse>>,
+
+	/// Keys in `responses`, oldest first
+	order: VecDeque<CacheKey>,
+
+	/// The total size of all bodies in `responses`
+	size: usize,
+}
+
+impl ProxyCache {
+	pub(crate) fn new(max_size: usize) -> Self {
+		Self {
+			max_size,
+			inner: Mutex::new(ProxyCacheInner {
+				responses: HashMap::new(),
+				order: VecDeque::new(),
+				size: 0,
+			}),
+		}
+	}
+
+	pub(crate) fn get(&self, key: &CacheKey) -> Option<Arc<CachedResponse>> {
+		let inner = self.inner.lock().ok()?;
+		inner.responses.get(key).cloned()
+	}
+
+	/// Remember an upstream response, replacing any response with the same key.
+	/// Responses larger than this cache are ignored.
+	pub(crate) fn insert(&self, key: CacheKey, response: Arc<CachedResponse>) {
+		let len = response.body.len();
+		if len > self.max_size {
+			return;
+		}
+
+		let Ok(mut inner) = self.inner.lock() else {
+			return;
+		};
+
+		if let Some(old) = inner.responses.remove(&key) {
+			inner.size -= old.body.len();
+			inner.order.retain(|x| *x != key);
+		}
+
+		while inner.size + len > self.max_size {
+			let Some(old) = inner.order.pop_front() else {
+				break;
+			};
+
+			if let Some(old) = inner.responses.remove(&old) {
+				inner.size -= old.body.len();
+			}
+		}
+
+		inner.size += len;
+		inner.order.push_back(key.clone());
+		inner.responses.insert(key, response);
+	}
+
+	/// Forget an upstream response
+	pub(crate) fn remove(&self, key: &CacheKey) {
+		let Ok(mut inner) = self.inner.lock() else {
+			return;
+		};
+
+		if let Some(old) = inner.responses.remove(key) {
+			inner.size -= old.body.len();
+			inner.order.retain(|x| x != key);
+		}
+	}
+}
@@ -0,0 +1,733 @@
+//! Serve pages from another http server.
+//!
+//! A [Proxy] forwards requests to an upstream server and
+//! propagates the caching headers it replies with
+//! (`Cache-Control`, `ETag`, `Last-Modified`, `Expires`),
+//! so clients and downstream caches can revalidate proxied pages.
+//!
+//! ```rust
+//! use servable::ServableRouter;
+//! use servable::proxy::Proxy;
+//!
+//! // Serve `/docs/*` from a local server, falling back to a
+//! // second server if it is down. Cache up to 16 MiB of responses.
+//! let docs = Proxy::new("http://127.0.0.1:8000")
+//! 	.unwrap()
+//! 	.with_upstream("http://127.0.0.1:8001")
+//! 	.with_cache(16 * 1024 * 1024);
+//!
+//! let router = ServableRouter::new().add_page("/docs/{*path}", docs);
+//! ```
+
+use axum::{
+	body::Bytes,
+	http::{HeaderMap, HeaderName, HeaderValue, Method, Request, StatusCode, header},
+};
+use chrono::{DateTime, TimeDelta};
+use http_body_util::{BodyExt, Empty};
+use hyper_util::{
+	client::legacy::{Client, connect::HttpConnector},
+	rt::TokioExecutor,
+};
+use std::{
+	pin::Pin,
+	sync::Arc,
+	time::{Duration, Instant},
+};
+use tracing::warn;
+
+use crate::{
+	Error, RenderContext, RenderMode, Rendered, RenderedBody, cachestatus::CacheStatus,
+	servable::Servable,
+};
+
+mod cache;
+use cache::*;
+
+mod upstream;
+pub use upstream::CircuitBreaker;
+pub(crate) use upstream::Upstream;
+
+/// Request headers we send upstream
+const FORWARD_HEADERS: [HeaderName; 4] = [
+	header::ACCEPT,
+	header::ACCEPT_ENCODING,
+	header::ACCEPT_LANGUAGE,
+	header::USER_AGENT,
+];
+
+/// Conditional request headers we send upstream if we have no cache
+const CONDITIONAL_HEADERS: [HeaderName; 2] = [header::IF_NONE_MATCH, header::IF_MODIFIED_SINCE];
+
+/// Upstream response headers we send to clients
+const PROPAGATE_HEADERS: [HeaderName; 11] = [
+	header::AGE,
+	header::CACHE_CONTROL,
+	HeaderName::from_static("cache-status"),
+	header::CONTENT_ENCODING,
+	header::CONTENT_LANGUAGE,
+	header::CONTENT_TYPE,
+	header::ETAG,
+	header::EXPIRES,
+	header::LAST_MODIFIED,
+	header::LOCATION,
+	header::VARY,
+];
+
+/// Response headers that are kept in `304 Not Modified` responses
+const NOT_MODIFIED_HEADERS: [HeaderName; 5] = [
+	header::CACHE_CONTROL,
+	header::ETAG,
+	header::EXPIRES,
+	header::LAST_MODIFIED,
+	header::VARY,
+];
+
+/// A [Servable] that forwards requests to another http server.
+///
+/// Requests are sent to the upstream url followed by the route
+/// and query of the request, so a proxy at `/docs/{*path}` with
+/// upstream `http://127.0.0.1:8000` turns `/docs/intro?v=2` into
+/// `http://127.0.0.1:8000/docs/intro?v=2`.
+///
+/// Only `Accept`, `Accept-Encoding`, `Accept-Language` and `User-Agent`
+/// are forwarded, along with `If-None-Match` and `If-Modified-Since` if
+/// this proxy has no cache. Cookies are never forwarded, and only headers
+/// that describe the body or its cacheability are sent back to the client.
+///
+/// Failed requests are retried, and may fail over to other upstreams
+/// (see [Proxy::with_upstream] and [Proxy::with_retries]).
+/// Attempts that take longer than [Proxy::with_attempt_timeout] fail.
+/// If every attempt fails, we reply with `502 Bad Gateway`
+/// or with the last upstream error response.
+/// Only plain `http://` upstreams are supported.
+pub struct Proxy {
+	/// Upstream servers, in order of preference
+	upstreams: Vec<Upstream>,
+	retries: usize,
+	attempt_timeout: Option<Duration>,
+	circuit_breaker: Option<CircuitBreaker>,
+	client: Client<HttpConnector, Empty<Bytes>>,
+	cache: Option<ProxyCache>,
+}
+
+impl Proxy {
+	/// Default value of [Self::with_attempt_timeout]
+	pub const DEFAULT_ATTEMPT_TIMEOUT: Option<Duration> = Some(Duration::from_secs(10));
+
+	/// Create a new [Proxy] that forwards requests to `upstream`,
+	/// like `http://127.0.0.1:8000` or `http://docs.internal/v2`.
+	///
+	/// Returns an error if `upstream` is not an `http://` url,
+	/// or if it has a query.
+	pub fn new(upstream: &str) -> Result<Self, Error> {
+		Ok(Self {
+			upstreams: vec![Upstream::new(upstream)?],
+			retries: 1,
+			attempt_timeout: Self::DEFAULT_ATTEMPT_TIMEOUT,
+			circuit_breaker: Some(CircuitBreaker::default()),
+			client: Client::builder(TokioExecutor::new()).build_http(),
+			cache: None,
+		})
+	}
+
+	/// Add a fallback upstream.
+	///
+	/// Requests are sent to the first available upstream,
+	/// in the order they were added. Upstreams are unavailable
+	/// while their circuit is open, see [Self::with_circuit_breaker].
+	///
+	/// - panics if `upstream` is invalid, see [Self::new].
+	///
+	/// See [Self::try_with_upstream] for a version of this method that does not panic.
+	#[inline(always)]
+	pub fn with_upstream(self, upstream: &str) -> Self {
+		match self.try_with_upstream(upstream) {
+			Ok(x) => x,
+			Err(err) => panic!("{err}"),
+		}
+	}
+
+	/// Add a fallback upstream.
+	/// Behaves exactly like [Self::with_upstream], but returns an [Error] instead of panicking.
+	#[inline(always)]
+	pub fn try_with_upstream(mut self, upstream: &str) -> Result<Self, Error> {
+		self.upstreams.push(Upstream::new(upstream)?);
+		Ok(self)
+	}
+
+	/// Set the number of times a failed request is retried. The default is 1.
+	///
+	/// A request fails if we can't connect to an upstream, or if it replies with
+	/// `502 Bad Gateway`, `503 Service Unavailable`, or `504 Gateway Timeout`.
+	/// Each retry is sent to the next available upstream, so a proxy with
+	/// two upstreams and one retry fails over to the second upstream.
+	///
+	/// Proxies only send `GET` and `HEAD` requests, which are always safe to retry.
+	#[inline(always)]
+	pub fn with_retries(mut self, retries: usize) -> Self {
+		self.retries = retries;
+		self
+	}
+
+	/// Fail attempts that take longer than `timeout`, including reading the body,
+	/// or never time out with `None`.
+	/// Timed out attempts count as failures, and are retried like any other failure.
+	///
+	/// The default is [Self::DEFAULT_ATTEMPT_TIMEOUT].
+	#[inline(always)]
+	pub fn with_attempt_timeout(mut self, timeout: Option<Duration>) -> Self {
+		self.attempt_timeout = timeout;
+		self
+	}
+
+	/// Set the circuit breaker of every upstream,
+	/// or disable circuit breaking with `None`.
+	/// The default is [CircuitBreaker::default].
+	#[inline(always)]
+	pub fn with_circuit_breaker(mut self, circuit_breaker: Option<CircuitBreaker>) -> Self {
+		self.circuit_breaker = circuit_breaker;
+		self
+	}
+
+	/// Cache upstream responses in memory, using at most `max_size` bytes.
+	///
+	/// Only `200 OK` responses to `GET` requests are cached, and only if
+	/// upstream allows it: responses with `Cache-Control: no-store` or `private`,
+	/// or that vary on headers other than `Accept-Encoding`, are never cached.
+	///
+	/// Cached responses are served until their `max-age` (or `s-maxage`) expires.
+	/// After that, they are revalidated with their `ETag` and `Last-Modified`
+	/// headers. Responses without these are dropped once they expire.
+	///
+	/// Conditional requests from clients are answered by this proxy.
+	/// Responses have a `Cache-Status` header that says how this cache handled them.
+	#[inline(always)]
+	pub fn with_cache(mut self, max_size: usize) -> Self {
+		self.cache = Some(ProxyCache::new(max_size));
+		self
+	}
+
+	/// The path and query we request upstream for the request in `ctx`
+	fn path(&self, ctx: &RenderContext) -> String {
+		let query = serde_urlencoded::to_string(&ctx.query).unwrap_or_default();
+		match query.is_empty() {
+			true => ctx.route.clone(),
+			false => format!("{}?{query}", ctx.route),
+		}
+	}
+
+	/// The headers we send upstream for the request in `ctx`
+	fn forward(&self, ctx: &RenderContext, conditional: bool) -> HeaderMap {
+		let mut headers = HeaderMap::new();
+		let conditional = match conditional {
+			true => &CONDITIONAL_HEADERS[..],
+			false => &[],
+		};
+
+		for name in FORWARD_HEADERS.iter().chain(conditional) {
+			for value in ctx.headers.get_all(name) {
+				headers.append(name, value.clone());
+			}
+		}
+
+		headers
+	}
+
+	/// Send a request to the first available upstream,
+	/// retrying and failing over as configured.
+	async fn fetch(
+		&self,
+		method: Method,
+		path: &str,
+		headers: HeaderMap,
+		now: Instant,
+	) -> Result<UpstreamResponse, Error> {
+		let available: Vec<&Upstream> =
+			self.upstreams.iter().filter(|x| x.available(now)).collect();
+		if available.is_empty() {
+			return Err(Error::Upstream("all upstreams are unavailable".to_owned()));
+		}
+
+		let mut last = None;
+		for attempt in 0..=self.retries {
+			let upstream = available[attempt % available.len()];
+			let url = format!("{}{path}", upstream.url);
+			let res = self.fetch_from(method.clone(), &url, headers.clone()).await;
+
+			match &res {
+				Ok(x) if !is_failure(x.code) => {
+					upstream.success();
+					return res;
+				}
+
+				Ok(x) => warn!(message = "Upstream failed", url, attempt, code = ?x.code),
+				Err(error) => warn!(message = "Upstream failed", url, attempt, ?error),
+			}
+
+			upstream.failure(self.circuit_breaker.as_ref(), now);
+			last = Some(res);
+		}
+
+		last.unwrap_or_else(|| Err(Error::Upstream("no upstream was tried".to_owned())))
+	}
+
+	/// Send a request to one upstream
+	async fn fetch_from(
+		&self,
+		method: Method,
+		url: &str,
+		headers: HeaderMap,
+	) -> Result<UpstreamResponse, Error> {
+		let mut req = Request::new(Empty::new());
+		*req.method_mut() = method;
+		*req.uri_mut() = url
+			.parse()
+			.map_err(|_err| Error::Upstream(format!("invalid upstream url `{url}`")))?;
+		*req.headers_mut() = headers;
+
+		// hyper's futures are not `Sync`, so we can't await them here
+		let client = self.client.clone();
+		let mut task = tokio::spawn(async move {
+			let res = client.request(req).await.map_err(|e| e.to_string())?;
+			let (parts, body) = res.into_parts();
+			let body = body.collect().await.map_err(|e| e.to_string())?;
+			Ok::<_, String>((parts, body.to_bytes()))
+		});
+
+		let res = match self.attempt_timeout {
+			None => task.await,
+			Some(timeout) => match tokio::time::timeout(timeout, &mut task).await {
+				Ok(x) => x,
+				Err(_) => {
+					task.abort();
+					return Err(Error::Upstream(format!(
+						"upstream did not reply within {timeout:?}"
+					)));
+				}
+			},
+		};
+
+		let (parts, body) = match res {
+			Ok(Ok(x)) => x,
+			Ok(Err(err)) => return Err(Error::Upstream(err)),
+			Err(err) => return Err(Error::Upstream(err.to_string())),
+		};
+
+		let mut headers = HeaderMap::new();
+		for name in PROPAGATE_HEADERS {
+			for value in parts.headers.get_all(&name) {
+				headers.append(&name, value.clone());
+			}
+		}
+
+		let content_length = parts
+			.headers
+			.get(header::CONTENT_LENGTH)
+			.and_then(|x| x.to_str().ok())
+			.and_then(|x| x.parse().ok());
+
+		Ok(UpstreamResponse {
+			code: parts.status,
+			headers,
+			body,
+			content_length,
+		})
+	}
+
+	/// Get the response to the request in `ctx`
+	async fn respond(&self, ctx: &RenderContext) -> Result<UpstreamResponse, Error> {
+		let path = self.path(ctx);
+		let now = ctx.clock.instant();
+		let head = ctx.mode == RenderMode::Head;
+
+		let Some(cache) = &self.cache else {
+			let method = if head { Method::HEAD } else { Method::GET };
+			return self
+				.fetch(method, &path, self.forward(ctx, true), now)
+				.await;
+		};
+
+		let encoding = ctx
+			.headers
+			.get(header::ACCEPT_ENCODING)
+			.and_then(|x| x.to_str().ok())
+			.unwrap_or("");
+		let key = (path.clone(), encoding.to_owned());
+		let debug_key = format!("{path}; encoding={encoding}");
+
+		let cached = cache.get(&key);
+		if let Some(cached) = &cached
+			&& cached.is_fresh(now)
+		{
+			let status = CacheStatus::Hit {
+				ttl: Some(cached.fresh_for - cached.age(now)),
+			};
+			let res = UpstreamResponse::from_cache(cached, head, now).conditional(ctx);
+			return Ok(res.with_cache_status(ctx, &debug_key, status));
+		}
+
+		// Don't fill the cache with bodiless responses
+		if head {
+			let res = self
+				.fetch(Method::HEAD, &path, self.forward(ctx, true), now)
+				.await?;
+			return Ok(res.with_cache_status(ctx, &debug_key, CacheStatus::Miss { stored: false }));
+		}
+
+		let mut headers = self.forward(ctx, false);
+		if let Some(cached) = &cached {
+			if let Some(etag) = cached.headers.get(header::ETAG) {
+				headers.insert(header::IF_NONE_MATCH, etag.clone());
+			}
+			if let Some(modified) = cached.headers.get(header::LAST_MODIFIED) {
+				headers.insert(header::IF_MODIFIED_SINCE, modified.clone());
+			}
+		}
+
+		let res = self.fetch(Method::GET, &path, headers, now).await?;
+
+		if res.code == StatusCode::NOT_MODIFIED
+			&& let Some(cached) = cached
+		{
+			// A 304 carries updated caching headers
+			let mut headers = cached.headers.clone();
+			for name in res.headers.keys() {
+				headers.remove(name);
+			}
+			for (name, value) in &res.headers {
+				headers.append(name, value.clone());
+			}
+
+			let Some(fresh_for) = fresh_for(&headers) else {
+				cache.remove(&key);
+				let res = UpstreamResponse::from_cache(&cached, false, now).conditional(ctx);
+				return Ok(res.with_cache_status(
+					ctx,
+					&debug_key,
+					CacheStatus::Stale {
+						status: StatusCode::NOT_MODIFIED,
+						stored: false,
+					},
+				));
+			};
+
+			let cached = Arc::new(CachedResponse {
+				headers,
+				body: cached.body.clone(),
+				stored: now,
+				fresh_for,
+			});
+			cache.insert(key, cached.clone());
+			let res = UpstreamResponse::from_cache(&cached, false, now).conditional(ctx);
+			return Ok(res.with_cache_status(
+				ctx,
+				&debug_key,
+				CacheStatus::Stale {
+					status: StatusCode::NOT_MODIFIED,
+					stored: true,
+				},
+			));
+		}
+
+		let stored = match fresh_for(&res.headers) {
+			Some(fresh_for) if res.code == StatusCode::OK => {
+				let mut headers = res.headers.clone();
+				headers.remove(header::AGE);
+				cache.insert(
+					key,
+					Arc::new(CachedResponse {
+						headers,
+						body: res.body.clone(),
+						stored: now,
+						fresh_for,
+					}),
+				);
+				true
+			}
+			_ => {
+				if cached.is_some() {
+					cache.remove(&key);
+				}
+				false
+			}
+		};
+
+		let status = match cached {
+			Some(_) => CacheStatus::Stale {
+				status: res.code,
+				stored,
+			},
+			None => CacheStatus::Miss { stored },
+		};
+		Ok(res
+			.conditional(ctx)
+			.with_cache_status(ctx, &debug_key, status))
+	}
+}
+
+impl Servable for Proxy {
+	fn head<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<()>> + 'a + Send + Sync>> {
+		Box::pin(async {
+			let ctx = RenderContext {
+				mode: RenderMode::Head,
+				..ctx.clone()
+			};
+
+			let rend = self.render(&ctx).await;
+			Rendered {
+				code: rend.code,
+				headers: rend.headers,
+				body: (),
+				mime: rend.mime,
+				ttl: rend.ttl,
+				private: rend.private,
+				content_length: rend.content_length,
+			}
+		})
+	}
+
+	fn render<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<RenderedBody>> + 'a + Send + Sync>> {
+		Box::pin(async {
+			match self.respond(ctx).await {
+				Ok(res) => res.rendered(),
+				Err(error) => {
+					warn!(
+						message = "Upstream request failed",
+						route = ctx.route,
+						?error
+					);
+
+					let code = error.status_code();
+					ctx.report_error(error);
+					Rendered {
+						code,
+						headers: HeaderMap::new(),
+						body: RenderedBody::Empty,
+						mime: None,
+						ttl: None,
+						private: true,
+						content_length: None,
+					}
+				}
+			}
+		})
+	}
+}
+
+//
+// MARK: helpers
+//
+
+/// A response from upstream, or from our cache
+struct UpstreamResponse {
+	code: StatusCode,
+
+	/// The headers we propagate to the client
+	headers: HeaderMap,
+	body: Bytes,
+
+	/// The length of the body, if known.
+	/// `body` is empty in responses to `HEAD` requests.
+	content_length: Option<u64>,
+}
+
+impl UpstreamResponse {
+	fn from_cache(cached: &CachedResponse, head: bool, now: Instant) -> Self {
+		let mut headers = cached.headers.clone();
+		headers.insert(header::AGE, HeaderValue::from(cached.age(now).as_secs()));
+
+		Self {
+			code: StatusCode::OK,
+			headers,
+			body: match head {
+				true => Bytes::new(),
+				false => cached.body.clone(),
+			},
+			content_length: Some(cached.body.len() as u64),
+		}
+	}
+
+	/// Add our cache's `Cache-Status` to this response,
+	/// and the diagnostic `X-Cache-Key` header if `ctx` allows it
+	fn with_cache_status(mut self, ctx: &RenderContext, key: &str, status: CacheStatus) -> Self {
+		status.append_to(&mut self.headers);
+		ctx.debug_header(&mut self.headers, "x-cache-key", key);
+		self
+	}
+
+	/// Reply with `304 Not Modified` if this is a `200 OK`
+	/// that the client in `ctx` already has
+	fn conditional(mut self, ctx: &RenderContext) -> Self {
+		if self.code != StatusCode::OK || !not_modified(&ctx.headers, &self.headers) {
+			return self;
+		}
+
+		self.code = StatusCode::NOT_MODIFIED;
+		let mut headers = HeaderMap::new();
+		for name in NOT_MODIFIED_HEADERS {
+			for value in self.headers.get_all(&name) {
+				headers.append(&name, value.clone());
+			}
+		}
+
+		self.headers = headers;
+		self.body = Bytes::new();
+		self.content_length = None;
+		self
+	}
+
+	fn rendered(self) -> Rendered<RenderedBody> {
+		let directives = CacheDirectives::parse(&self.headers);
+		let mime = self
+			.headers
+			.get(header::CONTENT_TYPE)
+			.and_then(|x| x.to_str().ok())
+			.and_then(|x| x.parse().ok());
+
+		Rendered {
+			code: self.code,
+			body: match self.body.is_empty() {
+				true => RenderedBody::Empty,
+				false => RenderedBody::Bytes(self.body.to_vec()),
+			},
+			headers: self.headers,
+			mime,
+			ttl: directives
+				.max_age
+				.and_then(|x| i64::try_from(x).ok())
+				.and_then(TimeDelta::try_seconds),
+			private: directives.private,
+			content_length: self.content_length,
+		}
+	}
+}
+
+/// If true, a response with this code counts as a failed request
+fn is_failure(code: StatusCode) -> bool {
+	matches!(
+		code,
+		StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE | StatusCode::GATEWAY_TIMEOUT
+	)
+}
+
+/// The `Cache-Control` directives we care about
+#[derive(Debug, Default)]
+struct CacheDirectives {
+	max_age: Option<u64>,
+	s_maxage: Option<u64>,
+	no_store: bool,
+	no_cache: bool,
+	private: bool,
+}
+
+impl CacheDirectives {
+	fn parse(headers: &HeaderMap) -> Self {
+		let mut out = Self::default();
+
+		for value in headers.get_all(header::CACHE_CONTROL) {
+			let Ok(value) = value.to_str() else {
+				continue;
+			};
+
+			for directive in value.split(',') {
+				let (name, arg) = match directive.split_once('=') {
+					Some((name, arg)) => (name.trim(), Some(arg.trim().trim_matches('"'))),
+					None => (directive.trim(), None),
+				};
+
+				match name.to_ascii_lowercase().as_str() {
+					"max-age" => out.max_age = arg.and_then(|x| x.parse().ok()),
+					"s-maxage" => out.s_maxage = arg.and_then(|x| x.parse().ok()),
+					"no-store" => out.no_store = true,
+					"no-cache" => out.no_cache = true,
+					"private" => out.private = true,
+					_ => {}
+				}
+			}
+		}
+
+		out
+	}
+}
+
+/// How long a response with the given headers may be served from our cache.
+/// Returns `None` if it may not be cached at all.
+fn fresh_for(headers: &HeaderMap) -> Option<Duration> {
+	let directives = CacheDirectives::parse(headers);
+	if directives.no_store || directives.private {
+		return None;
+	}
+
+	// We only key our cache by Accept-Encoding
+	for value in headers.get_all(header::VARY) {
+		let value = value.to_str().ok()?;
+		if !value
+			.split(',')
+			.all(|x| x.trim().eq_ignore_ascii_case("accept-encoding"))
+		{
+			return None;
+		}
+	}
+
+	let age: u64 = headers
+		.get(header::AGE)
+		.and_then(|x| x.to_str().ok())
+		.and_then(|x| x.parse().ok())
+		.unwrap_or(0);
+
+	let fresh = match directives.no_cache {
+		true => 0,
+		false => directives
+			.s_maxage
+			.or(directives.max_age)
+			.unwrap_or(0)
+			.saturating_sub(age),
+	};
+
+	let has_validators =
+		headers.contains_key(header::ETAG) || headers.contains_key(header::LAST_MODIFIED);
+	if fresh == 0 && !has_validators {
+		return None;
+	}
+
+	Some(Duration::from_secs(fresh))
+}
+
+/// Check the conditional headers in `request` against a `200 OK` with `response`.
+/// Returns `true` if the client's copy is up to date.
+fn not_modified(request: &HeaderMap, response: &HeaderMap) -> bool {
+	if let Some(if_none_match) = request.get(header::IF_NONE_MATCH) {
+		let Some(etag) = response.get(header::ETAG).and_then(|x| x.to_str().ok()) else {
+			return false;
+		};
+
+		// If-None-Match uses weak comparison
+		let etag = etag.trim_start_matches("W/");
+		return if_none_match.to_str().is_ok_and(|x| {
+			x.split(',').any(|x| {
+				let x = x.trim();
+				x == "*" || x.trim_start_matches("W/") == etag
+			})
+		});
+	}
+
+	let date = |x: Option<&HeaderValue>| {
+		x.and_then(|x| x.to_str().ok())
+			.and_then(|x| DateTime::parse_from_rfc2822(x).ok())
+	};
+
+	match (
+		date(request.get(header::IF_MODIFIED_SINCE)),
+		date(response.get(header::LAST_MODIFIED)),
+	) {
+		(Some(since), Some(modified)) => modified <= since,
+		_ => false,
+	}
+}
@@ -0,0 +1,106 @@
+use axum::http::Uri;
+use std::{
+	sync::{
+		Mutex,
+		atomic::{AtomicU32, Ordering},
+	},
+	time::{Duration, Instant},
+};
+
+use crate::Error;
+
+/// Stops sending requests to an upstream that keeps failing.
+/// See [super::Proxy::with_circuit_breaker].
+///
+/// After `failures` consecutive failed requests, an upstream is skipped for `cooldown`.
+/// Once that time has passed, requests are sent to it again. If the next request fails,
+/// the upstream is skipped for another `cooldown`. If it succeeds, it is healthy again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CircuitBreaker {
+	/// The number of consecutive failures that open the circuit
+	pub failures: u32,
+
+	/// How long to skip an upstream after its circuit opens
+	pub cooldown: Duration,
+}
+
+impl Default for CircuitBreaker {
+	fn default() -> Self {
+		Self {
+			failures: 5,
+			cooldown: Duration::from_secs(30),
+		}
+	}
+}
+
+/// One upstream server of a [super::Proxy]
+pub(crate) struct Upstream {
+	/// The upstream url, without a trailing `/`
+	pub url: String,
+
+	/// The number of consecutive failed requests
+	failures: AtomicU32,
+
+	/// If `Some`, this upstream is skipped until this time
+	open_until: Mutex<Option<Instant>>,
+}
+
+impl Upstream {
+	/// Check `url` and make a new [Upstream]
+	pub fn new(url: &str) -> Result<Self, Error> {
+		let invalid = |reason| Error::InvalidUpstream {
+			url: url.to_owned(),
+			reason,
+		};
+
+		let uri: Uri = url.parse().map_err(|_err| invalid("invalid url"))?;
+		if uri.scheme_str() != Some("http") {
+			return Err(invalid("upstream must be an http:// url"));
+		}
+
+		if uri.authority().is_none() {
+			return Err(invalid("upstream must have a host"));
+		}
+
+		if uri.query().is_some() {
+			return Err(invalid("upstream must not have a query"));
+		}
+
+		Ok(Self {
+			url: url.trim_end_matches('/').to_owned(),
+			failures: AtomicU32::new(0),
+			open_until: Mutex::new(None),
+		})
+	}
+
+	/// If false, requests should not be sent to this upstream
+	pub fn available(&self, now: Instant) -> bool {
+		match self.open_until.lock() {
+			Ok(x) => x.is_none_or(|x| now >= x),
+			Err(_) => true,
+		}
+	}
+
+	/// Record a successful request
+	pub fn success(&self) {
+		self.failures.store(0, Ordering::Relaxed);
+		if let Ok(mut x) = self.open_until.lock() {
+			*x = None;
+		}
+	}
+
+	/// Record a failed request
+	pub fn failure(&self, breaker: Option<&CircuitBreaker>, now: Instant) {
+		let failures = self
+			.failures
+			.fetch_add(1, Ordering::Relaxed)
+			.saturating_add(1);
+
+		if let Some(breaker) = breaker
+			&& failures >= breaker.failures
+			&& let Ok(mut x) = self.open_until.lock()
+		{
+			*x = Some(now + breaker.cooldown);
+		}
+	}
+}
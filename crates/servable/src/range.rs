@@ -0,0 +1,77 @@
+use std::ops::Range;
+
+/// The part of a response body requested by a `Range` header
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum RangeRequest {
+	/// Send the whole body.
+	/// This is also used for ranges we don't support.
+	Full,
+
+	/// Send only these bytes
+	Partial(Range<usize>),
+
+	/// The requested range is outside of the body
+	Unsatisfiable,
+}
+
+impl RangeRequest {
+	/// Parse the value of a `Range` header
+	/// for a body that is `len` bytes long.
+	///
+	/// Only single byte ranges are supported.
+	/// Anything else (including malformed headers)
+	/// is ignored, and the full body is sent.
+	pub(crate) fn parse(value: &str, len: usize) -> Self {
+		let Some(spec) = value.trim().strip_prefix("bytes=") else {
+			return Self::Full;
+		};
+
+		if spec.contains(',') {
+			return Self::Full;
+		}
+
+		let Some((start, end)) = spec.split_once('-') else {
+			return Self::Full;
+		};
+
+		let (start, end) = (start.trim(), end.trim());
+		let range = match (start.is_empty(), end.is_empty()) {
+			(true, true) => return Self::Full,
+
+			// `bytes=-n`, the last `n` bytes
+			(true, false) => {
+				let Ok(n) = end.parse::<usize>() else {
+					return Self::Full;
+				};
+				len.saturating_sub(n)..len
+			}
+
+			// `bytes=a-`, everything after `a`
+			(false, true) => {
+				let Ok(start) = start.parse::<usize>() else {
+					return Self::Full;
+				};
+				start..len
+			}
+
+			// `bytes=a-b`, inclusive
+			(false, false) => {
+				let (Ok(start), Ok(end)) = (start.parse::<usize>(), end.parse::<usize>()) else {
+					return Self::Full;
+				};
+
+				if end < start {
+					return Self::Full;
+				}
+
+				start..end.saturating_add(1).min(len)
+			}
+		};
+
+		if range.start >= len || range.is_empty() {
+			return Self::Unsatisfiable;
+		}
+
+		return Self::Partial(range);
+	}
+}
@@ -0,0 +1,177 @@
+use axum::http::{HeaderMap, HeaderValue, StatusCode, header};
+use std::{
+	collections::HashMap,
+	net::IpAddr,
+	pin::Pin,
+	sync::Mutex,
+	time::{Duration, Instant},
+};
+
+use crate::{
+	FormData, FormServable, RenderContext, Rendered, RenderedBody, forwarded::client_key,
+	servable::Servable,
+};
+
+/// The number of clients a [RateLimited] tracks before it forgets idle clients
+const PRUNE_THRESHOLD: usize = 10_000;
+
+/// A `Retry-After` value for `wait`, rounded up to whole seconds
+pub(crate) fn retry_after(wait: Duration) -> HeaderValue {
+	let secs = wait.as_secs() + u64::from(wait.subsec_nanos() > 0);
+	HeaderValue::from(secs.max(1))
+}
+
+/// One client's tokens
+struct Bucket {
+	tokens: f64,
+	updated: Instant,
+}
+
+/// A [Servable] that limits how often each client may request it.
+///
+/// Every client has a bucket of `burst` tokens, which refills at `per_second` tokens per second.
+/// Each request takes one token. Requests that find an empty bucket are answered
+/// with `429 Too Many Requests` and a `Retry-After` header.
+///
+/// Clients are identified by [RenderContext::client_ip].
+/// IPv6 clients are grouped by their `/64` prefix, since one client often owns a whole `/64`.
+/// Requests without a client ip are never limited.
+///
+/// ```rust
+/// use servable::{RateLimited, ServableRouter, StaticAsset};
+///
+/// let image = StaticAsset {
+/// 	bytes: b"fake image",
+/// 	br: None,
+/// 	gz: None,
+/// 	mime: mime::IMAGE_PNG,
+/// 	ttl: StaticAsset::DEFAULT_TTL,
+/// };
+///
+/// // Allow bursts of 10 requests, then one request every two seconds
+/// let router = ServableRouter::new().add_page("/image.png", RateLimited::new(image, 10, 0.5));
+/// ```
+pub struct RateLimited<S: Servable> {
+	inner: S,
+	burst: f64,
+	per_second: f64,
+	buckets: Mutex<HashMap<IpAddr, Bucket>>,
+}
+
+impl<S: Servable> RateLimited<S> {
+	/// Limit requests to `inner`. See [RateLimited].
+	pub fn new(inner: S, burst: u32, per_second: f64) -> Self {
+		Self {
+			inner,
+			burst: f64::from(burst.max(1)),
+			per_second: per_second.max(f64::MIN_POSITIVE),
+			buckets: Mutex::new(HashMap::new()),
+		}
+	}
+
+	/// Take a token from this client's bucket.
+	/// If the bucket is empty, returns how long the client should wait.
+	fn take(&self, ctx: &RenderContext) -> Result<(), Duration> {
+		let Some(ip) = ctx.client_ip else {
+			return Ok(());
+		};
+
+		let Ok(mut buckets) = self.buckets.lock() else {
+			return Ok(());
+		};
+
+		let now = ctx.clock.instant();
+		if buckets.len() >= PRUNE_THRESHOLD {
+			let full = self.burst / self.per_second;
+			buckets.retain(|_, b| now.duration_since(b.updated).as_secs_f64() < full);
+		}
+
+		let bucket = buckets.entry(client_key(ip)).or_insert(Bucket {
+			tokens: self.burst,
+			updated: now,
+		});
+
+		let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+		bucket.tokens = (bucket.tokens + elapsed * self.per_second).min(self.burst);
+		bucket.updated = now;
+
+		if bucket.tokens >= 1.0 {
+			bucket.tokens -= 1.0;
+			return Ok(());
+		}
+
+		let wait = (1.0 - bucket.tokens) / self.per_second;
+		Err(Duration::try_from_secs_f64(wait).unwrap_or(Duration::MAX))
+	}
+
+	fn too_many_requests(wait: Duration) -> Rendered<()> {
+		let mut headers = HeaderMap::with_capacity(1);
+		headers.insert(header::RETRY_AFTER, retry_after(wait));
+
+		Rendered {
+			code: StatusCode::TOO_MANY_REQUESTS,
+			body: (),
+			ttl: None,
+			private: true,
+			headers,
+			mime: None,
+			content_length: None,
+		}
+	}
+}
+
+impl<S: Servable> Servable for RateLimited<S> {
+	fn head<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<()>> + 'a + Send + Sync>> {
+		Box::pin(async {
+			match self.take(ctx) {
+				Ok(()) => self.inner.head(ctx).await,
+				Err(wait) => Self::too_many_requests(wait),
+			}
+		})
+	}
+
+	fn render<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<RenderedBody>> + 'a + Send + Sync>> {
+		Box::pin(async {
+			match self.take(ctx) {
+				Ok(()) => self.inner.render(ctx).await,
+				Err(wait) => Self::too_many_requests(wait).with_body(RenderedBody::Empty),
+			}
+		})
+	}
+
+	#[inline(always)]
+	fn header_template(&self) -> HeaderMap {
+		self.inner.header_template()
+	}
+
+	#[inline(always)]
+	fn linked_routes(&self) -> Vec<String> {
+		self.inner.linked_routes()
+	}
+
+	#[inline(always)]
+	fn preloads(&self) -> Vec<crate::Preload> {
+		self.inner.preloads()
+	}
+}
+
+impl<S: FormServable> FormServable for RateLimited<S> {
+	fn submit<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+		form: &'a FormData,
+	) -> Pin<Box<dyn Future<Output = Rendered<RenderedBody>> + 'a + Send + Sync>> {
+		Box::pin(async {
+			match self.take(ctx) {
+				Ok(()) => self.inner.submit(ctx, form).await,
+				Err(wait) => Self::too_many_requests(wait).with_body(RenderedBody::Empty),
+			}
+		})
+	}
+}
@@ -0,0 +1,58 @@
+use axum::http::StatusCode;
+
+use crate::RenderContext;
+
+/// A request that was answered with a server error (5xx),
+/// see [ErrorReporter].
+#[derive(Debug)]
+pub struct ErrorReport<'a> {
+	/// The status code of the response
+	pub code: StatusCode,
+
+	/// The errors that caused this response, in the order they happened.
+	/// These are reported by servables with [RenderContext::report_error],
+	/// or by the router (like [crate::Error::Timeout]).
+	///
+	/// This may be empty if a servable returned a server error without reporting why.
+	pub errors: &'a [Box<dyn std::error::Error + Send + Sync>],
+
+	/// The request that failed.
+	/// Its id is [RenderContext::request_id].
+	pub ctx: &'a RenderContext,
+}
+
+/// Receives every request a [crate::ServableRouter] answers with a server error (5xx),
+/// so failures can be sent to an alerting service.
+/// See [crate::ServableRouter::with_error_reporter].
+///
+/// Reporters are called while the response is being generated,
+/// so they should not block. Slow work (like network requests)
+/// should be moved to another task.
+///
+/// This is implemented for closures:
+///
+/// ```rust
+/// use servable::{ErrorReport, HtmlPage, ServableRouter};
+///
+/// let router = ServableRouter::new()
+/// 	.add_page("/", HtmlPage::default())
+/// 	.with_error_reporter(|report: &ErrorReport<'_>| {
+/// 		eprintln!(
+/// 			"{} on {} (request {}): {:?}",
+/// 			report.code, report.ctx.route, report.ctx.request_id, report.errors
+/// 		);
+/// 	});
+/// ```
+pub trait ErrorReporter: Send + Sync {
+	/// Report a failed request
+	fn report(&self, report: &ErrorReport<'_>);
+}
+
+impl<F> ErrorReporter for F
+where
+	F: Fn(&ErrorReport<'_>) + Send + Sync,
+{
+	fn report(&self, report: &ErrorReport<'_>) {
+		self(report)
+	}
+}
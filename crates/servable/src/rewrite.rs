@@ -0,0 +1,254 @@
+use axum::http::{HeaderValue, header};
+use rand::{Rng, distr::Alphanumeric};
+use std::sync::Arc;
+
+use crate::{
+	LazyImages, RenderContext, RenderMode, Rendered, RenderedBody,
+	scan::{attribute, find_ignore_case, rewrite_tags},
+};
+
+/// Rewrites the html of every `text/html` response sent by a [crate::ServableRouter],
+/// after its [crate::servable::Servable] renders it.
+/// See [crate::ServableRouter::with_html_rewriter].
+///
+/// This is implemented for closures,
+/// and for [LazyImages], [CanonicalLink] and [CspNonce].
+///
+/// ```rust
+/// use servable::{HtmlPage, LazyImages, RenderContext, Rendered, ServableRouter};
+///
+/// let router = ServableRouter::new()
+/// 	.add_page("/", HtmlPage::default())
+/// 	.with_html_rewriter(LazyImages::default())
+/// 	.with_html_rewriter(|_ctx: &RenderContext, _rend: &mut Rendered<()>, html: String| {
+/// 		html.replace("<body>", "<body><p>Maintenance tonight</p>")
+/// 	});
+/// ```
+pub trait HtmlRewriter: Send + Sync {
+	/// Rewrite the html document `html`.
+	///
+	/// `rend` is the response that contains `html`,
+	/// and may be modified to change its code or headers.
+	fn rewrite(&self, ctx: &RenderContext, rend: &mut Rendered<()>, html: String) -> String;
+
+	/// Modify the response to a `HEAD` request, which has no html to rewrite.
+	///
+	/// Rewriters that always change the headers of `rend`
+	/// should change them here too, so `HEAD` and `GET` responses match.
+	fn rewrite_head(&self, _ctx: &RenderContext, _rend: &mut Rendered<()>) {}
+}
+
+impl<F> HtmlRewriter for F
+where
+	F: Fn(&RenderContext, &mut Rendered<()>, String) -> String + Send + Sync,
+{
+	fn rewrite(&self, ctx: &RenderContext, rend: &mut Rendered<()>, html: String) -> String {
+		self(ctx, rend, html)
+	}
+}
+
+impl HtmlRewriter for LazyImages {
+	fn rewrite(&self, _ctx: &RenderContext, _rend: &mut Rendered<()>, html: String) -> String {
+		self.apply(&html)
+	}
+}
+
+/// An [HtmlRewriter] that adds `<link rel="canonical">` to the `<head>`
+/// of successful html responses that don't have one,
+/// pointing at [RenderContext::absolute_url] of the route.
+///
+/// Canonical links should be absolute,
+/// so this should be used with [crate::ServableRouter::with_base_url].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct CanonicalLink;
+
+impl HtmlRewriter for CanonicalLink {
+	fn rewrite(&self, ctx: &RenderContext, rend: &mut Rendered<()>, html: String) -> String {
+		if !rend.code.is_success() {
+			return html;
+		}
+
+		let mut exists = false;
+		rewrite_tags(&html, &["link"], |_, open, _| {
+			exists |= attribute(open, "rel").is_some_and(|x| x.eq_ignore_ascii_case("canonical"));
+			None
+		});
+		if exists {
+			return html;
+		}
+
+		let url = ctx
+			.absolute_url(&ctx.route)
+			.replace('&', "&amp;")
+			.replace('"', "&quot;");
+		let mut done = false;
+		rewrite_tags(&html, &["head"], |_, open, _| {
+			if done {
+				return None;
+			}
+			done = true;
+			Some(format!("{open}<link rel=\"canonical\" href=\"{url}\">"))
+		})
+	}
+}
+
+/// An [HtmlRewriter] that adds a random `nonce` to every `<script>` and `<style>` tag,
+/// and sends a `Content-Security-Policy` that only allows scripts and styles with that nonce.
+///
+/// Every response gets a new nonce. The policy is sent as a separate
+/// `Content-Security-Policy` header, so it is enforced alongside any other policy.
+/// Tags that already have a `nonce` keep it, and are blocked by this policy.
+/// Responses with a nonce are private, so shared caches never send one nonce twice.
+///
+/// If the `deterministic` feature is enabled, the nonce is always [DETERMINISTIC_NONCE].
+///
+/// ```rust
+/// use servable::{CspNonce, HtmlPage, ServableRouter};
+///
+/// let router = ServableRouter::new()
+/// 	.add_page("/", HtmlPage::default())
+/// 	.with_html_rewriter(CspNonce::default());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CspNonce {
+	/// The policy to send. Every `{nonce}` is replaced with the nonce.
+	pub policy: String,
+}
+
+/// The nonce of every [CspNonce] when the `deterministic` feature is enabled
+pub const DETERMINISTIC_NONCE: &str = "0000000000000000000000";
+
+impl Default for CspNonce {
+	/// Only allows scripts and styles with the nonce
+	fn default() -> Self {
+		Self {
+			policy: "script-src 'nonce-{nonce}' 'strict-dynamic'; style-src 'nonce-{nonce}'".into(),
+		}
+	}
+}
+
+impl CspNonce {
+	/// Send `policy` instead of the default policy.
+	/// Every `{nonce}` in `policy` is replaced with the nonce.
+	#[inline(always)]
+	pub fn with_policy(mut self, policy: impl Into<String>) -> Self {
+		self.policy = policy.into();
+		self
+	}
+
+	/// Make a new nonce, and add its policy to `rend`
+	fn add_policy(&self, rend: &mut Rendered<()>) -> String {
+		let nonce: String = match cfg!(feature = "deterministic") {
+			true => DETERMINISTIC_NONCE.to_owned(),
+			false => rand::rng()
+				.sample_iter(&Alphanumeric)
+				.take(22)
+				.map(char::from)
+				.collect(),
+		};
+
+		if let Ok(policy) = HeaderValue::from_str(&self.policy.replace("{nonce}", &nonce)) {
+			rend.headers.append(header::CONTENT_SECURITY_POLICY, policy);
+		}
+		rend.make_private();
+		nonce
+	}
+}
+
+impl HtmlRewriter for CspNonce {
+	fn rewrite(&self, _ctx: &RenderContext, rend: &mut Rendered<()>, html: String) -> String {
+		let nonce = self.add_policy(rend);
+
+		rewrite_tags(&html, &["script", "style"], |_, open, _| {
+			if attribute(open, "nonce").is_some() {
+				return None;
+			}
+
+			let (start, end) = match open.strip_suffix("/>") {
+				Some(x) => (x.trim_end(), " />"),
+				None => (&open[..open.len() - 1], ">"),
+			};
+			Some(format!("{start} nonce=\"{nonce}\"{end}"))
+		})
+	}
+
+	fn rewrite_head(&self, _ctx: &RenderContext, rend: &mut Rendered<()>) {
+		self.add_policy(rend);
+	}
+}
+
+/// Returns true if `rend` is an html document
+fn is_html(rend: &Rendered<RenderedBody>) -> bool {
+	match rend.headers.get(header::CONTENT_TYPE) {
+		Some(x) => x
+			.to_str()
+			.is_ok_and(|x| find_ignore_case(x.trim_start(), "text/html") == Some(0)),
+		None => rend
+			.mime
+			.as_ref()
+			.is_some_and(|x| x.essence_str() == "text/html"),
+	}
+}
+
+/// Pass the body of `rend` through `rewriters`, if it is html
+pub(crate) fn rewrite_html(
+	rewriters: &[Arc<dyn HtmlRewriter>],
+	ctx: &RenderContext,
+	rend: Rendered<RenderedBody>,
+) -> Rendered<RenderedBody> {
+	if rewriters.is_empty() || !is_html(&rend) {
+		return rend;
+	}
+
+	let Rendered {
+		code,
+		headers,
+		body,
+		mime,
+		ttl,
+		private,
+		content_length,
+	} = rend;
+
+	let mut rend = Rendered {
+		code,
+		headers,
+		body: (),
+		mime,
+		ttl,
+		private,
+		content_length,
+	};
+
+	let html = match body {
+		RenderedBody::String(s) => s,
+		RenderedBody::Bytes(b) => match String::from_utf8(b) {
+			Ok(s) => s,
+			Err(err) => return rend.with_body(RenderedBody::Bytes(err.into_bytes())),
+		},
+		RenderedBody::Static(b) => match std::str::from_utf8(b) {
+			Ok(s) => s.to_owned(),
+			Err(_) => return rend.with_body(RenderedBody::Static(b)),
+		},
+		RenderedBody::Chunks(c) => match String::from_utf8(c.concat()) {
+			Ok(s) => s,
+			Err(_) => return rend.with_body(RenderedBody::Chunks(c)),
+		},
+
+		// Rewriting may change the length of the body
+		RenderedBody::Empty if ctx.mode == RenderMode::Head => {
+			rend.content_length = None;
+			for rewriter in rewriters {
+				rewriter.rewrite_head(ctx, &mut rend);
+			}
+			return rend.with_body(RenderedBody::Empty);
+		}
+
+		body => return rend.with_body(body),
+	};
+
+	let html = rewriters
+		.iter()
+		.fold(html, |html, x| x.rewrite(ctx, &mut rend, html));
+	rend.with_body(RenderedBody::String(html))
+}
@@ -0,0 +1,184 @@
+use axum::http::{HeaderMap, HeaderValue, header};
+use std::pin::Pin;
+
+use crate::{
+	RenderContext, Rendered, RenderedBody, RenderedBodyType, router::merge_template,
+	servable::Servable, sign::sha256,
+};
+
+/// How a [RolloutServable] tells clients apart
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RolloutKey {
+	/// The value of the cookie with this name, like a session id.
+	/// Requests without this cookie fall back to [RolloutKey::ClientIp].
+	Cookie(String),
+
+	/// [RenderContext::client_ip]
+	ClientIp,
+}
+
+/// Which servable a [RolloutServable] picked for a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RolloutVariant {
+	/// The current version
+	Control,
+
+	/// The version being rolled out
+	Candidate,
+}
+
+/// A [Servable] that serves a new version of a page to a fraction of clients,
+/// so that new designs can be canaried before everyone sees them.
+///
+/// Clients are split into buckets by hashing their [RolloutKey] with this rollout's name,
+/// so each client always sees the same version, and different rollouts
+/// pick independent sets of clients. Raising the fraction only moves clients
+/// from the control to the candidate, never back.
+/// Requests without a key (no cookie and no client ip) always get the control.
+///
+/// Responses are private, since they depend on who is asking.
+///
+/// ```rust
+/// use servable::{HtmlPage, RolloutKey, RolloutServable, ServableRouter};
+/// use maud::html;
+///
+/// let old = HtmlPage::default().with_render(|_page, _ctx| Box::pin(async { html! { "Home" } }));
+/// let new = HtmlPage::default()
+/// 	.with_render(|_page, _ctx| Box::pin(async { html! { "Home, redesigned" } }));
+///
+/// // Show the redesign to 10% of sessions
+/// let home = RolloutServable::new("redesign-2026", old, new)
+/// 	.with_fraction(0.1)
+/// 	.with_key(RolloutKey::Cookie("session".into()));
+///
+/// let router = ServableRouter::new().add_page("/", home);
+/// ```
+pub struct RolloutServable<A: Servable, B: Servable> {
+	name: String,
+	control: A,
+	candidate: B,
+
+	/// The fraction of clients that get `candidate`, between 0 and 1
+	fraction: f64,
+	key: RolloutKey,
+
+	/// [Servable::header_template]s of `control` and `candidate`
+	templates: (HeaderMap, HeaderMap),
+}
+
+impl<A: Servable, B: Servable> RolloutServable<A, B> {
+	/// Serve `control` to every client. Use [Self::with_fraction] to start the rollout.
+	/// `name` identifies this rollout, and should change if it is restarted.
+	pub fn new(name: impl Into<String>, control: A, candidate: B) -> Self {
+		let templates = (control.header_template(), candidate.header_template());
+		Self {
+			name: name.into(),
+			control,
+			candidate,
+			fraction: 0.0,
+			key: RolloutKey::ClientIp,
+			templates,
+		}
+	}
+
+	/// Serve the candidate to this fraction of clients.
+	/// Values outside of `0..=1` are clamped.
+	#[inline(always)]
+	pub fn with_fraction(mut self, fraction: f64) -> Self {
+		self.fraction = fraction.clamp(0.0, 1.0);
+		self
+	}
+
+	/// Set how clients are told apart.
+	/// The default is [RolloutKey::ClientIp].
+	#[inline(always)]
+	pub fn with_key(mut self, key: RolloutKey) -> Self {
+		self.key = key;
+		self
+	}
+
+	/// The servable this request gets
+	pub fn variant(&self, ctx: &RenderContext) -> RolloutVariant {
+		let cookie = match &self.key {
+			RolloutKey::Cookie(name) => ctx.cookie(name),
+			RolloutKey::ClientIp => None,
+		};
+
+		let key = match (cookie, ctx.client_ip) {
+			(Some(cookie), _) => cookie.to_owned(),
+			(None, Some(ip)) => ip.to_string(),
+			(None, None) => return RolloutVariant::Control,
+		};
+
+		let hash = sha256(format!("{}\n{key}", self.name).as_bytes());
+		let mut bucket = [0u8; 8];
+		bucket.copy_from_slice(&hash[..8]);
+		let bucket = u64::from_be_bytes(bucket) as f64 / u64::MAX as f64;
+
+		match bucket < self.fraction {
+			true => RolloutVariant::Candidate,
+			false => RolloutVariant::Control,
+		}
+	}
+
+	/// Make `rend` private, and add the chosen servable's header template
+	fn finish<T: RenderedBodyType>(
+		&self,
+		variant: RolloutVariant,
+		mut rend: Rendered<T>,
+	) -> Rendered<T> {
+		rend.make_private();
+
+		if let RolloutKey::Cookie(_) = self.key {
+			rend.headers
+				.append(header::VARY, HeaderValue::from_static("cookie"));
+		}
+
+		let template = match variant {
+			RolloutVariant::Control => &self.templates.0,
+			RolloutVariant::Candidate => &self.templates.1,
+		};
+
+		if rend.code.is_success() {
+			merge_template(&mut rend.headers, template);
+		}
+
+		rend
+	}
+}
+
+impl<A: Servable, B: Servable> Servable for RolloutServable<A, B> {
+	fn head<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<()>> + 'a + Send + Sync>> {
+		Box::pin(async move {
+			let variant = self.variant(ctx);
+			let rend = match variant {
+				RolloutVariant::Control => self.control.head(ctx).await,
+				RolloutVariant::Candidate => self.candidate.head(ctx).await,
+			};
+			self.finish(variant, rend)
+		})
+	}
+
+	fn render<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<RenderedBody>> + 'a + Send + Sync>> {
+		Box::pin(async move {
+			let variant = self.variant(ctx);
+			let rend = match variant {
+				RolloutVariant::Control => self.control.render(ctx).await,
+				RolloutVariant::Candidate => self.candidate.render(ctx).await,
+			};
+			self.finish(variant, rend)
+		})
+	}
+
+	fn linked_routes(&self) -> Vec<String> {
+		let mut routes = self.control.linked_routes();
+		routes.extend(self.candidate.linked_routes());
+		routes
+	}
+}
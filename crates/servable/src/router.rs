@@ -1,25 +1,41 @@
 use axum::{
 	Router,
-	body::Body,
-	http::{HeaderMap, HeaderValue, Method, Request, StatusCode, header},
+	body::{Body, Bytes},
+	extract::ConnectInfo,
+	http::{
+		Extensions, HeaderMap, HeaderName, HeaderValue, Method, Request, StatusCode, Uri, header,
+	},
 	response::{IntoResponse, Response},
 };
 use chrono::TimeDelta;
+use http_body_util::{BodyExt, LengthLimitError, Limited};
+use rand::{Rng, distr::Alphanumeric};
 use std::{
+	borrow::Borrow,
 	collections::{BTreeMap, HashMap},
 	convert::Infallible,
 	net::SocketAddr,
 	pin::Pin,
-	sync::Arc,
+	sync::{Arc, PoisonError, RwLock},
 	task::{Context, Poll},
-	time::Instant,
+	time::{Duration, Instant},
 };
-use tower::Service;
-use tracing::trace;
+use tower::{Service, ServiceExt, util::BoxCloneSyncService};
+use tracing::{error, trace, warn};
 
 use crate::{
-	ClientInfo, RenderContext, Rendered, RenderedBody,
+	BanList, ClientInfo, Clock, DownloadQuota, Error, ErrorReport, ErrorReporter, FormData,
+	FormServable, HeaderScrub, Honeypot, HtmlRewriter, Mirror, NonCanonicalAction, PeriodicTask,
+	Preload, PreloadKind, PriorityHints, RenderContext, RenderMode, Rendered, RenderedBody,
+	RequestFilter, RouteGroup, SecurityHeaders, Signer, StatusClass, SystemClock, TaskHandle,
+	Throttle, UrlNormalization,
+	chunks::{ChunkedBody, slice_chunks},
+	forwarded::{Cidr, client_ip},
+	normalize::has_route_prefix,
+	range::RangeRequest,
+	rewrite::rewrite_html,
 	servable::{Servable, ServableWithRoute},
+	sign::constant_time_eq,
 };
 
 struct Default404 {}
@@ -37,6 +53,7 @@ impl Servable for Default404 {
 				headers: HeaderMap::new(),
 				mime: Some(mime::TEXT_HTML),
 				private: false,
+				content_length: None,
 			};
 		})
 	}
@@ -49,6 +66,335 @@ impl Servable for Default404 {
 	}
 }
 
+/// The page served by [ServableRouter::with_empty_favicon]
+struct EmptyFavicon {}
+
+impl Servable for EmptyFavicon {
+	fn head<'a>(
+		&'a self,
+		_ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<()>> + 'a + Send + Sync>> {
+		Box::pin(async {
+			return Rendered {
+				code: StatusCode::NO_CONTENT,
+				body: (),
+				ttl: Some(TimeDelta::days(1)),
+				headers: HeaderMap::new(),
+				mime: None,
+				private: false,
+				content_length: None,
+			};
+		})
+	}
+
+	fn render<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<RenderedBody>> + 'a + Send + Sync>> {
+		Box::pin(async { self.head(ctx).await.with_body(RenderedBody::Empty) })
+	}
+}
+
+/// The methods a [ServableRouter] responds to
+const ALLOW: HeaderValue = HeaderValue::from_static("GET, HEAD, OPTIONS");
+
+/// The methods a [ServableRouter] responds to on routes with a [FormServable]
+const ALLOW_FORM: HeaderValue = HeaderValue::from_static("GET, HEAD, OPTIONS, POST");
+
+/// The default maximum size of a form body, in bytes
+const DEFAULT_FORM_LIMIT: usize = 64 * 1024;
+
+struct DefaultOptions {}
+
+impl Servable for DefaultOptions {
+	fn head<'a>(
+		&'a self,
+		_ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<()>> + 'a + Send + Sync>> {
+		Box::pin(async {
+			return Rendered {
+				code: StatusCode::NO_CONTENT,
+				body: (),
+				ttl: None,
+				headers: HeaderMap::new(),
+				mime: None,
+				private: false,
+				content_length: None,
+			};
+		})
+	}
+
+	fn render<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<RenderedBody>> + 'a + Send + Sync>> {
+		Box::pin(async { self.head(ctx).await.with_body(RenderedBody::Empty) })
+	}
+}
+
+/// A route of a [ServableRouter], see [ServableRouter::route_table]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteInfo {
+	/// The route this page was added with, like `/post/{id}`
+	pub route: String,
+
+	/// What this page is, see [ServableRouter::add_page_with_description]
+	pub description: Option<String>,
+}
+
+/// A [Servable] in a [ServableRouter]
+#[derive(Clone)]
+struct RouterPage {
+	servable: Arc<dyn Servable>,
+
+	/// This servable's [Servable::header_template]
+	headers: Arc<HeaderMap>,
+
+	/// This servable's [Servable::preloads]
+	preloads: Arc<Vec<Preload>>,
+
+	/// If `Some`, override the router's timeout for this page.
+	timeout: Option<Option<Duration>>,
+
+	/// If `Some`, this page accepts `POST` requests
+	form: Option<Arc<dyn FormServable>>,
+
+	/// If true, `POST` bodies are never read, and `form` gets an empty form.
+	/// See [ServableRouter::add_honeypot].
+	ignore_body: bool,
+
+	/// What this page is, see [ServableRouter::add_page_with_description]
+	description: Option<Arc<str>>,
+}
+
+impl RouterPage {
+	fn new<S: Servable + 'static>(servable: S) -> Self {
+		let headers = Arc::new(servable.header_template());
+		let preloads = Arc::new(servable.preloads());
+		Self {
+			servable: Arc::new(servable),
+			headers,
+			preloads,
+			timeout: None,
+			form: None,
+			description: None,
+			ignore_body: false,
+		}
+	}
+
+	fn new_form<S: FormServable + 'static>(servable: S) -> Self {
+		let headers = Arc::new(servable.header_template());
+		let preloads = Arc::new(servable.preloads());
+		let servable = Arc::new(servable);
+		Self {
+			servable: servable.clone(),
+			headers,
+			preloads,
+			timeout: None,
+			form: Some(servable),
+			description: None,
+			ignore_body: false,
+		}
+	}
+
+	/// The methods this page responds to
+	fn allow(&self) -> HeaderValue {
+		match self.form {
+			Some(_) => ALLOW_FORM,
+			None => ALLOW,
+		}
+	}
+}
+
+/// The pages in a [ServableRouter]
+#[derive(Clone, Default)]
+struct Routes {
+	pages: Vec<RouterPage>,
+
+	/// Maps request paths to indices in `pages`
+	tree: matchit::Router<usize>,
+
+	/// Maps the routes pages were added with to indices in `pages`
+	index: HashMap<String, usize>,
+}
+
+impl Routes {
+	/// Add a page, replacing the page with an identical route
+	fn insert(&mut self, route: String, page: RouterPage) -> Result<(), Error> {
+		if let Some(i) = self.index.get(&route) {
+			self.pages[*i] = page;
+			return Ok(());
+		}
+
+		let i = self.pages.len();
+		if let Err(err) = self.tree.insert(route.clone(), i) {
+			let reason = match err {
+				matchit::InsertError::Conflict { .. } => "route conflicts with an existing route",
+				_ => "invalid route parameter",
+			};
+			return Err(Error::InvalidRoute { route, reason });
+		}
+
+		self.pages.push(page);
+		self.index.insert(route, i);
+		Ok(())
+	}
+
+	/// Remove the page that was added with `route`.
+	/// Returns `false` if there is no such page.
+	fn remove(&mut self, route: &str) -> Result<bool, Error> {
+		if !self.index.contains_key(route) {
+			return Ok(false);
+		}
+
+		// matchit can't remove routes, so we rebuild the tree
+		let mut pages: Vec<_> = self
+			.index
+			.iter()
+			.filter(|(x, _)| x.as_str() != route)
+			.map(|(x, i)| (*i, x.clone()))
+			.collect();
+		pages.sort();
+
+		let mut routes = Self::default();
+		for (i, route) in pages {
+			routes.insert(route, self.pages[i].clone())?;
+		}
+
+		*self = routes;
+		Ok(true)
+	}
+
+	/// Find the page that serves `path`
+	#[inline(always)]
+	fn get<'a, 'p>(&'a self, path: &'p str) -> Option<(&'a RouterPage, matchit::Params<'a, 'p>)> {
+		let m = self.tree.at(path).ok()?;
+		Some((&self.pages[*m.value], m.params))
+	}
+}
+
+/// The pages in a [ServableRouter], see [ServableRouter::with_dynamic_routes]
+#[derive(Clone)]
+enum PageMap {
+	/// Pages may only be added before the router is started
+	Static(Arc<Routes>),
+
+	/// Pages may be added and removed at any time.
+	/// Every request is served from a snapshot of these pages.
+	Dynamic(Arc<RwLock<Arc<Routes>>>),
+}
+
+impl PageMap {
+	/// The current pages
+	#[inline(always)]
+	fn snapshot(&self) -> Arc<Routes> {
+		match self {
+			Self::Static(x) => x.clone(),
+			Self::Dynamic(x) => x.read().unwrap_or_else(PoisonError::into_inner).clone(),
+		}
+	}
+
+	/// Change the pages of a router that is being built
+	fn update<T>(&mut self, f: impl FnOnce(&mut Routes) -> Result<T, Error>) -> Result<T, Error> {
+		match self {
+			Self::Static(x) => f(Arc::get_mut(x).ok_or(Error::RouterStarted)?),
+			Self::Dynamic(_) => self.update_shared(f),
+		}
+	}
+
+	/// Change the pages of a router that may be running
+	fn update_shared<T>(
+		&self,
+		f: impl FnOnce(&mut Routes) -> Result<T, Error>,
+	) -> Result<T, Error> {
+		match self {
+			Self::Static(_) => Err(Error::StaticRoutes),
+			Self::Dynamic(x) => {
+				let mut routes = x.write().unwrap_or_else(PoisonError::into_inner);
+				// Requests that hold a snapshot keep the old pages
+				f(Arc::make_mut(&mut routes))
+			}
+		}
+	}
+}
+
+/// Check that `route` is a valid route for a page
+fn check_route(route: &str) -> Result<(), Error> {
+	let reason = if !route.starts_with("/") {
+		Some("route must start with /")
+	} else if route.ends_with("/") && route != "/" {
+		Some("route must not end with /")
+	} else if route.contains("//") {
+		Some("route must not contain //")
+	} else {
+		None
+	};
+
+	match reason {
+		Some(reason) => Err(Error::InvalidRoute {
+			route: route.to_owned(),
+			reason,
+		}),
+		None => Ok(()),
+	}
+}
+
+/// Format a `Cache-Control` header
+pub(crate) fn cache_control(ttl: Option<TimeDelta>, private: bool) -> HeaderValue {
+	let max_age = ttl.map(|x| x.num_seconds()).unwrap_or(0).max(0);
+	let value = match private {
+		true => format!("private, max-age={max_age}"),
+		false => format!("public, max-age={max_age}"),
+	};
+
+	#[expect(clippy::unwrap_used)]
+	HeaderValue::from_str(&value).unwrap()
+}
+
+/// Add the headers of `template` that `headers` does not have,
+/// like the router does with [Servable::header_template]s.
+///
+/// `Vary` is merged instead: tokens of `template` that `headers` does not vary on are appended,
+/// so a wrapper that varies on more headers doesn't drop the `Vary` of what it wraps.
+pub(crate) fn merge_template(headers: &mut HeaderMap, template: &HeaderMap) {
+	for name in template.keys() {
+		if *name != header::VARY && !headers.contains_key(name) {
+			for value in template.get_all(name) {
+				headers.append(name, value.clone());
+			}
+		}
+	}
+
+	let tokens = |headers: &HeaderMap| -> Vec<String> {
+		headers
+			.get_all(header::VARY)
+			.iter()
+			.filter_map(|x| x.to_str().ok())
+			.flat_map(|x| x.split(','))
+			.map(|x| x.trim().to_ascii_lowercase())
+			.filter(|x| !x.is_empty())
+			.collect()
+	};
+
+	let existing = tokens(headers);
+	if existing.iter().any(|x| x == "*") {
+		return;
+	}
+
+	let mut missing: Vec<String> = Vec::new();
+	for token in tokens(template) {
+		if !existing.contains(&token) && !missing.contains(&token) {
+			missing.push(token);
+		}
+	}
+
+	if !missing.is_empty()
+		&& let Ok(value) = HeaderValue::from_str(&missing.join(", "))
+	{
+		headers.append(header::VARY, value);
+	}
+}
+
 /// A set of related [Servable]s under one route.
 ///
 /// Use as follows:
@@ -71,6 +417,8 @@ impl Servable for Default404 {
 /// 		"/page",
 /// 		StaticAsset {
 /// 			bytes: "I am a page".as_bytes(),
+/// 			br: None,
+/// 			gz: None,
 /// 			mime: mime::TEXT_PLAIN,
 /// 			ttl: StaticAsset::DEFAULT_TTL
 /// 		},
@@ -82,8 +430,46 @@ impl Servable for Default404 {
 /// ```
 #[derive(Clone)]
 pub struct ServableRouter {
-	pages: Arc<HashMap<String, Arc<dyn Servable>>>,
-	notfound: Arc<dyn Servable>,
+	pages: PageMap,
+	notfound: RouterPage,
+	favicon: Option<RouterPage>,
+	error_pages: HashMap<StatusCode, Arc<dyn Servable>>,
+	error_classes: HashMap<StatusClass, Arc<dyn Servable>>,
+	options: RouterPage,
+	extensions: Extensions,
+	security_headers: SecurityHeaders,
+	header_scrub: HeaderScrub,
+	request_filter: RequestFilter,
+	ban_list: Option<BanList>,
+	signed_urls: Vec<(String, Signer)>,
+	throttles: Vec<(String, Throttle)>,
+	quotas: Vec<(String, DownloadQuota)>,
+	html_rewriters: Vec<Arc<dyn HtmlRewriter>>,
+	error_reporters: Vec<Arc<dyn ErrorReporter>>,
+	#[cfg(feature = "compression")]
+	compression: Option<crate::Compression>,
+	normalization: UrlNormalization,
+	timeout: Option<Duration>,
+	early_hints: bool,
+	priority_hints: Option<PriorityHints>,
+	debug_headers: Option<(HeaderName, String)>,
+	encoding_override: bool,
+	form_limit: usize,
+	base_path: String,
+	base_url: Option<String>,
+	mirror: Option<Mirror>,
+	trusted_proxies: Vec<Cidr>,
+	tasks: Vec<PeriodicTask>,
+
+	/// If `Some`, image transformations are only allowed under these prefixes
+	#[cfg(feature = "image")]
+	transform_prefixes: Option<Vec<String>>,
+
+	clock: Arc<dyn Clock>,
+	fallback: Option<BoxCloneSyncService<Request<Body>, Response, Infallible>>,
+
+	/// The number of request ids we've made with the `deterministic` feature
+	request_count: Arc<std::sync::atomic::AtomicU64>,
 }
 
 impl ServableRouter {
@@ -91,15 +477,810 @@ impl ServableRouter {
 	#[inline(always)]
 	pub fn new() -> Self {
 		Self {
-			pages: Arc::new(HashMap::new()),
-			notfound: Arc::new(Default404 {}),
+			pages: PageMap::Static(Arc::new(Routes::default())),
+			notfound: RouterPage::new(Default404 {}),
+			favicon: None,
+			error_pages: HashMap::new(),
+			error_classes: HashMap::new(),
+			options: RouterPage::new(DefaultOptions {}),
+			extensions: Extensions::new(),
+			security_headers: SecurityHeaders::default(),
+			header_scrub: HeaderScrub::none(),
+			request_filter: RequestFilter::default(),
+			ban_list: None,
+			signed_urls: Vec::new(),
+			throttles: Vec::new(),
+			quotas: Vec::new(),
+			html_rewriters: Vec::new(),
+			error_reporters: Vec::new(),
+			#[cfg(feature = "compression")]
+			compression: None,
+			normalization: UrlNormalization::default(),
+			timeout: None,
+			early_hints: false,
+			priority_hints: None,
+			debug_headers: None,
+			encoding_override: false,
+			form_limit: DEFAULT_FORM_LIMIT,
+			base_path: String::new(),
+			base_url: None,
+			mirror: None,
+			trusted_proxies: Vec::new(),
+			tasks: Vec::new(),
+			#[cfg(feature = "image")]
+			transform_prefixes: None,
+			clock: Arc::new(SystemClock),
+			fallback: None,
+			request_count: Arc::new(std::sync::atomic::AtomicU64::new(0)),
+		}
+	}
+
+	/// Set this server's "not found" page
+	#[inline(always)]
+	pub fn with_404<S: Servable + 'static>(mut self, page: S) -> Self {
+		self.notfound = RouterPage::new(page);
+		self
+	}
+
+	/// Serve `page` at `/favicon.ico` if no page was added there.
+	/// Browsers request this icon from every site, even if pages don't link to it.
+	/// Without a fallback, these requests get this server's 404 page.
+	///
+	/// ```rust
+	/// use servable::{ServableRouter, StaticAsset};
+	///
+	/// static ICON: StaticAsset = StaticAsset {
+	/// 	bytes: b"fake icon",
+	/// 	br: None,
+	/// 	gz: None,
+	/// 	mime: mime::IMAGE_PNG,
+	/// 	ttl: StaticAsset::DEFAULT_TTL,
+	/// };
+	///
+	/// let router = ServableRouter::new().with_favicon_fallback(&ICON);
+	/// ```
+	#[inline(always)]
+	pub fn with_favicon_fallback<S: Servable + 'static>(mut self, page: S) -> Self {
+		self.favicon = Some(RouterPage::new(page));
+		self
+	}
+
+	/// Answer `/favicon.ico` with an empty `204 No Content` if no page was added there,
+	/// which browsers cache for a day. See [Self::with_favicon_fallback].
+	#[inline(always)]
+	pub fn with_empty_favicon(self) -> Self {
+		self.with_favicon_fallback(EmptyFavicon {})
+	}
+
+	/// Serve `page` in place of empty responses with status `code`.
+	///
+	/// Whenever a servable returns `code` with an empty body, the router renders `page`
+	/// and sends its body instead. The response keeps the original status code,
+	/// headers and cache settings, so `page` may return any status code.
+	/// `HEAD` responses are replaced if they do not have a content length.
+	///
+	/// Pages registered here take precedence over pages registered
+	/// with [Self::with_error_class].
+	///
+	/// ```rust
+	/// use servable::{HtmlPage, ServableRouter};
+	/// use axum::http::StatusCode;
+	///
+	/// let router = ServableRouter::new()
+	/// 	.add_page("/", HtmlPage::default())
+	/// 	.with_error_page(StatusCode::INTERNAL_SERVER_ERROR, HtmlPage::default());
+	/// ```
+	#[inline(always)]
+	pub fn with_error_page<S: Servable + 'static>(mut self, code: StatusCode, page: S) -> Self {
+		self.error_pages.insert(code, Arc::new(page));
+		self
+	}
+
+	/// Serve `page` in place of empty responses with any status in `class`.
+	/// Behaves exactly like [Self::with_error_page].
+	#[inline(always)]
+	pub fn with_error_class<S: Servable + 'static>(mut self, class: StatusClass, page: S) -> Self {
+		self.error_classes.insert(class, Arc::new(page));
+		self
+	}
+
+	/// Send requests that do not match any page to `service`,
+	/// instead of replying with this server's "not found" page.
+	///
+	/// `service` receives requests of any method, unmodified.
+	/// Requests for non-canonical paths of existing pages
+	/// are handled by this server (see [Self::with_url_normalization]).
+	///
+	/// This makes it easy to serve an api alongside static pages:
+	/// ```rust
+	/// use servable::{ServableRouter, StaticAsset};
+	/// use axum::{Router, routing::post};
+	///
+	/// let api: Router = Router::new().route("/api/echo", post(|body: String| async { body }));
+	///
+	/// let route = ServableRouter::new()
+	/// 	.add_page(
+	/// 		"/",
+	/// 		StaticAsset {
+	/// 			bytes: b"home",
+	/// 			br: None,
+	/// 			gz: None,
+	/// 			mime: mime::TEXT_HTML,
+	/// 			ttl: StaticAsset::DEFAULT_TTL,
+	/// 		},
+	/// 	)
+	/// 	.with_fallback(api);
+	/// ```
+	#[inline(always)]
+	pub fn with_fallback<S>(mut self, service: S) -> Self
+	where
+		S: Service<Request<Body>, Error = Infallible> + Clone + Send + Sync + 'static,
+		S::Response: IntoResponse,
+		S::Future: Send + 'static,
+	{
+		self.fallback = Some(BoxCloneSyncService::new(
+			service.map_response(IntoResponse::into_response),
+		));
+		self
+	}
+
+	/// Send a copy of some requests to `mirror`, see [Mirror].
+	/// Every request this server receives may be mirrored,
+	/// including requests that are passed to [Self::with_fallback].
+	///
+	/// Mirroring requires a tokio runtime.
+	#[inline(always)]
+	pub fn with_mirror(mut self, mirror: Mirror) -> Self {
+		self.mirror = Some(mirror);
+		self
+	}
+
+	/// Trust forwarding headers from proxies in `cidr`, like `10.0.0.0/8` or `::1`.
+	/// May be called more than once.
+	///
+	/// When a request comes from a trusted proxy, [RenderContext::client_ip]
+	/// is read from its `Forwarded` header (or `X-Forwarded-For`, if there is no `Forwarded` header).
+	/// We walk that header from the last hop to the first, and use the first address
+	/// that is not a trusted proxy. Forwarding headers from untrusted peers are ignored.
+	///
+	/// # Panics
+	/// - panics if `cidr` is not a valid ip address or range.
+	///
+	/// See [Self::try_with_trusted_proxy] for a version of this method that does not panic.
+	///
+	/// ```rust
+	/// use servable::{HtmlPage, ServableRouter};
+	///
+	/// // Behind a load balancer on the local network
+	/// let router = ServableRouter::new()
+	/// 	.add_page("/", HtmlPage::default())
+	/// 	.with_trusted_proxy("10.0.0.0/8")
+	/// 	.with_trusted_proxy("127.0.0.1");
+	/// ```
+	#[inline(always)]
+	pub fn with_trusted_proxy(self, cidr: &str) -> Self {
+		match self.try_with_trusted_proxy(cidr) {
+			Ok(x) => x,
+			Err(err) => panic!("{err}"),
+		}
+	}
+
+	/// Trust forwarding headers from proxies in `cidr`.
+	/// Behaves exactly like [Self::with_trusted_proxy], but returns an [Error] instead of panicking.
+	pub fn try_with_trusted_proxy(mut self, cidr: &str) -> Result<Self, Error> {
+		self.trusted_proxies.push(Cidr::parse(cidr)?);
+		Ok(self)
+	}
+
+	/// Set the clock servables read the time from, see [RenderContext::clock].
+	/// The default is [SystemClock]. Use a [crate::TestClock] to test
+	/// time-dependent behavior (like caching and rate limits) without sleeping.
+	#[inline(always)]
+	pub fn with_clock<C: Clock + 'static>(mut self, clock: C) -> Self {
+		self.clock = Arc::new(clock);
+		self
+	}
+
+	/// Set the [Servable] that responds to all `OPTIONS` requests,
+	/// for example to answer CORS preflight requests.
+	///
+	/// By default, we reply with `204 No Content` and an `Allow` header
+	/// that lists the methods the requested route responds to.
+	/// `Allow` is added to this servable's responses if they don't contain it.
+	/// `page` is rendered with [RenderMode::Full].
+	#[inline(always)]
+	pub fn with_options<S: Servable + 'static>(mut self, page: S) -> Self {
+		self.options = RouterPage::new(page);
+		self
+	}
+
+	/// Set the security headers this server adds to every response.
+	/// See [SecurityHeaders::default] for the defaults,
+	/// and use [SecurityHeaders::none] to disable them.
+	#[inline(always)]
+	pub fn with_security_headers(mut self, security_headers: SecurityHeaders) -> Self {
+		self.security_headers = security_headers;
+		self
+	}
+
+	/// Remove or rewrite headers in every response served by a page, see [HeaderScrub].
+	/// By default, no headers are changed.
+	///
+	/// Responses from [Self::with_fallback] are not changed.
+	#[inline(always)]
+	pub fn with_header_scrub(mut self, header_scrub: HeaderScrub) -> Self {
+		self.header_scrub = header_scrub;
+		self
+	}
+
+	/// Reject some requests before they are served, see [RequestFilter].
+	/// By default, no requests are rejected.
+	///
+	/// The filter runs before every request is dispatched,
+	/// including requests that are passed to [Self::with_fallback].
+	#[inline(always)]
+	pub fn with_request_filter(mut self, request_filter: RequestFilter) -> Self {
+		self.request_filter = request_filter;
+		self
+	}
+
+	/// Reject requests from clients in `ban_list` with `403 Forbidden`.
+	/// Bans are checked before every request is dispatched,
+	/// including requests that are passed to [Self::with_fallback].
+	///
+	/// See [BanList] and [Honeypot].
+	#[inline(always)]
+	pub fn with_ban_list(mut self, ban_list: BanList) -> Self {
+		self.ban_list = Some(ban_list);
+		self
+	}
+
+	/// Require a signed url for every route under `prefix`, like `/downloads`.
+	/// Requests without a valid, unexpired signature from `signer` get `403 Forbidden`.
+	/// `prefix` matches whole path segments, and does not include the base path.
+	///
+	/// Make urls for these routes with [Signer::sign_url]:
+	/// ```rust
+	/// use servable::{ServableRouter, Signer, StaticAsset};
+	/// use chrono::{TimeDelta, Utc};
+	///
+	/// let signer = Signer::new(b"a long, random secret");
+	/// let router = ServableRouter::new()
+	/// 	.add_page(
+	/// 		"/downloads/report.pdf",
+	/// 		StaticAsset {
+	/// 			bytes: b"fake pdf",
+	/// 			br: None,
+	/// 			gz: None,
+	/// 			mime: mime::APPLICATION_PDF,
+	/// 			ttl: StaticAsset::DEFAULT_TTL,
+	/// 		},
+	/// 	)
+	/// 	.with_signed_urls("/downloads", signer.clone());
+	///
+	/// let url = signer.sign_url("/downloads/report.pdf", Utc::now() + TimeDelta::hours(1));
+	/// ```
+	#[inline(always)]
+	pub fn with_signed_urls(mut self, prefix: impl Into<String>, signer: Signer) -> Self {
+		self.signed_urls.push((prefix.into(), signer));
+		self
+	}
+
+	/// If true, send a `Link: <url>; rel=preload` header for each of a page's [Servable::preloads]
+	/// (like an [crate::HtmlPage]'s linked scripts and styles) with every successful response.
+	/// By default, this is false.
+	///
+	/// Many CDNs and reverse proxies turn these headers into a `103 Early Hints` response,
+	/// which lets browsers fetch these resources while the page is still rendering.
+	/// Browsers that receive them directly start fetching as soon as the headers arrive.
+	#[inline(always)]
+	pub fn with_early_hints(mut self, early_hints: bool) -> Self {
+		self.early_hints = early_hints;
+		self
+	}
+
+	/// If `Some`, send a `Priority` header with every successful asset response,
+	/// picked by its type (see [PriorityHints]). By default, this is `None`.
+	///
+	/// HTTP/2 and HTTP/3 servers and proxies that understand this header
+	/// send urgent responses (like stylesheets) before others (like images).
+	#[inline(always)]
+	pub fn with_priority_hints(mut self, priority_hints: Option<PriorityHints>) -> Self {
+		self.priority_hints = priority_hints;
+		self
+	}
+
+	/// Add diagnostic headers to responses to requests that set the header `name` to `secret`,
+	/// so production servers can be debugged without showing these headers to everyone.
+	/// By default, diagnostic headers are never sent.
+	///
+	/// These headers are:
+	/// - `X-Render-Time`: how long this router took to produce the response
+	/// - `X-Cache-Key`: what a [crate::Cached] servable or a caching proxy stored the response under
+	/// - `X-Transform-Chain`: the canonical form of an image's transformation
+	/// - any header added with [RenderContext::debug_header]
+	///
+	/// `name` is removed from requests before they reach servables.
+	/// `secret` should be long and random. If it is empty, diagnostic headers are never sent.
+	///
+	/// ```rust
+	/// use axum::http::HeaderName;
+	/// use servable::ServableRouter;
+	///
+	/// let router = ServableRouter::new()
+	/// 	.with_debug_headers(HeaderName::from_static("x-debug"), "a long random secret");
+	/// ```
+	#[inline(always)]
+	pub fn with_debug_headers(mut self, name: HeaderName, secret: impl Into<String>) -> Self {
+		self.debug_headers = Some((name, secret.into()));
+		self
+	}
+
+	/// Limit how fast bodies are sent for every route under `prefix`, see [Throttle].
+	/// `prefix` matches whole path segments, and does not include the base path.
+	/// If several prefixes match a route, the first one added is used.
+	///
+	/// ```rust
+	/// use servable::{DownloadQuota, HtmlPage, ServableRouter, Throttle};
+	/// use std::time::Duration;
+	///
+	/// let router = ServableRouter::new()
+	/// 	.add_page("/", HtmlPage::default())
+	/// 	.with_throttle("/files", Throttle::new(512 * 1024))
+	/// 	.with_download_quota(
+	/// 		"/files",
+	/// 		DownloadQuota::new(1024 * 1024 * 1024, Duration::from_secs(24 * 3600)),
+	/// 	);
+	/// ```
+	#[inline(always)]
+	pub fn with_throttle(mut self, prefix: impl Into<String>, throttle: Throttle) -> Self {
+		self.throttles.push((prefix.into(), throttle));
+		self
+	}
+
+	/// Limit how many bytes each client may download from routes under `prefix`,
+	/// see [DownloadQuota]. Only successful `GET` responses are counted.
+	/// `prefix` matches whole path segments, and does not include the base path.
+	/// If several prefixes match a route, the first one added is used.
+	#[inline(always)]
+	pub fn with_download_quota(mut self, prefix: impl Into<String>, quota: DownloadQuota) -> Self {
+		self.quotas.push((prefix.into(), quota));
+		self
+	}
+
+	/// Only apply image transformations (`?t=`, see [crate::transform]) to routes under `prefix`.
+	/// Elsewhere, `?t=` is ignored and images are served unchanged.
+	/// Call this again to allow more prefixes.
+	/// `prefix` matches whole path segments, and does not include the base path.
+	///
+	/// Transformations are allowed on every route until this
+	/// (or [Self::without_transforms]) is called.
+	/// Remember to allow the routes of any [crate::Gallery].
+	///
+	/// ```rust
+	/// use servable::ServableRouter;
+	///
+	/// let router = ServableRouter::new()
+	/// 	// Only images under `/img` may be resized
+	/// 	.with_transform_prefix("/img");
+	/// ```
+	#[cfg(feature = "image")]
+	#[inline(always)]
+	pub fn with_transform_prefix(mut self, prefix: impl Into<String>) -> Self {
+		self.transform_prefixes
+			.get_or_insert_default()
+			.push(prefix.into());
+		self
+	}
+
+	/// Ignore image transformations (`?t=`) on every route,
+	/// except those allowed by [Self::with_transform_prefix].
+	/// To disable transformations for one servable, see [crate::transform::NoTransforms].
+	#[cfg(feature = "image")]
+	#[inline(always)]
+	pub fn without_transforms(mut self) -> Self {
+		self.transform_prefixes.get_or_insert_default();
+		self
+	}
+
+	/// Rewrite the body of every `text/html` response with `rewriter`, see [HtmlRewriter].
+	/// Rewriters run in the order they were added, after any [Servable] renders
+	/// (including error pages), and before the body is compressed.
+	#[inline(always)]
+	pub fn with_html_rewriter<R: HtmlRewriter + 'static>(mut self, rewriter: R) -> Self {
+		self.html_rewriters.push(Arc::new(rewriter));
+		self
+	}
+
+	/// Send every request answered with a server error (5xx) to `reporter`,
+	/// with the errors reported by [RenderContext::report_error].
+	/// See [ErrorReporter].
+	///
+	/// Reporters are called in the order they were added.
+	#[inline(always)]
+	pub fn with_error_reporter<R: ErrorReporter + 'static>(mut self, reporter: R) -> Self {
+		self.error_reporters.push(Arc::new(reporter));
+		self
+	}
+
+	/// Compress response bodies, see [crate::Compression].
+	/// By default, bodies are never compressed.
+	#[cfg(feature = "compression")]
+	#[inline(always)]
+	pub fn with_compression(mut self, compression: crate::Compression) -> Self {
+		self.compression = Some(compression);
+		self
+	}
+
+	/// Set the maximum amount of time a [Servable] may take to respond.
+	/// If a servable takes longer, it is aborted and a `504 Gateway Timeout` is returned.
+	///
+	/// If `None` (the default), servables may take as long as they like.
+	/// This may be overridden for individual pages with [Self::add_page_with_timeout].
+	/// Servables can check how much time they have left with [RenderContext::remaining].
+	///
+	/// Timeouts require a tokio runtime with the time driver enabled.
+	#[inline(always)]
+	pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+		self.timeout = timeout;
+		self
+	}
+
+	/// Run `task` every `every` while this router is served.
+	/// The first run is one period after the server starts,
+	/// and runs of one task never overlap.
+	///
+	/// Tasks are started by [Self::serve] and stop when the server shuts down.
+	/// Routers that are served some other way must call [Self::start_tasks].
+	///
+	/// - panics if `every` is zero.\
+	///   See [Self::try_with_task] for a non-panicking version.
+	///
+	/// ```rust
+	/// use servable::{HtmlPage, ServableRouter};
+	/// use std::time::Duration;
+	///
+	/// let router = ServableRouter::new()
+	/// 	.add_page("/", HtmlPage::default())
+	/// 	.with_task("refresh feeds", Duration::from_secs(600), || {
+	/// 		Box::pin(async { /* fetch and store feeds */ })
+	/// 	});
+	/// ```
+	#[inline(always)]
+	pub fn with_task<F>(self, name: impl Into<String>, every: Duration, task: F) -> Self
+	where
+		F: Send + Sync + 'static + Fn() -> Pin<Box<dyn Future<Output = ()> + Send + 'static>>,
+	{
+		match self.try_with_task(name, every, task) {
+			Ok(x) => x,
+			Err(err) => panic!("{err}"),
+		}
+	}
+
+	/// Behaves exactly like [Self::with_task], but returns an error instead of panicking.
+	pub fn try_with_task<F>(
+		mut self,
+		name: impl Into<String>,
+		every: Duration,
+		task: F,
+	) -> Result<Self, Error>
+	where
+		F: Send + Sync + 'static + Fn() -> Pin<Box<dyn Future<Output = ()> + Send + 'static>>,
+	{
+		let name = name.into();
+		if every.is_zero() {
+			return Err(Error::InvalidTask {
+				name,
+				reason: "period must not be zero",
+			});
 		}
+
+		self.tasks.push(PeriodicTask {
+			name,
+			every,
+			run: Arc::new(task),
+		});
+		Ok(self)
+	}
+
+	/// Start this router's periodic tasks on the current tokio runtime,
+	/// see [Self::with_task]. Tasks run until the returned handle is dropped.
+	///
+	/// This also starts the tasks the router needs itself,
+	/// like forgetting expired bans and download quota windows.
+	///
+	/// [Self::serve] calls this, so this is only needed
+	/// if this router is served with [Self::into_router].
+	pub fn start_tasks(&self) -> TaskHandle {
+		let mut tasks = self.tasks.clone();
+
+		let bans = self.ban_list.clone();
+		let quotas: Vec<DownloadQuota> = self.quotas.iter().map(|(_, x)| x.clone()).collect();
+		if bans.is_some() || !quotas.is_empty() {
+			let clock = self.clock.clone();
+			tasks.push(PeriodicTask {
+				name: "servable: prune expired limits".to_owned(),
+				every: Duration::from_secs(60),
+				run: Arc::new(move || {
+					let now = clock.instant();
+					if let Some(bans) = &bans {
+						bans.prune(now);
+					}
+					for quota in &quotas {
+						quota.prune(now);
+					}
+					Box::pin(async {})
+				}),
+			});
+		}
+
+		TaskHandle::start(tasks)
+	}
+
+	/// Set how this server normalizes request paths.
+	/// See [UrlNormalization].
+	#[inline(always)]
+	pub fn with_url_normalization(mut self, normalization: UrlNormalization) -> Self {
+		self.normalization = normalization;
+		self
+	}
+
+	/// If true, allow clients to force a content encoding with the
+	/// `encoding` query parameter, which may be `identity`, `gzip`, or `br`.
+	/// Other values are rejected with `400 Bad Request`.
+	///
+	/// The parameter replaces the request's `Accept-Encoding` header before it is
+	/// passed to [Servable]s, which makes it easy to check compressed responses from a browser.
+	/// Compression layers outside this router still see the original header.
+	///
+	/// This is a debugging tool, and is disabled by default.
+	#[inline(always)]
+	pub fn with_encoding_override(mut self, enabled: bool) -> Self {
+		self.encoding_override = enabled;
+		self
+	}
+
+	/// Serve this router under `base_path`, like `/app`.
+	/// This is useful behind a reverse proxy that mounts this server under a sub-path.
+	///
+	/// Routes are still added without the base path: with a base path of `/app`,
+	/// a page added at `/about` is served at `/app/about`, and `/` is served at `/app`.
+	/// Requests outside the base path receive this server's "not found" page.
+	///
+	/// [RenderContext::route] does not include the base path.
+	/// Use [RenderContext::url] to make links to other pages,
+	/// [crate::Redirect]s and linked resources in [crate::HtmlPage]s are prefixed automatically.
+	///
+	/// - panics if `base_path` is not empty and does not start with a `/`,
+	///   or if it ends with a `/` or contains `//`.
+	///   `/` is an exception, it is the same as an empty base path.
+	///
+	/// See [Self::try_with_base_path] for a version of this method that does not panic.
+	///
+	/// ```rust
+	/// use servable::{HtmlPage, Redirect, ServableRouter};
+	///
+	/// // Serves `/app` and `/app/old`, which redirects to `/app`.
+	/// let router = ServableRouter::new()
+	/// 	.with_base_path("/app")
+	/// 	.add_page("/", HtmlPage::default())
+	/// 	.add_page("/old", Redirect::new("/").unwrap());
+	/// ```
+	#[inline(always)]
+	pub fn with_base_path(self, base_path: impl Into<String>) -> Self {
+		match self.try_with_base_path(base_path) {
+			Ok(x) => x,
+			Err(err) => panic!("{err}"),
+		}
+	}
+
+	/// Serve this router under `base_path`.
+	/// Behaves exactly like [Self::with_base_path], but returns an [Error] instead of panicking.
+	pub fn try_with_base_path(mut self, base_path: impl Into<String>) -> Result<Self, Error> {
+		let base_path: String = base_path.into();
+		let base_path = match base_path.as_str() {
+			"/" => String::new(),
+			_ => base_path,
+		};
+
+		if !base_path.is_empty() && !base_path.starts_with("/") {
+			return Err(Error::InvalidRoute {
+				route: base_path,
+				reason: "base path must start with /",
+			});
+		}
+
+		if base_path.ends_with("/") || base_path.contains("//") {
+			return Err(Error::InvalidRoute {
+				route: base_path,
+				reason: "base path must not end with / or contain //",
+			});
+		}
+
+		self.base_path = base_path;
+		Ok(self)
+	}
+
+	/// Set the scheme and host this router is served at, like `https://example.com`.
+	/// Servables can use [RenderContext::absolute_url] to build absolute urls
+	/// (for canonical links, `og:url`, sitemaps, or feeds) without hard-coding a hostname.
+	///
+	/// With a base url, [crate::HtmlPage]s include `<link rel="canonical">` and `og:url`,
+	/// and redirects use absolute urls.
+	///
+	/// # Panics
+	/// - panics if `base_url` is not an `http://` or `https://` url with a host,
+	///   or if it has a path, query, or fragment. Use [Self::with_base_path] to set a path.
+	///
+	/// See [Self::try_with_base_url] for a version of this method that does not panic.
+	///
+	/// ```rust
+	/// use servable::{HtmlPage, ServableRouter};
+	///
+	/// let router = ServableRouter::new()
+	/// 	.with_base_url("https://example.com")
+	/// 	.add_page("/", HtmlPage::default());
+	/// ```
+	#[inline(always)]
+	pub fn with_base_url(self, base_url: &str) -> Self {
+		match self.try_with_base_url(base_url) {
+			Ok(x) => x,
+			Err(err) => panic!("{err}"),
+		}
+	}
+
+	/// Set the scheme and host this router is served at.
+	/// Behaves exactly like [Self::with_base_url], but returns an [Error] instead of panicking.
+	pub fn try_with_base_url(mut self, base_url: &str) -> Result<Self, Error> {
+		let invalid = |reason| Error::InvalidBaseUrl {
+			url: base_url.to_owned(),
+			reason,
+		};
+
+		let trimmed = base_url.trim_end_matches('/');
+		if trimmed.contains(['?', '#']) {
+			return Err(invalid("base url must not have a query or fragment"));
+		}
+
+		let uri: Uri = trimmed.parse().map_err(|_err| invalid("invalid url"))?;
+		if !matches!(uri.scheme_str(), Some("http" | "https")) {
+			return Err(invalid("base url must be an http:// or https:// url"));
+		}
+
+		if uri.authority().is_none() {
+			return Err(invalid("base url must have a host"));
+		}
+
+		if !matches!(uri.path(), "" | "/") {
+			return Err(invalid("base url must not have a path"));
+		}
+
+		self.base_url = Some(trimmed.to_owned());
+		Ok(self)
+	}
+
+	/// Check that every internal link of every page in this server leads to a page,
+	/// see [Servable::linked_routes]. Call this at startup to catch broken pages early.
+	///
+	/// Links are checked against the routes of this server,
+	/// pages served by [Self::with_fallback] are not considered.
+	/// Returns the first broken link we find.
+	///
+	/// ```rust
+	/// use servable::{HtmlPage, ServableRouter};
+	///
+	/// let router = ServableRouter::new()
+	/// 	.add_page("/", HtmlPage::default().with_style_linked("/style.css"));
+	///
+	/// assert!(router.validate().is_err());
+	/// ```
+	pub fn validate(&self) -> Result<(), Error> {
+		let pages = self.pages.snapshot();
+		let mut routes: Vec<_> = pages.index.iter().collect();
+		routes.sort();
+
+		for (route, i) in routes {
+			for link in pages.pages[*i].servable.linked_routes() {
+				if !link.starts_with('/') || link.starts_with("//") {
+					continue;
+				}
+
+				let path = link.split(['?', '#']).next().unwrap_or("");
+				let path = self.normalization.canonical_path(path);
+				if pages.get(&path).is_none() {
+					return Err(Error::BrokenLink {
+						route: route.clone(),
+						link,
+					});
+				}
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Every route of this router and the servable at it, sorted by route
+	pub(crate) fn page_routes(&self) -> Vec<(String, Arc<dyn Servable>)> {
+		let pages = self.pages.snapshot();
+		let mut routes: Vec<_> = pages
+			.index
+			.iter()
+			.map(|(route, i)| (route.clone(), pages.pages[*i].servable.clone()))
+			.collect();
+		routes.sort_by(|a, b| a.0.cmp(&b.0));
+		routes
+	}
+
+	/// Every route of this router and its description, sorted by route
+	pub fn route_table(&self) -> Vec<RouteInfo> {
+		let pages = self.pages.snapshot();
+		let mut routes: Vec<_> = pages
+			.index
+			.iter()
+			.map(|(route, i)| RouteInfo {
+				route: route.clone(),
+				description: pages.pages[*i].description.as_deref().map(str::to_owned),
+			})
+			.collect();
+		routes.sort_by(|a, b| a.route.cmp(&b.route));
+		routes
+	}
+
+	/// The path this router serves `route` at, see [Self::with_base_path]
+	pub(crate) fn route_url(&self, route: &str) -> String {
+		match route {
+			"/" if !self.base_path.is_empty() => self.base_path.clone(),
+			_ => format!("{}{route}", self.base_path),
+		}
+	}
+
+	/// This router's base url, see [Self::with_base_url]
+	#[inline(always)]
+	pub(crate) fn base_url(&self) -> Option<&str> {
+		self.base_url.as_deref()
+	}
+
+	/// Set the maximum size of a form submitted to a [FormServable], in bytes.
+	/// Larger forms are rejected with `413 Payload Too Large`.
+	///
+	/// The default is 64 KiB.
+	#[inline(always)]
+	pub fn with_form_limit(mut self, limit: usize) -> Self {
+		self.form_limit = limit;
+		self
 	}
 
-	/// Set this server's "not found" page
+	/// Attach shared state to this server.
+	///
+	/// `value` is cloned into the [RenderContext] of every request,
+	/// and may be retrieved with [RenderContext::extension].
+	/// Wrap large or mutable state (like a database pool) in an [Arc].
+	///
+	/// If a value of type `T` was already attached, it is replaced.
+	///
+	/// ```rust
+	/// use servable::{HtmlPage, ServableRouter};
+	/// use maud::html;
+	///
+	/// #[derive(Clone)]
+	/// struct Config {
+	/// 	site_name: &'static str,
+	/// }
+	///
+	/// let page = HtmlPage::default().with_render(|_page, ctx| {
+	/// 	Box::pin(async move {
+	/// 		let name = ctx.extension::<Config>().map(|x| x.site_name);
+	/// 		html! { h1 { (name.unwrap_or("")) } }
+	/// 	})
+	/// });
+	///
+	/// let route = ServableRouter::new()
+	/// 	.with_extension(Config { site_name: "My site" })
+	/// 	.add_page("/", page);
+	/// ```
 	#[inline(always)]
-	pub fn with_404<S: Servable + 'static>(mut self, page: S) -> Self {
-		self.notfound = Arc::new(page);
+	pub fn with_extension<T: Clone + Send + Sync + 'static>(mut self, value: T) -> Self {
+		self.extensions.insert(value);
 		self
 	}
 
@@ -107,32 +1288,223 @@ impl ServableRouter {
 	/// - panics if route does not start with a `/`, ends with a `/`, or contains `//`.
 	///   - urls are normalized, routes that violate this condition will never be served.
 	///   - `/` is an exception, it is valid.
-	/// - panics if called after this service is started
-	/// - overwrites existing pages
+	/// - panics if called after this service is started,
+	///   unless it has [dynamic routes](Self::with_dynamic_routes)
+	/// - panics if route conflicts with another route (like `/{a}` and `/{b}`)
+	/// - overwrites existing pages with the same route
+	///
+	/// Routes may contain parameters, which are available in [RenderContext::params]:
+	/// - `/post/{id}` matches `/post/1` and `/post/hello`, but not `/post/1/edit`.
+	/// - `/files/{*path}` matches `/files/a` and `/files/a/b`.
+	///
+	/// Static routes take precedence over parameters.
+	/// Use `{{` and `}}` to match literal braces.
+	///
+	/// See [Self::try_add_page] for a version of this method that does not panic.
 	#[inline(always)]
-	pub fn add_page<S: Servable + 'static>(mut self, route: impl Into<String>, page: S) -> Self {
-		let route = route.into();
+	pub fn add_page<S: Servable + 'static>(self, route: impl Into<String>, page: S) -> Self {
+		match self.try_add_page(route, page) {
+			Ok(x) => x,
+			Err(err) => panic!("{err}"),
+		}
+	}
 
-		if !route.starts_with("/") {
-			panic!("route must start with /")
-		};
+	/// Add a [Servable] to this server at the given route.
+	/// Behaves exactly like [Self::add_page], but returns an [Error] instead of panicking.
+	#[inline(always)]
+	pub fn try_add_page<S: Servable + 'static>(
+		self,
+		route: impl Into<String>,
+		page: S,
+	) -> Result<Self, Error> {
+		self.insert_router_page(route.into(), RouterPage::new(page))
+	}
 
-		if route.ends_with("/") && route != "/" {
-			panic!("route must not end with /")
-		};
+	/// Add a [Servable] to this server at the given route,
+	/// overriding this server's timeout (see [Self::with_timeout]).
+	/// If `timeout` is `None`, this page never times out.
+	///
+	/// Behaves exactly like [Self::add_page].
+	#[inline(always)]
+	pub fn add_page_with_timeout<S: Servable + 'static>(
+		self,
+		route: impl Into<String>,
+		page: S,
+		timeout: Option<Duration>,
+	) -> Self {
+		let mut page = RouterPage::new(page);
+		page.timeout = Some(timeout);
+		match self.insert_router_page(route.into(), page) {
+			Ok(x) => x,
+			Err(err) => panic!("{err}"),
+		}
+	}
 
-		if route.contains("//") {
-			panic!("route must not contain //")
-		};
+	/// Add a [Servable] to this server at the given route,
+	/// with a human-readable description of what it is.
+	/// Descriptions are listed by [Self::route_table],
+	/// and logged when this router is started with [Self::serve].
+	///
+	/// Behaves exactly like [Self::add_page].
+	///
+	/// ```rust
+	/// use servable::{HtmlPage, ServableRouter};
+	///
+	/// let router = ServableRouter::new()
+	/// 	.add_page("/", HtmlPage::default())
+	/// 	.add_page_with_description("/internal/x9", HtmlPage::default(), "Queue status for ops");
+	///
+	/// let table = router.route_table();
+	/// assert_eq!(table[1].route, "/internal/x9");
+	/// assert_eq!(table[1].description.as_deref(), Some("Queue status for ops"));
+	/// ```
+	#[inline(always)]
+	pub fn add_page_with_description<S: Servable + 'static>(
+		self,
+		route: impl Into<String>,
+		page: S,
+		description: impl Into<String>,
+	) -> Self {
+		let mut page = RouterPage::new(page);
+		page.description = Some(description.into().into());
+		match self.insert_router_page(route.into(), page) {
+			Ok(x) => x,
+			Err(err) => panic!("{err}"),
+		}
+	}
+
+	/// Add a [FormServable] to this server at the given route.
+	/// This page responds to `POST` requests with [FormServable::submit],
+	/// and to all other requests like a page added with [Self::add_page].
+	///
+	/// Panics in the same cases as [Self::add_page].
+	/// See [Self::try_add_form] for a version of this method that does not panic.
+	#[inline(always)]
+	pub fn add_form<S: FormServable + 'static>(self, route: impl Into<String>, page: S) -> Self {
+		match self.try_add_form(route, page) {
+			Ok(x) => x,
+			Err(err) => panic!("{err}"),
+		}
+	}
 
-		#[expect(clippy::expect_used)]
-		Arc::get_mut(&mut self.pages)
-			.expect("add_pages called after service was started")
-			.insert(route, Arc::new(page));
+	/// Add a [FormServable] to this server at the given route.
+	/// Behaves exactly like [Self::add_form], but returns an [Error] instead of panicking.
+	#[inline(always)]
+	pub fn try_add_form<S: FormServable + 'static>(
+		self,
+		route: impl Into<String>,
+		page: S,
+	) -> Result<Self, Error> {
+		self.insert_router_page(route.into(), RouterPage::new_form(page))
+	}
+
+	/// Add a [Honeypot] to this server at the given route.
+	/// Honeypots respond to `GET`, `HEAD` and `POST` requests.
+	/// Bodies of `POST` requests are never read, so every request is recorded,
+	/// whatever it sends.
+	///
+	/// # Panics
+	/// Panics under the same conditions as [Self::add_page].
+	///
+	/// See [Self::try_add_honeypot] for a version of this method that does not panic.
+	#[inline(always)]
+	pub fn add_honeypot(self, route: impl Into<String>, honeypot: Honeypot) -> Self {
+		match self.try_add_honeypot(route, honeypot) {
+			Ok(x) => x,
+			Err(err) => panic!("{err}"),
+		}
+	}
+
+	/// Add a [Honeypot] to this server at the given route.
+	/// Behaves exactly like [Self::add_honeypot], but returns an [Error] instead of panicking.
+	#[inline(always)]
+	pub fn try_add_honeypot(
+		self,
+		route: impl Into<String>,
+		honeypot: Honeypot,
+	) -> Result<Self, Error> {
+		let mut page = RouterPage::new_form(honeypot);
+		page.ignore_body = true;
+		self.insert_router_page(route.into(), page)
+	}
+
+	fn insert_router_page(mut self, route: String, page: RouterPage) -> Result<Self, Error> {
+		check_route(&route)?;
+		self.pages.update(|x| x.insert(route, page))?;
+		Ok(self)
+	}
 
+	/// Let pages be added and removed while this router is running,
+	/// with [Self::insert_page] and [Self::remove_page].
+	///
+	/// Every clone of this router shares the same pages.
+	/// Requests are served from a snapshot of the pages that existed when they arrived.
+	///
+	/// ```rust
+	/// use servable::{HtmlPage, ServableRouter};
+	///
+	/// let router = ServableRouter::new()
+	/// 	.add_page("/", HtmlPage::default())
+	/// 	.with_dynamic_routes();
+	///
+	/// // `handle` may be kept by the app after `router` is started
+	/// let handle = router.clone();
+	/// handle.insert_page("/post/1", HtmlPage::default()).unwrap();
+	/// assert!(handle.remove_page("/post/1").unwrap());
+	/// ```
+	#[inline(always)]
+	pub fn with_dynamic_routes(mut self) -> Self {
+		if let PageMap::Static(x) = self.pages {
+			self.pages = PageMap::Dynamic(Arc::new(RwLock::new(x)));
+		}
 		self
 	}
 
+	/// Add or replace a page while this router is running.
+	/// This is [Self::try_add_page] for routers with [Self::with_dynamic_routes].
+	///
+	/// Returns [Error::StaticRoutes] if this router's routes aren't dynamic.
+	pub fn insert_page<S: Servable + 'static>(
+		&self,
+		route: impl Into<String>,
+		page: S,
+	) -> Result<(), Error> {
+		let route = route.into();
+		check_route(&route)?;
+		self.pages
+			.update_shared(|x| x.insert(route, RouterPage::new(page)))
+	}
+
+	/// Remove the page that was added with `route` while this router is running.
+	/// Returns `false` if there was no such page.
+	///
+	/// Returns [Error::StaticRoutes] if this router's routes aren't dynamic.
+	pub fn remove_page(&self, route: &str) -> Result<bool, Error> {
+		self.pages.update_shared(|x| x.remove(route))
+	}
+
+	/// Add all pages in a [RouteGroup] to this server.
+	/// Behaves exactly like calling [Self::add_page] for each page in `group`.
+	#[inline(always)]
+	pub fn add_group(self, group: RouteGroup) -> Self {
+		match self.try_add_group(group) {
+			Ok(x) => x,
+			Err(err) => panic!("{err}"),
+		}
+	}
+
+	/// Add all pages in a [RouteGroup] to this server.
+	/// Behaves exactly like [Self::add_group], but returns an [Error] instead of panicking.
+	#[inline(always)]
+	pub fn try_add_group(mut self, group: RouteGroup) -> Result<Self, Error> {
+		for (route, page, timeout) in group.into_pages() {
+			let mut page = RouterPage::new(page);
+			page.timeout = timeout;
+			self = self.insert_router_page(route, page)?;
+		}
+		Ok(self)
+	}
+
 	/// Add a [ServableWithRoute] to this server.
 	/// Behaves exactly like [Self::add_page].
 	#[inline(always)]
@@ -171,127 +1543,684 @@ impl Service<Request<Body>> for ServableRouter {
 	}
 
 	fn call(&mut self, req: Request<Body>) -> Self::Future {
-		if req.method() != Method::GET && req.method() != Method::HEAD {
-			let mut headers = HeaderMap::with_capacity(1);
-			headers.insert(header::ACCEPT, HeaderValue::from_static("GET,HEAD"));
-			return Box::pin(async {
-				Ok((StatusCode::METHOD_NOT_ALLOWED, headers).into_response())
-			});
+		Self::dispatch(self.clone(), req)
+	}
+}
+
+impl ServableRouter {
+	/// Answer `req` with `router`, like [Service::call]
+	pub(crate) fn dispatch<R>(
+		router: R,
+		req: Request<Body>,
+	) -> <Self as Service<Request<Body>>>::Future
+	where
+		R: Borrow<Self> + Send + Sync + 'static,
+	{
+		if let Some(mirror) = &router.borrow().mirror {
+			mirror.mirror(&req);
 		}
 
-		let pages = self.pages.clone();
-		let notfound = self.notfound.clone();
 		Box::pin(async move {
-			let addr = req.extensions().get::<SocketAddr>().copied();
-			let route = req.uri().path().to_owned();
-			let headers = req.headers().clone();
-			let query: BTreeMap<String, String> =
-				serde_urlencoded::from_str(req.uri().query().unwrap_or("")).unwrap_or_default();
-
-			let start = Instant::now();
-			let client_info = ClientInfo::from_headers(&headers);
-			let ua = headers
-				.get("user-agent")
-				.and_then(|x| x.to_str().ok())
-				.unwrap_or("");
+			let this = router.borrow();
+			let mut res = this.respond(req).await;
+			this.security_headers.apply(res.headers_mut());
+			Ok(res)
+		})
+	}
+
+	/// Carry over the state of `old` that is still valid in this router:
+	/// bans, and quota usage of prefixes both routers limit.
+	pub(crate) fn warm_from(&self, old: &Self) {
+		if let (Some(new), Some(old)) = (&self.ban_list, &old.ban_list) {
+			new.absorb(old);
+		}
+
+		for (prefix, quota) in &self.quotas {
+			if let Some((_, old)) = old.quotas.iter().find(|(x, _)| x == prefix) {
+				quota.absorb(old);
+			}
+		}
+	}
+}
+
+impl ServableRouter {
+	/// Read a form submitted to a [FormServable]
+	async fn read_form(&self, headers: &HeaderMap, body: Body) -> Result<FormData, Error> {
+		let content_type = headers
+			.get(header::CONTENT_TYPE)
+			.and_then(|x| x.to_str().ok())
+			.unwrap_or("");
+
+		let mime = content_type.split(';').next().unwrap_or("").trim();
+		if !mime.eq_ignore_ascii_case("application/x-www-form-urlencoded") {
+			return Err(Error::UnsupportedFormType(content_type.to_owned()));
+		}
+
+		let bytes = match Limited::new(body, self.form_limit).collect().await {
+			Ok(x) => x.to_bytes(),
+			Err(err) if err.is::<LengthLimitError>() => {
+				return Err(Error::FormTooLarge(self.form_limit));
+			}
+			Err(_err) => return Err(Error::InvalidForm("could not read body".to_owned())),
+		};
+
+		FormData::from_urlencoded(&bytes).map_err(|err| Error::InvalidForm(err.to_string()))
+	}
+
+	/// Remove this router's base path from `path`.
+	/// Returns `None` if `path` is not under our base path.
+	fn strip_base_path<'a>(&self, path: &'a str) -> Option<&'a str> {
+		if self.base_path.is_empty() {
+			return Some(path);
+		}
+
+		match path.strip_prefix(self.base_path.as_str())? {
+			"" => Some("/"),
+			x if x.starts_with('/') => Some(x),
+			_ => None,
+		}
+	}
+
+	/// If `rend` is an empty error, replace its body with the matching error page
+	async fn error_page(
+		&self,
+		ctx: &RenderContext,
+		rend: Rendered<RenderedBody>,
+	) -> Rendered<RenderedBody> {
+		let page = self
+			.error_pages
+			.get(&rend.code)
+			.or_else(|| StatusClass::of(rend.code).and_then(|x| self.error_classes.get(&x)));
+
+		let empty = match ctx.mode {
+			RenderMode::Head => matches!(rend.content_length, None | Some(0)),
+			RenderMode::Full => rend.body.is_empty(),
+		};
+
+		let Some(page) = page else { return rend };
+		if !empty {
+			return rend;
+		}
+
+		let page_rend = match ctx.mode {
+			RenderMode::Head => page.head(ctx).await.with_body(RenderedBody::Empty),
+			RenderMode::Full => page.render(ctx).await,
+		};
+
+		let mut headers = rend.headers;
+		headers.remove(header::CONTENT_TYPE);
+		headers.remove(header::CONTENT_LENGTH);
+		merge_template(&mut headers, &page_rend.headers);
+
+		Rendered {
+			code: rend.code,
+			headers,
+			body: page_rend.body,
+			mime: page_rend.mime,
+			ttl: rend.ttl,
+			private: rend.private,
+			content_length: page_rend.content_length,
+		}
+	}
+
+	/// Find the page in `pages` that would serve a request for `path`
+	fn page_at<'a>(&'a self, pages: &'a Routes, path: &str) -> Option<&'a RouterPage> {
+		let route = self.strip_base_path(path)?;
+		let route = self.normalization.canonical_path(route);
+		pages
+			.get(&route)
+			.map(|(x, _)| x)
+			.or_else(|| self.favicon_at(&route))
+	}
+
+	/// The page served at `route` by [Self::with_favicon_fallback], if any
+	fn favicon_at(&self, route: &str) -> Option<&RouterPage> {
+		match route {
+			"/favicon.ico" => self.favicon.as_ref(),
+			_ => None,
+		}
+	}
+
+	/// The id of a request from `addr` with the given headers,
+	/// see [RenderContext::request_id]
+	fn request_id(&self, addr: Option<SocketAddr>, headers: &HeaderMap) -> String {
+		let trusted = addr.is_some_and(|x| self.trusted_proxies.iter().any(|c| c.contains(x.ip())));
+		if trusted
+			&& let Some(id) = headers.get("x-request-id").and_then(|x| x.to_str().ok())
+			&& (1..=128).contains(&id.len())
+			&& id.bytes().all(|x| x.is_ascii_graphic())
+		{
+			return id.to_owned();
+		}
+
+		if cfg!(feature = "deterministic") {
+			let n = self
+				.request_count
+				.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+			return format!("{:016}", n + 1);
+		}
+
+		rand::rng()
+			.sample_iter(&Alphanumeric)
+			.take(16)
+			.map(char::from)
+			.collect()
+	}
+
+	/// Send a failed request to every [ErrorReporter]
+	fn report(
+		&self,
+		ctx: &RenderContext,
+		code: StatusCode,
+		error: Option<Box<dyn std::error::Error + Send + Sync>>,
+	) {
+		if self.error_reporters.is_empty() {
+			return;
+		}
+
+		let mut errors = ctx.take_errors();
+		errors.extend(error);
+		let report = ErrorReport {
+			code,
+			errors: &errors,
+			ctx,
+		};
+
+		for reporter in &self.error_reporters {
+			reporter.report(&report);
+		}
+	}
+
+	/// Generate a response to `req`
+	async fn respond(&self, req: Request<Body>) -> Response {
+		let path = req.uri().path();
+		let route = self.strip_base_path(path).unwrap_or(path);
+		if let Some(reason) = self.request_filter.check(route, req.headers()) {
+			trace!(message = "Rejected request", route, reason);
+			return self.request_filter.status.into_response();
+		}
+
+		let addr = req.extensions().get::<SocketAddr>().copied().or_else(|| {
+			req.extensions()
+				.get::<ConnectInfo<SocketAddr>>()
+				.map(|x| x.0)
+		});
+		let client_ip = addr.map(|x| client_ip(x.ip(), req.headers(), &self.trusted_proxies));
+
+		if let Some(bans) = &self.ban_list
+			&& let Some(ip) = client_ip
+			&& bans.is_banned(ip, self.clock.instant())
+		{
+			trace!(message = "Rejected banned client", route, ?ip);
+			return StatusCode::FORBIDDEN.into_response();
+		}
+
+		let signed = self
+			.signed_urls
+			.iter()
+			.filter(|(prefix, _)| has_route_prefix(route, prefix))
+			.all(|(_, signer)| {
+				signer.verify_url(
+					req.uri().path(),
+					req.uri().query().unwrap_or(""),
+					self.clock.now(),
+				)
+			});
+		if !signed {
+			trace!(message = "Rejected unsigned url", route);
+			return StatusCode::FORBIDDEN.into_response();
+		}
+
+		let pages = self.pages.snapshot();
+		if let Some(fallback) = &self.fallback
+			&& self.page_at(&pages, req.uri().path()).is_none()
+		{
+			trace!(message = "Using fallback", route = req.uri().path());
+			let Ok(res) = fallback.clone().oneshot(req).await;
+			return res;
+		}
+
+		let (req, body) = req.into_parts();
+		let is_options = req.method == Method::OPTIONS;
+		let is_post = req.method == Method::POST;
+		if req.method != Method::GET && req.method != Method::HEAD && !is_options && !is_post {
+			let allow = self
+				.page_at(&pages, req.uri.path())
+				.map(|x| x.allow())
+				.unwrap_or(ALLOW);
+
+			let mut headers = HeaderMap::with_capacity(1);
+			headers.insert(header::ALLOW, allow);
+			return (StatusCode::METHOD_NOT_ALLOWED, headers).into_response();
+		}
+
+		// Paths outside our base path are never served
+		let (route, in_base) = match self.strip_base_path(req.uri.path()) {
+			Some(x) => (x.to_owned(), true),
+			None => (req.uri.path().to_owned(), false),
+		};
+		let raw_query = req.uri.query().unwrap_or("");
+		let canonical_query = self.normalization.canonical_query(raw_query);
+		let query: BTreeMap<String, String> =
+			serde_urlencoded::from_str(&canonical_query).unwrap_or_default();
+
+		let start = Instant::now();
+		let client_info = ClientInfo::from_headers(&req.headers);
+		let ua = req
+			.headers
+			.get("user-agent")
+			.and_then(|x| x.to_str().ok())
+			.unwrap_or("");
 
+		trace!(
+			message = "Serving route",
+			route,
+			addr = ?addr,
+			user_agent = ua,
+			device_type = ?client_info.device_type
+		);
+
+		// Normalize url
+		let canonical = self.normalization.canonical_path(&route);
+		let canonical_path = match (in_base, canonical.as_str()) {
+			(true, "/") if !self.base_path.is_empty() => self.base_path.clone(),
+			(true, _) => format!("{}{canonical}", self.base_path),
+			(false, _) => canonical.clone(),
+		};
+		let is_canonical = canonical_path == req.uri.path();
+		let is_canonical_query = canonical_query == raw_query;
+		// Preflight requests can't follow redirects
+		if (!is_canonical || !is_canonical_query)
+			&& !is_options
+			&& self.normalization.action == NonCanonicalAction::Redirect
+		{
 			trace!(
-				message = "Serving route",
+				message = "Redirecting",
 				route,
+				new_route = canonical_path,
 				addr = ?addr,
 				user_agent = ua,
 				device_type = ?client_info.device_type
 			);
 
-			// Normalize url with redirect
-			if (route.ends_with('/') && route != "/") || route.contains("//") {
-				let mut new_route = route.clone();
-				while new_route.contains("//") {
-					new_route = new_route.replace("//", "/");
-				}
-				let new_route = new_route.trim_matches('/');
-
-				trace!(
-					message = "Redirecting",
-					route,
-					new_route,
-					addr = ?addr,
-					user_agent = ua,
-					device_type = ?client_info.device_type
-				);
-
-				let mut headers = HeaderMap::with_capacity(1);
-				match HeaderValue::from_str(&format!("/{new_route}")) {
-					Ok(x) => headers.append(header::LOCATION, x),
-					Err(_) => return Ok(StatusCode::BAD_REQUEST.into_response()),
-				};
-				return Ok((StatusCode::PERMANENT_REDIRECT, headers).into_response());
-			}
+			let base_url = self.base_url.as_deref().unwrap_or("");
+			let location = match canonical_query.is_empty() {
+				false => format!("{base_url}{canonical_path}?{canonical_query}"),
+				true => format!("{base_url}{canonical_path}"),
+			};
 
-			let ctx = RenderContext {
-				client_info,
-				route,
-				query,
+			let mut headers = HeaderMap::with_capacity(1);
+			match HeaderValue::from_str(&location) {
+				Ok(x) => headers.append(header::LOCATION, x),
+				Err(_) => return StatusCode::BAD_REQUEST.into_response(),
 			};
+			return (StatusCode::PERMANENT_REDIRECT, headers).into_response();
+		}
 
-			let page = pages.get(&ctx.route).unwrap_or(&notfound);
-			let mut rend = match req.method() == Method::HEAD {
-				true => page.head(&ctx).await.with_body(RenderedBody::Empty),
-				false => page.render(&ctx).await,
+		let mut headers = req.headers.clone();
+		if self.encoding_override
+			&& let Some(encoding) = query.get("encoding")
+		{
+			let encoding = match encoding.as_str() {
+				"identity" => HeaderValue::from_static("identity"),
+				"gzip" => HeaderValue::from_static("gzip"),
+				"br" => HeaderValue::from_static("br"),
+				_ => return StatusCode::BAD_REQUEST.into_response(),
 			};
 
-			// Tweak headers
-			{
-				if !rend.headers.contains_key(header::CACHE_CONTROL) {
-					let max_age = rend.ttl.map(|x| x.num_seconds()).unwrap_or(0).max(0);
+			trace!(message = "Overriding encoding", route, ?encoding);
+			headers.insert(header::ACCEPT_ENCODING, encoding);
+		}
 
-					let mut value = String::new();
+		let debug = match &self.debug_headers {
+			None => false,
+			Some((name, secret)) => {
+				let debug = headers.get(name).is_some_and(|x| {
+					!secret.is_empty() && constant_time_eq(x.as_bytes(), secret.as_bytes())
+				});
+				headers.remove(name);
+				debug
+			}
+		};
 
-					value.push_str(match rend.private {
-						true => "private, ",
-						false => "public, ",
-					});
+		let target = match is_canonical && in_base {
+			true => pages.get(&route),
+			false => None,
+		};
 
-					value.push_str(&format!("max-age={}, ", max_age));
+		let allow = target.as_ref().map(|(x, _)| x.allow()).unwrap_or(ALLOW);
+		if is_post && target.as_ref().is_some_and(|(x, _)| x.form.is_none()) {
+			let mut headers = HeaderMap::with_capacity(1);
+			headers.insert(header::ALLOW, allow);
+			return (StatusCode::METHOD_NOT_ALLOWED, headers).into_response();
+		}
 
-					#[expect(clippy::unwrap_used)]
-					rend.headers.insert(
-						header::CACHE_CONTROL,
-						HeaderValue::from_str(value.trim().trim_end_matches(',')).unwrap(),
-					);
+		let (page, params) = match (is_options, target) {
+			(true, _) => (&self.options, BTreeMap::new()),
+			(false, None) => match self.favicon_at(&route) {
+				Some(favicon) if is_canonical && in_base => (favicon, BTreeMap::new()),
+				_ => (&self.notfound, BTreeMap::new()),
+			},
+			(false, Some((page, params))) => (
+				page,
+				params
+					.iter()
+					.map(|(k, v)| (k.to_owned(), v.to_owned()))
+					.collect(),
+			),
+		};
+
+		let form = match (is_post, &page.form) {
+			(true, Some(_)) if page.ignore_body => Some(FormData::default()),
+			(true, Some(_)) => match self.read_form(&req.headers, body).await {
+				Ok(x) => Some(x),
+				Err(error) => {
+					trace!(message = "Rejected form", route, ?error);
+					return error.into_response();
 				}
+			},
+			_ => None,
+		};
+
+		#[cfg(feature = "image")]
+		let transforms = match &self.transform_prefixes {
+			None => true,
+			Some(x) => x.iter().any(|prefix| has_route_prefix(&route, prefix)),
+		};
+
+		let timeout = page.timeout.unwrap_or(self.timeout);
+		let ctx = RenderContext {
+			client_info,
+			client_ip,
+			clock: self.clock.clone(),
+			deadline: timeout.map(|x| self.clock.instant() + x),
+			route,
+			query,
+			params,
+			base_path: self.base_path.clone(),
+			base_url: self.base_url.clone(),
+			headers,
+			mode: match req.method == Method::HEAD {
+				true => RenderMode::Head,
+				false => RenderMode::Full,
+			},
+			extensions: self.extensions.clone(),
+			request_id: self.request_id(addr, &req.headers),
+			debug,
+			#[cfg(feature = "image")]
+			transforms,
+			errors: Default::default(),
+			vary: Default::default(),
+		};
+
+		let is_get = req.method == Method::GET;
+		let rend = async {
+			let rend = match (&form, &page.form, ctx.mode) {
+				(Some(form), Some(servable), _) => servable.submit(&ctx, form).await,
+				(_, _, RenderMode::Head) => page
+					.servable
+					.head(&ctx)
+					.await
+					.with_body(RenderedBody::Empty),
+				(_, _, RenderMode::Full) => page.servable.render(&ctx).await,
+			};
+
+			self.error_page(&ctx, rend).await
+		};
 
-				if !rend.headers.contains_key("Accept-CH") {
-					rend.headers
-						.insert("Accept-CH", HeaderValue::from_static("Sec-CH-UA-Mobile"));
+		let mut rend = match timeout {
+			None => rend.await,
+			Some(timeout) => match tokio::time::timeout(timeout, rend).await {
+				Ok(x) => x,
+				Err(_) => {
+					warn!(
+						message = "Servable timed out",
+						route = ctx.route,
+						timeout_ms = timeout.as_millis(),
+					);
+					let err = Error::Timeout(timeout);
+					let code = err.status_code();
+					self.report(&ctx, code, Some(err.into()));
+					return code.into_response();
 				}
+			},
+		};
 
-				if !rend.headers.contains_key(header::CONTENT_TYPE)
-					&& let Some(mime) = &rend.mime
-				{
-					#[expect(clippy::unwrap_used)]
-					rend.headers.insert(
-						header::CONTENT_TYPE,
-						HeaderValue::from_str(mime.as_ref()).unwrap(),
+		if rend.code.is_server_error() {
+			self.report(&ctx, rend.code, None);
+		}
+
+		// Tweak headers
+		{
+			if rend.code.is_success() && form.is_none() {
+				merge_template(&mut rend.headers, &page.headers);
+			}
+
+			// Request headers the servable reported with `RenderContext::vary`
+			merge_template(&mut rend.headers, &ctx.vary_headers());
+
+			// After the header template, so rewriters see its headers
+			rend = rewrite_html(&self.html_rewriters, &ctx, rend);
+
+			if self.early_hints && rend.code.is_success() && form.is_none() {
+				for preload in page.preloads.iter() {
+					let crossorigin = match preload.kind {
+						PreloadKind::Font => "; crossorigin",
+						_ => "",
+					};
+					let link = format!(
+						"<{}>; rel=preload; as={}{crossorigin}",
+						ctx.url(&preload.url),
+						preload.kind.as_str()
 					);
+					if let Ok(link) = HeaderValue::from_str(&link) {
+						rend.headers.append(header::LINK, link);
+					}
 				}
 			}
 
-			trace!(
-				message = "Served route",
-				route = ctx.route,
-				addr = ?addr,
-				user_agent = ua,
-				device_type = ?client_info.device_type,
-				time_ns = start.elapsed().as_nanos()
+			if is_options && !rend.headers.contains_key(header::ALLOW) {
+				rend.headers.insert(header::ALLOW, allow);
+			}
+
+			if ctx.mode == RenderMode::Head
+				&& let Some(len) = rend.content_length
+				&& !rend.headers.contains_key(header::CONTENT_LENGTH)
+			{
+				rend.headers
+					.insert(header::CONTENT_LENGTH, HeaderValue::from(len));
+			}
+
+			if !rend.headers.contains_key(header::CACHE_CONTROL) {
+				rend.headers
+					.insert(header::CACHE_CONTROL, cache_control(rend.ttl, rend.private));
+			}
+
+			if rend.code == StatusCode::OK && !rend.headers.contains_key(header::ACCEPT_RANGES) {
+				rend.headers
+					.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+			}
+
+			if !rend.headers.contains_key("Accept-CH") {
+				rend.headers
+					.insert("Accept-CH", HeaderValue::from_static("Sec-CH-UA-Mobile"));
+			}
+
+			if !rend.headers.contains_key(header::CONTENT_TYPE)
+				&& let Some(mime) = &rend.mime
+			{
+				#[expect(clippy::unwrap_used)]
+				rend.headers.insert(
+					header::CONTENT_TYPE,
+					HeaderValue::from_str(mime.as_ref()).unwrap(),
+				);
+			}
+
+			if rend.code.is_success()
+				&& let Some(priority_hints) = &self.priority_hints
+			{
+				priority_hints.apply(&mut rend.headers);
+			}
+
+			ctx.debug_header(
+				&mut rend.headers,
+				"x-render-time",
+				&format!("{:.3}ms", start.elapsed().as_secs_f64() * 1000.0),
 			);
 
-			Ok(match rend.body {
-				RenderedBody::Static(d) => (rend.code, rend.headers, d).into_response(),
-				RenderedBody::Bytes(d) => (rend.code, rend.headers, d).into_response(),
-				RenderedBody::String(s) => (rend.code, rend.headers, s).into_response(),
-				RenderedBody::Empty => (rend.code, rend.headers).into_response(),
-			})
-		})
+			self.header_scrub.apply(&ctx.route, &mut rend.headers);
+		}
+
+		trace!(
+			message = "Served route",
+			route = ctx.route,
+			addr = ?addr,
+			user_agent = ua,
+			device_type = ?client_info.device_type,
+			time_ns = start.elapsed().as_nanos()
+		);
+
+		let quota = match (is_get && rend.code.is_success(), ctx.client_ip) {
+			(true, Some(ip)) => self
+				.quotas
+				.iter()
+				.find(|(prefix, _)| has_route_prefix(&ctx.route, prefix))
+				.map(|(_, quota)| (ip, quota)),
+			_ => None,
+		};
+
+		// Check the quota before serializing bodies of known length.
+		// Range requests may download less, they are only checked below.
+		if let Some((ip, quota)) = quota
+			&& let Some(len) = rend.content_length
+			&& !ctx.headers.contains_key(header::RANGE)
+			&& let Err(exceeded) = quota.check(ip, self.clock.instant(), len)
+		{
+			trace!(message = "Download quota exceeded", route = ctx.route, ?ip);
+			return exceeded.into_response();
+		}
+
+		// Serialize and slice the body
+		#[cfg(feature = "compression")]
+		let is_static = matches!(rend.body, RenderedBody::Static(_));
+		let (chunks, default_type): (Vec<Bytes>, &'static str) = match rend.body {
+			RenderedBody::Static(d) => (vec![Bytes::from_static(d)], "application/octet-stream"),
+			RenderedBody::Bytes(d) => (vec![Bytes::from(d)], "application/octet-stream"),
+			RenderedBody::String(s) => (vec![Bytes::from(s)], "text/plain; charset=utf-8"),
+			RenderedBody::Chunks(c) => (c, "application/octet-stream"),
+			RenderedBody::Json(j) => match serde_json::to_vec(&*j) {
+				Ok(d) => (vec![Bytes::from(d)], "application/json"),
+				Err(error) => {
+					error!(
+						message = "Error while serializing json",
+						route = ctx.route,
+						?error
+					);
+					let err = Error::Json(error);
+					let code = err.status_code();
+					self.report(&ctx, code, Some(err.into()));
+					return code.into_response();
+				}
+			},
+			RenderedBody::Empty => {
+				#[cfg(feature = "compression")]
+				if let Some(compression) = &self.compression
+					&& ctx.mode == RenderMode::Head
+					&& rend.code.is_success()
+				{
+					compression.apply_head(&ctx.headers, &mut rend.headers, rend.content_length);
+				}
+				return (rend.code, rend.headers).into_response();
+			}
+		};
+
+		if !rend.headers.contains_key(header::CONTENT_TYPE) {
+			rend.headers
+				.insert(header::CONTENT_TYPE, HeaderValue::from_static(default_type));
+		}
+
+		// Only bodies made of one buffer are compressed
+		#[cfg(feature = "compression")]
+		let mut chunks = chunks;
+		#[cfg(feature = "compression")]
+		if let Some(compression) = &self.compression
+			&& rend.code.is_success()
+			&& let [bytes] = chunks.as_mut_slice()
+		{
+			*bytes = compression
+				.apply(&ctx.headers, &mut rend.headers, std::mem::take(bytes), is_static)
+				.await;
+		}
+
+		let len = chunks.iter().map(Bytes::len).sum();
+		let range = match ctx.headers.get(header::RANGE) {
+			Some(range)
+				if is_get
+					&& rend.code == StatusCode::OK
+					&& !ctx.headers.contains_key(header::IF_RANGE)
+					&& rend.headers.get(header::ACCEPT_RANGES)
+						== Some(&HeaderValue::from_static("bytes")) =>
+			{
+				range
+					.to_str()
+					.map(|x| RangeRequest::parse(x, len))
+					.unwrap_or(RangeRequest::Full)
+			}
+			_ => RangeRequest::Full,
+		};
+
+		let (chunks, len) = match range {
+			RangeRequest::Full => (chunks, len),
+			RangeRequest::Partial(range) => {
+				#[expect(clippy::unwrap_used)]
+				rend.headers.insert(
+					header::CONTENT_RANGE,
+					HeaderValue::from_str(&format!(
+						"bytes {}-{}/{len}",
+						range.start,
+						range.end - 1,
+					))
+					.unwrap(),
+				);
+				rend.code = StatusCode::PARTIAL_CONTENT;
+				(slice_chunks(chunks, range.clone()), range.len())
+			}
+			RangeRequest::Unsatisfiable => {
+				let mut headers = HeaderMap::with_capacity(1);
+				#[expect(clippy::unwrap_used)]
+				headers.insert(
+					header::CONTENT_RANGE,
+					HeaderValue::from_str(&format!("bytes */{len}")).unwrap(),
+				);
+				return (StatusCode::RANGE_NOT_SATISFIABLE, headers).into_response();
+			}
+		};
+
+		if let Some((ip, quota)) = quota
+			&& rend.code.is_success()
+			&& let Err(exceeded) = quota.take(ip, self.clock.instant(), len as u64)
+		{
+			trace!(message = "Download quota exceeded", route = ctx.route, ?ip);
+			return exceeded.into_response();
+		}
+
+		let throttle = self
+			.throttles
+			.iter()
+			.find(|(prefix, _)| has_route_prefix(&ctx.route, prefix));
+		let body = match (throttle, <[Bytes; 1]>::try_from(chunks)) {
+			(Some((_, throttle)), Ok(chunks)) => Body::new(throttle.body(chunks.into())),
+			(Some((_, throttle)), Err(chunks)) => Body::new(throttle.body(chunks)),
+			(None, Ok([bytes])) => Body::from(bytes),
+			(None, Err(chunks)) => Body::new(ChunkedBody::new(chunks)),
+		};
+
+		// Build the response directly, moving our headers into it
+		// instead of merging them into a fresh map.
+		// Static bodies are never copied.
+		let mut res = Response::new(body);
+		*res.status_mut() = rend.code;
+		*res.headers_mut() = rend.headers;
+		res
 	}
 }
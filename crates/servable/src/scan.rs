@@ -0,0 +1,127 @@
+//! Helpers for rewriting generated html
+
+/// Copy `html`, passing the opening tag of every element named one of `names` to `f`.
+/// If `f` returns `Some`, the opening tag is replaced with it.
+///
+/// `f` receives the name of the element (from `names`),
+/// its opening tag (including `<` and `>`), and all html after it.
+/// The contents of `<script>` and `<style>` elements are never passed to `f`,
+/// but their opening tags are if they are in `names`.
+pub(crate) fn rewrite_tags(
+	html: &str,
+	names: &[&str],
+	mut f: impl FnMut(&str, &str, &str) -> Option<String>,
+) -> String {
+	let mut out = String::with_capacity(html.len() + 256);
+
+	let mut rest = html;
+	while let Some(start) = rest.find('<') {
+		out.push_str(&rest[..start]);
+		rest = &rest[start..];
+
+		// Copy raw text elements as-is
+		if let Some(name) = ["script", "style"]
+			.into_iter()
+			.find(|x| starts_tag(rest, x))
+		{
+			if names.contains(&name)
+				&& let Some(open_end) = tag_end(rest)
+			{
+				let open = &rest[..open_end];
+				match f(name, open, &rest[open_end..]) {
+					Some(x) => out.push_str(&x),
+					None => out.push_str(open),
+				}
+				rest = &rest[open_end..];
+			}
+
+			let end = find_ignore_case(rest, &format!("</{name}"))
+				.and_then(|x| rest[x..].find('>').map(|y| x + y + 1))
+				.unwrap_or(rest.len());
+			out.push_str(&rest[..end]);
+			rest = &rest[end..];
+			continue;
+		}
+
+		let name = names.iter().find(|x| starts_tag(rest, x));
+		let (Some(name), Some(open_end)) = (name, tag_end(rest)) else {
+			out.push('<');
+			rest = &rest[1..];
+			continue;
+		};
+
+		let open = &rest[..open_end];
+		match f(name, open, &rest[open_end..]) {
+			Some(x) => out.push_str(&x),
+			None => out.push_str(open),
+		}
+		rest = &rest[open_end..];
+	}
+
+	out.push_str(rest);
+	out
+}
+
+/// Returns true if `html` starts with an opening tag named `name`
+fn starts_tag(html: &str, name: &str) -> bool {
+	let Some(rest) = html.strip_prefix('<') else {
+		return false;
+	};
+
+	rest.len() > name.len()
+		&& rest.is_char_boundary(name.len())
+		&& rest[..name.len()].eq_ignore_ascii_case(name)
+		&& matches!(
+			rest.as_bytes()[name.len()],
+			b'>' | b'/' | b' ' | b'\t' | b'\n' | b'\r'
+		)
+}
+
+/// Find the index of `needle` in `haystack`, ignoring ascii case
+pub(crate) fn find_ignore_case(haystack: &str, needle: &str) -> Option<usize> {
+	haystack
+		.as_bytes()
+		.windows(needle.len())
+		.position(|x| x.eq_ignore_ascii_case(needle.as_bytes()))
+}
+
+/// Find the end of the tag at the start of `html`,
+/// skipping quoted attribute values.
+/// Returns the index after the closing `>`.
+fn tag_end(html: &str) -> Option<usize> {
+	let mut quote = None;
+	for (i, c) in html.char_indices() {
+		match (quote, c) {
+			(None, '"' | '\'') => quote = Some(c),
+			(Some(q), c) if q == c => quote = None,
+			(None, '>') => return Some(i + 1),
+			_ => {}
+		}
+	}
+	None
+}
+
+/// Get the value of attribute `name` in the opening tag `tag`
+pub(crate) fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+	let mut rest = tag;
+	while let Some(i) = find_ignore_case(rest, name) {
+		let before = rest[..i].chars().last();
+		let after = rest[i + name.len()..].trim_start();
+		rest = &rest[i + name.len()..];
+
+		if !before.is_some_and(char::is_whitespace) {
+			continue;
+		}
+
+		let Some(value) = after.strip_prefix('=') else {
+			continue;
+		};
+
+		let value = value.trim_start();
+		return match value.chars().next() {
+			Some(q @ ('"' | '\'')) => value[1..].split(q).next(),
+			_ => value.split(|c: char| c.is_whitespace() || c == '>').next(),
+		};
+	}
+	None
+}
@@ -0,0 +1,85 @@
+use std::{fmt::Debug, pin::Pin, sync::Arc, time::Duration};
+use tokio::{task::JoinSet, time::MissedTickBehavior};
+use tracing::warn;
+
+/// The closure run by a [PeriodicTask]
+pub type TaskFn =
+	dyn Send + Sync + 'static + Fn() -> Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
+
+/// A job that a [crate::ServableRouter] runs periodically while it is served,
+/// see [crate::ServableRouter::with_task].
+#[derive(Clone)]
+pub struct PeriodicTask {
+	/// A name for this task, used in logs
+	pub name: String,
+
+	/// How often this task runs
+	pub every: Duration,
+
+	/// The job to run
+	pub run: Arc<TaskFn>,
+}
+
+impl Debug for PeriodicTask {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("PeriodicTask")
+			.field("name", &self.name)
+			.field("every", &self.every)
+			.finish()
+	}
+}
+
+impl PeriodicTask {
+	/// Run this task forever, once every `self.every`.
+	/// The first run is one period after this is called.
+	async fn run_forever(self) {
+		let start = tokio::time::Instant::now() + self.every;
+		let mut interval = tokio::time::interval_at(start, self.every);
+		interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+		loop {
+			interval.tick().await;
+
+			// Run in a separate task, so that a panic doesn't stop later runs
+			if let Err(error) = tokio::spawn((self.run)()).await {
+				warn!(message = "Periodic task failed", task = self.name, ?error);
+			}
+		}
+	}
+}
+
+/// The periodic tasks of a running router, see [crate::ServableRouter::start_tasks].
+///
+/// Tasks stop when this handle is dropped.
+/// A run that is in progress is allowed to finish.
+#[derive(Debug)]
+pub struct TaskHandle {
+	tasks: JoinSet<()>,
+}
+
+impl TaskHandle {
+	/// Start running `tasks` on the current tokio runtime
+	pub(crate) fn start(tasks: impl IntoIterator<Item = PeriodicTask>) -> Self {
+		let mut set = JoinSet::new();
+		for task in tasks {
+			set.spawn(task.run_forever());
+		}
+		Self { tasks: set }
+	}
+
+	/// The number of tasks this handle runs
+	#[inline(always)]
+	pub fn len(&self) -> usize {
+		self.tasks.len()
+	}
+
+	/// Returns true if this handle runs no tasks
+	#[inline(always)]
+	pub fn is_empty(&self) -> bool {
+		self.tasks.is_empty()
+	}
+
+	/// Stop all tasks. This is the same as dropping this handle.
+	#[inline(always)]
+	pub fn stop(self) {}
+}
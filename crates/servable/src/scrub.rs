@@ -0,0 +1,108 @@
+use axum::http::{HeaderMap, HeaderName, HeaderValue, header};
+
+use crate::normalize::has_route_prefix;
+
+/// Headers that are removed or rewritten in every response
+/// sent by a [crate::ServableRouter], see [crate::ServableRouter::with_header_scrub].
+///
+/// Unlike [crate::SecurityHeaders], this overrides headers set by servables.
+/// Use it to hide headers that leak implementation details,
+/// like `Server`, `X-Powered-By` or internal debug headers.
+///
+/// ```rust
+/// use servable::{HeaderScrub, HtmlPage, ServableRouter};
+/// use axum::http::{HeaderName, HeaderValue, header};
+///
+/// let scrub = HeaderScrub::default()
+/// 	.with_remove(HeaderName::from_static("x-debug-timing"))
+/// 	.with_rewrite(header::SERVER, HeaderValue::from_static("servable"))
+/// 	// Keep debug headers on internal pages
+/// 	.with_exception("/internal", HeaderName::from_static("x-debug-timing"));
+///
+/// let router = ServableRouter::new()
+/// 	.add_page("/", HtmlPage::default())
+/// 	.with_header_scrub(scrub);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeaderScrub {
+	/// Headers that are removed from every response.
+	/// Headers in [Self::rewrite] are never removed.
+	pub remove: Vec<HeaderName>,
+
+	/// Headers that are replaced with the given value if a response contains them
+	pub rewrite: Vec<(HeaderName, HeaderValue)>,
+
+	/// `(route prefix, header)` pairs.
+	/// Responses to routes that start with `route prefix` keep `header` unchanged.
+	///
+	/// A prefix matches whole path segments:
+	/// `/internal` matches `/internal` and `/internal/x`, but not `/internals`.
+	pub exceptions: Vec<(String, HeaderName)>,
+}
+
+impl Default for HeaderScrub {
+	/// Removes `Server` and `X-Powered-By`
+	fn default() -> Self {
+		Self {
+			remove: vec![header::SERVER, HeaderName::from_static("x-powered-by")],
+			rewrite: Vec::new(),
+			exceptions: Vec::new(),
+		}
+	}
+}
+
+impl HeaderScrub {
+	/// A [HeaderScrub] that doesn't change any headers
+	pub const fn none() -> Self {
+		Self {
+			remove: Vec::new(),
+			rewrite: Vec::new(),
+			exceptions: Vec::new(),
+		}
+	}
+
+	/// Remove `name` from every response
+	#[inline(always)]
+	pub fn with_remove(mut self, name: HeaderName) -> Self {
+		self.remove.push(name);
+		self
+	}
+
+	/// Replace the value of `name` with `value` in every response that contains it
+	#[inline(always)]
+	pub fn with_rewrite(mut self, name: HeaderName, value: HeaderValue) -> Self {
+		self.rewrite.push((name, value));
+		self
+	}
+
+	/// Don't remove or rewrite `name` in responses to routes under `route_prefix`
+	#[inline(always)]
+	pub fn with_exception(mut self, route_prefix: impl Into<String>, name: HeaderName) -> Self {
+		self.exceptions.push((route_prefix.into(), name));
+		self
+	}
+
+	/// Returns true if `name` may be changed in a response to `route`
+	fn applies(&self, route: &str, name: &HeaderName) -> bool {
+		!self
+			.exceptions
+			.iter()
+			.any(|(prefix, x)| x == name && has_route_prefix(route, prefix))
+	}
+
+	/// Remove and rewrite headers in a response to `route`
+	pub(crate) fn apply(&self, route: &str, headers: &mut HeaderMap) {
+		for name in &self.remove {
+			let rewritten = self.rewrite.iter().any(|(x, _)| x == name);
+			if !rewritten && self.applies(route, name) {
+				headers.remove(name);
+			}
+		}
+
+		for (name, value) in &self.rewrite {
+			if headers.contains_key(name) && self.applies(route, name) {
+				headers.insert(name, value.clone());
+			}
+		}
+	}
+}
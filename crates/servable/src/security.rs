@@ -0,0 +1,159 @@
+use axum::http::{HeaderMap, HeaderValue, header};
+use chrono::TimeDelta;
+
+/// Possible values of the `X-Frame-Options` header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameOptions {
+	/// Never allow this site to be shown in a frame.
+	/// Also sets `frame-ancestors 'none'`.
+	Deny,
+
+	/// Only allow this site to be framed by itself.
+	/// Also sets `frame-ancestors 'self'`.
+	SameOrigin,
+}
+
+/// Security headers added to every response
+/// sent by a [crate::ServableRouter].
+///
+/// A header is only added if the response does not already contain it,
+/// so any [crate::servable::Servable] may override these defaults.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecurityHeaders {
+	/// If true, send `X-Content-Type-Options: nosniff`
+	pub nosniff: bool,
+
+	/// The value of `Referrer-Policy`.
+	/// If `None`, this header is not sent.
+	pub referrer_policy: Option<HeaderValue>,
+
+	/// The value of `X-Frame-Options`.
+	/// If `None`, this header is not sent.
+	///
+	/// The matching `frame-ancestors` directive is sent in a
+	/// `Content-Security-Policy` header if the response's policies don't have one.
+	pub frame_options: Option<FrameOptions>,
+
+	/// The `max-age` of `Strict-Transport-Security`.
+	/// If `None`, this header is not sent.
+	///
+	/// Only enable this if this site is always served over https.
+	pub hsts: Option<TimeDelta>,
+
+	/// If true, add `includeSubDomains` to `Strict-Transport-Security`.
+	/// Does nothing if `hsts` is `None`.
+	pub hsts_include_subdomains: bool,
+}
+
+impl Default for SecurityHeaders {
+	fn default() -> Self {
+		Self {
+			nosniff: true,
+			referrer_policy: Some(HeaderValue::from_static("strict-origin-when-cross-origin")),
+			frame_options: Some(FrameOptions::SameOrigin),
+			hsts: None,
+			hsts_include_subdomains: false,
+		}
+	}
+}
+
+impl SecurityHeaders {
+	/// A [SecurityHeaders] that doesn't add any headers
+	pub const fn none() -> Self {
+		Self {
+			nosniff: false,
+			referrer_policy: None,
+			frame_options: None,
+			hsts: None,
+			hsts_include_subdomains: false,
+		}
+	}
+
+	/// Set `self.nosniff`
+	#[inline(always)]
+	pub fn with_nosniff(mut self, nosniff: bool) -> Self {
+		self.nosniff = nosniff;
+		self
+	}
+
+	/// Set `self.referrer_policy`
+	#[inline(always)]
+	pub fn with_referrer_policy(mut self, referrer_policy: Option<HeaderValue>) -> Self {
+		self.referrer_policy = referrer_policy;
+		self
+	}
+
+	/// Set `self.frame_options`
+	#[inline(always)]
+	pub fn with_frame_options(mut self, frame_options: Option<FrameOptions>) -> Self {
+		self.frame_options = frame_options;
+		self
+	}
+
+	/// Set `self.hsts` and `self.hsts_include_subdomains`
+	#[inline(always)]
+	pub fn with_hsts(mut self, max_age: Option<TimeDelta>, include_subdomains: bool) -> Self {
+		self.hsts = max_age;
+		self.hsts_include_subdomains = include_subdomains;
+		self
+	}
+
+	/// Add these headers to `headers`, skipping any that are already set.
+	pub(crate) fn apply(&self, headers: &mut HeaderMap) {
+		if self.nosniff && !headers.contains_key(header::X_CONTENT_TYPE_OPTIONS) {
+			headers.insert(
+				header::X_CONTENT_TYPE_OPTIONS,
+				HeaderValue::from_static("nosniff"),
+			);
+		}
+
+		if let Some(policy) = &self.referrer_policy
+			&& !headers.contains_key(header::REFERRER_POLICY)
+		{
+			headers.insert(header::REFERRER_POLICY, policy.clone());
+		}
+
+		if let Some(frame) = self.frame_options {
+			if !headers.contains_key(header::X_FRAME_OPTIONS) {
+				headers.insert(
+					header::X_FRAME_OPTIONS,
+					HeaderValue::from_static(match frame {
+						FrameOptions::Deny => "DENY",
+						FrameOptions::SameOrigin => "SAMEORIGIN",
+					}),
+				);
+			}
+
+			// Other policies may be sent alongside this one, see [crate::CspNonce]
+			let has_ancestors = headers
+				.get_all(header::CONTENT_SECURITY_POLICY)
+				.iter()
+				.any(|x| x.to_str().is_ok_and(|x| x.contains("frame-ancestors")));
+			if !has_ancestors {
+				headers.append(
+					header::CONTENT_SECURITY_POLICY,
+					HeaderValue::from_static(match frame {
+						FrameOptions::Deny => "frame-ancestors 'none'",
+						FrameOptions::SameOrigin => "frame-ancestors 'self'",
+					}),
+				);
+			}
+		}
+
+		if let Some(max_age) = self.hsts
+			&& !headers.contains_key(header::STRICT_TRANSPORT_SECURITY)
+		{
+			let max_age = max_age.num_seconds().max(0);
+			let value = match self.hsts_include_subdomains {
+				true => format!("max-age={max_age}; includeSubDomains"),
+				false => format!("max-age={max_age}"),
+			};
+
+			#[expect(clippy::unwrap_used)]
+			headers.insert(
+				header::STRICT_TRANSPORT_SECURITY,
+				HeaderValue::from_str(&value).unwrap(),
+			);
+		}
+	}
+}
@@ -0,0 +1,225 @@
+use axum::{
+	body::Bytes,
+	http::{HeaderValue, Method, Request, Uri, header},
+};
+use http_body_util::Full;
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use hyper_util::{
+	client::legacy::{Client, connect::HttpConnector},
+	rt::TokioExecutor,
+};
+use rand::Rng;
+use serde_json::json;
+use std::{fmt::Debug, sync::Arc};
+use tokio::sync::Semaphore;
+use tracing::warn;
+
+use crate::{Error, ErrorReport, ErrorReporter};
+
+/// An [ErrorReporter] that sends failed requests to a Sentry-compatible
+/// error tracker (Sentry, GlitchTip, a Sentry relay...) with its store api.
+///
+/// Events are sent in the background, and are dropped if they can't be sent.
+/// At most [SentryReporter::MAX_IN_FLIGHT] events are sent at once,
+/// events reported while that many are in flight are dropped.
+/// Each event includes the route, status code, request id, user agent,
+/// and every reported error. Cookies and other headers are never sent.
+///
+/// ```rust
+/// use servable::{HtmlPage, SentryReporter, ServableRouter};
+///
+/// let sentry = SentryReporter::new("http://public-key@127.0.0.1:3000/42")
+/// 	.unwrap()
+/// 	.with_environment("production");
+///
+/// let router = ServableRouter::new()
+/// 	.add_page("/", HtmlPage::default())
+/// 	.with_error_reporter(sentry);
+/// ```
+#[derive(Clone)]
+pub struct SentryReporter {
+	/// The url events are sent to
+	store: Uri,
+
+	/// The value of `X-Sentry-Auth`
+	auth: HeaderValue,
+
+	environment: Option<String>,
+	release: Option<String>,
+	client: Client<HttpsConnector<HttpConnector>, Full<Bytes>>,
+
+	/// Limits the number of events sent at once
+	in_flight: Arc<Semaphore>,
+}
+
+impl Debug for SentryReporter {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("SentryReporter")
+			.field("store", &self.store)
+			.field("environment", &self.environment)
+			.field("release", &self.release)
+			.finish()
+	}
+}
+
+impl SentryReporter {
+	/// The maximum number of events sent at once
+	pub const MAX_IN_FLIGHT: usize = 16;
+
+	/// Send events to the project with the given dsn,
+	/// like `https://public-key@o0.ingest.sentry.io/42`.
+	///
+	/// Returns an error if `dsn` is not an `http://` or `https://` url
+	/// with a key and a project id.
+	pub fn new(dsn: &str) -> Result<Self, Error> {
+		let invalid = |reason| Error::InvalidDsn {
+			dsn: dsn.to_owned(),
+			reason,
+		};
+
+		let (scheme, rest) = dsn
+			.split_once("://")
+			.filter(|(scheme, _)| *scheme == "http" || *scheme == "https")
+			.ok_or(invalid("dsn must be an http:// or https:// url"))?;
+		let (key, rest) = rest.split_once('@').ok_or(invalid("dsn has no key"))?;
+		let key = key.split(':').next().unwrap_or_default();
+		let (host, path) = rest.split_once('/').ok_or(invalid("dsn has no project"))?;
+		let (prefix, project) = match path.trim_end_matches('/').rsplit_once('/') {
+			Some((prefix, project)) => (format!("/{prefix}"), project),
+			None => (String::new(), path.trim_end_matches('/')),
+		};
+
+		if key.is_empty() || host.is_empty() {
+			return Err(invalid("dsn has no key"));
+		}
+		if project.is_empty() || !project.bytes().all(|x| x.is_ascii_digit()) {
+			return Err(invalid("dsn has no project"));
+		}
+
+		let store = format!("{scheme}://{host}{prefix}/api/{project}/store/")
+			.parse()
+			.map_err(|_err| invalid("dsn is not a valid url"))?;
+
+		let auth = format!(
+			"Sentry sentry_version=7, sentry_client=servable/{}, sentry_key={key}",
+			env!("CARGO_PKG_VERSION")
+		);
+		let auth = HeaderValue::from_str(&auth).map_err(|_err| invalid("invalid key"))?;
+
+		let connector = HttpsConnectorBuilder::new()
+			.with_provider_and_webpki_roots(Arc::new(rustls::crypto::ring::default_provider()))
+			.map_err(|_err| invalid("could not set up tls"))?
+			.https_or_http()
+			.enable_http1()
+			.build();
+
+		Ok(Self {
+			store,
+			auth,
+			environment: None,
+			release: None,
+			client: Client::builder(TokioExecutor::new()).build(connector),
+			in_flight: Arc::new(Semaphore::new(Self::MAX_IN_FLIGHT)),
+		})
+	}
+
+	/// Tag events with this environment, like `production`
+	#[inline(always)]
+	pub fn with_environment(mut self, environment: impl Into<String>) -> Self {
+		self.environment = Some(environment.into());
+		self
+	}
+
+	/// Tag events with this release, like a version or a commit hash
+	#[inline(always)]
+	pub fn with_release(mut self, release: impl Into<String>) -> Self {
+		self.release = Some(release.into());
+		self
+	}
+
+	/// The Sentry event that describes `report`
+	fn event(&self, report: &ErrorReport<'_>) -> serde_json::Value {
+		let ctx = report.ctx;
+		let event_id: String = (0..32)
+			.map(|_| char::from(b"0123456789abcdef"[rand::rng().random_range(0..16)]))
+			.collect();
+
+		let exceptions: Vec<serde_json::Value> = report
+			.errors
+			.iter()
+			.map(|err| {
+				// `Debug` usually starts with the name of the variant or struct
+				let debug = format!("{err:?}");
+				let ty = debug
+					.split(|c: char| !c.is_alphanumeric() && c != '_')
+					.next()
+					.filter(|x| !x.is_empty())
+					.unwrap_or("Error");
+				json!({ "type": ty, "value": err.to_string() })
+			})
+			.collect();
+
+		let user_agent = ctx
+			.headers
+			.get(header::USER_AGENT)
+			.and_then(|x| x.to_str().ok());
+
+		json!({
+			"event_id": event_id,
+			"timestamp": ctx.clock.now().timestamp_millis() as f64 / 1000.0,
+			"platform": "other",
+			"level": "error",
+			"logger": "servable",
+			"transaction": ctx.route,
+			"environment": self.environment,
+			"release": self.release,
+			"message": { "formatted": format!("{} on {}", report.code, ctx.route) },
+			"exception": { "values": exceptions },
+			"tags": {
+				"request_id": ctx.request_id,
+				"status_code": report.code.as_u16().to_string(),
+			},
+			"request": {
+				"url": ctx.absolute_url(&ctx.route),
+				"headers": { "User-Agent": user_agent },
+			},
+		})
+	}
+}
+
+impl ErrorReporter for SentryReporter {
+	fn report(&self, report: &ErrorReport<'_>) {
+		let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+			return;
+		};
+
+		let Ok(permit) = self.in_flight.clone().try_acquire_owned() else {
+			warn!(message = "Too many events in flight, dropping Sentry event");
+			return;
+		};
+
+		let Ok(body) = serde_json::to_vec(&self.event(report)) else {
+			return;
+		};
+
+		let req = Request::builder()
+			.method(Method::POST)
+			.uri(self.store.clone())
+			.header(header::CONTENT_TYPE, "application/json")
+			.header("X-Sentry-Auth", self.auth.clone())
+			.body(Full::new(Bytes::from(body)));
+		let Ok(req) = req else {
+			return;
+		};
+
+		let client = self.client.clone();
+		runtime.spawn(async move {
+			match client.request(req).await {
+				Ok(res) if res.status().is_success() => {}
+				Ok(res) => warn!(message = "Sentry rejected event", status = ?res.status()),
+				Err(error) => warn!(message = "Could not send event to Sentry", ?error),
+			}
+			drop(permit);
+		});
+	}
+}
@@ -1,15 +1,26 @@
-use axum::http::{HeaderMap, StatusCode};
+use axum::http::{HeaderMap, HeaderValue, StatusCode, header};
 use chrono::TimeDelta;
 use mime::Mime;
 use std::pin::Pin;
 
-use crate::{RenderContext, Rendered, RenderedBody, servable::Servable};
+use crate::{
+	RenderContext, Rendered, RenderedBody, encoding::ContentEncoding, router::cache_control,
+	servable::Servable,
+};
 
 /// A static blob of bytes
 pub struct StaticAsset {
 	/// The data to return
 	pub bytes: &'static [u8],
 
+	/// `bytes`, compressed with brotli.
+	/// If present, this is sent to clients that accept `br`.
+	pub br: Option<&'static [u8]>,
+
+	/// `bytes`, compressed with gzip.
+	/// If present, this is sent to clients that accept `gzip`.
+	pub gz: Option<&'static [u8]>,
+
 	/// The type of `bytes`
 	pub mime: Mime,
 	/// How long to cache this response.
@@ -26,6 +37,218 @@ impl StaticAsset {
 		self.ttl = ttl;
 		self
 	}
+
+	/// Set `self.br`
+	pub const fn with_br(mut self, br: &'static [u8]) -> Self {
+		self.br = Some(br);
+		self
+	}
+
+	/// Set `self.gz`
+	pub const fn with_gz(mut self, gz: &'static [u8]) -> Self {
+		self.gz = Some(gz);
+		self
+	}
+
+	/// A hash of `self.bytes`, which changes whenever they do.
+	/// Use it in routes to bust caches only when an asset changes:
+	/// ```rust
+	/// use servable::{ServableWithRoute, StaticAsset, asset};
+	///
+	/// static JS: StaticAsset = asset!(concat!(env!("CARGO_MANIFEST_DIR"), "/htmx/json-enc-1.9.12.js"));
+	/// const JS_HASH: u64 = JS.content_hash(); // Computed at compile time
+	///
+	/// pub static ROUTED: ServableWithRoute<&StaticAsset> =
+	/// 	ServableWithRoute::new(|| format!("/{JS_HASH:016x}/json-enc.js"), &JS);
+	/// ```
+	///
+	/// This is [crate::hash::content_hash_u64],
+	/// whose algorithm and format are stable (see [crate::hash]).
+	/// `{JS_HASH:016x}` is the same string as [crate::hash::content_hash].
+	pub const fn content_hash(&self) -> u64 {
+		crate::hash::content_hash_u64(self.bytes)
+	}
+
+	/// Infer the type of the file at `path` from its extension, like [Self::mime_from_extension].
+	pub const fn mime_from_path(path: &str) -> Option<Mime> {
+		let bytes = path.as_bytes();
+		let mut i = bytes.len();
+		while i > 0 {
+			i -= 1;
+			match bytes[i] {
+				b'.' => {
+					let (_, extension) = bytes.split_at(i + 1);
+					return match std::str::from_utf8(extension) {
+						Ok(extension) => Self::mime_from_extension(extension),
+						Err(_) => None,
+					};
+				}
+				b'/' | b'\\' => return None,
+				_ => {}
+			}
+		}
+		None
+	}
+
+	/// Infer the type of a file from its extension, like `png`, ignoring case.
+	/// This works in const contexts (see [crate::asset!]),
+	/// so it only knows the types that [mime] has a constant for.
+	/// Returns `None` for any other extension.
+	pub const fn mime_from_extension(extension: &str) -> Option<Mime> {
+		const fn is(extension: &str, x: &str) -> bool {
+			let (a, b) = (extension.as_bytes(), x.as_bytes());
+			if a.len() != b.len() {
+				return false;
+			}
+
+			let mut i = 0;
+			while i < a.len() {
+				if a[i].to_ascii_lowercase() != b[i] {
+					return false;
+				}
+				i += 1;
+			}
+			true
+		}
+
+		let e = extension;
+		Some(match () {
+			_ if is(e, "html") || is(e, "htm") => mime::TEXT_HTML_UTF_8,
+			_ if is(e, "css") => mime::TEXT_CSS_UTF_8,
+			_ if is(e, "js") || is(e, "mjs") => mime::TEXT_JAVASCRIPT,
+			_ if is(e, "json") || is(e, "map") => mime::APPLICATION_JSON,
+			_ if is(e, "txt") => mime::TEXT_PLAIN_UTF_8,
+			_ if is(e, "csv") => mime::TEXT_CSV_UTF_8,
+			_ if is(e, "xml") => mime::TEXT_XML,
+			_ if is(e, "pdf") => mime::APPLICATION_PDF,
+			_ if is(e, "png") => mime::IMAGE_PNG,
+			_ if is(e, "jpg") || is(e, "jpeg") => mime::IMAGE_JPEG,
+			_ if is(e, "gif") => mime::IMAGE_GIF,
+			_ if is(e, "bmp") => mime::IMAGE_BMP,
+			_ if is(e, "svg") => mime::IMAGE_SVG,
+			_ if is(e, "woff") => mime::FONT_WOFF,
+			_ if is(e, "woff2") => mime::FONT_WOFF2,
+			_ => return None,
+		})
+	}
+
+	/// The usual extension of files of type `mime`, without a dot, like `png`.
+	/// This is the inverse of [Self::mime_from_extension], and ignores parameters like `charset`.
+	/// Returns `None` for any other type.
+	pub fn extension_from_mime(mime: &Mime) -> Option<&'static str> {
+		Some(match (mime.type_().as_str(), mime.subtype().as_str()) {
+			("text", "html") => "html",
+			("text", "css") => "css",
+			("text" | "application", "javascript") => "js",
+			("application", "json") => "json",
+			("text", "plain") => "txt",
+			("text", "csv") => "csv",
+			("text" | "application", "xml") => "xml",
+			("application", "pdf") => "pdf",
+			("application", "zip") => "zip",
+			("image", "png") => "png",
+			("image", "jpeg") => "jpg",
+			("image", "gif") => "gif",
+			("image", "bmp") => "bmp",
+			("image", "svg") => "svg",
+			("font", "woff") => "woff",
+			("font", "woff2") => "woff2",
+			_ => return None,
+		})
+	}
+
+	/// Pick the body to send to the client that sent `ctx`,
+	/// using its `Accept-Encoding` header.
+	///
+	/// Returns the body and the headers that describe it.
+	fn encoded(&self, ctx: &RenderContext) -> (&'static [u8], HeaderMap) {
+		let available: &[ContentEncoding] = match (self.br, self.gz) {
+			(Some(_), Some(_)) => &[ContentEncoding::Brotli, ContentEncoding::Gzip],
+			(Some(_), None) => &[ContentEncoding::Brotli],
+			(None, Some(_)) => &[ContentEncoding::Gzip],
+			(None, None) => return (self.bytes, HeaderMap::new()),
+		};
+
+		let encoding =
+			ContentEncoding::negotiate(ctx.headers.get(header::ACCEPT_ENCODING), available);
+
+		let bytes = match encoding {
+			Some(ContentEncoding::Brotli) => self.br,
+			Some(ContentEncoding::Gzip) => self.gz,
+			_ => None,
+		};
+
+		let mut headers = HeaderMap::new();
+		match (encoding, bytes) {
+			(Some(encoding), Some(bytes)) => {
+				headers.insert(header::CONTENT_ENCODING, encoding.header_value());
+				(bytes, headers)
+			}
+			_ => (self.bytes, headers),
+		}
+	}
+
+	/// True if this asset has precompressed variants,
+	/// in which case responses depend on `Accept-Encoding`.
+	fn has_variants(&self) -> bool {
+		self.br.is_some() || self.gz.is_some()
+	}
+}
+
+/// A [StaticAsset] with the contents of the file at `path`,
+/// relative to the current file (like [include_bytes!]).
+///
+/// The type of the asset is inferred from its extension
+/// (see [StaticAsset::mime_from_extension]), and its ttl is [StaticAsset::DEFAULT_TTL].
+/// Files of other types don't compile, and need their type as a second argument.
+/// This is a constant expression, so it may be used in `const`s and `static`s,
+/// unless its type is given as a non-constant [mime::Mime].
+///
+/// ```rust
+/// use servable::{ServableRouter, StaticAsset, asset};
+///
+/// static JSON_ENC: StaticAsset = asset!(concat!(env!("CARGO_MANIFEST_DIR"), "/htmx/json-enc-1.9.12.js"));
+/// assert_eq!(JSON_ENC.mime, mime::TEXT_JAVASCRIPT);
+///
+/// let router = ServableRouter::new()
+/// 	.add_page("/json-enc.js", &JSON_ENC)
+/// 	// `.md` has no constant in `mime`
+/// 	.add_page(
+/// 		"/readme.md",
+/// 		asset!(
+/// 			concat!(env!("CARGO_MANIFEST_DIR"), "/README.md"),
+/// 			"text/markdown".parse().unwrap(),
+/// 		),
+/// 	);
+/// ```
+///
+/// Use [StaticAsset::content_hash] to add a hash of the file to its route.
+#[macro_export]
+macro_rules! asset {
+	($path:expr $(,)?) => {{
+		const ASSET: $crate::StaticAsset = $crate::StaticAsset {
+			bytes: ::std::include_bytes!($path),
+			br: ::std::option::Option::None,
+			gz: ::std::option::Option::None,
+			mime: $crate::StaticAsset::mime_from_path($path).expect(::std::concat!(
+				"could not infer the type of `",
+				$path,
+				"`, use `asset!(path, mime)`"
+			)),
+			ttl: $crate::StaticAsset::DEFAULT_TTL,
+		};
+		ASSET
+	}};
+
+	($path:expr, $mime:expr $(,)?) => {
+		$crate::StaticAsset {
+			bytes: ::std::include_bytes!($path),
+			br: ::std::option::Option::None,
+			gz: ::std::option::Option::None,
+			mime: $mime,
+			ttl: $crate::StaticAsset::DEFAULT_TTL,
+		}
+	};
 }
 
 #[cfg(feature = "image")]
@@ -35,12 +258,15 @@ impl Servable for StaticAsset {
 		ctx: &'a RenderContext,
 	) -> Pin<Box<dyn Future<Output = Rendered<()>> + 'a + Send + Sync>> {
 		Box::pin(async {
-			use crate::transform::TransformerChain;
+			use crate::{
+				cachestatus::CacheStatus,
+				transform::{TRANSFORM_CACHE, TransformerChain},
+			};
 			use std::str::FromStr;
 
 			let is_image = TransformerChain::mime_is_image(&self.mime);
 
-			let transform = match (is_image, ctx.query.get("t")) {
+			let transform = match (is_image && ctx.transforms, ctx.query.get("t")) {
 				(false, _) | (_, None) => None,
 
 				(true, Some(x)) => match TransformerChain::from_str(x) {
@@ -54,6 +280,7 @@ impl Servable for StaticAsset {
 
 							headers: HeaderMap::new(),
 							mime: None,
+							content_length: None,
 						};
 					}
 				},
@@ -61,30 +288,44 @@ impl Servable for StaticAsset {
 
 			match transform {
 				Some(transform) => {
+					// We only know the size of images we've already transformed
+					let cached = TRANSFORM_CACHE.get(self.bytes, &transform.to_string());
+
+					let mut headers = HeaderMap::new();
+					match cached {
+						Some(_) => CacheStatus::Hit { ttl: None },
+						None => CacheStatus::Miss { stored: false },
+					}
+					.append_to(&mut headers);
+					ctx.debug_header(&mut headers, "x-transform-chain", &transform.to_string());
+
 					return Rendered {
 						code: StatusCode::OK,
 						body: (),
 						ttl: self.ttl,
 						private: false,
 
-						headers: HeaderMap::new(),
+						headers,
 						mime: Some(
 							transform
 								.output_mime(&self.mime)
 								.unwrap_or(self.mime.clone()),
 						),
+						content_length: cached.map(|x| x.1.len() as u64),
 					};
 				}
 
 				None => {
+					let (bytes, headers) = self.encoded(ctx);
 					return Rendered {
 						code: StatusCode::OK,
 						body: (),
 						ttl: self.ttl,
 						private: false,
 
-						headers: HeaderMap::new(),
+						headers,
 						mime: Some(self.mime.clone()),
+						content_length: Some(bytes.len() as u64),
 					};
 				}
 			}
@@ -96,27 +337,38 @@ impl Servable for StaticAsset {
 		ctx: &'a RenderContext,
 	) -> Pin<Box<dyn Future<Output = Rendered<RenderedBody>> + 'a + Send + Sync>> {
 		Box::pin(async {
-			use crate::transform::TransformerChain;
-			use std::str::FromStr;
+			use crate::{
+				Error, RenderMode,
+				cachestatus::CacheStatus,
+				transform::{TRANSFORM_CACHE, TransformerChain},
+			};
+			use axum::body::Bytes;
+			use std::{str::FromStr, sync::Arc};
 			use tracing::{error, trace};
 
+			if ctx.mode == RenderMode::Head {
+				return self.head(ctx).await.with_body(RenderedBody::Empty);
+			}
+
 			// Automatically provide transformation if this is an image
 			let is_image = TransformerChain::mime_is_image(&self.mime);
 
-			let transform = match (is_image, ctx.query.get("t")) {
+			let transform = match (is_image && ctx.transforms, ctx.query.get("t")) {
 				(false, _) | (_, None) => None,
 
 				(true, Some(x)) => match TransformerChain::from_str(x) {
 					Ok(x) => Some(x),
 					Err(err) => {
+						let err = Error::InvalidTransform(err);
 						return Rendered {
-							code: StatusCode::BAD_REQUEST,
-							body: RenderedBody::String(err),
+							code: err.status_code(),
+							body: RenderedBody::String(err.to_string()),
 							ttl: self.ttl,
 							private: false,
 
 							headers: HeaderMap::new(),
 							mime: None,
+							content_length: None,
 						};
 					}
 				},
@@ -124,6 +376,27 @@ impl Servable for StaticAsset {
 
 			match transform {
 				Some(transform) => {
+					// Range requests are applied to the transformed image.
+					// Clients usually send many of these for one image,
+					// so we cache transformed images.
+					let key = transform.to_string();
+					if let Some(image) = TRANSFORM_CACHE.get(self.bytes, &key) {
+						trace!(message = "Using cached image", ?transform);
+						let mut headers = HeaderMap::new();
+						CacheStatus::Hit { ttl: None }.append_to(&mut headers);
+						ctx.debug_header(&mut headers, "x-transform-chain", &key);
+						return Rendered {
+							code: StatusCode::OK,
+							body: RenderedBody::Chunks(vec![image.1.clone()]),
+							ttl: self.ttl,
+							private: false,
+
+							headers,
+							mime: Some(image.0.clone()),
+							content_length: None,
+						};
+					}
+
 					trace!(message = "Transforming image", ?transform);
 
 					let task = {
@@ -138,78 +411,117 @@ impl Servable for StaticAsset {
 						Ok(x) => x,
 						Err(error) => {
 							error!(message = "Error while transforming image", ?error);
+							let body = format!("Error while transforming image: {error:?}");
+							ctx.report_error(error);
 							return Rendered {
 								code: StatusCode::INTERNAL_SERVER_ERROR,
-								body: RenderedBody::String(format!(
-									"Error while transforming image: {error:?}"
-								)),
+								body: RenderedBody::String(body),
 								ttl: None,
 								private: false,
 
 								headers: HeaderMap::new(),
 								mime: None,
+								content_length: None,
 							};
 						}
 					};
 
 					match res {
 						Ok((mime, bytes)) => {
+							let bytes = Bytes::from(bytes);
+							let stored = TRANSFORM_CACHE.insert(
+								self.bytes,
+								&key,
+								Arc::new((mime.clone(), bytes.clone())),
+							);
+
+							let mut headers = HeaderMap::new();
+							CacheStatus::Miss { stored }.append_to(&mut headers);
+							ctx.debug_header(&mut headers, "x-transform-chain", &key);
 							return Rendered {
 								code: StatusCode::OK,
-								body: RenderedBody::Bytes(bytes),
+								body: RenderedBody::Chunks(vec![bytes]),
 								ttl: self.ttl,
 								private: false,
 
-								headers: HeaderMap::new(),
+								headers,
 								mime: Some(mime),
+								content_length: None,
 							};
 						}
 
 						Err(err) => {
+							let err = Error::from(err);
+							let (code, body) = (err.status_code(), err.to_string());
+							ctx.report_error(err);
 							return Rendered {
-								code: StatusCode::INTERNAL_SERVER_ERROR,
-								body: RenderedBody::String(format!("{err}")),
+								code,
+								body: RenderedBody::String(body),
 								ttl: self.ttl,
 								private: false,
 
 								headers: HeaderMap::new(),
 								mime: None,
+								content_length: None,
 							};
 						}
 					}
 				}
 
 				None => {
+					let (bytes, headers) = self.encoded(ctx);
 					return Rendered {
 						code: StatusCode::OK,
-						body: RenderedBody::Static(self.bytes),
+						body: RenderedBody::Static(bytes),
 						ttl: self.ttl,
 						private: false,
 
-						headers: HeaderMap::new(),
+						headers,
 						mime: Some(self.mime.clone()),
+						content_length: None,
 					};
 				}
 			}
 		})
 	}
+
+	fn header_template(&self) -> HeaderMap {
+		use crate::transform::TransformerChain;
+
+		let mut headers = HeaderMap::with_capacity(3);
+		headers.insert(header::CACHE_CONTROL, cache_control(self.ttl, false));
+		if self.has_variants() {
+			headers.insert(header::VARY, HeaderValue::from_static("accept-encoding"));
+		}
+
+		// Transformed images may change type
+		if !TransformerChain::mime_is_image(&self.mime)
+			&& let Ok(mime) = HeaderValue::from_str(self.mime.as_ref())
+		{
+			headers.insert(header::CONTENT_TYPE, mime);
+		}
+
+		headers
+	}
 }
 
 #[cfg(not(feature = "image"))]
 impl Servable for StaticAsset {
 	fn head<'a>(
 		&'a self,
-		_ctx: &'a RenderContext,
+		ctx: &'a RenderContext,
 	) -> Pin<Box<dyn Future<Output = Rendered<()>> + 'a + Send + Sync>> {
 		Box::pin(async {
+			let (bytes, headers) = self.encoded(ctx);
 			return Rendered {
 				code: StatusCode::OK,
 				body: (),
 				ttl: self.ttl,
 				private: false,
 
-				headers: HeaderMap::new(),
+				headers,
 				mime: Some(self.mime.clone()),
+				content_length: Some(bytes.len() as u64),
 			};
 		})
 	}
@@ -219,9 +531,20 @@ impl Servable for StaticAsset {
 		ctx: &'a RenderContext,
 	) -> Pin<Box<dyn Future<Output = Rendered<RenderedBody>> + 'a + Send + Sync>> {
 		Box::pin(async {
-			self.head(ctx)
-				.await
-				.with_body(RenderedBody::Static(self.bytes))
+			let (bytes, _) = self.encoded(ctx);
+			self.head(ctx).await.with_body(RenderedBody::Static(bytes))
 		})
 	}
+
+	fn header_template(&self) -> HeaderMap {
+		let mut headers = HeaderMap::with_capacity(3);
+		headers.insert(header::CACHE_CONTROL, cache_control(self.ttl, false));
+		if self.has_variants() {
+			headers.insert(header::VARY, HeaderValue::from_static("accept-encoding"));
+		}
+		if let Ok(mime) = HeaderValue::from_str(self.mime.as_ref()) {
+			headers.insert(header::CONTENT_TYPE, mime);
+		}
+		headers
+	}
 }
@@ -0,0 +1,175 @@
+use axum::http::{HeaderMap, StatusCode, header};
+use chrono::TimeDelta;
+use mime::Mime;
+use std::pin::Pin;
+
+use crate::{
+	Error, RenderContext, RenderMode, Rendered, RenderedBody, StaticAsset, router::cache_control,
+	servable::Servable,
+};
+
+/// Many [StaticAsset]s of the same type, served as one response.
+/// Use this to send many small stylesheets or scripts in one request.
+///
+/// Sources are joined in order, separated by a newline.
+/// Scripts are also separated by a `;`, so that a source
+/// without a trailing semicolon doesn't run into the next one.
+///
+/// Bundles are never compressed ahead of time, even if their sources are.
+/// Use [crate::ServableRouter::with_compression] to compress them.
+///
+/// ```rust
+/// use servable::{BundleAsset, ServableRouter, StaticAsset};
+///
+/// static RESET: StaticAsset = StaticAsset {
+/// 	bytes: b"* { margin: 0; }",
+/// 	br: None,
+/// 	gz: None,
+/// 	mime: mime::TEXT_CSS,
+/// 	ttl: StaticAsset::DEFAULT_TTL,
+/// };
+///
+/// static SITE: StaticAsset = StaticAsset {
+/// 	bytes: b"body { color: black; }",
+/// 	br: None,
+/// 	gz: None,
+/// 	mime: mime::TEXT_CSS,
+/// 	ttl: StaticAsset::DEFAULT_TTL,
+/// };
+///
+/// let bundle = BundleAsset::new(&[&RESET, &SITE]);
+/// let route = format!("/{:016x}/bundle.css", bundle.content_hash());
+///
+/// let router = ServableRouter::new().add_page(route, bundle);
+/// ```
+pub struct BundleAsset {
+	bytes: Vec<u8>,
+	mime: Mime,
+
+	/// How long to cache this response.
+	/// If None, never cache.
+	/// The default is [StaticAsset::DEFAULT_TTL].
+	pub ttl: Option<TimeDelta>,
+}
+
+impl BundleAsset {
+	/// Join `sources` into one asset.
+	/// - panics if `sources` is empty
+	/// - panics if `sources` have different types
+	///
+	/// See [Self::try_new] for a version of this method that does not panic.
+	pub fn new(sources: &[&StaticAsset]) -> Self {
+		match Self::try_new(sources) {
+			Ok(x) => x,
+			Err(err) => panic!("{err}"),
+		}
+	}
+
+	/// Join `sources` into one asset.
+	/// Behaves exactly like [Self::new], but returns an [Error] instead of panicking.
+	pub fn try_new(sources: &[&StaticAsset]) -> Result<Self, Error> {
+		let Some(first) = sources.first() else {
+			return Err(Error::InvalidBundle {
+				reason: "bundle has no sources",
+			});
+		};
+
+		if sources
+			.iter()
+			.any(|x| x.mime.essence_str() != first.mime.essence_str())
+		{
+			return Err(Error::InvalidBundle {
+				reason: "bundle sources have different types",
+			});
+		}
+
+		let separator = Self::separator(&first.mime);
+		let mut bytes = Vec::with_capacity(
+			sources
+				.iter()
+				.map(|x| x.bytes.len() + separator.len())
+				.sum(),
+		);
+		for (i, source) in sources.iter().enumerate() {
+			if i != 0 {
+				bytes.extend_from_slice(separator);
+			}
+			bytes.extend_from_slice(source.bytes);
+		}
+
+		Ok(Self {
+			bytes,
+			mime: first.mime.clone(),
+			ttl: StaticAsset::DEFAULT_TTL,
+		})
+	}
+
+	/// Set `self.ttl`
+	#[inline(always)]
+	pub fn with_ttl(mut self, ttl: Option<TimeDelta>) -> Self {
+		self.ttl = ttl;
+		self
+	}
+
+	/// The type of this bundle, which is the type of its sources
+	pub fn mime(&self) -> &Mime {
+		&self.mime
+	}
+
+	/// The joined bytes of this bundle
+	pub fn bytes(&self) -> &[u8] {
+		&self.bytes
+	}
+
+	/// A hash of this bundle's joined bytes, which changes whenever any source does.
+	/// See [StaticAsset::content_hash] and [crate::hash].
+	pub fn content_hash(&self) -> u64 {
+		crate::hash::content_hash_u64(&self.bytes)
+	}
+
+	/// The bytes placed between sources of type `mime`
+	fn separator(mime: &Mime) -> &'static [u8] {
+		match StaticAsset::extension_from_mime(mime) {
+			Some("js") => b"\n;\n",
+			_ => b"\n",
+		}
+	}
+}
+
+impl Servable for BundleAsset {
+	fn head<'a>(
+		&'a self,
+		_ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<()>> + 'a + Send + Sync>> {
+		Box::pin(async {
+			Rendered {
+				code: StatusCode::OK,
+				body: (),
+				ttl: self.ttl,
+				private: false,
+				headers: HeaderMap::new(),
+				mime: Some(self.mime.clone()),
+				content_length: Some(self.bytes.len() as u64),
+			}
+		})
+	}
+
+	fn render<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<RenderedBody>> + 'a + Send + Sync>> {
+		Box::pin(async {
+			let rend = self.head(ctx).await;
+			match ctx.mode {
+				RenderMode::Head => rend.with_body(RenderedBody::Empty),
+				_ => rend.with_body(RenderedBody::Bytes(self.bytes.clone())),
+			}
+		})
+	}
+
+	fn header_template(&self) -> HeaderMap {
+		let mut headers = HeaderMap::with_capacity(1);
+		headers.insert(header::CACHE_CONTROL, cache_control(self.ttl, false));
+		headers
+	}
+}
@@ -0,0 +1,227 @@
+use chrono::TimeDelta;
+use mime::Mime;
+use std::{collections::HashMap, io::ErrorKind, path::PathBuf, pin::Pin};
+
+use crate::{
+	Error, FileAsset, RenderContext, Rendered, RenderedBody, StaticAsset, servable::Servable,
+};
+
+/// A [Servable] that serves the files in a directory on disk.
+///
+/// Mount it at a route with a catch-all parameter named `path`, like `/static/{*path}`:
+/// `/static/css/site.css` is then served from `{root}/css/site.css`.
+/// Files are read when they are requested, like [FileAsset].
+///
+/// - the type of each file is inferred from its extension
+/// - requests for a directory are answered with its `index.html`, if it has one
+/// - requests can't leave `root`, not even through `..` or symlinks
+/// - hidden files (like `.env`) are never served, unless [DirServable::with_hidden] is set
+///
+/// Anything else is answered with `404 Not Found`.
+///
+/// ```rust
+/// use servable::{DirServable, ServableRouter};
+/// use chrono::TimeDelta;
+///
+/// let dir = DirServable::new("./static")
+/// 	// Fonts never change
+/// 	.with_ttl_for("woff2", Some(TimeDelta::days(365)))
+/// 	.with_ttl_for("html", None);
+///
+/// let router = ServableRouter::new()
+/// 	// Serve `./static/index.html` at `/static`
+/// 	.add_page("/static", dir.clone())
+/// 	.add_page("/static/{*path}", dir);
+/// ```
+#[derive(Debug, Clone)]
+pub struct DirServable {
+	/// The directory to serve
+	root: PathBuf,
+
+	/// The name of the route parameter with the path of the requested file
+	param: String,
+
+	/// The file served for requests for a directory
+	index: Option<String>,
+	hidden: bool,
+
+	ttl: Option<TimeDelta>,
+
+	/// Ttls of files with these extensions, lowercase
+	ttls: HashMap<String, Option<TimeDelta>>,
+}
+
+impl DirServable {
+	/// Serve the files in `root`
+	pub fn new(root: impl Into<PathBuf>) -> Self {
+		Self {
+			root: root.into(),
+			param: "path".into(),
+			index: Some("index.html".into()),
+			hidden: false,
+			ttl: FileAsset::DEFAULT_TTL,
+			ttls: HashMap::new(),
+		}
+	}
+
+	/// Read the path of the requested file from the route parameter named `param`.
+	/// The default is `path`, as in `/static/{*path}`.
+	#[inline(always)]
+	pub fn with_param(mut self, param: impl Into<String>) -> Self {
+		self.param = param.into();
+		self
+	}
+
+	/// Set the file served for requests for a directory.
+	/// If `None`, directories are not found. The default is `index.html`.
+	#[inline(always)]
+	pub fn with_index(mut self, index: Option<String>) -> Self {
+		self.index = index;
+		self
+	}
+
+	/// If true, serve files and directories whose names start with a `.`
+	#[inline(always)]
+	pub fn with_hidden(mut self, hidden: bool) -> Self {
+		self.hidden = hidden;
+		self
+	}
+
+	/// Set how long to cache files.
+	/// The default is [FileAsset::DEFAULT_TTL].
+	#[inline(always)]
+	pub fn with_ttl(mut self, ttl: Option<TimeDelta>) -> Self {
+		self.ttl = ttl;
+		self
+	}
+
+	/// Set how long to cache files with the extension `extension`, like `woff2`.
+	/// This overrides [Self::with_ttl].
+	#[inline(always)]
+	pub fn with_ttl_for(mut self, extension: &str, ttl: Option<TimeDelta>) -> Self {
+		self.ttls.insert(extension.to_ascii_lowercase(), ttl);
+		self
+	}
+
+	/// Find the file requested by `ctx`
+	async fn resolve(&self, ctx: &RenderContext) -> Result<FileAsset, Error> {
+		let not_found = || Error::Io(ErrorKind::NotFound.into());
+
+		let mut path = self.root.clone();
+		let rel = ctx
+			.params
+			.get(&self.param)
+			.map(String::as_str)
+			.unwrap_or("");
+		for segment in rel.split('/').filter(|x| !x.is_empty()) {
+			let segment = percent_decode(segment).ok_or_else(not_found)?;
+			let valid = segment != ".."
+				&& segment != "."
+				&& !segment.contains(['/', '\\', '\0'])
+				&& (self.hidden || !segment.starts_with('.'));
+			if !valid {
+				return Err(not_found());
+			}
+			path.push(segment);
+		}
+
+		if tokio::fs::metadata(&path).await?.is_dir() {
+			let index = self.index.as_ref().ok_or_else(not_found)?;
+			path.push(index);
+		}
+
+		// Symlinks may point outside of root
+		let root = tokio::fs::canonicalize(&self.root).await?;
+		if !tokio::fs::canonicalize(&path).await?.starts_with(&root) {
+			return Err(not_found());
+		}
+
+		let extension = path
+			.extension()
+			.and_then(|x| x.to_str())
+			.map(|x| x.to_ascii_lowercase())
+			.unwrap_or_default();
+
+		let mime = mime_from_extension(&extension);
+		Ok(FileAsset::new(path, mime)
+			.with_ttl(self.ttls.get(&extension).copied().unwrap_or(self.ttl)))
+	}
+}
+
+impl Servable for DirServable {
+	fn head<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<()>> + 'a + Send + Sync>> {
+		Box::pin(async {
+			match self.resolve(ctx).await {
+				Ok(file) => file.head(ctx).await,
+				Err(err) => FileAsset::error(ctx, &self.root, err, ()),
+			}
+		})
+	}
+
+	fn render<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<RenderedBody>> + 'a + Send + Sync>> {
+		Box::pin(async {
+			match self.resolve(ctx).await {
+				Ok(file) => file.render(ctx).await,
+				Err(err) => FileAsset::error(ctx, &self.root, err, RenderedBody::Empty),
+			}
+		})
+	}
+}
+
+/// Decode a percent-encoded path segment.
+/// Returns `None` if it is invalid or not UTF-8.
+fn percent_decode(s: &str) -> Option<String> {
+	let bytes = s.as_bytes();
+	let mut out = Vec::with_capacity(bytes.len());
+	let mut i = 0;
+	while i < bytes.len() {
+		match bytes[i] {
+			b'%' => {
+				let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+				out.push(u8::from_str_radix(hex, 16).ok()?);
+				i += 3;
+			}
+			x => {
+				out.push(x);
+				i += 1;
+			}
+		}
+	}
+	String::from_utf8(out).ok()
+}
+
+/// Guess the type of a file from its lowercase extension.
+/// Unknown files are `application/octet-stream`.
+#[expect(clippy::unwrap_used)]
+pub(crate) fn mime_from_extension(extension: &str) -> Mime {
+	if let Some(mime) = StaticAsset::mime_from_extension(extension) {
+		return mime;
+	}
+
+	match extension {
+		"md" => "text/markdown; charset=utf-8".parse().unwrap(),
+		"wasm" => "application/wasm".parse().unwrap(),
+		"zip" => "application/zip".parse().unwrap(),
+
+		"webp" => "image/webp".parse().unwrap(),
+		"avif" => "image/avif".parse().unwrap(),
+		"ico" => "image/x-icon".parse().unwrap(),
+
+		"ttf" => "font/ttf".parse().unwrap(),
+		"otf" => "font/otf".parse().unwrap(),
+
+		"mp3" => "audio/mpeg".parse().unwrap(),
+		"ogg" => "audio/ogg".parse().unwrap(),
+		"wav" => "audio/wav".parse().unwrap(),
+		"mp4" => "video/mp4".parse().unwrap(),
+		"webm" => "video/webm".parse().unwrap(),
+
+		_ => mime::APPLICATION_OCTET_STREAM,
+	}
+}
@@ -0,0 +1,197 @@
+use axum::{
+	body::Bytes,
+	http::{HeaderMap, HeaderValue, StatusCode, header},
+};
+use chrono::TimeDelta;
+use mime::Mime;
+use std::{
+	path::{Path, PathBuf},
+	pin::Pin,
+};
+use tracing::error;
+
+use crate::{
+	Error, RenderContext, RenderMode, Rendered, RenderedBody, router::cache_control,
+	servable::Servable,
+};
+
+/// A file on disk, read when it is requested.
+///
+/// Unlike [crate::StaticAsset], the file is read with every request,
+/// so changes on disk are served without a rebuild.
+/// Use [FileAsset::with_preload] to read it once at startup instead.
+///
+/// Missing files are answered with `404 Not Found`,
+/// and files we can't read with `500 Internal Server Error`.
+///
+/// ```rust
+/// use servable::{FileAsset, ServableRouter};
+///
+/// let router = ServableRouter::new()
+/// 	.add_page("/robots.txt", FileAsset::new("static/robots.txt", mime::TEXT_PLAIN));
+/// ```
+#[derive(Debug, Clone)]
+pub struct FileAsset {
+	/// The file to serve
+	pub path: PathBuf,
+
+	/// The type of this file
+	pub mime: Mime,
+
+	/// How long to cache this response.
+	/// If None, never cache
+	pub ttl: Option<TimeDelta>,
+
+	/// If `Some`, this is served instead of reading `path`.
+	/// See [FileAsset::with_preload].
+	pub preloaded: Option<Bytes>,
+}
+
+impl FileAsset {
+	/// Default ttl of a [FileAsset].
+	/// This is shorter than [crate::StaticAsset::DEFAULT_TTL], since files may change.
+	pub const DEFAULT_TTL: Option<TimeDelta> = Some(TimeDelta::minutes(10));
+
+	/// Serve the file at `path`, which has type `mime`
+	pub fn new(path: impl Into<PathBuf>, mime: Mime) -> Self {
+		Self {
+			path: path.into(),
+			mime,
+			ttl: Self::DEFAULT_TTL,
+			preloaded: None,
+		}
+	}
+
+	/// Set `self.ttl`
+	#[inline(always)]
+	pub fn with_ttl(mut self, ttl: Option<TimeDelta>) -> Self {
+		self.ttl = ttl;
+		self
+	}
+
+	/// Read this file now, and serve these contents instead of reading it on every request.
+	/// - panics if the file could not be read
+	///
+	/// See [Self::try_with_preload] for a version of this method that does not panic.
+	pub fn with_preload(self) -> Self {
+		match self.try_with_preload() {
+			Ok(x) => x,
+			Err(err) => panic!("{err}"),
+		}
+	}
+
+	/// Read this file now, and serve these contents instead of reading it on every request.
+	/// Behaves exactly like [Self::with_preload], but returns an [Error] instead of panicking.
+	pub fn try_with_preload(mut self) -> Result<Self, Error> {
+		self.preloaded = Some(Bytes::from(std::fs::read(&self.path)?));
+		Ok(self)
+	}
+
+	/// A response for `err`, which happened while reading the file at `path`
+	pub(super) fn error<T: crate::RenderedBodyType>(
+		ctx: &RenderContext,
+		path: &Path,
+		err: Error,
+		body: T,
+	) -> Rendered<T> {
+		if err.status_code().is_server_error() {
+			error!(
+				message = "Could not read file",
+				route = ctx.route,
+				?path,
+				?err
+			);
+		}
+
+		let code = err.status_code();
+		ctx.report_error(err);
+		Rendered {
+			code,
+			body,
+			ttl: None,
+			private: false,
+			headers: HeaderMap::new(),
+			mime: None,
+			content_length: None,
+		}
+	}
+
+	/// A successful response
+	fn ok<T: crate::RenderedBodyType>(&self, body: T, len: u64) -> Rendered<T> {
+		Rendered {
+			code: StatusCode::OK,
+			body,
+			ttl: self.ttl,
+			private: false,
+			headers: HeaderMap::new(),
+			mime: Some(self.mime.clone()),
+			content_length: Some(len),
+		}
+	}
+}
+
+impl Servable for FileAsset {
+	fn head<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<()>> + 'a + Send + Sync>> {
+		Box::pin(async {
+			if let Some(bytes) = &self.preloaded {
+				return self.ok((), bytes.len() as u64);
+			}
+
+			match tokio::fs::metadata(&self.path).await {
+				Ok(x) if x.is_file() => self.ok((), x.len()),
+				Ok(_) => Self::error(
+					ctx,
+					&self.path,
+					Error::Io(std::io::ErrorKind::NotFound.into()),
+					(),
+				),
+				Err(err) => Self::error(ctx, &self.path, err.into(), ()),
+			}
+		})
+	}
+
+	fn render<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<RenderedBody>> + 'a + Send + Sync>> {
+		Box::pin(async {
+			if ctx.mode == RenderMode::Head {
+				return self.head(ctx).await.with_body(RenderedBody::Empty);
+			}
+
+			if let Some(bytes) = &self.preloaded {
+				return self.ok(
+					RenderedBody::Chunks(vec![bytes.clone()]),
+					bytes.len() as u64,
+				);
+			}
+
+			match tokio::fs::read(&self.path).await {
+				Ok(bytes) => {
+					let len = bytes.len() as u64;
+					self.ok(RenderedBody::Bytes(bytes), len)
+				}
+				// Like `head`, directories are not found
+				Err(err) if err.kind() == std::io::ErrorKind::IsADirectory => Self::error(
+					ctx,
+					&self.path,
+					Error::Io(std::io::ErrorKind::NotFound.into()),
+					RenderedBody::Empty,
+				),
+				Err(err) => Self::error(ctx, &self.path, err.into(), RenderedBody::Empty),
+			}
+		})
+	}
+
+	fn header_template(&self) -> HeaderMap {
+		let mut headers = HeaderMap::with_capacity(2);
+		headers.insert(header::CACHE_CONTROL, cache_control(self.ttl, false));
+		if let Ok(mime) = HeaderValue::from_str(self.mime.as_ref()) {
+			headers.insert(header::CONTENT_TYPE, mime);
+		}
+		headers
+	}
+}
@@ -1,10 +1,13 @@
-use axum::http::{HeaderMap, StatusCode};
+use axum::http::{HeaderMap, HeaderValue, StatusCode, header};
 use chrono::TimeDelta;
 use maud::{DOCTYPE, Markup, PreEscaped, html};
 use serde::Deserialize;
 use std::{hash::Hash, pin::Pin, sync::Arc};
 
-use crate::{RenderContext, Rendered, RenderedBody, servable::Servable};
+use crate::{
+	FetchPriority, HeadingAnchors, LazyImages, Preload, PreloadKind, RenderContext, RenderMode,
+	Rendered, RenderedBody, router::cache_control, servable::Servable,
+};
 
 #[expect(missing_docs)]
 #[derive(Debug, Clone, Hash, PartialEq, Eq, Deserialize)]
@@ -41,13 +44,77 @@ pub enum ScriptSource<S> {
 	/// Raw script data
 	Inline(S),
 
-	/// Load script from a url
+	/// Load script from a url.
+	/// Absolute paths are prefixed with the router's base path.
 	Linked(S),
 }
 
+/// Something that generates the contents of an [HtmlPage].
+///
+/// [DynHtmlRender] wraps a closure and is the default renderer of an [HtmlPage],
+/// which lets pages with different render functions share one type.
+/// Implementing this trait on your own type avoids allocating
+/// an extra future every time a page is rendered.
+///
+/// ```rust
+/// use servable::{HtmlPage, HtmlRender, RenderContext};
+/// use maud::{Markup, html};
+///
+/// struct Hello;
+///
+/// impl HtmlRender for Hello {
+/// 	async fn render(&self, _page: &HtmlPage<Self>, ctx: &RenderContext) -> Markup {
+/// 		html! { p { "Hello from " (ctx.route) } }
+/// 	}
+/// }
+///
+/// let page: HtmlPage<Hello> = HtmlPage::default().with_renderer(Hello);
+/// ```
+pub trait HtmlRender: Send + Sync + Sized + 'static {
+	/// Generate the contents of `page`.
+	///
+	/// This should return the contents of this page's <body> tag,
+	/// or the contents of a wrapper element (defined in the page server struct).
+	///
+	/// This must never return `<html>` or `<head>`.
+	fn render<'a>(
+		&'a self,
+		page: &'a HtmlPage<Self>,
+		ctx: &'a RenderContext,
+	) -> impl Future<Output = Markup> + Send + Sync + 'a;
+}
+
+/// The closure wrapped by a [DynHtmlRender]
+pub type HtmlRenderFn = dyn Send
+	+ Sync
+	+ 'static
+	+ for<'a> Fn(
+		&'a HtmlPage,
+		&'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Markup> + Send + Sync + 'a>>;
+
+/// A type-erased [HtmlRender] that wraps a closure.
+/// See [HtmlPage::with_render].
+#[derive(Clone)]
+pub struct DynHtmlRender(pub Arc<HtmlRenderFn>);
+
+impl HtmlRender for DynHtmlRender {
+	#[inline(always)]
+	fn render<'a>(
+		&'a self,
+		page: &'a HtmlPage<Self>,
+		ctx: &'a RenderContext,
+	) -> impl Future<Output = Markup> + Send + Sync + 'a {
+		(self.0)(page, ctx)
+	}
+}
+
 /// A complete, dynamically-rendered blob of HTML.
+///
+/// The contents of this page are generated by `R`,
+/// see [HtmlRender].
 #[derive(Clone)]
-pub struct HtmlPage {
+pub struct HtmlPage<R: HtmlRender = DynHtmlRender> {
 	/// This page's metadata
 	pub meta: PageMetadata,
 
@@ -60,21 +127,8 @@ pub struct HtmlPage {
 	/// If `None`, this page is never cached.
 	pub ttl: Option<TimeDelta>,
 
-	/// A function that generates this page's html.
-	///
-	/// This should return the contents of this page's <body> tag,
-	/// or the contents of a wrapper element (defined in the page server struct).
-	///
-	/// This closure must never return `<html>` or `<head>`.
-	pub render: Arc<
-		dyn Send
-			+ Sync
-			+ 'static
-			+ for<'a> Fn(
-				&'a HtmlPage,
-				&'a RenderContext,
-			) -> Pin<Box<dyn Future<Output = Markup> + Send + Sync + 'a>>,
-	>,
+	/// The object that generates this page's html.
+	pub render: R,
 
 	/// The response code that should accompany this html
 	pub response_code: StatusCode,
@@ -85,8 +139,24 @@ pub struct HtmlPage {
 	/// Styles to include in this page. Order is preserved.
 	pub styles: Vec<ScriptSource<String>>,
 
+	/// The `fetchpriority` of linked scripts and styles, by url.
+	/// See [HtmlPage::with_fetch_priority].
+	pub fetch_priority: Vec<(String, FetchPriority)>,
+
 	/// `name`, `content` for extra `<meta>` tags
 	pub extra_meta: Vec<(String, String)>,
+
+	/// The route of this page's [crate::WebManifest], if any
+	pub manifest: Option<String>,
+
+	/// If `Some`, add ids to the headings in this page
+	/// and generate a table of contents.
+	/// See [HtmlPage::with_heading_anchors].
+	pub heading_anchors: Option<HeadingAnchors>,
+
+	/// If `Some`, make the images in this page lazy.
+	/// See [HtmlPage::with_lazy_images].
+	pub lazy_images: Option<LazyImages>,
 }
 
 impl Default for HtmlPage {
@@ -97,16 +167,20 @@ impl Default for HtmlPage {
 			private: false,
 
 			meta: Default::default(),
-			render: Arc::new(|_, _| Box::pin(async { html!() })),
+			render: DynHtmlRender(Arc::new(|_, _| Box::pin(async { html!() }))),
 			response_code: StatusCode::OK,
 			scripts: Vec::new(),
 			styles: Vec::new(),
+			fetch_priority: Vec::new(),
 			extra_meta: Vec::new(),
+			manifest: None,
+			heading_anchors: None,
+			lazy_images: None,
 		}
 	}
 }
 
-impl HtmlPage {
+impl<R: HtmlRender> HtmlPage<R> {
 	/// Set `self.meta`
 	#[inline(always)]
 	pub fn with_meta(mut self, meta: PageMetadata) -> Self {
@@ -114,10 +188,10 @@ impl HtmlPage {
 		self
 	}
 
-	/// Set `self.generate`
+	/// Set `self.render` to the given closure.
 	#[inline(always)]
 	pub fn with_render<
-		R: Send
+		F: Send
 			+ Sync
 			+ 'static
 			+ for<'a> Fn(
@@ -125,11 +199,29 @@ impl HtmlPage {
 				&'a RenderContext,
 			) -> Pin<Box<dyn Future<Output = Markup> + Send + Sync + 'a>>,
 	>(
-		mut self,
-		render: R,
-	) -> Self {
-		self.render = Arc::new(render);
-		self
+		self,
+		render: F,
+	) -> HtmlPage {
+		self.with_renderer(DynHtmlRender(Arc::new(render)))
+	}
+
+	/// Set `self.render`
+	#[inline(always)]
+	pub fn with_renderer<R2: HtmlRender>(self, render: R2) -> HtmlPage<R2> {
+		HtmlPage {
+			meta: self.meta,
+			private: self.private,
+			ttl: self.ttl,
+			render,
+			response_code: self.response_code,
+			scripts: self.scripts,
+			styles: self.styles,
+			fetch_priority: self.fetch_priority,
+			extra_meta: self.extra_meta,
+			manifest: self.manifest,
+			heading_anchors: self.heading_anchors,
+			lazy_images: self.lazy_images,
+		}
 	}
 
 	/// Set `self.private`
@@ -205,15 +297,163 @@ impl HtmlPage {
 		self
 	}
 
+	/// Set `self.heading_anchors`.
+	///
+	/// If `Some`, every heading rendered by this page gets a stable `id`,
+	/// and the first [crate::TOC_PLACEHOLDER] is replaced with a table of contents.
+	/// See [HeadingAnchors].
+	///
+	/// ```rust
+	/// use servable::{HeadingAnchors, HtmlPage, TOC_PLACEHOLDER};
+	/// use maud::html;
+	///
+	/// let page = HtmlPage::default()
+	/// 	.with_heading_anchors(Some(HeadingAnchors::default()))
+	/// 	.with_render(|_page, _ctx| {
+	/// 		Box::pin(async {
+	/// 			html! {
+	/// 				(TOC_PLACEHOLDER)
+	/// 				h2 { "Installation" }
+	/// 				h2 { "Usage" }
+	/// 			}
+	/// 		})
+	/// 	});
+	/// ```
+	#[inline(always)]
+	pub fn with_heading_anchors(mut self, heading_anchors: Option<HeadingAnchors>) -> Self {
+		self.heading_anchors = heading_anchors;
+		self
+	}
+
+	/// Set `self.lazy_images`.
+	///
+	/// If `Some`, `loading="lazy"` and `decoding="async"` are added
+	/// to the images rendered by this page. See [LazyImages].
+	#[inline(always)]
+	pub fn with_lazy_images(mut self, lazy_images: Option<LazyImages>) -> Self {
+		self.lazy_images = lazy_images;
+		self
+	}
+
+	/// Set the `fetchpriority` of the linked script or style at `url`,
+	/// which must be exactly the url it was added with
+	#[inline(always)]
+	pub fn with_fetch_priority(mut self, url: impl Into<String>, priority: FetchPriority) -> Self {
+		let url = url.into();
+		self.fetch_priority.retain(|(x, _)| *x != url);
+		self.fetch_priority.push((url, priority));
+		self
+	}
+
+	/// The `fetchpriority` of the linked script or style at `url`
+	fn fetch_priority_of(&self, url: &str) -> Option<&'static str> {
+		self.fetch_priority
+			.iter()
+			.find(|(x, _)| x == url)
+			.map(|(_, x)| x.as_str())
+	}
+
 	/// Add a `<meta>` to this page (after existing `<meta>s`)
 	#[inline(always)]
 	pub fn with_extra_meta(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
 		self.extra_meta.push((key.into(), value.into()));
 		self
 	}
+
+	/// Link the [crate::WebManifest] served at `route` from this page
+	#[inline(always)]
+	pub fn with_manifest(mut self, route: impl Into<String>) -> Self {
+		self.manifest = Some(route.into());
+		self
+	}
+
+	/// Render this page with the given contents, ignoring `self.render`.
+	pub(crate) async fn render_with(
+		&self,
+		ctx: &RenderContext,
+		inner_html: Markup,
+	) -> Rendered<RenderedBody> {
+		let inner_html = match &self.heading_anchors {
+			Some(anchors) => PreEscaped(anchors.apply(&inner_html.0)),
+			None => inner_html,
+		};
+		let inner_html = match &self.lazy_images {
+			Some(lazy) => PreEscaped(lazy.apply(&inner_html.0)),
+			None => inner_html,
+		};
+
+		let html = html! {
+			(DOCTYPE)
+			html {
+				head {
+					meta charset="UTF-8";
+					meta name="viewport" content="width=device-width, initial-scale=1,user-scalable=no";
+					meta content="text/html; charset=UTF-8" http-equiv="content-type";
+					meta property="og:type" content="website";
+					@if ctx.base_url.is_some() {
+						link rel="canonical" href=(ctx.absolute_url(&ctx.route));
+						meta property="og:url" content=(ctx.absolute_url(&ctx.route));
+					}
+					@for (name, content) in &self.extra_meta {
+						meta name=(name) content=(content);
+					}
+
+					//
+					// Metadata
+					//
+					title { (PreEscaped(self.meta.title.clone())) }
+					meta property="og:site_name" content=(self.meta.title);
+					meta name="title" content=(self.meta.title);
+					meta property="og:title" content=(self.meta.title);
+					meta property="twitter:title" content=(self.meta.title);
+
+					@if let Some(author) = &self.meta.author {
+						meta name="author" content=(author);
+					}
+
+					@if let Some(desc) = &self.meta.description {
+						meta name="description" content=(desc);
+						meta property="og:description" content=(desc);
+						meta property="twitter:description" content=(desc);
+					}
+
+					@if let Some(image) = &self.meta.image {
+						meta content=(ctx.absolute_url(image)) property="og:image";
+						link rel="shortcut icon" href=(ctx.url(image)) type="image/x-icon";
+					}
+
+					@if let Some(manifest) = &self.manifest {
+						link rel="manifest" href=(ctx.url(manifest));
+					}
+
+					//
+					// Scripts & styles
+					//
+
+					@for style in &self.styles {
+						@match style {
+							ScriptSource::Linked(x) => link rel="stylesheet" type="text/css" href=(ctx.url(x)) fetchpriority=[self.fetch_priority_of(x)];,
+							ScriptSource::Inline(x) => style { (PreEscaped(x)) }
+						}
+					}
+
+					@for script in &self.scripts {
+						@match script {
+							ScriptSource::Linked(x) => script src=(ctx.url(x)) fetchpriority=[self.fetch_priority_of(x)] {},
+							ScriptSource::Inline(x) => script { (PreEscaped(x)) }
+						}
+					}
+				}
+
+				body { main { (inner_html) } }
+			}
+		};
+
+		return self.head(ctx).await.with_body(RenderedBody::String(html.0));
+	}
 }
 
-impl Servable for HtmlPage {
+impl<R: HtmlRender> Servable for HtmlPage<R> {
 	fn head<'a>(
 		&'a self,
 		_ctx: &'a RenderContext,
@@ -226,6 +466,7 @@ impl Servable for HtmlPage {
 				private: self.private,
 				headers: HeaderMap::new(),
 				mime: Some(mime::TEXT_HTML),
+				content_length: None,
 			};
 		})
 	}
@@ -235,68 +476,51 @@ impl Servable for HtmlPage {
 		ctx: &'a RenderContext,
 	) -> Pin<Box<dyn Future<Output = Rendered<RenderedBody>> + 'a + Send + Sync>> {
 		Box::pin(async {
-			let inner_html = (self.render)(self, ctx).await;
-
-			let html = html! {
-				(DOCTYPE)
-				html {
-					head {
-						meta charset="UTF-8";
-						meta name="viewport" content="width=device-width, initial-scale=1,user-scalable=no";
-						meta content="text/html; charset=UTF-8" http-equiv="content-type";
-						meta property="og:type" content="website";
-						@for (name, content) in &self.extra_meta {
-							meta name=(name) content=(content);
-						}
-
-						//
-						// Metadata
-						//
-						title { (PreEscaped(self.meta.title.clone())) }
-						meta property="og:site_name" content=(self.meta.title);
-						meta name="title" content=(self.meta.title);
-						meta property="og:title" content=(self.meta.title);
-						meta property="twitter:title" content=(self.meta.title);
-
-						@if let Some(author) = &self.meta.author {
-							meta name="author" content=(author);
-						}
-
-						@if let Some(desc) = &self.meta.description {
-							meta name="description" content=(desc);
-							meta property="og:description" content=(desc);
-							meta property="twitter:description" content=(desc);
-						}
+			if ctx.mode == RenderMode::Head {
+				return self.head(ctx).await.with_body(RenderedBody::Empty);
+			}
 
-						@if let Some(image) = &self.meta.image {
-							meta content=(image) property="og:image";
-							link rel="shortcut icon" href=(image) type="image/x-icon";
-						}
-
-						//
-						// Scripts & styles
-						//
-
-						@for style in &self.styles {
-							@match style {
-								ScriptSource::Linked(x) => link rel="stylesheet" type="text/css" href=(x);,
-								ScriptSource::Inline(x) => style { (PreEscaped(x)) }
-							}
-						}
+			let inner_html = self.render.render(self, ctx).await;
+			self.render_with(ctx, inner_html).await
+		})
+	}
 
-						@for script in &self.scripts {
-							@match script {
-								ScriptSource::Linked(x) => script src=(x) {},
-								ScriptSource::Inline(x) => script { (PreEscaped(x)) }
-							}
-						}
-					}
+	fn header_template(&self) -> HeaderMap {
+		let mut headers = HeaderMap::with_capacity(2);
+		headers.insert(header::CACHE_CONTROL, cache_control(self.ttl, self.private));
+		headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("text/html"));
+		headers
+	}
 
-					body { main { (inner_html) } }
-				}
-			};
+	fn linked_routes(&self) -> Vec<String> {
+		let linked = self
+			.styles
+			.iter()
+			.chain(&self.scripts)
+			.filter_map(|x| match x {
+				ScriptSource::Linked(x) => Some(x.clone()),
+				ScriptSource::Inline(_) => None,
+			});
+
+		linked
+			.chain(self.meta.image.clone())
+			.chain(self.manifest.clone())
+			.collect()
+	}
 
-			return self.head(ctx).await.with_body(RenderedBody::String(html.0));
-		})
+	fn preloads(&self) -> Vec<Preload> {
+		let styles = self.styles.iter().map(|x| (x, PreloadKind::Style));
+		let scripts = self.scripts.iter().map(|x| (x, PreloadKind::Script));
+
+		styles
+			.chain(scripts)
+			.filter_map(|(x, kind)| match x {
+				ScriptSource::Linked(url) => Some(Preload {
+					url: url.clone(),
+					kind,
+				}),
+				ScriptSource::Inline(_) => None,
+			})
+			.collect()
 	}
 }
@@ -5,12 +5,24 @@ mod asset;
 
 pub use asset::*;
 
+mod bundle;
+pub use bundle::*;
+
+mod dir;
+pub use dir::*;
+
+mod file;
+pub use file::*;
+
 mod html;
 pub use html::*;
 
 mod redirect;
 pub use redirect::*;
 
+mod zip;
+pub use zip::*;
+
 /// Something that may be served over http. If implementing this trait,
 /// refer to sample implementations in [redirect::Redirect], [asset::StaticAsset] and [html::HtmlPage].
 pub trait Servable: Send + Sync {
@@ -26,12 +38,42 @@ pub trait Servable: Send + Sync {
 	/// Consider using [crate::Rendered::with_body] and [Servable::head] to implement this fn.
 	///
 	/// This method is used to respond to `GET` requests.
+	/// If [crate::RenderContext::mode] is [crate::RenderMode::Head],
+	/// the body of the response will be discarded, and implementations
+	/// should skip generating it. The provided servables do this.
 	fn render<'a>(
 		&'a self,
 		ctx: &'a crate::RenderContext,
 	) -> std::pin::Pin<
 		Box<dyn Future<Output = crate::Rendered<crate::RenderedBody>> + 'a + Send + Sync>,
 	>;
+
+	/// Headers that are identical in every successful (2xx) response of this servable,
+	/// like `Cache-Control` or `Content-Type`.
+	///
+	/// This is called once when this servable is added to a [crate::ServableRouter].
+	/// The router merges these headers into every successful response,
+	/// skipping any that [crate::Rendered::headers] already contains.
+	/// This lets us skip formatting the same headers on every request.
+	fn header_template(&self) -> axum::http::HeaderMap {
+		axum::http::HeaderMap::new()
+	}
+
+	/// Urls this servable's responses always link to, like linked scripts and styles.
+	/// Used by [crate::ServableRouter::validate] to find broken links.
+	///
+	/// Only absolute paths (like `/style.css`) are checked, other urls are ignored.
+	fn linked_routes(&self) -> Vec<String> {
+		Vec::new()
+	}
+
+	/// Resources clients should fetch early when loading this servable,
+	/// like linked scripts and styles. See [crate::ServableRouter::with_early_hints].
+	///
+	/// This is called once when this servable is added to a [crate::ServableRouter].
+	fn preloads(&self) -> Vec<crate::Preload> {
+		Vec::new()
+	}
 }
 
 //
@@ -80,6 +122,21 @@ impl<S: Servable> Servable for ServableWithRoute<S> {
 	> {
 		self.servable.render(ctx)
 	}
+
+	#[inline(always)]
+	fn header_template(&self) -> axum::http::HeaderMap {
+		self.servable.header_template()
+	}
+
+	#[inline(always)]
+	fn linked_routes(&self) -> Vec<String> {
+		self.servable.linked_routes()
+	}
+
+	#[inline(always)]
+	fn preloads(&self) -> Vec<crate::Preload> {
+		self.servable.preloads()
+	}
 }
 
 impl<S: Servable> Servable for &'static S {
@@ -100,6 +157,58 @@ impl<S: Servable> Servable for &'static S {
 	> {
 		(*self).render(ctx)
 	}
+
+	#[inline(always)]
+	fn header_template(&self) -> axum::http::HeaderMap {
+		(*self).header_template()
+	}
+
+	#[inline(always)]
+	fn linked_routes(&self) -> Vec<String> {
+		(*self).linked_routes()
+	}
+
+	#[inline(always)]
+	fn preloads(&self) -> Vec<crate::Preload> {
+		(*self).preloads()
+	}
+}
+
+/// Shared servables keep their state (like caches) in every router they are added to,
+/// see [crate::SwappableRouter].
+impl<S: Servable + ?Sized> Servable for std::sync::Arc<S> {
+	#[inline(always)]
+	fn head<'a>(
+		&'a self,
+		ctx: &'a crate::RenderContext,
+	) -> std::pin::Pin<Box<dyn Future<Output = crate::Rendered<()>> + 'a + Send + Sync>> {
+		(**self).head(ctx)
+	}
+
+	#[inline(always)]
+	fn render<'a>(
+		&'a self,
+		ctx: &'a crate::RenderContext,
+	) -> std::pin::Pin<
+		Box<dyn Future<Output = crate::Rendered<crate::RenderedBody>> + 'a + Send + Sync>,
+	> {
+		(**self).render(ctx)
+	}
+
+	#[inline(always)]
+	fn header_template(&self) -> axum::http::HeaderMap {
+		(**self).header_template()
+	}
+
+	#[inline(always)]
+	fn linked_routes(&self) -> Vec<String> {
+		(**self).linked_routes()
+	}
+
+	#[inline(always)]
+	fn preloads(&self) -> Vec<crate::Preload> {
+		(**self).preloads()
+	}
 }
 
 impl<S: Servable> Servable for std::sync::LazyLock<S> {
@@ -120,4 +229,19 @@ impl<S: Servable> Servable for std::sync::LazyLock<S> {
 	> {
 		(**self).render(ctx)
 	}
+
+	#[inline(always)]
+	fn header_template(&self) -> axum::http::HeaderMap {
+		(**self).header_template()
+	}
+
+	#[inline(always)]
+	fn linked_routes(&self) -> Vec<String> {
+		(**self).linked_routes()
+	}
+
+	#[inline(always)]
+	fn preloads(&self) -> Vec<crate::Preload> {
+		(**self).preloads()
+	}
 }
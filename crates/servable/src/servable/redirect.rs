@@ -25,7 +25,10 @@ pub struct Redirect {
 
 impl Redirect {
 	/// Create a new [Redirect] to the given route.
-	/// Returns an http 308 (permanent redirect)
+	/// Returns an http 308 (permanent redirect).
+	///
+	/// Absolute paths are prefixed with the router's base path and base url,
+	/// see [crate::ServableRouter::with_base_path] and [crate::ServableRouter::with_base_url].
 	pub fn new(to: impl Into<String>) -> Result<Self, InvalidHeaderValue> {
 		Ok(Self {
 			to: HeaderValue::from_str(&to.into())?,
@@ -46,11 +49,20 @@ impl Redirect {
 impl Servable for Redirect {
 	fn head<'a>(
 		&'a self,
-		_ctx: &'a RenderContext,
+		ctx: &'a RenderContext,
 	) -> Pin<Box<dyn Future<Output = Rendered<()>> + 'a + Send + Sync>> {
 		Box::pin(async {
+			// Routes on this router are relative to its base path
+			let to = match self.to.to_str() {
+				Ok(x) if !ctx.base_path.is_empty() || ctx.base_url.is_some() => {
+					HeaderValue::from_str(&ctx.absolute_url(x))
+						.unwrap_or_else(|_err| self.to.clone())
+				}
+				_ => self.to.clone(),
+			};
+
 			let mut headers = HeaderMap::with_capacity(1);
-			headers.append(header::LOCATION, self.to.clone());
+			headers.append(header::LOCATION, to);
 
 			return Rendered {
 				code: match self.code {
@@ -62,6 +74,7 @@ impl Servable for Redirect {
 				ttl: None,
 				private: false,
 				mime: None,
+				content_length: None,
 			};
 		})
 	}
@@ -72,4 +85,11 @@ impl Servable for Redirect {
 	) -> Pin<Box<dyn Future<Output = Rendered<RenderedBody>> + 'a + Send + Sync>> {
 		Box::pin(async { self.head(ctx).await.with_body(RenderedBody::Empty) })
 	}
+
+	fn linked_routes(&self) -> Vec<String> {
+		self.to
+			.to_str()
+			.map(|x| vec![x.to_owned()])
+			.unwrap_or_default()
+	}
 }
@@ -0,0 +1,341 @@
+use axum::{
+	body::Bytes,
+	http::{HeaderMap, StatusCode, header},
+};
+use chrono::{Datelike, NaiveDateTime, TimeDelta, Timelike};
+use std::pin::Pin;
+use tracing::error;
+
+use crate::{RenderContext, Rendered, RenderedBody, StaticAsset, servable::Servable};
+
+/// A zip archive of static files, for "download all" links.
+///
+/// The archive is assembled on the fly from borrowed file contents,
+/// without ever copying or buffering the whole archive:
+/// only the small zip headers are generated for each request.
+/// Files are stored without compression, since downloads like images
+/// and archives are usually compressed already.
+///
+/// Archives must be smaller than 4 GiB and contain fewer than 65535 files.
+/// Larger archives are answered with `500 Internal Server Error`.
+///
+/// ```rust
+/// use servable::{ServableRouter, StaticAsset, ZipDownload};
+///
+/// let image = StaticAsset {
+/// 	bytes: b"fake image",
+/// 	br: None,
+/// 	gz: None,
+/// 	mime: mime::IMAGE_PNG,
+/// 	ttl: StaticAsset::DEFAULT_TTL,
+/// };
+///
+/// let zip = ZipDownload::new("gallery.zip")
+/// 	.with_asset("gallery/image.png", &image)
+/// 	.with_file("gallery/README.txt", b"Photos from our trip");
+///
+/// let router = ServableRouter::new()
+/// 	.add_page("/gallery/image.png", image)
+/// 	.add_page("/gallery.zip", zip);
+/// ```
+///
+/// Archives are plain zip files, with one local header per file
+/// and a central directory at the end:
+///
+/// ```rust
+/// use axum::{body::Body, http::Request};
+/// use http_body::Body as _;
+/// use http_body_util::BodyExt;
+/// use servable::{ServableRouter, ZipDownload};
+/// use tower::ServiceExt;
+///
+/// # tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap().block_on(async {
+/// let zip = ZipDownload::new("files.zip")
+/// 	.with_file("check.txt", b"123456789")
+/// 	.with_file("dir/hello.txt", b"hello");
+/// let router = ServableRouter::new().add_page("/files.zip", zip).into_router::<()>();
+///
+/// let req = Request::get("/files.zip").body(Body::empty()).unwrap();
+/// let res = router.oneshot(req).await.unwrap();
+/// let length = res.body().size_hint().exact();
+/// let zip = res.into_body().collect().await.unwrap().to_bytes();
+/// assert_eq!(Some(zip.len() as u64), length);
+///
+/// let u16_at = |i: usize| u16::from_le_bytes([zip[i], zip[i + 1]]) as usize;
+/// let u32_at = |i: usize| u32::from_le_bytes([zip[i], zip[i + 1], zip[i + 2], zip[i + 3]]);
+///
+/// // The end of central directory record is the last 22 bytes
+/// let eocd = zip.len() - 22;
+/// assert_eq!(u32_at(eocd), 0x06054b50);
+/// assert_eq!(u16_at(eocd + 10), 2);
+/// let (central_size, central_offset) = (u32_at(eocd + 12) as usize, u32_at(eocd + 16) as usize);
+/// assert_eq!(central_offset + central_size, eocd);
+///
+/// // Each central header points at a local header with the same name and checksum
+/// let mut at = central_offset;
+/// let mut crcs = Vec::new();
+/// for name in ["check.txt", "dir/hello.txt"] {
+/// 	assert_eq!(u32_at(at), 0x02014b50);
+/// 	assert_eq!(&zip[at + 46..at + 46 + u16_at(at + 28)], name.as_bytes());
+///
+/// 	let local = u32_at(at + 42) as usize;
+/// 	assert_eq!(u32_at(local), 0x04034b50);
+/// 	assert_eq!(u32_at(local + 14), u32_at(at + 16));
+/// 	assert_eq!(&zip[local + 30..local + 30 + name.len()], name.as_bytes());
+///
+/// 	crcs.push(u32_at(at + 16));
+/// 	at += 46 + name.len();
+/// }
+/// assert_eq!(at, eocd);
+///
+/// // The CRC-32 check value of `123456789`
+/// assert_eq!(crcs[0], 0xcbf43926);
+/// assert_eq!(crcs[1], 0x3610a686);
+/// # });
+/// ```
+pub struct ZipDownload {
+	/// The name browsers save this archive as
+	filename: String,
+	entries: Vec<ZipEntry>,
+
+	/// The modification time of every file, in MS-DOS format
+	dos_time: u16,
+	dos_date: u16,
+
+	/// How long to cache this response.
+	/// If None, never cache
+	pub ttl: Option<TimeDelta>,
+}
+
+/// One file in a [ZipDownload]
+struct ZipEntry {
+	name: String,
+	bytes: &'static [u8],
+	crc: u32,
+}
+
+impl ZipDownload {
+	/// Create an empty archive that browsers save as `filename`
+	pub fn new(filename: impl Into<String>) -> Self {
+		Self {
+			filename: filename.into(),
+			entries: Vec::new(),
+			// 1980-01-01 00:00, the earliest time zip supports
+			dos_time: 0,
+			dos_date: (1 << 5) | 1,
+			ttl: StaticAsset::DEFAULT_TTL,
+		}
+	}
+
+	/// Add a file named `name` to this archive.
+	/// Use `/` to put it in a directory, like `photos/a.jpg`.
+	pub fn with_file(mut self, name: impl Into<String>, bytes: &'static [u8]) -> Self {
+		self.entries.push(ZipEntry {
+			name: name.into(),
+			bytes,
+			crc: crc32(bytes),
+		});
+		self
+	}
+
+	/// Add the contents of `asset` to this archive as `name`
+	#[inline(always)]
+	pub fn with_asset(self, name: impl Into<String>, asset: &StaticAsset) -> Self {
+		self.with_file(name, asset.bytes)
+	}
+
+	/// Set the modification time of every file in this archive.
+	/// Zip stores times with a precision of two seconds, between 1980 and 2107.
+	pub fn with_modified(mut self, modified: NaiveDateTime) -> Self {
+		let year = modified.year().clamp(1980, 2107) - 1980;
+		self.dos_date =
+			((year as u16) << 9) | ((modified.month() as u16) << 5) | modified.day() as u16;
+		self.dos_time = ((modified.hour() as u16) << 11)
+			| ((modified.minute() as u16) << 5)
+			| (modified.second() as u16 / 2);
+		self
+	}
+
+	/// Set `self.ttl`
+	#[inline(always)]
+	pub fn with_ttl(mut self, ttl: Option<TimeDelta>) -> Self {
+		self.ttl = ttl;
+		self
+	}
+
+	/// The size of this archive, in bytes
+	fn len(&self) -> u64 {
+		let entries: u64 = self
+			.entries
+			.iter()
+			.map(|x| 30 + 46 + 2 * x.name.len() as u64 + x.bytes.len() as u64)
+			.sum();
+		entries + 22
+	}
+
+	/// Returns true if this archive can be written without zip64
+	fn fits(&self) -> bool {
+		self.entries.len() < usize::from(u16::MAX)
+			&& self.len() <= u64::from(u32::MAX)
+			&& self
+				.entries
+				.iter()
+				.all(|x| x.name.len() <= usize::from(u16::MAX))
+	}
+
+	/// The buffers that make up this archive.
+	/// File contents are borrowed, not copied.
+	///
+	/// The archive must [Self::fits].
+	fn chunks(&self) -> Vec<Bytes> {
+		let mut chunks = Vec::with_capacity(self.entries.len() * 2 + 1);
+		let mut central = Vec::new();
+		let mut offset: u32 = 0;
+
+		for entry in &self.entries {
+			let name = entry.name.as_bytes();
+			let size = entry.bytes.len() as u32;
+
+			// Local file header
+			let mut local = Vec::with_capacity(30 + name.len());
+			local.extend_from_slice(&0x04034b50u32.to_le_bytes());
+			self.write_common(&mut local, entry);
+			local.extend_from_slice(&0u16.to_le_bytes()); // extra field length
+			local.extend_from_slice(name);
+
+			// Central directory header
+			central.extend_from_slice(&0x02014b50u32.to_le_bytes());
+			central.extend_from_slice(&20u16.to_le_bytes()); // version made by
+			self.write_common(&mut central, entry);
+			central.extend_from_slice(&0u16.to_le_bytes()); // extra field length
+			central.extend_from_slice(&0u16.to_le_bytes()); // comment length
+			central.extend_from_slice(&0u16.to_le_bytes()); // disk number
+			central.extend_from_slice(&0u16.to_le_bytes()); // internal attributes
+			central.extend_from_slice(&0u32.to_le_bytes()); // external attributes
+			central.extend_from_slice(&offset.to_le_bytes());
+			central.extend_from_slice(name);
+
+			offset += local.len() as u32 + size;
+			chunks.push(Bytes::from(local));
+			chunks.push(Bytes::from_static(entry.bytes));
+		}
+
+		// End of central directory
+		let count = self.entries.len() as u16;
+		let central_len = central.len() as u32;
+		central.extend_from_slice(&0x06054b50u32.to_le_bytes());
+		central.extend_from_slice(&0u16.to_le_bytes()); // this disk
+		central.extend_from_slice(&0u16.to_le_bytes()); // central directory disk
+		central.extend_from_slice(&count.to_le_bytes());
+		central.extend_from_slice(&count.to_le_bytes());
+		central.extend_from_slice(&central_len.to_le_bytes());
+		central.extend_from_slice(&offset.to_le_bytes());
+		central.extend_from_slice(&0u16.to_le_bytes()); // comment length
+
+		chunks.push(Bytes::from(central));
+		chunks
+	}
+
+	/// Write the fields shared by local and central headers,
+	/// from "version needed" to "file name length".
+	fn write_common(&self, out: &mut Vec<u8>, entry: &ZipEntry) {
+		out.extend_from_slice(&10u16.to_le_bytes()); // version needed
+		out.extend_from_slice(&0x0800u16.to_le_bytes()); // flags: utf-8 names
+		out.extend_from_slice(&0u16.to_le_bytes()); // method: stored
+		out.extend_from_slice(&self.dos_time.to_le_bytes());
+		out.extend_from_slice(&self.dos_date.to_le_bytes());
+		out.extend_from_slice(&entry.crc.to_le_bytes());
+		out.extend_from_slice(&(entry.bytes.len() as u32).to_le_bytes()); // compressed size
+		out.extend_from_slice(&(entry.bytes.len() as u32).to_le_bytes()); // size
+		out.extend_from_slice(&(entry.name.len() as u16).to_le_bytes());
+	}
+}
+
+impl Servable for ZipDownload {
+	fn head<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<()>> + 'a + Send + Sync>> {
+		Box::pin(async {
+			if !self.fits() {
+				error!(
+					message = "Zip archive is too large",
+					route = ctx.route,
+					files = self.entries.len(),
+					bytes = self.len()
+				);
+				ctx.report_error(format!(
+					"zip archive is too large: {} files, {} bytes",
+					self.entries.len(),
+					self.len()
+				));
+
+				return Rendered {
+					code: StatusCode::INTERNAL_SERVER_ERROR,
+					body: (),
+					ttl: None,
+					private: false,
+					headers: HeaderMap::new(),
+					mime: None,
+					content_length: None,
+				};
+			}
+
+			let mut headers = HeaderMap::with_capacity(1);
+			headers.insert(
+				header::CONTENT_DISPOSITION,
+				crate::download::attachment(&self.filename),
+			);
+
+			return Rendered {
+				code: StatusCode::OK,
+				body: (),
+				ttl: self.ttl,
+				private: false,
+				headers,
+				#[expect(clippy::unwrap_used)]
+				mime: Some("application/zip".parse().unwrap()),
+				content_length: Some(self.len()),
+			};
+		})
+	}
+
+	fn render<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<RenderedBody>> + 'a + Send + Sync>> {
+		Box::pin(async {
+			let rend = self.head(ctx).await;
+			match rend.code {
+				StatusCode::OK => rend.with_body(RenderedBody::Chunks(self.chunks())),
+				_ => rend.with_body(RenderedBody::Empty),
+			}
+		})
+	}
+}
+
+/// The CRC-32 (IEEE) checksum of `bytes`, as used by zip
+fn crc32(bytes: &[u8]) -> u32 {
+	const TABLE: [u32; 256] = {
+		let mut table = [0u32; 256];
+		let mut i = 0;
+		while i < 256 {
+			let mut crc = i as u32;
+			let mut j = 0;
+			while j < 8 {
+				crc = match crc & 1 {
+					1 => (crc >> 1) ^ 0xedb88320,
+					_ => crc >> 1,
+				};
+				j += 1;
+			}
+			table[i] = crc;
+			i += 1;
+		}
+		table
+	};
+
+	!bytes.iter().fold(!0u32, |crc, x| {
+		TABLE[((crc ^ u32::from(*x)) & 0xff) as usize] ^ (crc >> 8)
+	})
+}
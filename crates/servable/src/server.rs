@@ -0,0 +1,58 @@
+use axum::Router;
+use std::net::SocketAddr;
+use tokio::net::{TcpListener, ToSocketAddrs};
+use tracing::info;
+
+use crate::ServableRouter;
+
+impl ServableRouter {
+	/// Serve this router on `addr`, forever.
+	/// Connections are served with http/1 and http/2, and each request
+	/// knows its peer address (see [crate::RenderContext::client_ip]).
+	/// This router's periodic tasks run while it is served (see [Self::with_task]).
+	/// Every route is logged at startup, with its description (see [Self::route_table]).
+	///
+	/// Returns an error if we could not bind to `addr`.
+	///
+	/// ```rust,no_run
+	/// use servable::{HtmlPage, ServableRouter};
+	///
+	/// # async fn run() -> std::io::Result<()> {
+	/// ServableRouter::new()
+	/// 	.add_page("/", HtmlPage::default())
+	/// 	.serve("0.0.0.0:8080")
+	/// 	.await
+	/// # }
+	/// ```
+	pub async fn serve(self, addr: impl ToSocketAddrs) -> std::io::Result<()> {
+		self.serve_with_shutdown(addr, std::future::pending()).await
+	}
+
+	/// Serve this router on `addr` until `signal` completes.
+	/// Behaves exactly like [Self::serve], but shuts down gracefully when `signal` resolves,
+	/// waiting for open requests to finish.
+	pub async fn serve_with_shutdown(
+		self,
+		addr: impl ToSocketAddrs,
+		signal: impl Future<Output = ()> + Send + 'static,
+	) -> std::io::Result<()> {
+		let listener = TcpListener::bind(addr).await?;
+		for route in self.route_table() {
+			info!(
+				message = "Serving route",
+				route = route.route,
+				description = route.description
+			);
+		}
+
+		let _tasks = self.start_tasks();
+		let router: Router = self.into_router();
+
+		axum::serve(
+			listener,
+			router.into_make_service_with_connect_info::<SocketAddr>(),
+		)
+		.with_graceful_shutdown(signal)
+		.await
+	}
+}
@@ -0,0 +1,192 @@
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use std::{borrow::Cow, fmt::Debug, sync::Arc};
+
+/// Signs and verifies data with HMAC-SHA256.
+/// See [crate::ServableRouter::with_signed_urls].
+///
+/// A [Signer] has one key that new signatures are made with,
+/// and any number of old keys that are still accepted.
+/// To rotate keys, add a new key and keep the old one with [Signer::with_old_key]
+/// until everything it signed has expired.
+///
+/// Cloning a [Signer] is cheap. Its [Debug] output never contains keys.
+///
+/// ```rust
+/// use servable::Signer;
+///
+/// let signer = Signer::new(b"new secret").with_old_key(b"old secret");
+/// let old = Signer::new(b"old secret");
+///
+/// let sig = old.sign(b"hello");
+/// assert!(signer.verify(b"hello", &sig));
+/// assert!(!signer.verify(b"goodbye", &sig));
+/// ```
+#[derive(Clone)]
+pub struct Signer {
+	/// The first key signs, all keys verify
+	keys: Arc<Vec<Vec<u8>>>,
+}
+
+impl Debug for Signer {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("Signer")
+			.field("keys", &self.keys.len())
+			.finish()
+	}
+}
+
+impl Signer {
+	/// Create a [Signer] that signs with `key`.
+	/// Keys should be at least 32 random bytes.
+	pub fn new(key: impl Into<Vec<u8>>) -> Self {
+		Self {
+			keys: Arc::new(vec![key.into()]),
+		}
+	}
+
+	/// Also accept signatures made with `key`
+	pub fn with_old_key(mut self, key: impl Into<Vec<u8>>) -> Self {
+		Arc::make_mut(&mut self.keys).push(key.into());
+		self
+	}
+
+	/// Sign `data`, returning a hex-encoded signature.
+	/// This is the HMAC-SHA256 of `data`:
+	///
+	/// ```rust
+	/// use servable::Signer;
+	///
+	/// // RFC 4231, test cases 1, 2 and 6
+	/// assert_eq!(
+	/// 	Signer::new([0x0b; 20]).sign(b"Hi There"),
+	/// 	"b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7",
+	/// );
+	/// assert_eq!(
+	/// 	Signer::new(b"Jefe").sign(b"what do ya want for nothing?"),
+	/// 	"5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+	/// );
+	/// assert_eq!(
+	/// 	Signer::new([0xaa; 131]).sign(b"Test Using Larger Than Block-Size Key - Hash Key First"),
+	/// 	"60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54",
+	/// );
+	/// ```
+	pub fn sign(&self, data: &[u8]) -> String {
+		let key = self.keys.first().map(Vec::as_slice).unwrap_or_default();
+		hmac_sha256(key, data)
+			.iter()
+			.map(|x| format!("{x:02x}"))
+			.collect()
+	}
+
+	/// Returns true if `signature` is a signature of `data` made with any of our keys
+	pub fn verify(&self, data: &[u8], signature: &str) -> bool {
+		let Some(signature) = decode_hex(signature) else {
+			return false;
+		};
+
+		self.keys.iter().any(|key| {
+			let expected = hmac_sha256(key, data);
+			constant_time_eq(&expected, &signature)
+		})
+	}
+
+	/// Make a signed url that expires at `expires`.
+	///
+	/// `url` is the path and query clients will request, including the router's
+	/// base path (see [crate::RenderContext::url]). It must not contain `exp` or `sig`.
+	///
+	/// ```rust
+	/// use servable::Signer;
+	/// use chrono::{TimeDelta, Utc};
+	///
+	/// let signer = Signer::new(b"secret");
+	/// let url = signer.sign_url("/downloads/report.pdf", Utc::now() + TimeDelta::hours(1));
+	/// assert!(url.starts_with("/downloads/report.pdf?exp="));
+	/// ```
+	pub fn sign_url(&self, url: &str, expires: DateTime<Utc>) -> String {
+		let url = match url.contains('?') {
+			true => format!("{url}&exp={}", expires.timestamp()),
+			false => format!("{url}?exp={}", expires.timestamp()),
+		};
+
+		let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+		let sig = self.sign(url_message(path, query).as_bytes());
+		format!("{url}&sig={sig}")
+	}
+
+	/// Check a request for a url made by [Self::sign_url]
+	pub(crate) fn verify_url(&self, path: &str, query: &str, now: DateTime<Utc>) -> bool {
+		let sig = query
+			.split('&')
+			.find_map(|x| x.strip_prefix("sig="))
+			.unwrap_or("");
+
+		let expires = query
+			.split('&')
+			.find_map(|x| x.strip_prefix("exp="))
+			.and_then(|x| x.parse::<i64>().ok());
+
+		match expires {
+			Some(expires) if now.timestamp() < expires => {
+				self.verify(url_message(path, query).as_bytes(), sig)
+			}
+			_ => false,
+		}
+	}
+}
+
+/// The data we sign for a url.
+/// Query parameters are sorted, so that urls stay valid if the router reorders them.
+/// Image transformations are signed in canonical form, see [crate::transform::TransformerChain].
+fn url_message(path: &str, query: &str) -> String {
+	let mut params: Vec<Cow<'_, str>> = query
+		.split('&')
+		.filter(|x| !x.is_empty() && !x.starts_with("sig="))
+		.map(|x| {
+			#[cfg(feature = "image")]
+			if let Some(x) = crate::transform::canonical_param(x) {
+				return Cow::Owned(x);
+			}
+
+			Cow::Borrowed(x)
+		})
+		.collect();
+	params.sort_unstable();
+	format!("{path}?{}", params.join("&"))
+}
+
+fn decode_hex(s: &str) -> Option<Vec<u8>> {
+	if !s.len().is_multiple_of(2) {
+		return None;
+	}
+
+	(0..s.len())
+		.step_by(2)
+		.map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+		.collect()
+}
+
+//
+// MARK: hmac-sha256
+//
+
+/// Returns true if `a == b`, in time that only depends on their lengths
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+	a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+/// The sha256 hash of `data`
+pub(crate) fn sha256(data: &[u8]) -> [u8; 32] {
+	Sha256::digest(data).into()
+}
+
+/// The HMAC-SHA256 of `data` with `key` (RFC 2104)
+fn hmac_sha256(key: &[u8], data: &[u8]) -> [u8; 32] {
+	// HMAC accepts keys of any length
+	#[expect(clippy::unwrap_used)]
+	let mut mac = Hmac::<Sha256>::new_from_slice(key).unwrap();
+	mac.update(data);
+	mac.finalize().into_bytes().into()
+}
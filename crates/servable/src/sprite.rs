@@ -0,0 +1,407 @@
+use axum::http::{HeaderMap, StatusCode, header};
+use chrono::TimeDelta;
+use image::{DynamicImage, ImageFormat, RgbaImage, imageops};
+use mime::Mime;
+use serde::Serialize;
+use std::{collections::BTreeMap, io::Cursor, pin::Pin, sync::Arc};
+use tokio::sync::OnceCell;
+
+use crate::{
+	Error, RenderContext, Rendered, RenderedBody, ServableRouter, StaticAsset, failsafe::head_of,
+	router::cache_control, servable::Servable, transform::TransformerChain,
+};
+
+/// Where one image is in a [SpriteSheet], in pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct SpriteRect {
+	/// The left edge of this image
+	pub x: u32,
+
+	/// The top edge of this image
+	pub y: u32,
+
+	#[expect(missing_docs)]
+	pub width: u32,
+
+	#[expect(missing_docs)]
+	pub height: u32,
+}
+
+/// The coordinate map of a [SpriteSheet], served as json
+#[derive(Debug, Serialize)]
+struct SpriteMap<'a> {
+	width: u32,
+	height: u32,
+	sprites: BTreeMap<&'a str, SpriteRect>,
+}
+
+/// A generated [SpriteSheet]
+struct Generated {
+	mime: Mime,
+	sheet: Vec<u8>,
+	json: String,
+	css: String,
+}
+
+/// Many small images (like icons or video scrubber previews)
+/// combined into one image, so clients can fetch them in one request.
+///
+/// Every image is resized with [SpriteSheet::cell] and placed on a grid.
+/// The sheet is then transformed with [SpriteSheet::sheet], so it may be transcoded or quantized.
+/// The router serves (see [ServableRouter::add_sprite_sheet]):
+/// - the sheet, at [SpriteSheet::sheet_route],
+/// - a json map of where each image is, at [SpriteSheet::map_route],
+/// - a stylesheet with one class per image, at [SpriteSheet::css_route].
+///
+/// The sheet is generated once, when it or its map is first requested.
+///
+/// ```rust
+/// use servable::{ServableRouter, SpriteSheet, StaticAsset};
+///
+/// static HOME: StaticAsset = StaticAsset {
+/// 	bytes: b"fake image",
+/// 	br: None,
+/// 	gz: None,
+/// 	mime: mime::IMAGE_PNG,
+/// 	ttl: StaticAsset::DEFAULT_TTL,
+/// };
+///
+/// let icons = SpriteSheet::new("/icons")
+/// 	.with_image("home", &HOME)
+/// 	.with_cell("maxdim(32,32)".parse().unwrap())
+/// 	.with_sheet("format(webp)".parse().unwrap());
+///
+/// // `<span class="sprite sprite-home">` shows the home icon
+/// // when `/icons.css` is included in a page.
+/// assert_eq!(icons.class("home"), "sprite sprite-home");
+/// assert_eq!(icons.sheet_route(), "/icons.webp");
+/// assert_eq!(icons.css_route(), "/icons.css");
+///
+/// let router = ServableRouter::new().add_sprite_sheet(&icons);
+/// ```
+#[derive(Clone)]
+pub struct SpriteSheet {
+	/// The route of this sheet, without an extension
+	route: String,
+	images: Vec<(String, &'static StaticAsset)>,
+
+	/// The transformation applied to every image.
+	/// The default is `maxdim(64,64)`.
+	pub cell: TransformerChain,
+
+	/// The transformation applied to the whole sheet.
+	/// The default is empty, which produces a png.
+	pub sheet: TransformerChain,
+
+	/// The number of images in each row of the sheet.
+	/// If `None` (the default), the sheet is about as wide as it is tall.
+	pub columns: Option<u32>,
+
+	/// How long clients may cache the sheet and its maps
+	pub ttl: Option<TimeDelta>,
+
+	generated: Arc<OnceCell<Result<Generated, String>>>,
+}
+
+impl SpriteSheet {
+	/// Create an empty sprite sheet served at `route`, like `/icons`.
+	/// Its routes add an extension to `route`.
+	#[expect(clippy::unwrap_used)]
+	pub fn new(route: impl Into<String>) -> Self {
+		let route: String = route.into();
+		Self {
+			route: route.trim_end_matches('/').to_owned(),
+			images: Vec::new(),
+			cell: "maxdim(64,64)".parse().unwrap(),
+			sheet: "".parse().unwrap(),
+			columns: None,
+			ttl: StaticAsset::DEFAULT_TTL,
+			generated: Arc::new(OnceCell::new()),
+		}
+	}
+
+	/// Add an image to the end of this sheet.
+	/// `name` identifies this image in the map and in css classes, like `home`.
+	#[inline(always)]
+	pub fn with_image(mut self, name: impl Into<String>, asset: &'static StaticAsset) -> Self {
+		self.images.push((name.into(), asset));
+		self
+	}
+
+	/// Set `self.cell`
+	#[inline(always)]
+	pub fn with_cell(mut self, cell: TransformerChain) -> Self {
+		self.cell = cell;
+		self
+	}
+
+	/// Set `self.sheet`
+	#[inline(always)]
+	pub fn with_sheet(mut self, sheet: TransformerChain) -> Self {
+		self.sheet = sheet;
+		self
+	}
+
+	/// Set `self.columns`
+	#[inline(always)]
+	pub fn with_columns(mut self, columns: Option<u32>) -> Self {
+		self.columns = columns;
+		self
+	}
+
+	/// Set `self.ttl`
+	#[inline(always)]
+	pub fn with_ttl(mut self, ttl: Option<TimeDelta>) -> Self {
+		self.ttl = ttl;
+		self
+	}
+
+	/// The route of the sheet, like `/icons.png`
+	pub fn sheet_route(&self) -> String {
+		let extension = self
+			.sheet
+			.output_mime(&mime::IMAGE_PNG)
+			.and_then(|x| ImageFormat::from_mime_type(&x))
+			.and_then(|x| x.extensions_str().first().copied())
+			.unwrap_or("png");
+		format!("{}.{extension}", self.route)
+	}
+
+	/// The route of the json map, like `/icons.json`.
+	///
+	/// The map looks like this:
+	/// ```json
+	/// {
+	/// 	"width": 128,
+	/// 	"height": 64,
+	/// 	"sprites": {
+	/// 		"home": { "x": 0, "y": 0, "width": 64, "height": 64 },
+	/// 		"search": { "x": 64, "y": 0, "width": 64, "height": 48 }
+	/// 	}
+	/// }
+	/// ```
+	pub fn map_route(&self) -> String {
+		format!("{}.json", self.route)
+	}
+
+	/// The route of the stylesheet, like `/icons.css`
+	pub fn css_route(&self) -> String {
+		format!("{}.css", self.route)
+	}
+
+	/// The css classes that show the image named `name`,
+	/// if this sheet's stylesheet is included in the page.
+	pub fn class(&self, name: &str) -> String {
+		format!("sprite sprite-{}", class_name(name))
+	}
+
+	/// Generate this sheet, or get it if it was already generated
+	async fn generated(&self, ctx: &RenderContext) -> Result<&Generated, Rendered<()>> {
+		let generated = self
+			.generated
+			.get_or_init(|| async {
+				let sheet = self.clone();
+				match tokio::task::spawn_blocking(move || sheet.generate()).await {
+					Ok(Ok(x)) => Ok(x),
+					Ok(Err(err)) => Err(err.to_string()),
+					Err(err) => Err(format!("error while generating sprite sheet: {err}")),
+				}
+			})
+			.await;
+
+		generated.as_ref().map_err(|err| {
+			ctx.report_error(err.clone());
+			Rendered {
+				code: StatusCode::INTERNAL_SERVER_ERROR,
+				headers: HeaderMap::new(),
+				body: (),
+				ttl: None,
+				private: false,
+				mime: None,
+				content_length: None,
+			}
+		})
+	}
+
+	/// Generate this sheet
+	fn generate(&self) -> Result<Generated, Error> {
+		let mut cells = Vec::with_capacity(self.images.len());
+		for (name, asset) in &self.images {
+			let format = ImageFormat::from_mime_type(&asset.mime).ok_or_else(|| {
+				crate::transform::TransformBytesError::NotAnImage(asset.mime.to_string())
+			})?;
+
+			let image = image::load_from_memory_with_format(asset.bytes, format)
+				.map_err(crate::transform::TransformBytesError::from)?;
+			cells.push((name.as_str(), self.cell.transform_image(image).into_rgba8()));
+		}
+
+		// Every cell is as large as the largest image
+		let cell_width = cells.iter().map(|x| x.1.width()).max().unwrap_or(0).max(1);
+		let cell_height = cells.iter().map(|x| x.1.height()).max().unwrap_or(0).max(1);
+		let n = u32::try_from(cells.len()).unwrap_or(u32::MAX).max(1);
+		let columns = self
+			.columns
+			.unwrap_or_else(|| f64::from(n).sqrt().ceil() as u32)
+			.clamp(1, n);
+		let rows = n.div_ceil(columns);
+
+		let mut sheet = RgbaImage::new(columns * cell_width, rows * cell_height);
+		let mut map = SpriteMap {
+			width: sheet.width(),
+			height: sheet.height(),
+			sprites: BTreeMap::new(),
+		};
+
+		for (i, (name, image)) in cells.iter().enumerate() {
+			let i = u32::try_from(i).unwrap_or(u32::MAX);
+			let rect = SpriteRect {
+				x: (i % columns) * cell_width,
+				y: (i / columns) * cell_height,
+				width: image.width(),
+				height: image.height(),
+			};
+
+			imageops::replace(&mut sheet, image, i64::from(rect.x), i64::from(rect.y));
+			map.sprites.insert(name, rect);
+		}
+
+		let mut png = Cursor::new(Vec::new());
+		DynamicImage::ImageRgba8(sheet)
+			.write_to(&mut png, ImageFormat::Png)
+			.map_err(crate::transform::TransformBytesError::from)?;
+		let (mime, bytes) = self
+			.sheet
+			.transform_bytes(png.get_ref(), Some(&mime::IMAGE_PNG))?;
+
+		// The stylesheet is served next to the sheet
+		let sheet_route = self.sheet_route();
+		let sheet_file = sheet_route.rsplit('/').next().unwrap_or(&sheet_route);
+		let mut css = format!(
+			".sprite{{display:inline-block;background-image:url(\"{sheet_file}\");background-repeat:no-repeat}}\n"
+		);
+		for (name, rect) in &map.sprites {
+			css.push_str(&format!(
+				".sprite-{}{{width:{}px;height:{}px;background-position:{}px {}px}}\n",
+				class_name(name),
+				rect.width,
+				rect.height,
+				-i64::from(rect.x),
+				-i64::from(rect.y)
+			));
+		}
+
+		Ok(Generated {
+			mime,
+			sheet: bytes,
+			json: serde_json::to_string(&map)?,
+			css,
+		})
+	}
+}
+
+/// The css class of the image named `name`, without the `sprite-` prefix
+fn class_name(name: &str) -> String {
+	name.chars()
+		.map(
+			|x| match x.is_ascii_alphanumeric() || x == '-' || x == '_' {
+				true => x,
+				false => '-',
+			},
+		)
+		.collect()
+}
+
+/// One of the files a [SpriteSheet] is served as
+#[derive(Clone, Copy)]
+enum SpritePart {
+	Sheet,
+	Map,
+	Css,
+}
+
+/// A [Servable] that serves a [SpritePart]
+struct SpriteServable {
+	sheet: SpriteSheet,
+	part: SpritePart,
+}
+
+impl SpriteServable {
+	async fn get(&self, ctx: &RenderContext) -> Rendered<RenderedBody> {
+		let generated = match self.sheet.generated(ctx).await {
+			Ok(x) => x,
+			Err(rend) => return rend.with_body(RenderedBody::Empty),
+		};
+
+		let (mime, body) = match self.part {
+			SpritePart::Sheet => (generated.mime.clone(), generated.sheet.clone()),
+			SpritePart::Map => (mime::APPLICATION_JSON, generated.json.clone().into_bytes()),
+			SpritePart::Css => (mime::TEXT_CSS_UTF_8, generated.css.clone().into_bytes()),
+		};
+
+		Rendered {
+			code: StatusCode::OK,
+			headers: HeaderMap::new(),
+			content_length: Some(body.len() as u64),
+			body: RenderedBody::Bytes(body),
+			ttl: self.sheet.ttl,
+			private: false,
+			mime: Some(mime),
+		}
+	}
+}
+
+impl Servable for SpriteServable {
+	fn head<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<()>> + 'a + Send + Sync>> {
+		Box::pin(async { head_of(self.get(ctx).await) })
+	}
+
+	fn render<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<RenderedBody>> + 'a + Send + Sync>> {
+		Box::pin(self.get(ctx))
+	}
+
+	fn header_template(&self) -> HeaderMap {
+		let mut headers = HeaderMap::with_capacity(1);
+		headers.insert(header::CACHE_CONTROL, cache_control(self.sheet.ttl, false));
+		headers
+	}
+}
+
+impl ServableRouter {
+	/// Serve `sheet` and its maps at [SpriteSheet::sheet_route],
+	/// [SpriteSheet::map_route] and [SpriteSheet::css_route].
+	/// - panics if one of these routes is invalid or already taken, like [Self::add_page].
+	///
+	/// See [Self::try_add_sprite_sheet] for a version of this method that does not panic.
+	pub fn add_sprite_sheet(self, sheet: &SpriteSheet) -> Self {
+		match self.try_add_sprite_sheet(sheet) {
+			Ok(x) => x,
+			Err(err) => panic!("{err}"),
+		}
+	}
+
+	/// Serve `sheet` and its maps.
+	/// Behaves exactly like [Self::add_sprite_sheet], but returns an [Error] instead of panicking.
+	pub fn try_add_sprite_sheet(mut self, sheet: &SpriteSheet) -> Result<Self, Error> {
+		for (route, part) in [
+			(sheet.sheet_route(), SpritePart::Sheet),
+			(sheet.map_route(), SpritePart::Map),
+			(sheet.css_route(), SpritePart::Css),
+		] {
+			self = self.try_add_page(
+				route,
+				SpriteServable {
+					sheet: sheet.clone(),
+					part,
+				},
+			)?;
+		}
+		Ok(self)
+	}
+}
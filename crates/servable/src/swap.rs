@@ -0,0 +1,102 @@
+use axum::{Router, body::Body, http::Request, response::Response};
+use std::{
+	convert::Infallible,
+	sync::{Arc, PoisonError, RwLock},
+	task::{Context, Poll},
+};
+use tower::Service;
+
+use crate::ServableRouter;
+
+/// A [ServableRouter] that may be replaced while it is running,
+/// for example to reload configuration without dropping connections.
+///
+/// Build the next router in the background, then install it with [SwappableRouter::swap].
+/// Requests that have already started finish with the router they started with,
+/// and every later request is answered by the new one.
+///
+/// When a router is swapped in, it keeps the state of the old router
+/// that still applies to it:
+/// - clients banned by the old router's [crate::BanList] stay banned,
+/// - [crate::DownloadQuota] usage is kept for prefixes both routers limit.
+///
+/// Servables keep their own state (like [crate::FailSafe]'s stale responses).
+/// To keep that state across swaps, add the same servable to both routers
+/// with an [Arc] (or a `&'static` reference).
+///
+/// Cloning a [SwappableRouter] is cheap, and clones always serve the same router.
+///
+/// ```rust
+/// use servable::{HtmlPage, ServableRouter, SwappableRouter};
+/// use std::sync::Arc;
+///
+/// let home = Arc::new(HtmlPage::default());
+///
+/// let live = SwappableRouter::new(
+/// 	ServableRouter::new().add_page("/", home.clone()),
+/// );
+/// let app: axum::Router = live.clone().into_router();
+///
+/// // Later, after the configuration changes:
+/// let next = ServableRouter::new()
+/// 	.add_page("/", home.clone())
+/// 	.add_page("/about", HtmlPage::default());
+///
+/// let _old = live.swap(next);
+/// ```
+#[derive(Clone)]
+pub struct SwappableRouter {
+	router: Arc<RwLock<Arc<ServableRouter>>>,
+}
+
+impl SwappableRouter {
+	/// Serve `router` until it is replaced
+	pub fn new(router: ServableRouter) -> Self {
+		Self {
+			router: Arc::new(RwLock::new(Arc::new(router))),
+		}
+	}
+
+	/// The router that is currently serving requests
+	pub fn current(&self) -> Arc<ServableRouter> {
+		self.router
+			.read()
+			.unwrap_or_else(PoisonError::into_inner)
+			.clone()
+	}
+
+	/// Serve every new request with `router`,
+	/// after carrying over the state of the current router.
+	/// Returns the router that was replaced.
+	pub fn swap(&self, router: ServableRouter) -> Arc<ServableRouter> {
+		let mut current = self.router.write().unwrap_or_else(PoisonError::into_inner);
+		router.warm_from(&current);
+		std::mem::replace(&mut *current, Arc::new(router))
+	}
+
+	/// Convenience method.
+	/// Turns this service into a router.
+	///
+	/// Equivalent to:
+	/// ```ignore
+	/// Router::new().fallback_service(self)
+	/// ```
+	#[inline(always)]
+	pub fn into_router<T: Clone + Send + Sync + 'static>(self) -> Router<T> {
+		Router::new().fallback_service(self)
+	}
+}
+
+impl Service<Request<Body>> for SwappableRouter {
+	type Response = Response;
+	type Error = Infallible;
+	type Future = <ServableRouter as Service<Request<Body>>>::Future;
+
+	fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+		Poll::Ready(Ok(()))
+	}
+
+	fn call(&mut self, req: Request<Body>) -> Self::Future {
+		ServableRouter::dispatch(self.current(), req)
+	}
+}
@@ -0,0 +1,258 @@
+use axum::http::{HeaderMap, StatusCode};
+use maud::PreEscaped;
+use serde::Serialize;
+use std::{collections::BTreeMap, pin::Pin};
+
+use crate::{
+	DeviceType, HtmlPage, Preload, RenderContext, RenderMode, Rendered, RenderedBody,
+	servable::Servable,
+};
+
+/// The error a [PageTemplate] may fail with
+pub type TemplateError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Something that renders the contents of a [TemplatePage],
+/// like a [TeraTemplate] or an [AskamaTemplate].
+pub trait PageTemplate: Send + Sync + 'static {
+	/// Render the contents of a page.
+	///
+	/// This should return the contents of the page's `<body>` tag,
+	/// and must never return `<html>` or `<head>`, see [crate::HtmlRender].
+	fn render_template(&self, ctx: &RenderContext) -> Result<String, TemplateError>;
+}
+
+/// The parts of a [RenderContext] that are given to templates.
+///
+/// [TeraTemplate]s get this as `request`,
+/// [AskamaTemplate]s may keep it in a field of their template.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TemplateContext {
+	/// The route that was requested, see [RenderContext::route]
+	pub route: String,
+
+	/// This request's query parameters
+	pub query: BTreeMap<String, String>,
+
+	/// The values of the parameters in the matched route
+	pub params: BTreeMap<String, String>,
+
+	/// The base path of the router serving this request, see [RenderContext::base_path]
+	pub base_path: String,
+
+	/// The scheme and host this router is served at, see [RenderContext::base_url]
+	pub base_url: Option<String>,
+
+	/// True if this request was sent by a mobile device
+	pub mobile: bool,
+
+	/// The id of this request, see [RenderContext::request_id]
+	pub request_id: String,
+}
+
+impl TemplateContext {
+	/// The template context of `ctx`
+	pub fn new(ctx: &RenderContext) -> Self {
+		Self {
+			route: ctx.route.clone(),
+			query: ctx.query.clone(),
+			params: ctx.params.clone(),
+			base_path: ctx.base_path.clone(),
+			base_url: ctx.base_url.clone(),
+			mobile: ctx.client_info.device_type == DeviceType::Mobile,
+			request_id: ctx.request_id.clone(),
+		}
+	}
+}
+
+/// An [HtmlPage] whose contents are rendered by a template engine.
+///
+/// The page's title, scripts, styles and other settings are taken from `page`,
+/// its contents from `template`. The render function of `page` is ignored.
+///
+/// If the template fails to render, the error is reported with
+/// [RenderContext::report_error] and the page is answered with a 500.
+#[derive(Clone)]
+pub struct TemplatePage<T: PageTemplate> {
+	/// The page the template is rendered into
+	pub page: HtmlPage,
+
+	/// The template that renders this page's contents
+	pub template: T,
+}
+
+impl<T: PageTemplate> TemplatePage<T> {
+	/// Render `template` into the default [HtmlPage]
+	pub fn new(template: T) -> Self {
+		Self {
+			page: HtmlPage::default(),
+			template,
+		}
+	}
+
+	/// Set `self.page`
+	#[inline(always)]
+	pub fn with_page(mut self, page: HtmlPage) -> Self {
+		self.page = page;
+		self
+	}
+}
+
+impl<T: PageTemplate> Servable for TemplatePage<T> {
+	#[inline(always)]
+	fn head<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<()>> + 'a + Send + Sync>> {
+		self.page.head(ctx)
+	}
+
+	fn render<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<RenderedBody>> + 'a + Send + Sync>> {
+		Box::pin(async {
+			if ctx.mode == RenderMode::Head {
+				return self.page.head(ctx).await.with_body(RenderedBody::Empty);
+			}
+
+			match self.template.render_template(ctx) {
+				Ok(html) => self.page.render_with(ctx, PreEscaped(html)).await,
+				Err(err) => {
+					ctx.report_error(format!("error while rendering template: {err}"));
+					Rendered {
+						code: StatusCode::INTERNAL_SERVER_ERROR,
+						headers: HeaderMap::new(),
+						body: RenderedBody::Empty,
+						ttl: None,
+						private: false,
+						mime: None,
+						content_length: None,
+					}
+				}
+			}
+		})
+	}
+
+	#[inline(always)]
+	fn header_template(&self) -> HeaderMap {
+		self.page.header_template()
+	}
+
+	#[inline(always)]
+	fn linked_routes(&self) -> Vec<String> {
+		self.page.linked_routes()
+	}
+
+	#[inline(always)]
+	fn preloads(&self) -> Vec<Preload> {
+		self.page.preloads()
+	}
+}
+
+//
+// MARK: tera
+//
+
+/// A [PageTemplate] that renders one template of a [tera::Tera].
+///
+/// The template is given a [TemplateContext] as `request`,
+/// and any values added by [TeraTemplate::with_context].
+///
+/// ```rust
+/// use servable::{ServableRouter, TemplatePage, TeraTemplate};
+/// use std::sync::Arc;
+///
+/// let mut tera = tera::Tera::default();
+/// tera.add_raw_template("post.html", "<h1>Post {{ request.params.id }}</h1>")
+/// 	.unwrap();
+///
+/// let template = TeraTemplate::new(Arc::new(tera), "post.html")
+/// 	.with_context(|_ctx, context| context.insert("author", "me"));
+///
+/// let router = ServableRouter::new().add_page("/post/{id}", TemplatePage::new(template));
+/// ```
+#[cfg(feature = "tera")]
+#[derive(Clone)]
+pub struct TeraTemplate {
+	tera: std::sync::Arc<tera::Tera>,
+	name: String,
+	context: Option<std::sync::Arc<dyn Fn(&RenderContext, &mut tera::Context) + Send + Sync>>,
+}
+
+#[cfg(feature = "tera")]
+impl TeraTemplate {
+	/// Render the template named `name` in `tera`
+	pub fn new(tera: std::sync::Arc<tera::Tera>, name: impl Into<String>) -> Self {
+		Self {
+			tera,
+			name: name.into(),
+			context: None,
+		}
+	}
+
+	/// Add values to this template's context before it is rendered
+	#[inline(always)]
+	pub fn with_context(
+		mut self,
+		context: impl Fn(&RenderContext, &mut tera::Context) + Send + Sync + 'static,
+	) -> Self {
+		self.context = Some(std::sync::Arc::new(context));
+		self
+	}
+}
+
+#[cfg(feature = "tera")]
+impl PageTemplate for TeraTemplate {
+	fn render_template(&self, ctx: &RenderContext) -> Result<String, TemplateError> {
+		let mut context = tera::Context::new();
+		context.insert("request", &TemplateContext::new(ctx));
+		if let Some(f) = &self.context {
+			f(ctx, &mut context);
+		}
+
+		Ok(self.tera.render(&self.name, &context)?)
+	}
+}
+
+//
+// MARK: askama
+//
+
+/// A [PageTemplate] that renders the [askama::Template] returned by a closure.
+///
+/// ```rust
+/// use askama::Template;
+/// use servable::{AskamaTemplate, RenderContext, ServableRouter, TemplateContext, TemplatePage};
+///
+/// #[derive(Template)]
+/// #[template(source = "<h1>Post {{ request.params[\"id\"] }}</h1>", ext = "html")]
+/// struct Post {
+/// 	request: TemplateContext,
+/// }
+///
+/// let page = TemplatePage::new(AskamaTemplate::new(|ctx: &RenderContext| Post {
+/// 	request: TemplateContext::new(ctx),
+/// }));
+///
+/// let router = ServableRouter::new().add_page("/post/{id}", page);
+/// ```
+#[cfg(feature = "askama")]
+#[derive(Clone)]
+pub struct AskamaTemplate<F>(pub F);
+
+#[cfg(feature = "askama")]
+impl<T: askama::Template, F: Fn(&RenderContext) -> T + Send + Sync + 'static> AskamaTemplate<F> {
+	/// Render the templates returned by `template`
+	pub fn new(template: F) -> Self {
+		Self(template)
+	}
+}
+
+#[cfg(feature = "askama")]
+impl<T: askama::Template, F: Fn(&RenderContext) -> T + Send + Sync + 'static> PageTemplate
+	for AskamaTemplate<F>
+{
+	fn render_template(&self, ctx: &RenderContext) -> Result<String, TemplateError> {
+		Ok((self.0)(ctx).render()?)
+	}
+}
@@ -0,0 +1,320 @@
+use axum::{
+	body::Bytes,
+	http::{HeaderMap, StatusCode, header},
+	response::{IntoResponse, Response},
+};
+use http_body::{Body, Frame, SizeHint};
+use std::{
+	collections::{HashMap, VecDeque},
+	convert::Infallible,
+	fmt::Debug,
+	net::IpAddr,
+	pin::Pin,
+	sync::{Arc, Mutex},
+	task::{Context, Poll},
+	time::{Duration, Instant},
+};
+use tokio::time::Sleep;
+
+use crate::{forwarded::client_key, ratelimit::retry_after};
+
+/// The largest chunk a throttled body sends at once
+const MAX_CHUNK: u64 = 16 * 1024;
+
+/// Limit how fast response bodies are sent, see [crate::ServableRouter::with_throttle].
+///
+/// Each response gets its own bucket of `burst` bytes,
+/// which refills at `bytes_per_second`. The body is sent in small chunks,
+/// waiting for the bucket to refill whenever it is empty.
+///
+/// ```rust
+/// use servable::Throttle;
+///
+/// // Send the first 1 MiB at full speed, then 256 KiB per second
+/// let throttle = Throttle::new(256 * 1024).with_burst(1024 * 1024);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Throttle {
+	/// The sustained rate, in bytes per second
+	pub bytes_per_second: u64,
+
+	/// The number of bytes that may be sent at once before throttling starts.
+	/// By default, one second's worth.
+	pub burst: u64,
+}
+
+impl Throttle {
+	/// Send at most `bytes_per_second` bytes per second
+	pub fn new(bytes_per_second: u64) -> Self {
+		let bytes_per_second = bytes_per_second.max(1);
+		Self {
+			bytes_per_second,
+			burst: bytes_per_second,
+		}
+	}
+
+	/// Set `self.burst`
+	#[inline(always)]
+	pub fn with_burst(mut self, burst: u64) -> Self {
+		self.burst = burst;
+		self
+	}
+
+	/// Wrap the body made of `chunks` in a body that is sent at this rate
+	pub(crate) fn body(&self, chunks: Vec<Bytes>) -> ThrottledBody {
+		let chunk = (self.bytes_per_second / 10).clamp(1, MAX_CHUNK);
+		ThrottledBody {
+			chunks: chunks.into_iter().filter(|x| !x.is_empty()).collect(),
+			rate: self.bytes_per_second as f64,
+			burst: self.burst.max(chunk) as f64,
+			chunk: chunk as usize,
+			tokens: self.burst.max(chunk) as f64,
+			updated: tokio::time::Instant::now(),
+			sleep: None,
+		}
+	}
+}
+
+/// A response body that is sent no faster than a [Throttle] allows
+pub(crate) struct ThrottledBody {
+	chunks: VecDeque<Bytes>,
+	rate: f64,
+	burst: f64,
+	chunk: usize,
+
+	/// The bytes we may send right now
+	tokens: f64,
+	updated: tokio::time::Instant,
+
+	/// If `Some`, we're waiting for the bucket to refill
+	sleep: Option<Pin<Box<Sleep>>>,
+}
+
+impl Body for ThrottledBody {
+	type Data = Bytes;
+	type Error = Infallible;
+
+	fn poll_frame(
+		mut self: Pin<&mut Self>,
+		cx: &mut Context<'_>,
+	) -> Poll<Option<Result<Frame<Bytes>, Infallible>>> {
+		if let Some(sleep) = &mut self.sleep {
+			if sleep.as_mut().poll(cx).is_pending() {
+				return Poll::Pending;
+			}
+			self.sleep = None;
+		}
+
+		let Some(len) = self.chunks.front().map(Bytes::len) else {
+			return Poll::Ready(None);
+		};
+
+		let now = tokio::time::Instant::now();
+		let elapsed = now.duration_since(self.updated).as_secs_f64();
+		self.tokens = (self.tokens + elapsed * self.rate).min(self.burst);
+		self.updated = now;
+
+		let n = self.chunk.min(len);
+		if self.tokens < n as f64 {
+			let wait = (n as f64 - self.tokens) / self.rate;
+			let mut sleep = Box::pin(tokio::time::sleep(Duration::from_secs_f64(wait)));
+			// Register for a wakeup
+			if sleep.as_mut().poll(cx).is_pending() {
+				self.sleep = Some(sleep);
+				return Poll::Pending;
+			}
+		}
+
+		self.tokens -= n as f64;
+		let data = match n == len {
+			true => self.chunks.pop_front().unwrap_or_default(),
+			false => self
+				.chunks
+				.front_mut()
+				.map(|x| x.split_to(n))
+				.unwrap_or_default(),
+		};
+		Poll::Ready(Some(Ok(Frame::data(data))))
+	}
+
+	fn is_end_stream(&self) -> bool {
+		self.chunks.is_empty()
+	}
+
+	fn size_hint(&self) -> SizeHint {
+		SizeHint::with_exact(self.chunks.iter().map(|x| x.len() as u64).sum())
+	}
+}
+
+/// One client's usage of a [DownloadQuota]
+struct Usage {
+	/// When this client's window started
+	start: Instant,
+	bytes: u64,
+}
+
+/// A shared limit on how many bytes each client may download,
+/// see [crate::ServableRouter::with_download_quota].
+///
+/// Every client may download `limit` bytes per `window`.
+/// A response that would take a client over its quota is answered
+/// with `429 Too Many Requests` and a `Retry-After` header.
+/// A response larger than `limit` can never be sent, and is answered
+/// with `403 Forbidden` instead, without charging the client.
+/// Responses with a known length are checked before their body is serialized.
+/// Responses are charged in full when they start, even if the client
+/// disconnects before receiving the whole body.
+///
+/// Clients are identified by [crate::RenderContext::client_ip].
+/// IPv6 clients are grouped by their `/64` prefix, like [crate::RateLimited].
+/// Requests without a client ip are never limited.
+///
+/// Cloning a [DownloadQuota] is cheap, and clones share the same usage.
+///
+/// ```rust
+/// use servable::DownloadQuota;
+/// use std::time::Duration;
+///
+/// // 1 GiB per client per day
+/// let quota = DownloadQuota::new(1024 * 1024 * 1024, Duration::from_secs(24 * 3600));
+/// ```
+#[derive(Clone)]
+pub struct DownloadQuota {
+	limit: u64,
+	window: Duration,
+	usage: Arc<Mutex<HashMap<IpAddr, Usage>>>,
+}
+
+impl Debug for DownloadQuota {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("DownloadQuota")
+			.field("limit", &self.limit)
+			.field("window", &self.window)
+			.finish()
+	}
+}
+
+impl DownloadQuota {
+	/// Allow each client `limit` bytes every `window`
+	pub fn new(limit: u64, window: Duration) -> Self {
+		Self {
+			limit,
+			window,
+			usage: Arc::new(Mutex::new(HashMap::new())),
+		}
+	}
+
+	/// The number of bytes `ip` may still download at `now`.
+	/// `now` should come from the router's [crate::Clock].
+	pub fn remaining(&self, ip: IpAddr, now: Instant) -> u64 {
+		let Ok(usage) = self.usage.lock() else {
+			return self.limit;
+		};
+
+		match usage.get(&client_key(ip)) {
+			Some(x) if now < x.start + self.window => self.limit.saturating_sub(x.bytes),
+			_ => self.limit,
+		}
+	}
+
+	/// Copy the usage in `other` into this quota.
+	/// Clients that already have usage here keep it.
+	pub(crate) fn absorb(&self, other: &DownloadQuota) {
+		if Arc::ptr_eq(&self.usage, &other.usage) {
+			return;
+		}
+
+		let Ok(other) = other.usage.lock() else {
+			return;
+		};
+		let Ok(mut usage) = self.usage.lock() else {
+			return;
+		};
+
+		for (ip, x) in other.iter() {
+			usage.entry(*ip).or_insert(Usage {
+				start: x.start,
+				bytes: x.bytes,
+			});
+		}
+	}
+
+	/// Forget windows that ended before `now`
+	pub(crate) fn prune(&self, now: Instant) {
+		if let Ok(mut usage) = self.usage.lock() {
+			usage.retain(|_, x| now < x.start + self.window);
+		}
+	}
+
+	/// Check if `ip` may download `bytes` at `now`, without charging it
+	pub(crate) fn check(&self, ip: IpAddr, now: Instant, bytes: u64) -> Result<(), QuotaExceeded> {
+		self.charge(ip, now, bytes, false)
+	}
+
+	/// Charge `bytes` to `ip`'s quota, if that doesn't exceed it
+	pub(crate) fn take(&self, ip: IpAddr, now: Instant, bytes: u64) -> Result<(), QuotaExceeded> {
+		self.charge(ip, now, bytes, true)
+	}
+
+	fn charge(&self, ip: IpAddr, now: Instant, bytes: u64, take: bool) -> Result<(), QuotaExceeded> {
+		if bytes > self.limit {
+			return Err(QuotaExceeded::TooLarge);
+		}
+
+		let Ok(mut usage) = self.usage.lock() else {
+			return Ok(());
+		};
+
+		// Expired windows are forgotten by [Self::prune],
+		// but may still be here.
+		let key = client_key(ip);
+		let used = match usage.get(&key) {
+			Some(x) if now < x.start + self.window => x.bytes,
+			_ => 0,
+		};
+
+		if used.saturating_add(bytes) > self.limit {
+			let start = usage.get(&key).map(|x| x.start).unwrap_or(now);
+			return Err(QuotaExceeded::Wait(
+				(start + self.window).saturating_duration_since(now),
+			));
+		}
+
+		if take {
+			let entry = usage.entry(key).or_insert(Usage {
+				start: now,
+				bytes: 0,
+			});
+			if now >= entry.start + self.window {
+				*entry = Usage {
+					start: now,
+					bytes: 0,
+				};
+			}
+			entry.bytes += bytes;
+		}
+		Ok(())
+	}
+}
+
+/// Why a [DownloadQuota] refused a response
+pub(crate) enum QuotaExceeded {
+	/// The response is larger than the whole quota
+	TooLarge,
+
+	/// The client may retry after this long
+	Wait(Duration),
+}
+
+impl IntoResponse for QuotaExceeded {
+	fn into_response(self) -> Response {
+		match self {
+			Self::TooLarge => StatusCode::FORBIDDEN.into_response(),
+			Self::Wait(wait) => {
+				let mut headers = HeaderMap::with_capacity(1);
+				headers.insert(header::RETRY_AFTER, retry_after(wait));
+				(StatusCode::TOO_MANY_REQUESTS, headers).into_response()
+			}
+		}
+	}
+}
@@ -0,0 +1,181 @@
+use maud::{Markup, PreEscaped, html};
+use std::collections::HashSet;
+
+use crate::scan::{attribute, find_ignore_case, rewrite_tags};
+
+/// The names of all heading tags
+const HEADINGS: [&str; 6] = ["h1", "h2", "h3", "h4", "h5", "h6"];
+
+/// Replaced by a table of contents in pages with [HeadingAnchors].
+/// See [crate::HtmlPage::with_heading_anchors].
+pub const TOC_PLACEHOLDER: PreEscaped<&str> = PreEscaped("<!--servable-toc-->");
+
+/// A heading in an html document, see [HeadingAnchors]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Heading {
+	/// The level of this heading, from 1 (`<h1>`) to 6 (`<h6>`)
+	pub level: u8,
+
+	/// This heading's `id`
+	pub id: String,
+
+	/// The text in this heading, without tags
+	pub text: String,
+}
+
+/// Assigns stable `id`s to the headings in an html document,
+/// and generates a table of contents.
+///
+/// Ids are derived from the text of each heading, so they only change
+/// when that text does. Headings that already have an `id` keep it.
+///
+/// ```rust
+/// use servable::HeadingAnchors;
+///
+/// let (html, headings) = HeadingAnchors::default()
+/// 	.anchor("<h2>Getting started</h2><h2>Getting started</h2><h3 id=\"x\">More</h3>");
+///
+/// assert_eq!(
+/// 	html,
+/// 	"<h2 id=\"getting-started\">Getting started</h2>\
+/// 	<h2 id=\"getting-started-1\">Getting started</h2>\
+/// 	<h3 id=\"x\">More</h3>"
+/// );
+/// assert_eq!(headings[2].id, "x");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HeadingAnchors {
+	/// The smallest heading level included in the table of contents
+	pub toc_min_level: u8,
+
+	/// The largest heading level included in the table of contents
+	pub toc_max_level: u8,
+}
+
+impl Default for HeadingAnchors {
+	fn default() -> Self {
+		Self {
+			toc_min_level: 2,
+			toc_max_level: 3,
+		}
+	}
+}
+
+impl HeadingAnchors {
+	/// Set `self.toc_min_level` and `self.toc_max_level`
+	#[inline(always)]
+	pub fn with_toc_levels(mut self, min: u8, max: u8) -> Self {
+		self.toc_min_level = min;
+		self.toc_max_level = max;
+		self
+	}
+
+	/// Add an `id` to every heading in `html`.
+	/// Returns the new html and all headings in it, in order.
+	///
+	/// Headings inside `<script>` and `<style>` tags are ignored.
+	pub fn anchor(&self, html: &str) -> (String, Vec<Heading>) {
+		let mut headings = Vec::new();
+		let mut used = HashSet::new();
+
+		let out = rewrite_tags(html, &HEADINGS, |name, open, after| {
+			let level = name.as_bytes()[1] - b'0';
+			let content_end = find_ignore_case(after, &format!("</{name}")).unwrap_or(after.len());
+			let text = strip_tags(&after[..content_end]);
+
+			let (id, open) = match attribute(open, "id") {
+				Some(id) => (id.to_owned(), None),
+				None => {
+					let id = unique_slug(&text, &used);
+					let open = format!("{} id=\"{id}\">", &open[..open.len() - 1]);
+					(id, Some(open))
+				}
+			};
+
+			used.insert(id.clone());
+			headings.push(Heading { level, id, text });
+			open
+		});
+
+		(out, headings)
+	}
+
+	/// Generate a table of contents that links to `headings`.
+	/// Headings outside of this struct's toc levels are skipped.
+	///
+	/// This is a flat `<nav class="toc">` with one `<li>` per heading.
+	/// Each `<li>` has the class `toc-h{level}`, use css to indent them.
+	pub fn toc(&self, headings: &[Heading]) -> Markup {
+		html! {
+			nav class="toc" {
+				ul {
+					@for h in headings {
+						@if h.level >= self.toc_min_level && h.level <= self.toc_max_level {
+							li class=(format!("toc-h{}", h.level)) {
+								a href=(format!("#{}", h.id)) { (h.text) }
+							}
+						}
+					}
+				}
+			}
+		}
+	}
+
+	/// Add an `id` to every heading in `html`,
+	/// then replace the first [TOC_PLACEHOLDER] with a table of contents.
+	pub fn apply(&self, html: &str) -> String {
+		let (html, headings) = self.anchor(html);
+		match html.contains(TOC_PLACEHOLDER.0) {
+			true => html.replacen(TOC_PLACEHOLDER.0, &self.toc(&headings).0, 1),
+			false => html,
+		}
+	}
+}
+
+/// Remove all tags from `html` and decode common entities
+fn strip_tags(html: &str) -> String {
+	let mut out = String::with_capacity(html.len());
+	let mut in_tag = false;
+	for c in html.chars() {
+		match (in_tag, c) {
+			(false, '<') => in_tag = true,
+			(true, '>') => in_tag = false,
+			(false, c) => out.push(c),
+			(true, _) => {}
+		}
+	}
+
+	out.replace("&lt;", "<")
+		.replace("&gt;", ">")
+		.replace("&quot;", "\"")
+		.replace("&#39;", "'")
+		.replace("&nbsp;", " ")
+		.replace("&amp;", "&")
+		.trim()
+		.to_owned()
+}
+
+/// Make a url fragment from `text` that is not in `used`
+fn unique_slug(text: &str, used: &HashSet<String>) -> String {
+	let mut slug = String::with_capacity(text.len());
+	for c in text.chars() {
+		if c.is_alphanumeric() {
+			slug.extend(c.to_lowercase());
+		} else if (c.is_whitespace() || c == '-' || c == '_') && !slug.ends_with('-') {
+			slug.push('-');
+		}
+	}
+
+	let slug = match slug.trim_matches('-') {
+		"" => "section",
+		x => x,
+	};
+
+	let mut id = slug.to_owned();
+	let mut n = 1;
+	while used.contains(&id) {
+		id = format!("{slug}-{n}");
+		n += 1;
+	}
+	id
+}
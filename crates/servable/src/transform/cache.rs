@@ -0,0 +1,102 @@
+use axum::body::Bytes;
+use mime::Mime;
+use std::{
+	collections::{HashMap, VecDeque},
+	sync::{Arc, LazyLock, Mutex},
+};
+
+/// The maximum total size of all images in [TRANSFORM_CACHE], in bytes
+const CACHE_SIZE: usize = 64 * 1024 * 1024;
+
+/// Identifies one transformation of one image:
+/// `(input address, input length, transformer chain)`
+type CacheKey = (usize, usize, String);
+
+/// A transformed image
+pub(crate) type CachedImage = Arc<(Mime, Bytes)>;
+
+/// A small cache of transformed images.
+///
+/// This makes repeated requests for the same transformed image cheap,
+/// which is common with range requests (i.e, from media players and
+/// scroll-restoring browsers).
+pub(crate) static TRANSFORM_CACHE: LazyLock<TransformCache> = LazyLock::new(TransformCache::new);
+
+pub(crate) struct TransformCache {
+	inner: Mutex<TransformCacheInner>,
+}
+
+struct TransformCacheInner {
+	images: HashMap<CacheKey, CachedImage>,
+
+	/// Keys in `images`, least recently used first
+	order: VecDeque<CacheKey>,
+
+	/// The total size of all images in `images`
+	size: usize,
+}
+
+impl TransformCache {
+	fn new() -> Self {
+		Self {
+			inner: Mutex::new(TransformCacheInner {
+				images: HashMap::new(),
+				order: VecDeque::new(),
+				size: 0,
+			}),
+		}
+	}
+
+	fn key(input: &'static [u8], chain: &str) -> CacheKey {
+		(input.as_ptr() as usize, input.len(), chain.to_owned())
+	}
+
+	/// Get the result of transforming `input` with `chain`,
+	/// and mark it as recently used.
+	pub(crate) fn get(&self, input: &'static [u8], chain: &str) -> Option<CachedImage> {
+		let mut inner = self.inner.lock().ok()?;
+		let key = Self::key(input, chain);
+		let image = inner.images.get(&key).cloned()?;
+
+		if let Some(i) = inner.order.iter().position(|x| *x == key) {
+			inner.order.remove(i);
+			inner.order.push_back(key);
+		}
+
+		Some(image)
+	}
+
+	/// Remember the result of transforming `input` with `chain`.
+	/// Images larger than this cache are ignored.
+	/// Returns true if this image is now in the cache.
+	pub(crate) fn insert(&self, input: &'static [u8], chain: &str, image: CachedImage) -> bool {
+		let len = image.1.len();
+		if len > CACHE_SIZE {
+			return false;
+		}
+
+		let Ok(mut inner) = self.inner.lock() else {
+			return false;
+		};
+
+		let key = Self::key(input, chain);
+		if inner.images.contains_key(&key) {
+			return true;
+		}
+
+		while inner.size + len > CACHE_SIZE {
+			let Some(old) = inner.order.pop_front() else {
+				break;
+			};
+
+			if let Some(old) = inner.images.remove(&old) {
+				inner.size -= old.1.len();
+			}
+		}
+
+		inner.size += len;
+		inner.order.push_back(key.clone());
+		inner.images.insert(key, image);
+		true
+	}
+}
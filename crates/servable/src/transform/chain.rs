@@ -1,10 +1,13 @@
-use image::{DynamicImage, ImageFormat};
+use image::{
+	DynamicImage, ImageDecoder, ImageEncoder, ImageFormat, ImageReader, ImageResult, Rgb, RgbImage,
+	codecs::{jpeg::JpegEncoder, png::PngEncoder, webp::WebPEncoder},
+};
 use mime::Mime;
 use serde::{Deserialize, Deserializer, de};
 use std::{fmt::Display, hash::Hash, io::Cursor, str::FromStr};
 use thiserror::Error;
 
-use super::transformers::{ImageTransformer, TransformerEnum};
+use super::transformers::{ImageTransformer, TransformerEnum, supports_alpha};
 
 #[expect(missing_docs)]
 #[derive(Debug, Error)]
@@ -19,7 +22,21 @@ pub enum TransformBytesError {
 	ImageError(#[from] image::ImageError),
 }
 
-/// A sequence of transformations to apply to an image
+/// A sequence of transformations to apply to an image.
+///
+/// Chains are normalized when they are parsed, so that equivalent chains
+/// have the same string (see [Display]), which is used as their cache key:
+/// whitespace is removed, format aliases (like `jpeg`) are replaced by one name,
+/// percentages are rounded to two decimals, and steps that never change an image are dropped.
+///
+/// ```rust
+/// use servable::transform::TransformerChain;
+///
+/// let a: TransformerChain = "maxdim(100vw, 100vh); crop(50, 50, c); format(JPEG)".parse().unwrap();
+/// let b: TransformerChain = "crop(50,50,c);format(jpg)".parse().unwrap();
+/// assert_eq!(a.to_string(), b.to_string());
+/// assert_eq!(a, b);
+/// ```
 #[derive(Debug, Clone)]
 pub struct TransformerChain {
 	steps: Vec<TransformerEnum>,
@@ -29,7 +46,7 @@ impl TransformerChain {
 	/// Returns `true` if `mime` is a type that can be transformed
 	#[inline(always)]
 	pub fn mime_is_image(mime: &Mime) -> bool {
-		ImageFormat::from_mime_type(mime.to_string()).is_some()
+		ImageFormat::from_mime_type(mime).is_some()
 	}
 
 	/// Transform the given image using this chain
@@ -40,6 +57,8 @@ impl TransformerChain {
 				TransformerEnum::Format { .. } => {}
 				TransformerEnum::MaxDim(t) => t.transform(&mut image),
 				TransformerEnum::Crop(t) => t.transform(&mut image),
+				#[cfg(feature = "quantize")]
+				TransformerEnum::Quantize(t) => t.transform(&mut image),
 			}
 		}
 
@@ -55,14 +74,14 @@ impl TransformerChain {
 			.steps
 			.last()
 			.and_then(|x| match x {
-				TransformerEnum::Format { format } => Some(
+				TransformerEnum::Format { format, .. } => Some(
 					Mime::from_str(format.to_mime_type()).unwrap_or(mime::APPLICATION_OCTET_STREAM),
 				),
 				_ => None,
 			})
 			.unwrap_or(input_mime.clone());
 
-		let fmt = ImageFormat::from_mime_type(mime.to_string());
+		let fmt = ImageFormat::from_mime_type(&mime);
 		fmt.map(|_| mime)
 	}
 
@@ -71,38 +90,227 @@ impl TransformerChain {
 	///
 	/// `image_format` tells us the type of `image_bytes`.
 	/// If it is `None`, we try to infer it.
+	///
+	/// If `image_bytes` has an embedded color profile, it is copied into
+	/// png, jpeg and webp output. With the `color-management` feature,
+	/// the image is converted to sRGB instead.
 	pub fn transform_bytes(
 		&self,
 		image_bytes: &[u8],
 		image_format: Option<&Mime>,
 	) -> Result<(Mime, Vec<u8>), TransformBytesError> {
 		let format: ImageFormat = match image_format {
-			Some(x) => ImageFormat::from_mime_type(x.to_string())
+			Some(x) => ImageFormat::from_mime_type(x)
 				.ok_or(TransformBytesError::NotAnImage(x.to_string()))?,
 			None => image::guess_format(image_bytes)?,
 		};
 
-		let out_format = self
-			.steps
-			.last()
-			.and_then(|x| match x {
-				TransformerEnum::Format { format } => Some(format),
-				_ => None,
-			})
-			.unwrap_or(&format);
+		let (out_format, background) = match self.steps.last() {
+			Some(TransformerEnum::Format { format, background }) => (format, *background),
+			_ => (&format, TransformerEnum::DEFAULT_BACKGROUND),
+		};
 
-		let img = image::load_from_memory_with_format(image_bytes, format)?;
-		let img = self.transform_image(img);
+		let mut decoder =
+			ImageReader::with_format(Cursor::new(image_bytes), format).into_decoder()?;
+		let icc = decoder.icc_profile().ok().flatten();
+		let img = DynamicImage::from_decoder(decoder)?;
+
+		#[cfg(feature = "color-management")]
+		let (img, icc) = match icc {
+			Some(icc) => to_srgb(img, icc),
+			None => (img, None),
+		};
+
+		let mut img = self.transform_image(img);
+		if !supports_alpha(*out_format) && img.color().has_alpha() {
+			img = flatten(img, background);
+		}
 
 		let out_mime =
 			Mime::from_str(out_format.to_mime_type()).unwrap_or(mime::APPLICATION_OCTET_STREAM);
-		let mut out_bytes = Cursor::new(Vec::new());
-		img.write_to(&mut out_bytes, *out_format)?;
 
-		return Ok((out_mime, out_bytes.into_inner()));
+		#[cfg(feature = "quantize")]
+		if *out_format == ImageFormat::Png
+			&& self
+				.steps
+				.iter()
+				.any(|x| matches!(x, TransformerEnum::Quantize(_)))
+			&& let Some(out_bytes) = encode_indexed_png(&img, icc.as_deref())?
+		{
+			return Ok((out_mime, out_bytes));
+		}
+
+		let out_bytes = encode(&img, *out_format, icc)?;
+
+		return Ok((out_mime, out_bytes));
 	}
 }
 
+/// Encode `image` as `format`, embedding the color profile `icc`
+/// if `format` supports it.
+fn encode(image: &DynamicImage, format: ImageFormat, icc: Option<Vec<u8>>) -> ImageResult<Vec<u8>> {
+	let mut out = Cursor::new(Vec::new());
+
+	fn write_with_icc(
+		image: &DynamicImage,
+		mut encoder: impl ImageEncoder,
+		icc: Vec<u8>,
+	) -> ImageResult<()> {
+		if let Err(err) = encoder.set_icc_profile(icc) {
+			tracing::debug!(message = "could not embed color profile", ?err);
+		}
+		image.write_with_encoder(encoder)
+	}
+
+	// These are the encoders `write_to` uses for these formats
+	match (format, icc) {
+		(ImageFormat::Png, Some(icc)) => write_with_icc(image, PngEncoder::new(&mut out), icc)?,
+		(ImageFormat::Jpeg, Some(icc)) => write_with_icc(image, JpegEncoder::new(&mut out), icc)?,
+		(ImageFormat::WebP, Some(icc)) => {
+			write_with_icc(image, WebPEncoder::new_lossless(&mut out), icc)?
+		}
+		_ => image.write_to(&mut out, format)?,
+	}
+
+	Ok(out.into_inner())
+}
+
+/// Encode `image` as a png with indexed colors, embedding the color profile `icc`.
+/// Returns `None` if `image` has more than 256 colors.
+#[cfg(feature = "quantize")]
+fn encode_indexed_png(image: &DynamicImage, icc: Option<&[u8]>) -> ImageResult<Option<Vec<u8>>> {
+	use image::error::{EncodingError, ImageError, ImageFormatHint};
+	use png::{BitDepth, ColorType, Encoder, Info};
+	use std::borrow::Cow;
+
+	let image = image.to_rgba8();
+	let Some(palette) = super::transformers::palette(&image) else {
+		return Ok(None);
+	};
+
+	let mut colors = vec![[0u8; 4]; palette.len()];
+	for (color, i) in &palette {
+		colors[usize::from(*i)] = *color;
+	}
+
+	// Use the smallest bit depth that fits the palette
+	let (depth, bit_depth) = match colors.len() {
+		0..=2 => (1, BitDepth::One),
+		3..=4 => (2, BitDepth::Two),
+		5..=16 => (4, BitDepth::Four),
+		_ => (8, BitDepth::Eight),
+	};
+
+	let per_byte = 8 / depth;
+	let row_len = (image.width() as usize).div_ceil(per_byte);
+	let mut data = vec![0u8; row_len * image.height() as usize];
+	for (x, y, pixel) in image.enumerate_pixels() {
+		let (x, y) = (x as usize, y as usize);
+		let index = palette.get(&pixel.0).copied().unwrap_or_default();
+		data[y * row_len + x / per_byte] |= index << (8 - depth * (x % per_byte + 1));
+	}
+
+	let mut info = Info::with_size(image.width(), image.height());
+	info.color_type = ColorType::Indexed;
+	info.bit_depth = bit_depth;
+	info.palette = Some(Cow::Owned(
+		colors.iter().flat_map(|x| [x[0], x[1], x[2]]).collect(),
+	));
+	info.icc_profile = icc.map(Cow::Borrowed);
+
+	// Trailing opaque entries may be left out of `tRNS`
+	let alpha: Vec<u8> = colors.iter().map(|x| x[3]).collect();
+	if let Some(last) = alpha.iter().rposition(|x| *x != 255) {
+		info.trns = Some(Cow::Owned(alpha[..=last].to_vec()));
+	}
+
+	let err = |err: png::EncodingError| {
+		ImageError::Encoding(EncodingError::new(
+			ImageFormatHint::Exact(ImageFormat::Png),
+			err,
+		))
+	};
+
+	let mut out = Vec::new();
+	let mut writer = Encoder::with_info(&mut out, info)
+		.and_then(|x| x.write_header())
+		.map_err(err)?;
+	writer.write_image_data(&data).map_err(err)?;
+	writer.finish().map_err(err)?;
+
+	Ok(Some(out))
+}
+
+/// Convert `image` from the color profile `icc` to sRGB.
+///
+/// Returns the converted image and no profile, or
+/// `image` and `icc` unchanged if `icc` could not be applied.
+#[cfg(feature = "color-management")]
+fn to_srgb(image: DynamicImage, icc: Vec<u8>) -> (DynamicImage, Option<Vec<u8>>) {
+	use moxcms::{ColorProfile, Layout, TransformOptions};
+
+	let Ok(profile) = ColorProfile::new_from_slice(&icc) else {
+		return (image, Some(icc));
+	};
+
+	let srgb = ColorProfile::new_srgb();
+	let layout = match image.color().has_alpha() {
+		true => Layout::Rgba,
+		false => Layout::Rgb,
+	};
+
+	let Ok(transform) =
+		profile.create_transform_8bit(layout, &srgb, layout, TransformOptions::default())
+	else {
+		return (image, Some(icc));
+	};
+
+	let converted = match layout {
+		Layout::Rgba => {
+			let src = image.to_rgba8();
+			let mut dst = src.clone();
+			transform
+				.transform(src.as_raw(), &mut dst)
+				.map(|()| DynamicImage::ImageRgba8(dst))
+		}
+		_ => {
+			let src = image.to_rgb8();
+			let mut dst = src.clone();
+			transform
+				.transform(src.as_raw(), &mut dst)
+				.map(|()| DynamicImage::ImageRgb8(dst))
+		}
+	};
+
+	match converted {
+		Ok(x) => (x, None),
+		Err(err) => {
+			tracing::debug!(message = "could not convert image to sRGB", ?err);
+			(image, Some(icc))
+		}
+	}
+}
+
+/// Blend `image` onto `background`, removing its alpha channel
+fn flatten(image: DynamicImage, background: [u8; 3]) -> DynamicImage {
+	let image = image.into_rgba8();
+	let flat = RgbImage::from_fn(image.width(), image.height(), |x, y| {
+		let [r, g, b, a] = image.get_pixel(x, y).0;
+		let blend = |c: u8, bg: u8| {
+			let (c, bg, a) = (u16::from(c), u16::from(bg), u16::from(a));
+			((c * a + bg * (255 - a) + 127) / 255) as u8
+		};
+
+		Rgb([
+			blend(r, background[0]),
+			blend(g, background[1]),
+			blend(b, background[2]),
+		])
+	});
+
+	DynamicImage::ImageRgb8(flat)
+}
+
 impl FromStr for TransformerChain {
 	type Err = String;
 	fn from_str(s: &str) -> Result<Self, Self::Err> {
@@ -126,7 +334,7 @@ impl FromStr for TransformerChain {
 			.iter()
 			.filter(|x| matches!(x, TransformerEnum::Format { .. }))
 			.count();
-		if n_format > 2 {
+		if n_format > 1 {
 			return Err("provide at most one format()".to_owned());
 		}
 
@@ -134,10 +342,23 @@ impl FromStr for TransformerChain {
 			return Err("format() must be last".to_owned());
 		}
 
+		steps.retain(|x| !x.is_noop());
 		return Ok(Self { steps });
 	}
 }
 
+/// The canonical form of a raw query parameter `t=...` (see [TransformerChain]).
+/// Returns `None` if `param` is not a valid transformation.
+pub(crate) fn canonical_param(param: &str) -> Option<String> {
+	if !param.starts_with("t=") {
+		return None;
+	}
+
+	let value: Vec<(String, String)> = serde_urlencoded::from_str(param).ok()?;
+	let chain = TransformerChain::from_str(&value.first()?.1).ok()?;
+	Some(format!("t={chain}"))
+}
+
 impl<'de> Deserialize<'de> for TransformerChain {
 	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
 	where
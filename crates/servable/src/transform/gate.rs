@@ -0,0 +1,74 @@
+use axum::http::HeaderMap;
+use std::pin::Pin;
+
+use crate::{RenderContext, Rendered, RenderedBody, servable::Servable};
+
+/// A [Servable] that ignores image transformations (`?t=`),
+/// so its image is always served unchanged.
+///
+/// Use [crate::ServableRouter::with_transform_prefix] to allow transformations
+/// only under some routes.
+///
+/// ```rust
+/// use servable::{ServableRouter, StaticAsset, transform::NoTransforms};
+///
+/// let logo = StaticAsset {
+/// 	bytes: b"fake image",
+/// 	br: None,
+/// 	gz: None,
+/// 	mime: mime::IMAGE_PNG,
+/// 	ttl: StaticAsset::DEFAULT_TTL,
+/// };
+///
+/// let router = ServableRouter::new().add_page("/logo.png", NoTransforms::new(logo));
+/// ```
+pub struct NoTransforms<S: Servable> {
+	inner: S,
+}
+
+impl<S: Servable> NoTransforms<S> {
+	/// Serve `inner` without transformations
+	#[inline(always)]
+	pub fn new(inner: S) -> Self {
+		Self { inner }
+	}
+
+	/// A copy of `ctx` that does not allow transformations
+	fn untransformed(ctx: &RenderContext) -> RenderContext {
+		RenderContext {
+			transforms: false,
+			..ctx.clone()
+		}
+	}
+}
+
+impl<S: Servable> Servable for NoTransforms<S> {
+	fn head<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<()>> + 'a + Send + Sync>> {
+		Box::pin(async { self.inner.head(&Self::untransformed(ctx)).await })
+	}
+
+	fn render<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<RenderedBody>> + 'a + Send + Sync>> {
+		Box::pin(async { self.inner.render(&Self::untransformed(ctx)).await })
+	}
+
+	#[inline(always)]
+	fn header_template(&self) -> HeaderMap {
+		self.inner.header_template()
+	}
+
+	#[inline(always)]
+	fn linked_routes(&self) -> Vec<String> {
+		self.inner.linked_routes()
+	}
+
+	#[inline(always)]
+	fn preloads(&self) -> Vec<crate::Preload> {
+		self.inner.preloads()
+	}
+}
@@ -1,9 +1,15 @@
 //! Provides simple server-side image optimization
 //! using query parameters.
 
+mod cache;
+pub(crate) use cache::*;
+
 mod pixeldim;
 
 pub mod transformers;
 
 mod chain;
 pub use chain::*;
+
+mod gate;
+pub use gate::*;
@@ -13,10 +13,17 @@ pub enum PixelDim {
 	HeightPercent(f32),
 }
 
+/// Round a percentage to the precision it is displayed with,
+/// so that percentages with the same string are the same
+fn round_percent(x: f32) -> f32 {
+	(x * 100.0).round() / 100.0
+}
+
 impl FromStr for PixelDim {
 	type Err = String;
 
 	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let s = s.trim();
 		let numeric_end = s.find(|c: char| !c.is_ascii_digit() && c != '.');
 
 		let (quantity, unit) = numeric_end.map(|x| s.split_at(x)).unwrap_or((s, "px"));
@@ -24,17 +31,17 @@ impl FromStr for PixelDim {
 		let unit = unit.trim();
 
 		match unit {
-			"vw" => Ok(PixelDim::WidthPercent(
+			"vw" => Ok(PixelDim::WidthPercent(round_percent(
 				quantity
 					.parse()
 					.map_err(|_err| format!("invalid quantity {quantity}"))?,
-			)),
+			))),
 
-			"vh" => Ok(PixelDim::HeightPercent(
+			"vh" => Ok(PixelDim::HeightPercent(round_percent(
 				quantity
 					.parse()
 					.map_err(|_err| format!("invalid quantity {quantity}"))?,
-			)),
+			))),
 
 			"px" => Ok(PixelDim::Pixels(
 				quantity
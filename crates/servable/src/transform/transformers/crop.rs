@@ -175,6 +175,13 @@ impl ImageTransformer for CropTransformer {
 		})
 	}
 
+	fn is_noop(&self) -> bool {
+		matches!(
+			(&self.w, &self.h),
+			(PixelDim::WidthPercent(w), PixelDim::HeightPercent(h)) if *w >= 100.0 && *h >= 100.0
+		)
+	}
+
 	fn transform(&self, input: &mut DynamicImage) {
 		let (img_width, img_height) = (input.width(), input.height());
 		let (crop_width, crop_height) = self.crop_dim(img_width, img_height);
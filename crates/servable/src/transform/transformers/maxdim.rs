@@ -74,6 +74,23 @@ impl ImageTransformer for MaxDimTransformer {
 		Ok(Self { w, h })
 	}
 
+	fn is_noop(&self) -> bool {
+		// Images are never scaled up, and a dimension in the other axis is not a limit
+		let w = match self.w {
+			PixelDim::Pixels(_) => false,
+			PixelDim::WidthPercent(x) => x >= 100.0,
+			PixelDim::HeightPercent(_) => true,
+		};
+
+		let h = match self.h {
+			PixelDim::Pixels(_) => false,
+			PixelDim::HeightPercent(x) => x >= 100.0,
+			PixelDim::WidthPercent(_) => true,
+		};
+
+		w && h
+	}
+
 	fn transform(&self, input: &mut DynamicImage) {
 		let (img_width, img_height) = (input.width(), input.height());
 		let (target_width, target_height) = self.target_dim(img_width, img_height);
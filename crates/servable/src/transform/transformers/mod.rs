@@ -11,6 +11,11 @@ pub use crop::*;
 mod maxdim;
 pub use maxdim::*;
 
+#[cfg(feature = "quantize")]
+mod quantize;
+#[cfg(feature = "quantize")]
+pub use quantize::*;
+
 /// A single transformation that may be applied to an image.
 pub trait ImageTransformer
 where
@@ -25,6 +30,12 @@ where
 	///
 	/// `name({arg_string})`
 	fn parse_args(args: &str) -> Result<Self, String>;
+
+	/// Returns true if this transformation never changes an image.
+	/// These steps are removed from parsed [crate::transform::TransformerChain]s.
+	fn is_noop(&self) -> bool {
+		false
+	}
 }
 
 use serde::{Deserialize, Deserializer};
@@ -59,12 +70,33 @@ pub enum TransformerEnum {
 	/// For example, `maxdim(50,100vh)` will not limit width.
 	Crop(CropTransformer),
 
-	/// Usage: `format(format)`
+	/// Usage: `quantize(colors)`
+	///
+	/// Reduce the image to a palette of at most `colors` colors,
+	/// between 2 and 256. Images that already have few enough colors are unchanged.
+	///
+	/// Quantized png images are written with indexed colors,
+	/// which is usually much smaller than truecolor png.
+	/// Steps after this one (like `maxdim`) may add colors back,
+	/// so this should be the last step before `format`.
+	///
+	/// Examples:
+	/// - `quantize(256)`
+	/// - `maxdim(64,64);quantize(16);format(png)`
+	#[cfg(feature = "quantize")]
+	Quantize(QuantizeTransformer),
+
+	/// Usage: `format(format)` or `format(format, bg=color)`
 	///
 	/// Transcode the image to the given format.
 	/// This step must be last, and cannot be provided
 	/// more than once.
 	///
+	/// Formats without transparency (jpeg) can't keep an image's alpha channel,
+	/// so transparent pixels are blended onto `bg`, a hex color like `fff` or `#1a2b3c`.
+	/// The default is white. In urls, `#` must be written as `%23` (or left out).
+	/// `bg` is ignored for formats with transparency.
+	///
 	/// Valid formats:
 	/// - bmp
 	/// - gif
@@ -74,8 +106,9 @@ pub enum TransformerEnum {
 	/// - qoi
 	/// - webp
 	///
-	/// Example:
+	/// Examples:
 	/// - `format(png)`
+	/// - `format(jpeg, bg=000)` (transparent areas become black)
 	///
 	/// When transcoding an animated gif, the first frame is taken
 	/// and all others are thrown away. This happens even if we
@@ -83,6 +116,10 @@ pub enum TransformerEnum {
 	Format {
 		/// The format to produce
 		format: ImageFormat,
+
+		/// The color transparent pixels are blended onto,
+		/// if `format` has no transparency
+		background: [u8; 3],
 	},
 }
 
@@ -138,16 +175,79 @@ impl FromStr for TransformerEnum {
 			"maxdim" => Ok(Self::MaxDim(MaxDimTransformer::parse_args(args)?)),
 			"crop" => Ok(Self::Crop(CropTransformer::parse_args(args)?)),
 
-			"format" => Ok(TransformerEnum::Format {
-				format: ImageFormat::from_extension(args)
-					.ok_or(format!("invalid image format {args}"))?,
-			}),
+			#[cfg(feature = "quantize")]
+			"quantize" => Ok(Self::Quantize(QuantizeTransformer::parse_args(args)?)),
+
+			"format" => parse_format(args),
 
 			_ => Err(format!("unknown transformation {name}")),
 		}
 	}
 }
 
+/// Parse the args of `format(format, bg=color)`
+fn parse_format(args: &str) -> Result<TransformerEnum, String> {
+	let mut args = args.split(',').map(str::trim);
+	let name = args.next().unwrap_or_default();
+	let format = ImageFormat::from_extension(name).ok_or(format!("invalid image format {name}"))?;
+
+	let mut background = TransformerEnum::DEFAULT_BACKGROUND;
+	for arg in args {
+		match arg.split_once('=').map(|(k, v)| (k.trim(), v.trim())) {
+			Some(("bg", color)) => background = parse_color(color)?,
+			_ => return Err(format!("invalid format argument {arg}")),
+		}
+	}
+
+	// The background of formats with transparency is never used
+	if supports_alpha(format) {
+		background = TransformerEnum::DEFAULT_BACKGROUND;
+	}
+
+	Ok(TransformerEnum::Format { format, background })
+}
+
+/// Parse a hex color like `#fff` or `1a2b3c`
+pub(crate) fn parse_color(color: &str) -> Result<[u8; 3], String> {
+	let hex = color.strip_prefix('#').unwrap_or(color);
+	let digit = |i: usize| {
+		hex.get(i..=i)
+			.and_then(|x| u8::from_str_radix(x, 16).ok())
+			.ok_or(format!("invalid color {color}"))
+	};
+
+	match hex.len() {
+		3 => Ok([digit(0)? * 17, digit(1)? * 17, digit(2)? * 17]),
+		6 => Ok([
+			digit(0)? * 16 + digit(1)?,
+			digit(2)? * 16 + digit(3)?,
+			digit(4)? * 16 + digit(5)?,
+		]),
+		_ => Err(format!("invalid color {color}")),
+	}
+}
+
+/// Returns true if images in `format` may be transparent
+pub(crate) fn supports_alpha(format: ImageFormat) -> bool {
+	!matches!(format, ImageFormat::Jpeg)
+}
+
+impl TransformerEnum {
+	/// The default background of [TransformerEnum::Format]
+	pub const DEFAULT_BACKGROUND: [u8; 3] = [255, 255, 255];
+
+	/// Returns true if this step never changes an image, see [ImageTransformer::is_noop]
+	pub(crate) fn is_noop(&self) -> bool {
+		match self {
+			Self::MaxDim(x) => x.is_noop(),
+			Self::Crop(x) => x.is_noop(),
+			#[cfg(feature = "quantize")]
+			Self::Quantize(x) => x.is_noop(),
+			Self::Format { .. } => false,
+		}
+	}
+}
+
 impl<'de> Deserialize<'de> for TransformerEnum {
 	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
 	where
@@ -163,8 +263,15 @@ impl Display for TransformerEnum {
 		match self {
 			TransformerEnum::MaxDim(x) => Display::fmt(x, f),
 			TransformerEnum::Crop(x) => Display::fmt(x, f),
-			TransformerEnum::Format { format } => {
-				write!(f, "format({})", format.extensions_str()[0])
+			#[cfg(feature = "quantize")]
+			TransformerEnum::Quantize(x) => Display::fmt(x, f),
+			TransformerEnum::Format { format, background } => {
+				write!(f, "format({}", format.extensions_str()[0])?;
+				if *background != Self::DEFAULT_BACKGROUND {
+					let [r, g, b] = background;
+					write!(f, ",bg={r:02x}{g:02x}{b:02x}")?;
+				}
+				write!(f, ")")
 			}
 		}
 	}
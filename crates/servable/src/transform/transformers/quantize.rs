@@ -0,0 +1,76 @@
+use color_quant::NeuQuant;
+use image::{DynamicImage, RgbaImage};
+use std::{collections::HashMap, fmt::Display};
+
+use super::super::transformers::ImageTransformer;
+
+/// Reduce an image to a palette of at most `colors` colors.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuantizeTransformer {
+	colors: u16,
+}
+
+impl QuantizeTransformer {
+	/// Create a new [QuantizeTransformer] that reduces an image to at most `colors` colors.
+	/// `colors` is clamped to `2..=256`.
+	pub fn new(colors: u16) -> Self {
+		Self {
+			colors: colors.clamp(2, 256),
+		}
+	}
+}
+
+impl Display for QuantizeTransformer {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "quantize({})", self.colors)
+	}
+}
+
+impl ImageTransformer for QuantizeTransformer {
+	fn parse_args(args: &str) -> Result<Self, String> {
+		let colors = args
+			.trim()
+			.parse::<u16>()
+			.map_err(|_err| format!("invalid color count {args}"))?;
+
+		if !(2..=256).contains(&colors) {
+			return Err(format!(
+				"color count must be between 2 and 256, got {colors}"
+			));
+		}
+
+		Ok(Self { colors })
+	}
+
+	fn transform(&self, input: &mut DynamicImage) {
+		let has_alpha = input.color().has_alpha();
+		let mut image = input.to_rgba8();
+
+		// Images with few colors, like icons, keep them exactly
+		if palette(&image).is_none_or(|x| x.len() > usize::from(self.colors)) {
+			let quant = NeuQuant::new(10, usize::from(self.colors), image.as_raw());
+			for pixel in image.pixels_mut() {
+				quant.map_pixel(&mut pixel.0);
+			}
+		}
+
+		*input = match has_alpha {
+			true => DynamicImage::ImageRgba8(image),
+			false => DynamicImage::ImageRgb8(DynamicImage::ImageRgba8(image).into_rgb8()),
+		};
+	}
+}
+
+/// The distinct colors of `image` and their index, in order of appearance.
+/// Returns `None` if `image` has more than 256 colors.
+pub(crate) fn palette(image: &RgbaImage) -> Option<HashMap<[u8; 4], u8>> {
+	let mut palette = HashMap::new();
+	for pixel in image.pixels() {
+		if !palette.contains_key(&pixel.0) {
+			let index = u8::try_from(palette.len()).ok()?;
+			palette.insert(pixel.0, index);
+		}
+	}
+
+	Some(palette)
+}
@@ -1,7 +1,19 @@
-use axum::http::{HeaderMap, StatusCode};
+use axum::{
+	body::Bytes,
+	http::{Extensions, HeaderMap, HeaderName, HeaderValue, StatusCode, header},
+};
+
+use crate::Clock;
 use chrono::TimeDelta;
 use mime::Mime;
-use std::collections::BTreeMap;
+use serde::Serialize;
+use std::{
+	collections::BTreeMap,
+	hash::{Hash, Hasher},
+	net::IpAddr,
+	sync::{Arc, Mutex},
+	time::{Duration, Instant},
+};
 
 //
 // MARK: rendered
@@ -20,10 +32,45 @@ pub enum RenderedBody {
 	/// A UTF-8 string
 	String(String),
 
+	/// A value that is serialized to JSON when this response is sent.
+	/// Serialization is skipped if the body is never sent (i.e, HEAD).
+	///
+	/// This does not set a mime type, [Rendered::mime] should
+	/// usually be [mime::APPLICATION_JSON].
+	/// See [RenderedBody::json].
+	Json(Arc<dyn erased_serde::Serialize + Send + Sync>),
+
+	/// Raw bytes made of several buffers, which are sent one after another
+	/// without being joined. See [crate::ZipDownload].
+	///
+	/// These bodies are never compressed by [crate::ServableRouter::with_compression].
+	Chunks(Vec<Bytes>),
+
 	/// No body. Equivalent to `Self::Static(&[])`.
 	Empty,
 }
 
+impl RenderedBody {
+	/// Create a [RenderedBody::Json] from any serializable value.
+	#[inline(always)]
+	pub fn json<T: Serialize + Send + Sync + 'static>(value: T) -> Self {
+		Self::Json(Arc::new(value))
+	}
+
+	/// Returns true if this body contains no bytes.
+	/// [RenderedBody::Json] is never empty.
+	pub fn is_empty(&self) -> bool {
+		match self {
+			Self::Static(x) => x.is_empty(),
+			Self::Bytes(x) => x.is_empty(),
+			Self::String(x) => x.is_empty(),
+			Self::Json(_) => false,
+			Self::Chunks(x) => x.iter().all(Bytes::is_empty),
+			Self::Empty => true,
+		}
+	}
+}
+
 trait RenderedBodyTypeSealed {}
 impl RenderedBodyTypeSealed for () {}
 impl RenderedBodyTypeSealed for RenderedBody {}
@@ -59,6 +106,27 @@ pub struct Rendered<T: RenderedBodyType> {
 
 	/// If true, this response sets `Cache-Control: private`
 	pub private: bool,
+
+	/// The length of the body of this response, in bytes.
+	///
+	/// This is sent as `Content-Length` in responses to `HEAD` requests,
+	/// and should be `None` if it is not cheap to compute.
+	/// It is ignored in all other responses.
+	pub content_length: Option<u64>,
+}
+
+impl<T: RenderedBodyType> Rendered<T> {
+	/// Make this response private.
+	///
+	/// Inner servables and header templates may have set a public `Cache-Control`,
+	/// and the router never replaces one, so this overwrites it.
+	pub(crate) fn make_private(&mut self) {
+		self.private = true;
+		self.headers.insert(
+			axum::http::header::CACHE_CONTROL,
+			crate::router::cache_control(self.ttl, true),
+		);
+	}
 }
 
 impl Rendered<()> {
@@ -71,23 +139,346 @@ impl Rendered<()> {
 			mime: self.mime,
 			ttl: self.ttl,
 			private: self.private,
+			content_length: self.content_length,
 		}
 	}
 }
 
 /// Additional context available to [crate::servable::Servable]s
 /// when generating their content
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone)]
 pub struct RenderContext {
 	/// Information about the request
 	pub client_info: ClientInfo,
 
+	/// The ip address of the client that sent this request, if known.
+	/// This is read from the [std::net::SocketAddr] or [axum::extract::ConnectInfo]
+	/// in the request's extensions. If that address is a trusted proxy,
+	/// the client ip is read from the `Forwarded` or `X-Forwarded-For` header instead,
+	/// see [crate::ServableRouter::with_trusted_proxy].
+	///
+	/// This is ignored when comparing or hashing a [RenderContext].
+	pub client_ip: Option<IpAddr>,
+
+	/// The clock servables should read the time from,
+	/// see [crate::ServableRouter::with_clock].
+	///
+	/// This is ignored when comparing or hashing a [RenderContext].
+	pub clock: Arc<dyn Clock>,
+
+	/// When this request times out, as measured by [Self::clock].
+	/// `None` if this request has no timeout, see [crate::ServableRouter::with_timeout].
+	///
+	/// Servables that do slow, optional work may check [Self::remaining]
+	/// and skip that work instead of being cancelled.
+	///
+	/// This is ignored when comparing or hashing a [RenderContext].
+	pub deadline: Option<Instant>,
+
 	/// The route that was requested.
-	/// Starts with a /.
+	/// Starts with a /, and does not include [Self::base_path].
 	pub route: String,
 
 	/// This request's query parameters
 	pub query: BTreeMap<String, String>,
+
+	/// The values of the parameters in the matched route,
+	/// see [crate::ServableRouter::add_page].
+	/// For example, `/post/{id}` sets `id`.
+	pub params: BTreeMap<String, String>,
+
+	/// The base path of the router serving this request,
+	/// see [crate::ServableRouter::with_base_path].
+	/// Empty if there is none.
+	pub base_path: String,
+
+	/// The scheme and host this router is served at, like `https://example.com`,
+	/// see [crate::ServableRouter::with_base_url].
+	/// Never ends with a `/`.
+	pub base_url: Option<String>,
+
+	/// This request's headers
+	pub headers: HeaderMap,
+
+	/// The kind of response that will be sent
+	pub mode: RenderMode,
+
+	/// Shared state attached to the router that is serving this request,
+	/// see [crate::ServableRouter::with_extension].
+	///
+	/// This is ignored when comparing or hashing a [RenderContext].
+	pub extensions: Extensions,
+
+	/// A unique id for this request, used to match error reports with logs.
+	/// This is read from the `X-Request-Id` header of requests sent by a
+	/// trusted proxy (see [crate::ServableRouter::with_trusted_proxy]), and random otherwise.
+	/// If the `deterministic` feature is enabled, ids that aren't read from a proxy
+	/// count up from `0000000000000001` in each router instead.
+	///
+	/// This is ignored when comparing or hashing a [RenderContext].
+	pub request_id: String,
+
+	/// If true, this request carries the router's debug secret,
+	/// and servables may add diagnostic headers to their responses with [Self::debug_header].
+	/// See [crate::ServableRouter::with_debug_headers].
+	///
+	/// This is ignored when comparing or hashing a [RenderContext].
+	pub debug: bool,
+
+	/// If false, servables must ignore image transformations (`?t=`),
+	/// see [crate::ServableRouter::with_transform_prefix] and [crate::transform::NoTransforms].
+	#[cfg(feature = "image")]
+	pub transforms: bool,
+
+	/// Errors reported with [Self::report_error].
+	/// Shared between clones of this context.
+	pub(crate) errors: Arc<Mutex<Vec<Box<dyn std::error::Error + Send + Sync>>>>,
+
+	/// Request headers reported with [Self::vary].
+	/// Shared between clones of this context.
+	pub(crate) vary: Arc<Mutex<Vec<HeaderName>>>,
+}
+
+impl RenderContext {
+	/// Get a value of type `T` from [Self::extensions].
+	/// Returns `None` if no such value was attached to the router.
+	#[inline(always)]
+	pub fn extension<T: Send + Sync + 'static>(&self) -> Option<&T> {
+		self.extensions.get::<T>()
+	}
+
+	/// Report an error that caused this request to fail.
+	/// If this request is answered with a server error (5xx), reported errors are
+	/// passed to the router's [crate::ErrorReporter]s, see [crate::ServableRouter::with_error_reporter].
+	/// Otherwise, they are ignored.
+	pub fn report_error(&self, error: impl Into<Box<dyn std::error::Error + Send + Sync>>) {
+		if let Ok(mut errors) = self.errors.lock() {
+			errors.push(error.into());
+		}
+	}
+
+	/// Report that the response to this request depends on the request header `name`.
+	/// The router adds `name` to the `Vary` header of the response.
+	pub fn vary(&self, name: HeaderName) {
+		if let Ok(mut vary) = self.vary.lock()
+			&& !vary.contains(&name)
+		{
+			vary.push(name);
+		}
+	}
+
+	/// The request headers reported with [Self::vary], as a `Vary` header.
+	/// Returns an empty map if there are none.
+	pub(crate) fn vary_headers(&self) -> HeaderMap {
+		let mut headers = HeaderMap::new();
+		let vary = self.vary.lock().map(|x| x.clone()).unwrap_or_default();
+		let vary = vary.iter().map(|x| x.as_str()).collect::<Vec<_>>().join(", ");
+		if let Ok(value) = HeaderValue::from_str(&vary)
+			&& !vary.is_empty()
+		{
+			headers.insert(header::VARY, value);
+		}
+		headers
+	}
+
+	/// Set the diagnostic header `name` to `value`, if [Self::debug] is true.
+	/// Values that aren't valid in a header are ignored.
+	pub fn debug_header(&self, headers: &mut HeaderMap, name: &'static str, value: &str) {
+		if self.debug
+			&& let Ok(value) = HeaderValue::from_str(value)
+		{
+			headers.insert(name, value);
+		}
+	}
+
+	/// Remove and return the errors reported with [Self::report_error]
+	pub(crate) fn take_errors(&self) -> Vec<Box<dyn std::error::Error + Send + Sync>> {
+		self.errors
+			.lock()
+			.map(|mut x| std::mem::take(&mut *x))
+			.unwrap_or_default()
+	}
+
+	/// The time left before this request times out, see [Self::deadline].
+	/// Returns `None` if this request has no timeout.
+	///
+	/// ```rust
+	/// use servable::HtmlPage;
+	/// use maud::html;
+	/// use std::time::Duration;
+	///
+	/// let page = HtmlPage::default().with_render(|_page, ctx| {
+	/// 	// Skip slow, optional sections if we're running out of time
+	/// 	let slow_ok = ctx.remaining().is_none_or(|x| x > Duration::from_millis(500));
+	/// 	Box::pin(async move {
+	/// 		html! {
+	/// 			p { "Hello" }
+	/// 			@if slow_ok { p { "Related posts..." } }
+	/// 		}
+	/// 	})
+	/// });
+	/// ```
+	pub fn remaining(&self) -> Option<Duration> {
+		self.deadline
+			.map(|x| x.saturating_duration_since(self.clock.instant()))
+	}
+
+	/// The value of the cookie named `name`, if this request has one
+	pub fn cookie(&self, name: &str) -> Option<&str> {
+		self.headers
+			.get_all(header::COOKIE)
+			.iter()
+			.filter_map(|x| x.to_str().ok())
+			.flat_map(|x| x.split(';'))
+			.filter_map(|x| x.trim().split_once('='))
+			.find_map(|(k, v)| (k == name).then_some(v))
+	}
+
+	/// The token in this request's `Authorization: Bearer <token>` header, if it has one
+	pub fn bearer_token(&self) -> Option<&str> {
+		let value = self.headers.get(header::AUTHORIZATION)?.to_str().ok()?;
+		let (scheme, token) = value.trim().split_once(' ')?;
+		let token = token.trim();
+		(scheme.eq_ignore_ascii_case("bearer") && !token.is_empty()).then_some(token)
+	}
+
+	/// Turn a route on this router into a url path by prepending [Self::base_path].
+	/// With a base path of `/app`, `/about` becomes `/app/about` and `/` becomes `/app`.
+	///
+	/// Anything that isn't an absolute path (like `https://example.com`,
+	/// `//cdn.example.com/x.js` or `style.css`) is returned unchanged.
+	pub fn url(&self, route: &str) -> String {
+		if self.base_path.is_empty() || !route.starts_with('/') || route.starts_with("//") {
+			return route.to_owned();
+		}
+
+		match route {
+			"/" => self.base_path.clone(),
+			_ => format!("{}{route}", self.base_path),
+		}
+	}
+
+	/// Turn a route on this router into an absolute url, like `https://example.com/app/about`.
+	/// This is [Self::url] prefixed with [Self::base_url].
+	///
+	/// If there is no base url, this is the same as [Self::url].
+	pub fn absolute_url(&self, route: &str) -> String {
+		let url = self.url(route);
+		match &self.base_url {
+			Some(base) if url.starts_with('/') && !url.starts_with("//") => format!("{base}{url}"),
+			_ => url,
+		}
+	}
+}
+
+impl PartialEq for RenderContext {
+	fn eq(&self, other: &Self) -> bool {
+		let eq = self.client_info == other.client_info
+			&& self.route == other.route
+			&& self.query == other.query
+			&& self.params == other.params
+			&& self.base_path == other.base_path
+			&& self.base_url == other.base_url
+			&& self.headers == other.headers
+			&& self.mode == other.mode;
+
+		#[cfg(feature = "image")]
+		let eq = eq && self.transforms == other.transforms;
+
+		eq
+	}
+}
+
+impl Eq for RenderContext {}
+
+impl Hash for RenderContext {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		self.client_info.hash(state);
+		self.route.hash(state);
+		self.query.hash(state);
+		self.params.hash(state);
+		self.base_path.hash(state);
+		self.base_url.hash(state);
+		self.mode.hash(state);
+		#[cfg(feature = "image")]
+		self.transforms.hash(state);
+		// Headers aren't hashable, skipping them is still consistent with `eq`.
+	}
+}
+
+/// The kind of response a [crate::servable::Servable] is generating
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum RenderMode {
+	/// Only metadata will be sent (i.e, HEAD).
+	/// Servables should not generate a body.
+	Head,
+
+	/// A complete response will be sent (i.e, GET)
+	#[default]
+	Full,
+}
+
+/// A resource clients should fetch early, see [crate::servable::Servable::preloads]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Preload {
+	/// The resource's url.
+	/// Absolute paths are prefixed with the router's base path.
+	pub url: String,
+
+	/// What kind of resource this is
+	pub kind: PreloadKind,
+}
+
+/// The kind of resource a [Preload] fetches.
+/// This is the `as` attribute of a preload link.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PreloadKind {
+	/// A script
+	Script,
+
+	/// A stylesheet
+	Style,
+
+	/// An image
+	Image,
+
+	/// A font. Fonts are always preloaded in `cors` mode.
+	Font,
+}
+
+impl PreloadKind {
+	/// The value of this kind's `as` attribute
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			Self::Script => "script",
+			Self::Style => "style",
+			Self::Image => "image",
+			Self::Font => "font",
+		}
+	}
+}
+
+/// A class of error status codes, see [crate::ServableRouter::with_error_class]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StatusClass {
+	/// `4xx` status codes
+	ClientError,
+
+	/// `5xx` status codes
+	ServerError,
+}
+
+impl StatusClass {
+	/// The class of `code`, if it is an error
+	pub fn of(code: StatusCode) -> Option<Self> {
+		if code.is_client_error() {
+			Some(Self::ClientError)
+		} else if code.is_server_error() {
+			Some(Self::ServerError)
+		} else {
+			None
+		}
+	}
 }
 
 /// The type of device that requested a page
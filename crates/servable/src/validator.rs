@@ -0,0 +1,232 @@
+use html5ever::{
+	Attribute, ParseOpts, QualName, local_name, ns, parse_document,
+	tendril::{StrTendril, TendrilSink},
+	tree_builder::{ElementFlags, NodeOrText, QuirksMode, TreeBuilderOpts, TreeSink},
+};
+use std::{
+	borrow::Cow,
+	cell::{Cell, Ref, RefCell},
+	collections::HashSet,
+	fmt::Display,
+};
+use tracing::warn;
+
+use crate::{HtmlRewriter, RenderContext, Rendered};
+
+/// A problem in an html document found by [HtmlValidator]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum HtmlProblem {
+	/// The html5 parser had to recover from an error,
+	/// like an unclosed or misnested tag
+	Parse {
+		/// The line of the document the error is on
+		line: u64,
+
+		/// A description of the error
+		message: String,
+	},
+
+	/// Two elements have the same `id`
+	DuplicateId {
+		/// The line of the document the second element is on
+		line: u64,
+
+		/// The id
+		id: String,
+	},
+}
+
+impl Display for HtmlProblem {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::Parse { line, message } => write!(f, "line {line}: {message}"),
+			Self::DuplicateId { line, id } => write!(f, "line {line}: duplicate id `{id}`"),
+		}
+	}
+}
+
+/// An [crate::HtmlRewriter] that parses every successful html response with an html5 parser
+/// and logs a warning for each structural problem, with the route of the page.
+/// Html is not changed.
+///
+/// This catches unclosed and misnested tags (which are easy to produce with [maud::PreEscaped]),
+/// stray end tags, and duplicate ids (see [HtmlProblem]).
+/// Parsing every response is slow, so this is meant for dev builds and tests.
+///
+/// ```rust
+/// use servable::{HtmlPage, HtmlValidator, ServableRouter};
+///
+/// let router = ServableRouter::new()
+/// 	.add_page("/", HtmlPage::default())
+/// 	.with_html_rewriter(HtmlValidator::default());
+///
+/// let problems = HtmlValidator::check(
+/// 	"<!DOCTYPE html><html><head><title>x</title></head>\
+/// 	<body><p id=a>1</p><p id=a>2</div></body></html>",
+/// );
+/// assert_eq!(problems.len(), 2);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct HtmlValidator {
+	/// If true, panic on the first response with problems instead of logging them.
+	/// This makes tests that render pages fail on invalid html.
+	pub panic: bool,
+}
+
+impl HtmlValidator {
+	/// Panic on invalid html instead of logging, see [Self::panic]
+	#[inline(always)]
+	pub fn with_panic(mut self, panic: bool) -> Self {
+		self.panic = panic;
+		self
+	}
+
+	/// Find every problem in the html document `html`
+	pub fn check(html: &str) -> Vec<HtmlProblem> {
+		let opts = ParseOpts {
+			tree_builder: TreeBuilderOpts {
+				exact_errors: true,
+				..Default::default()
+			},
+			..Default::default()
+		};
+
+		parse_document(ProblemSink::new(), opts).one(html)
+	}
+}
+
+impl HtmlRewriter for HtmlValidator {
+	fn rewrite(&self, ctx: &RenderContext, rend: &mut Rendered<()>, html: String) -> String {
+		if !rend.code.is_success() {
+			return html;
+		}
+
+		let problems = Self::check(&html);
+		if self.panic && !problems.is_empty() {
+			let problems: Vec<String> = problems.iter().map(|x| x.to_string()).collect();
+			panic!("invalid html at {}:\n{}", ctx.route, problems.join("\n"));
+		}
+
+		for problem in problems {
+			warn!(message = "Invalid html", route = ctx.route, %problem);
+		}
+
+		html
+	}
+}
+
+//
+// MARK: sink
+//
+
+/// A [TreeSink] that doesn't build a tree, and only records problems.
+/// Handles are indices into `names`.
+struct ProblemSink {
+	/// The name of every node we've created.
+	/// Nodes that aren't elements have an empty name.
+	names: RefCell<Vec<QualName>>,
+
+	/// Ids we've seen
+	ids: RefCell<HashSet<String>>,
+
+	problems: RefCell<Vec<HtmlProblem>>,
+	line: Cell<u64>,
+}
+
+impl ProblemSink {
+	fn new() -> Self {
+		Self {
+			// Handle 0 is the document
+			names: RefCell::new(vec![Self::no_name()]),
+			ids: RefCell::new(HashSet::new()),
+			problems: RefCell::new(Vec::new()),
+			line: Cell::new(1),
+		}
+	}
+
+	/// The name of nodes that aren't elements
+	fn no_name() -> QualName {
+		QualName::new(None, ns!(), local_name!(""))
+	}
+
+	fn node(&self, name: QualName) -> usize {
+		let mut names = self.names.borrow_mut();
+		names.push(name);
+		names.len() - 1
+	}
+}
+
+impl TreeSink for ProblemSink {
+	type Handle = usize;
+	type Output = Vec<HtmlProblem>;
+	type ElemName<'a> = Ref<'a, QualName>;
+
+	fn finish(self) -> Self::Output {
+		self.problems.into_inner()
+	}
+
+	fn parse_error(&self, msg: Cow<'static, str>) {
+		self.problems.borrow_mut().push(HtmlProblem::Parse {
+			line: self.line.get(),
+			message: msg.into_owned(),
+		});
+	}
+
+	fn set_current_line(&self, line_number: u64) {
+		self.line.set(line_number);
+	}
+
+	fn get_document(&self) -> usize {
+		0
+	}
+
+	fn elem_name<'a>(&'a self, target: &'a usize) -> Self::ElemName<'a> {
+		Ref::map(self.names.borrow(), |x| &x[*target])
+	}
+
+	fn create_element(&self, name: QualName, attrs: Vec<Attribute>, _flags: ElementFlags) -> usize {
+		for attr in &attrs {
+			if attr.name.local == local_name!("id")
+				&& !self.ids.borrow_mut().insert(attr.value.to_string())
+			{
+				self.problems.borrow_mut().push(HtmlProblem::DuplicateId {
+					line: self.line.get(),
+					id: attr.value.to_string(),
+				});
+			}
+		}
+
+		self.node(name)
+	}
+
+	fn create_comment(&self, _text: StrTendril) -> usize {
+		self.node(Self::no_name())
+	}
+
+	fn create_pi(&self, _target: StrTendril, _data: StrTendril) -> usize {
+		self.node(Self::no_name())
+	}
+
+	fn get_template_contents(&self, _target: &usize) -> usize {
+		self.node(Self::no_name())
+	}
+
+	fn same_node(&self, x: &usize, y: &usize) -> bool {
+		x == y
+	}
+
+	fn append(&self, _parent: &usize, _child: NodeOrText<usize>) {}
+	fn append_before_sibling(&self, _sibling: &usize, _new_node: NodeOrText<usize>) {}
+	fn append_based_on_parent_node(
+		&self,
+		_element: &usize,
+		_prev_element: &usize,
+		_child: NodeOrText<usize>,
+	) {
+	}
+	fn append_doctype_to_document(&self, _: StrTendril, _: StrTendril, _: StrTendril) {}
+	fn set_quirks_mode(&self, _mode: QuirksMode) {}
+	fn add_attrs_if_missing(&self, _target: &usize, _attrs: Vec<Attribute>) {}
+	fn remove_from_parent(&self, _target: &usize) {}
+	fn reparent_children(&self, _node: &usize, _new_parent: &usize) {}
+}
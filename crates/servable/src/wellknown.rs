@@ -0,0 +1,276 @@
+use axum::http::{HeaderMap, StatusCode, header};
+use chrono::{DateTime, SecondsFormat, TimeDelta, Utc};
+use std::{fmt::Write, pin::Pin};
+
+use crate::{
+	Error, Redirect, RenderContext, RenderMode, Rendered, RenderedBody, ServableRouter,
+	router::cache_control, servable::Servable,
+};
+
+/// A [`security.txt`](https://www.rfc-editor.org/rfc/rfc9116) file,
+/// which tells security researchers how to report vulnerabilities.
+/// See [WellKnown].
+///
+/// If this has no `Canonical` url and the router has a base url,
+/// the url this file is served at is used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecurityTxt {
+	/// Where to report vulnerabilities, like `mailto:security@example.com`
+	/// or `https://example.com/report`. At least one is required.
+	pub contact: Vec<String>,
+
+	/// When this file should no longer be trusted.
+	/// This should be less than a year in the future.
+	pub expires: DateTime<Utc>,
+
+	/// Urls of keys to encrypt reports with
+	pub encryption: Vec<String>,
+
+	/// Urls of pages that thank researchers
+	pub acknowledgments: Vec<String>,
+
+	/// Languages reports may be written in, like `en` or `de`
+	pub preferred_languages: Vec<String>,
+
+	/// The urls this file is served at
+	pub canonical: Vec<String>,
+
+	/// Urls of vulnerability disclosure policies
+	pub policy: Vec<String>,
+
+	/// Urls of security-related job listings
+	pub hiring: Vec<String>,
+}
+
+impl SecurityTxt {
+	/// Create a [SecurityTxt] with one `contact` that expires at `expires`
+	pub fn new(contact: impl Into<String>, expires: DateTime<Utc>) -> Self {
+		Self {
+			contact: vec![contact.into()],
+			expires,
+			encryption: Vec::new(),
+			acknowledgments: Vec::new(),
+			preferred_languages: Vec::new(),
+			canonical: Vec::new(),
+			policy: Vec::new(),
+			hiring: Vec::new(),
+		}
+	}
+
+	/// Add a `Contact` url
+	#[inline(always)]
+	pub fn with_contact(mut self, contact: impl Into<String>) -> Self {
+		self.contact.push(contact.into());
+		self
+	}
+
+	/// Add an `Encryption` url
+	#[inline(always)]
+	pub fn with_encryption(mut self, url: impl Into<String>) -> Self {
+		self.encryption.push(url.into());
+		self
+	}
+
+	/// Add an `Acknowledgments` url
+	#[inline(always)]
+	pub fn with_acknowledgments(mut self, url: impl Into<String>) -> Self {
+		self.acknowledgments.push(url.into());
+		self
+	}
+
+	/// Add a preferred language
+	#[inline(always)]
+	pub fn with_preferred_language(mut self, language: impl Into<String>) -> Self {
+		self.preferred_languages.push(language.into());
+		self
+	}
+
+	/// Add a `Canonical` url
+	#[inline(always)]
+	pub fn with_canonical(mut self, url: impl Into<String>) -> Self {
+		self.canonical.push(url.into());
+		self
+	}
+
+	/// Add a `Policy` url
+	#[inline(always)]
+	pub fn with_policy(mut self, url: impl Into<String>) -> Self {
+		self.policy.push(url.into());
+		self
+	}
+
+	/// Add a `Hiring` url
+	#[inline(always)]
+	pub fn with_hiring(mut self, url: impl Into<String>) -> Self {
+		self.hiring.push(url.into());
+		self
+	}
+
+	/// The contents of this file, served at `ctx.route`
+	pub fn render_txt(&self, ctx: &RenderContext) -> String {
+		let mut out = String::new();
+		let mut field = |name: &str, value: &str| {
+			// Values may not span lines
+			let value: String = value.chars().filter(|x| !x.is_control()).collect();
+			let _ = writeln!(out, "{name}: {}", value.trim());
+		};
+
+		for x in &self.contact {
+			field("Contact", x);
+		}
+		field(
+			"Expires",
+			&self.expires.to_rfc3339_opts(SecondsFormat::Secs, true),
+		);
+		for x in &self.encryption {
+			field("Encryption", x);
+		}
+		for x in &self.acknowledgments {
+			field("Acknowledgments", x);
+		}
+		if !self.preferred_languages.is_empty() {
+			field("Preferred-Languages", &self.preferred_languages.join(", "));
+		}
+		for x in &self.canonical {
+			field("Canonical", x);
+		}
+		if self.canonical.is_empty() && ctx.base_url.is_some() {
+			field("Canonical", &ctx.absolute_url(&ctx.route));
+		}
+		for x in &self.policy {
+			field("Policy", x);
+		}
+		for x in &self.hiring {
+			field("Hiring", x);
+		}
+
+		out
+	}
+}
+
+impl Servable for SecurityTxt {
+	fn head<'a>(
+		&'a self,
+		_ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<()>> + 'a + Send + Sync>> {
+		Box::pin(async {
+			Rendered {
+				code: StatusCode::OK,
+				headers: HeaderMap::new(),
+				body: (),
+				mime: Some(mime::TEXT_PLAIN_UTF_8),
+				ttl: Some(TimeDelta::days(1)),
+				private: false,
+				content_length: None,
+			}
+		})
+	}
+
+	fn render<'a>(
+		&'a self,
+		ctx: &'a RenderContext,
+	) -> Pin<Box<dyn Future<Output = Rendered<RenderedBody>> + 'a + Send + Sync>> {
+		Box::pin(async {
+			let rend = self.head(ctx).await;
+			match ctx.mode {
+				RenderMode::Head => rend.with_body(RenderedBody::Empty),
+				_ => rend.with_body(RenderedBody::String(self.render_txt(ctx))),
+			}
+		})
+	}
+
+	fn header_template(&self) -> HeaderMap {
+		let mut headers = HeaderMap::with_capacity(1);
+		headers.insert(
+			header::CACHE_CONTROL,
+			cache_control(Some(TimeDelta::days(1)), false),
+		);
+		headers
+	}
+}
+
+/// Common `/.well-known` endpoints, added to a router with [ServableRouter::add_well_known].
+///
+/// These are served relative to the router's base path, like every other route.
+/// Clients only look for them at the root of a host,
+/// so routers with a base path should not serve them.
+///
+/// ```rust
+/// use chrono::{TimeZone, Utc};
+/// use servable::{SecurityTxt, ServableRouter, WellKnown};
+///
+/// let expires = Utc.with_ymd_and_hms(2030, 1, 1, 0, 0, 0).unwrap();
+/// let well_known = WellKnown::new()
+/// 	.with_security_txt(
+/// 		SecurityTxt::new("mailto:security@example.com", expires)
+/// 			.with_policy("https://example.com/security"),
+/// 	)
+/// 	.with_change_password("/account/password");
+///
+/// // Serves `/.well-known/security.txt` and `/.well-known/change-password`
+/// let router = ServableRouter::new().add_well_known(well_known);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WellKnown {
+	/// Served at `/.well-known/security.txt`
+	pub security_txt: Option<SecurityTxt>,
+
+	/// Where `/.well-known/change-password` redirects to,
+	/// see <https://w3c.github.io/webappsec-change-password-url/>
+	pub change_password: Option<String>,
+}
+
+impl WellKnown {
+	/// Create a [WellKnown] without endpoints
+	#[inline(always)]
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Serve `security_txt` at `/.well-known/security.txt`
+	#[inline(always)]
+	pub fn with_security_txt(mut self, security_txt: SecurityTxt) -> Self {
+		self.security_txt = Some(security_txt);
+		self
+	}
+
+	/// Redirect `/.well-known/change-password` to `to`,
+	/// the page where users change their password.
+	#[inline(always)]
+	pub fn with_change_password(mut self, to: impl Into<String>) -> Self {
+		self.change_password = Some(to.into());
+		self
+	}
+}
+
+impl ServableRouter {
+	/// Serve the endpoints in `well_known`.
+	///
+	/// - panics if one of these routes is already taken, like [Self::add_page].
+	/// - panics if the change-password url is not a valid header value.
+	///
+	/// See [Self::try_add_well_known] for a version of this method that does not panic.
+	pub fn add_well_known(self, well_known: WellKnown) -> Self {
+		match self.try_add_well_known(well_known) {
+			Ok(x) => x,
+			Err(err) => panic!("{err}"),
+		}
+	}
+
+	/// Serve the endpoints in `well_known`.
+	/// Behaves exactly like [Self::add_well_known], but returns an [Error] instead of panicking.
+	pub fn try_add_well_known(mut self, well_known: WellKnown) -> Result<Self, Error> {
+		if let Some(security_txt) = well_known.security_txt {
+			self = self.try_add_page("/.well-known/security.txt", security_txt)?;
+		}
+
+		if let Some(to) = well_known.change_password {
+			let redirect = Redirect::new_307(to).map_err(|_err| Error::InvalidHeader {
+				name: "location".to_owned(),
+			})?;
+			self = self.try_add_page("/.well-known/change-password", redirect)?;
+		}
+
+		Ok(self)
+	}
+}